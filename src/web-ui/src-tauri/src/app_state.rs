@@ -1,19 +1,94 @@
+use crate::commands::notifications::NotificationConfig;
+use crate::commands::security::SafetyValidation;
+use crate::utils::analysis_cache::AnalysisCache;
+use crate::utils::audit::AuditLog;
 use crate::utils::config::AppConfig;
+use crate::utils::delete_tokens::DeleteTokens;
+use crate::utils::deletion::DeletionReport;
+use crate::utils::dir_size_cache::DirSizeCache;
+use crate::utils::hash_index::HashIndex;
+use crate::utils::history::CleanupHistory;
+use crate::utils::metrics::Metrics;
+use crate::utils::notification_throttle::NotificationThrottle;
+use crate::utils::operations::OperationRegistry;
+use crate::utils::overrides::OverrideRules;
+use crate::utils::rate_limiter::RateLimiter;
+use crate::utils::throughput::ThroughputHistory;
+use crate::utils::validation_cache::ValidationCache;
+use crate::utils::volume_locks::VolumeLocks;
+use crate::{AppError, AppResult};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock};
+use tracing::{info, warn};
+
+/// Upper bound on how long shutdown waits for cancelled operations to finish
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// How long a `prepare_delete` token stays valid for `confirm_delete`
+const DELETE_TOKEN_TTL: Duration = Duration::from_secs(120);
+/// How long a cached path safety validation is reused
+const VALIDATION_CACHE_TTL: Duration = Duration::from_secs(10);
 
 /// Shared application state
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub config: Arc<RwLock<AppConfig>>,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub history: Arc<Mutex<CleanupHistory>>,
+    pub operations: Arc<OperationRegistry>,
+    pub audit: Arc<AuditLog>,
+    pub analysis_cache: Arc<AnalysisCache>,
+    pub dir_size_cache: Arc<DirSizeCache>,
+    /// File hashes kept across duplicate scans so interrupted ones resume
+    pub hash_index: Arc<HashIndex>,
+    pub notification_throttle: Arc<Mutex<NotificationThrottle<NotificationConfig>>>,
+    pub delete_tokens: Arc<DeleteTokens>,
+    pub validation_cache: Arc<ValidationCache<SafetyValidation>>,
+    pub override_rules: Arc<RwLock<OverrideRules>>,
+    pub volume_locks: Arc<VolumeLocks>,
+    /// Measured speed of recent deletes, for `estimate_operation_duration`
+    pub throughput: Arc<ThroughputHistory>,
+    /// Recent performance figures for `get_metrics`, kept for the session only
+    pub metrics: Arc<Metrics>,
+    /// Directory the user explicitly confirmed; deletions outside it are refused
+    scan_root: Arc<RwLock<Option<PathBuf>>>,
+    /// Scan session most recently produced or loaded, which the UI is showing
+    active_session: Arc<RwLock<Option<String>>>,
+    shutdown_requested: Arc<AtomicBool>,
+    shutdown_complete: Arc<AtomicBool>,
 }
 
 impl AppState {
     /// Create new application state
     pub fn new() -> Self {
         let config = AppConfig::load_or_create();
+        let rate_limiter = RateLimiter::new(config.ai.requests_per_minute);
+        let analysis_cache = AnalysisCache::load(&config.cache_directory);
+        let dir_size_cache = DirSizeCache::load(&config.cache_directory);
+        let hash_index = HashIndex::load(&config.cache_directory);
         Self {
             config: Arc::new(RwLock::new(config)),
+            rate_limiter: Arc::new(rate_limiter),
+            history: Arc::new(Mutex::new(CleanupHistory::load())),
+            operations: Arc::new(OperationRegistry::default()),
+            audit: Arc::new(AuditLog::new()),
+            analysis_cache: Arc::new(analysis_cache),
+            dir_size_cache: Arc::new(dir_size_cache),
+            hash_index: Arc::new(hash_index),
+            notification_throttle: Arc::new(Mutex::new(NotificationThrottle::default())),
+            delete_tokens: Arc::new(DeleteTokens::new(DELETE_TOKEN_TTL)),
+            validation_cache: Arc::new(ValidationCache::new(VALIDATION_CACHE_TTL)),
+            override_rules: Arc::new(RwLock::new(OverrideRules::load())),
+            volume_locks: Arc::new(VolumeLocks::default()),
+            throughput: Arc::new(ThroughputHistory::default()),
+            metrics: Arc::new(Metrics::default()),
+            scan_root: Arc::new(RwLock::new(None)),
+            active_session: Arc::new(RwLock::new(None)),
+            shutdown_requested: Arc::new(AtomicBool::new(false)),
+            shutdown_complete: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -29,7 +104,114 @@ impl AppState {
     {
         let mut config = self.config.write().await;
         updater(&mut config);
-        config.save()?;
+        // Saving syncs to disk and may sleep between retries
+        let snapshot = config.clone();
+        tokio::task::spawn_blocking(move || snapshot.save()).await??;
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Confirm the directory that delete commands are restricted to
+    pub async fn set_scan_root(&self, root: PathBuf) {
+        *self.scan_root.write().await = Some(root);
+    }
+
+    /// The confirmed scan root, if the user has selected one
+    pub async fn scan_root(&self) -> Option<PathBuf> {
+        self.scan_root.read().await.clone()
+    }
+
+    /// The confirmed scan root, or an error for delete commands to return
+    pub async fn require_scan_root(&self) -> AppResult<PathBuf> {
+        self.scan_root().await.ok_or_else(|| {
+            AppError::SecurityError(
+                "Deletion requires a confirmed scan root; call set_scan_root first".to_string(),
+            )
+        })
+    }
+
+    /// Require a fresh confirmation before the next delete
+    pub async fn clear_scan_root(&self) {
+        *self.scan_root.write().await = None;
+    }
+
+    /// Remember the scan session the UI is working with
+    pub async fn set_active_session(&self, session_id: String) {
+        *self.active_session.write().await = Some(session_id);
+    }
+
+    /// The scan session last produced or loaded, if any
+    pub async fn active_session(&self) -> Option<String> {
+        self.active_session.read().await.clone()
+    }
+
+    /// Record a completed cleanup session in the persistent history
+    pub async fn record_cleanup(&self, directory: &str, report: &DeletionReport) {
+        if report.deleted_count == 0 {
+            return;
+        }
+
+        let mut history = self.history.lock().await;
+        history.record(directory, report.deleted_count, report.bytes_freed);
+        if let Err(e) = history.save() {
+            warn!("Failed to save cleanup history: {}", e);
+        }
+    }
+
+    /// Claim the shutdown; returns false if another caller already started it
+    pub fn request_shutdown(&self) -> bool {
+        !self.shutdown_requested.swap(true, Ordering::SeqCst)
+    }
+
+    /// Whether `shutdown` has already run to completion
+    pub fn is_shut_down(&self) -> bool {
+        self.shutdown_complete.load(Ordering::SeqCst)
+    }
+
+    /// Cancel in-flight work and flush everything that is buffered in memory.
+    ///
+    /// Waiting for cancelled operations is bounded by `SHUTDOWN_TIMEOUT` so a
+    /// stuck filesystem call can never keep the application from exiting.
+    pub async fn shutdown(&self) {
+        let cancelled = self.operations.cancel_all();
+        if !self.operations.is_empty() {
+            info!("Cancelling {} in-flight operation(s)", cancelled);
+            let drained = tokio::time::timeout(SHUTDOWN_TIMEOUT, async {
+                while !self.operations.is_empty() {
+                    tokio::time::sleep(SHUTDOWN_POLL_INTERVAL).await;
+                }
+            })
+            .await;
+            if drained.is_err() {
+                warn!(
+                    "{} operation(s) still running after {:?}, continuing shutdown",
+                    self.operations.len(),
+                    SHUTDOWN_TIMEOUT
+                );
+            }
+        }
+
+        if let Err(e) = self.analysis_cache.flush() {
+            warn!("Failed to flush analysis cache: {}", e);
+        }
+        if let Err(e) = self.dir_size_cache.flush() {
+            warn!("Failed to flush directory size cache: {}", e);
+        }
+        if let Err(e) = self.hash_index.flush() {
+            warn!("Failed to flush file hash index: {}", e);
+        }
+        if let Err(e) = self.audit.flush() {
+            warn!("Failed to flush audit log: {}", e);
+        }
+        let config = self.config.read().await.clone();
+        let saved = tokio::task::spawn_blocking(move || config.save()).await;
+        if let Err(e) = saved.map_err(anyhow::Error::from).and_then(|saved| saved) {
+            warn!("Failed to save configuration: {}", e);
+        }
+        if let Err(e) = self.history.lock().await.save() {
+            warn!("Failed to save cleanup history: {}", e);
+        }
+
+        self.shutdown_complete.store(true, Ordering::SeqCst);
+        info!("Shutdown hook completed");
+    }
+}
@@ -0,0 +1,1172 @@
+use crate::utils::ai_client::{AiClient, AiError};
+use crate::utils::analysis_cache::AnalysisCache;
+use crate::utils::batch_tuner::BatchTuner;
+use crate::utils::classifier::{self, Classification, ClassificationSource, FileCategory, FileFacts};
+use crate::utils::config::{AiOverride, AppConfig, RunOverrides};
+use crate::utils::logging::timed_command;
+use crate::utils::operations::{
+    ErrorCode, ItemError, OperationGuard, OperationKind, OperationTally,
+};
+use crate::utils::overrides::{AppliedOverride, OverrideRules};
+use crate::utils::snapshots::{ScanSnapshot, SessionResults, SnapshotEntry, SnapshotStore};
+use crate::{AppError, AppResult, AppState};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tauri::{command, AppHandle, Emitter, Runtime, State};
+use tracing::{info, warn};
+
+/// Event carrying `AnalysisProgress` after each batch of `analyze_session`
+pub const ANALYSIS_PROGRESS_EVENT: &str = "analysis-progress";
+
+#[derive(Debug, Serialize)]
+pub struct AiConnectionTest {
+    pub success: bool,
+    pub provider: String,
+    pub model: Option<String>,
+    pub latency_ms: u64,
+    pub error: Option<AiError>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PathAnalysis {
+    pub path: String,
+    pub category: FileCategory,
+    pub confidence: f32,
+    pub reason: String,
+    pub source: ClassificationSource,
+    pub cached: bool,
+    /// The user override rule that replaced the verdict, if any
+    pub overridden_by: Option<AppliedOverride>,
+}
+
+/// Totals for one category, split by classification confidence
+#[derive(Debug, Serialize)]
+pub struct CategoryTotals {
+    pub category: FileCategory,
+    pub reclaimable: bool,
+    pub file_count: u64,
+    pub total_bytes: u64,
+    pub high_confidence_count: u64,
+    pub high_confidence_bytes: u64,
+    pub low_confidence_count: u64,
+    pub low_confidence_bytes: u64,
+}
+
+impl CategoryTotals {
+    fn new(category: FileCategory) -> Self {
+        Self {
+            category,
+            reclaimable: category.is_reclaimable(),
+            file_count: 0,
+            total_bytes: 0,
+            high_confidence_count: 0,
+            high_confidence_bytes: 0,
+            low_confidence_count: 0,
+            low_confidence_bytes: 0,
+        }
+    }
+
+    fn add(&mut self, size: u64, confidence: f32, threshold: f32) {
+        self.file_count += 1;
+        self.total_bytes += size;
+        if confidence >= threshold {
+            self.high_confidence_count += 1;
+            self.high_confidence_bytes += size;
+        } else {
+            self.low_confidence_count += 1;
+            self.low_confidence_bytes += size;
+        }
+    }
+}
+
+/// Sort largest reclaimable categories first and total up what they free
+fn rank_categories(categories: &mut [CategoryTotals]) -> (u64, u64) {
+    categories.sort_by_key(|totals| (Reverse(totals.reclaimable), Reverse(totals.total_bytes)));
+    let reclaimable = || categories.iter().filter(|totals| totals.reclaimable);
+    (
+        reclaimable().map(|totals| totals.total_bytes).sum(),
+        reclaimable().map(|totals| totals.high_confidence_bytes).sum(),
+    )
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnalysisSummary {
+    pub session_id: String,
+    pub high_confidence_threshold: f32,
+    pub categories: Vec<CategoryTotals>,
+    pub reclaimable_bytes: u64,
+    pub high_confidence_reclaimable_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisProgress {
+    pub session_id: String,
+    pub analyzed: u64,
+    pub total: u64,
+    /// Files per request for the next batch, after any auto-tuning
+    pub batch_size: usize,
+    /// Estimated seconds remaining; absent during the first few seconds
+    pub eta_seconds: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnalyzedFile {
+    pub path: String,
+    pub size: u64,
+    pub classification: Classification,
+    pub cached: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionAnalysis {
+    pub session_id: String,
+    pub files: Vec<AnalyzedFile>,
+    pub cached_files: u64,
+    pub ai_requests: u64,
+    /// Batch size in effect when the run finished
+    pub batch_size: usize,
+}
+
+/// Where the classification of a file in an incremental run came from
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultOrigin {
+    /// Unchanged since the previous analyzed scan, whose result was kept
+    Reused,
+    /// New or modified, but found in the analysis cache
+    Cached,
+    /// New or modified and classified in this run
+    Analyzed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IncrementalFile {
+    pub path: String,
+    pub size: u64,
+    pub classification: Classification,
+    pub origin: ResultOrigin,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IncrementalAnalysis {
+    pub session_id: String,
+    /// Session whose results were reused; `None` if no earlier analysis of
+    /// the root was found, in which case nothing is reused
+    pub previous_session_id: Option<String>,
+    pub files: Vec<IncrementalFile>,
+    pub reused_files: u64,
+    pub cached_files: u64,
+    pub analyzed_files: u64,
+    pub ai_requests: u64,
+    /// Batch size in effect when the run finished
+    pub batch_size: usize,
+}
+
+/// One file of a `reanalyze_low_confidence` run
+#[derive(Debug, Serialize)]
+pub struct ReanalyzedFile {
+    pub path: String,
+    pub size: u64,
+    pub previous: Classification,
+    /// The better of the previous and the retried verdict
+    pub classification: Classification,
+    pub improved: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Reanalysis {
+    pub session_id: String,
+    pub threshold: f32,
+    pub provider: String,
+    pub model: String,
+    /// Files below the threshold that were re-submitted
+    pub candidates: u64,
+    pub improved: u64,
+    pub ai_requests: u64,
+    pub files: Vec<ReanalyzedFile>,
+}
+
+/// Receives the `AnalysisProgress` of a run after each batch
+pub type ProgressSink<'a> = dyn Fn(AnalysisProgress) + Send + Sync + 'a;
+
+/// Result of `classify_in_batches`
+struct BatchRun {
+    classifications: Vec<Classification>,
+    ai_requests: u64,
+    batch_size: usize,
+    /// Files the provider failed on, which kept a heuristic verdict
+    errors: Vec<ItemError>,
+}
+
+/// Files of one selected root that were not already counted under a more
+/// specific root
+#[derive(Debug, Serialize)]
+pub struct RootSubtotal {
+    pub session_id: String,
+    pub root: String,
+    pub file_count: u64,
+    pub total_bytes: u64,
+    pub reclaimable_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MultiRootSummary {
+    pub high_confidence_threshold: f32,
+    pub categories: Vec<CategoryTotals>,
+    pub roots: Vec<RootSubtotal>,
+    pub reclaimable_bytes: u64,
+    pub high_confidence_reclaimable_bytes: u64,
+    /// Files listed by more than one session that were counted only once
+    pub overlapping_files: u64,
+}
+
+#[command]
+pub async fn test_ai_connection(state: State<'_, AppState>) -> AppResult<AiConnectionTest> {
+    timed_command("test_ai_connection", Some(state.inner()), None, async {
+        let config = state.get_config().await;
+        let provider = config.ai.provider.clone();
+        let started = Instant::now();
+
+        let result = match AiClient::from_config(&config.ai, config.analysis.ai_timeout) {
+            Ok(client) => client.probe().await,
+            Err(e) => Err(e),
+        };
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        Ok(match result {
+            Ok(model) => {
+                info!("AI connection test succeeded for {} ({} ms)", provider, latency_ms);
+                AiConnectionTest {
+                    success: true,
+                    provider,
+                    model: Some(model),
+                    latency_ms,
+                    error: None,
+                }
+            }
+            Err(e) => {
+                warn!("AI connection test failed for {}: {}", provider, e);
+                AiConnectionTest {
+                    success: false,
+                    provider,
+                    model: None,
+                    latency_ms,
+                    error: Some(e),
+                }
+            }
+        })
+    })
+    .await
+}
+
+/// Classify one path on demand, e.g. for a "what is this file" action
+#[command]
+pub async fn analyze_single_path(
+    state: State<'_, AppState>,
+    path: String,
+    overrides: Option<RunOverrides>,
+) -> AppResult<PathAnalysis> {
+    timed_command("analyze_single_path", Some(state.inner()), Some(path.clone()), async {
+        let target = PathBuf::from(&path);
+        let facts = tokio::task::spawn_blocking(move || FileFacts::from_path(&target))
+            .await
+            .map_err(|e| AppError::SystemError(e.to_string()))?
+            .map_err(|e| AppError::FileSystemError(format!("Cannot read {}: {}", path, e)))?;
+
+        let mut config = state.get_config().await;
+        config.analysis = overrides
+            .unwrap_or_default()
+            .apply(&config.analysis)
+            .map_err(|e| AppError::ConfigError(e.to_string()))?;
+        let use_cache = config.analysis.enable_caching;
+
+        let cached = use_cache
+            .then(|| {
+                state.analysis_cache.get(
+                    &facts,
+                    config.analysis.cache_ttl_seconds,
+                    &config.analysis.response_language,
+                )
+            })
+            .flatten();
+        if use_cache {
+            let hit = usize::from(cached.is_some());
+            state.metrics.record_cache_lookups(hit, 1 - hit);
+        }
+        let (classification, cached) = match cached {
+            Some(classification) => (classification, true),
+            None => {
+                let classification = classifier::classify(&config, &state.rate_limiter, &facts).await;
+                if use_cache && classification.source == ClassificationSource::Ai {
+                    state.analysis_cache.insert(
+                        &facts,
+                        &classification,
+                        &config.analysis.response_language,
+                    );
+                }
+                (classification, false)
+            }
+        };
+        let classification = state
+            .override_rules
+            .read()
+            .await
+            .apply(Path::new(&facts.path), classification);
+
+        Ok(PathAnalysis {
+            path: facts.path,
+            category: classification.category,
+            confidence: classification.confidence,
+            reason: classification.reason,
+            source: classification.source,
+            cached,
+            overridden_by: classification.overridden_by,
+        })
+    })
+    .await
+}
+
+/// Classify every file of a scan session, sending uncached files to the AI
+/// provider in batches of `analysis.batch_size` (auto-tuned when
+/// `analysis.adaptive_batch_size` is set) and emitting
+/// `ANALYSIS_PROGRESS_EVENT` after each batch. User override rules are
+/// applied to the results.
+#[command]
+pub async fn analyze_session<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, AppState>,
+    session_id: String,
+    overrides: Option<RunOverrides>,
+) -> AppResult<SessionAnalysis> {
+    timed_command("analyze_session", Some(state.inner()), None, async {
+        let overrides = overrides.unwrap_or_default();
+        run_session_analysis(&state, session_id, overrides, &progress_emitter(&app)).await
+    })
+    .await
+}
+
+/// Classify every file of a scan session as `analyze_session` does, passing
+/// progress to `on_progress`. Shared by the command and headless runs.
+pub async fn run_session_analysis(
+    state: &AppState,
+    session_id: String,
+    overrides: RunOverrides,
+    on_progress: &ProgressSink<'_>,
+) -> AppResult<SessionAnalysis> {
+    let mut config = state.get_config().await;
+    config.analysis = overrides
+        .apply(&config.analysis)
+        .map_err(|e| AppError::ConfigError(e.to_string()))?;
+    let snapshot = SnapshotStore::new(&config.cache_directory)
+        .load(&session_id)
+        .map_err(|e| AppError::FileSystemError(e.to_string()))?;
+    let mut operation = state.operations.start(OperationKind::Analyze, &snapshot.root);
+
+    let use_cache = config.analysis.enable_caching;
+    let ttl_seconds = config.analysis.cache_ttl_seconds;
+    let language = config.analysis.response_language.as_str();
+    let facts: Vec<FileFacts> = snapshot.entries.iter().map(|entry| entry.facts()).collect();
+    let mut results: Vec<Option<(Classification, bool)>> = facts
+        .iter()
+        .map(|facts| {
+            use_cache
+                .then(|| state.analysis_cache.get(facts, ttl_seconds, language))
+                .flatten()
+                .map(|classification| (classification, true))
+        })
+        .collect();
+    let pending: Vec<usize> = (0..facts.len()).filter(|&i| results[i].is_none()).collect();
+    let cached_files = (facts.len() - pending.len()) as u64;
+    if use_cache {
+        state.metrics.record_cache_lookups(cached_files as usize, pending.len());
+    }
+
+    let batch: Vec<FileFacts> = pending.iter().map(|&i| facts[i].clone()).collect();
+    let run = classify_in_batches(
+        on_progress,
+        state,
+        &config,
+        &session_id,
+        &batch,
+        (cached_files, facts.len() as u64),
+        &operation,
+    )
+    .await?;
+    for ((&i, facts), classification) in pending.iter().zip(&batch).zip(run.classifications) {
+        if use_cache && classification.source == ClassificationSource::Ai {
+            state.analysis_cache.insert(facts, &classification, language);
+        }
+        results[i] = Some((classification, false));
+    }
+    save_results(
+        &config,
+        &session_id,
+        &snapshot.entries,
+        results.iter().map(|result| result.as_ref().map(|(c, _)| c)),
+    );
+
+    let rules = state.override_rules.read().await;
+    let files: Vec<AnalyzedFile> = snapshot
+        .entries
+        .into_iter()
+        .zip(results)
+        .filter_map(|(entry, result)| {
+            let (classification, cached) = result?;
+            Some(AnalyzedFile {
+                classification: rules.apply(Path::new(&entry.path), classification),
+                path: entry.path,
+                size: entry.size,
+                cached,
+            })
+        })
+        .collect();
+
+    info!(
+        "Analyzed session {}: {} cached, {} AI request(s)",
+        session_id, cached_files, run.ai_requests
+    );
+    operation.complete(analysis_tally(
+        files.len() as u64,
+        files.iter().map(|file| file.size).sum(),
+        run.errors,
+    ));
+
+    Ok(SessionAnalysis {
+        session_id,
+        files,
+        cached_files,
+        ai_requests: run.ai_requests,
+        batch_size: run.batch_size,
+    })
+}
+
+/// Classify a scan session reusing the results of an earlier analyzed scan
+/// of the same root: files whose path, size and modification time are
+/// unchanged keep their previous classification, and only new or modified
+/// files go through the cache and the AI provider. The earlier session is
+/// `previous_session_id`, or else the newest older session of the root that
+/// was analyzed in the current response language.
+#[command]
+pub async fn analyze_incremental<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, AppState>,
+    session_id: String,
+    previous_session_id: Option<String>,
+    overrides: Option<RunOverrides>,
+) -> AppResult<IncrementalAnalysis> {
+    timed_command("analyze_incremental", Some(state.inner()), None, async {
+        let mut config = state.get_config().await;
+        config.analysis = overrides
+            .unwrap_or_default()
+            .apply(&config.analysis)
+            .map_err(|e| AppError::ConfigError(e.to_string()))?;
+        let store = SnapshotStore::new(&config.cache_directory);
+        let snapshot = store
+            .load(&session_id)
+            .map_err(|e| AppError::FileSystemError(e.to_string()))?;
+        let language = config.analysis.response_language.as_str();
+        let previous = previous_analysis(&store, &snapshot, previous_session_id, language)?;
+        let mut operation = state.operations.start(OperationKind::Analyze, &snapshot.root);
+
+        let use_cache = config.analysis.enable_caching;
+        let ttl_seconds = config.analysis.cache_ttl_seconds;
+        let facts: Vec<FileFacts> = snapshot.entries.iter().map(|entry| entry.facts()).collect();
+        let mut results: Vec<Option<(Classification, ResultOrigin)>> = snapshot
+            .entries
+            .iter()
+            .zip(&facts)
+            .map(|(entry, facts)| {
+                let reused = previous.as_ref().and_then(|previous| {
+                    previous
+                        .entries
+                        .get(&entry.path)
+                        .filter(|old| entry.unchanged_from(old))?;
+                    previous.results.classifications.get(&entry.path).cloned()
+                });
+                reused
+                    .map(|classification| (classification, ResultOrigin::Reused))
+                    .or_else(|| {
+                        use_cache
+                            .then(|| state.analysis_cache.get(facts, ttl_seconds, language))
+                            .flatten()
+                            .map(|classification| (classification, ResultOrigin::Cached))
+                    })
+            })
+            .collect();
+        let pending: Vec<usize> = (0..facts.len()).filter(|&i| results[i].is_none()).collect();
+        let count = |origin| {
+            results
+                .iter()
+                .flatten()
+                .filter(|(_, o)| *o == origin)
+                .count() as u64
+        };
+        let reused_files = count(ResultOrigin::Reused);
+        let cached_files = count(ResultOrigin::Cached);
+        if use_cache {
+            state.metrics.record_cache_lookups(cached_files as usize, pending.len());
+        }
+
+        let batch: Vec<FileFacts> = pending.iter().map(|&i| facts[i].clone()).collect();
+        let run = classify_in_batches(
+            &progress_emitter(&app),
+            &state,
+            &config,
+            &session_id,
+            &batch,
+            (reused_files + cached_files, facts.len() as u64),
+            &operation,
+        )
+        .await?;
+        for ((&i, facts), classification) in pending.iter().zip(&batch).zip(run.classifications) {
+            if use_cache && classification.source == ClassificationSource::Ai {
+                state.analysis_cache.insert(facts, &classification, language);
+            }
+            results[i] = Some((classification, ResultOrigin::Analyzed));
+        }
+        save_results(
+            &config,
+            &session_id,
+            &snapshot.entries,
+            results.iter().map(|result| result.as_ref().map(|(c, _)| c)),
+        );
+
+        let rules = state.override_rules.read().await;
+        let files: Vec<IncrementalFile> = snapshot
+            .entries
+            .into_iter()
+            .zip(results)
+            .filter_map(|(entry, result)| {
+                let (classification, origin) = result?;
+                Some(IncrementalFile {
+                    classification: rules.apply(Path::new(&entry.path), classification),
+                    path: entry.path,
+                    size: entry.size,
+                    origin,
+                })
+            })
+            .collect();
+
+        let analyzed_files = pending.len() as u64;
+        info!(
+            "Incrementally analyzed session {}: {} reused, {} cached, {} analyzed in {} AI \
+             request(s)",
+            session_id, reused_files, cached_files, analyzed_files, run.ai_requests
+        );
+        operation.complete(analysis_tally(
+            files.len() as u64,
+            files.iter().map(|file| file.size).sum(),
+            run.errors,
+        ));
+
+        Ok(IncrementalAnalysis {
+            session_id,
+            previous_session_id: previous.map(|previous| previous.session_id),
+            files,
+            reused_files,
+            cached_files,
+            analyzed_files,
+            ai_requests: run.ai_requests,
+            batch_size: run.batch_size,
+        })
+    })
+    .await
+}
+
+/// Re-submit the files of a session whose current classification (cached or
+/// heuristic) is below `threshold`, by default the high-confidence
+/// threshold, optionally to a different provider or model. A new verdict is
+/// kept, and cached, only when it is more confident than the old one. Files
+/// decided by a user override rule are left alone.
+#[command]
+pub async fn reanalyze_low_confidence<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, AppState>,
+    session_id: String,
+    threshold: Option<f32>,
+    provider: Option<AiOverride>,
+    overrides: Option<RunOverrides>,
+) -> AppResult<Reanalysis> {
+    timed_command("reanalyze_low_confidence", Some(state.inner()), None, async {
+        let mut config = state.get_config().await;
+        config.analysis = overrides
+            .unwrap_or_default()
+            .apply(&config.analysis)
+            .map_err(|e| AppError::ConfigError(e.to_string()))?;
+        let threshold = threshold.unwrap_or(config.analysis.high_confidence_threshold);
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err(AppError::ConfigError(
+                "Confidence threshold must be between 0 and 1".to_string(),
+            ));
+        }
+        let snapshot = SnapshotStore::new(&config.cache_directory)
+            .load(&session_id)
+            .map_err(|e| AppError::FileSystemError(e.to_string()))?;
+
+        let rules = state.override_rules.read().await.clone();
+        let (entries, previous): (Vec<SnapshotEntry>, Vec<Classification>) = snapshot
+            .entries
+            .into_iter()
+            .filter_map(|entry| {
+                let classification = rules.apply(
+                    Path::new(&entry.path),
+                    classifier::classify_offline(&config, &state.analysis_cache, &entry.facts()),
+                );
+                (classification.overridden_by.is_none() && classification.confidence < threshold)
+                    .then_some((entry, classification))
+            })
+            .unzip();
+
+        config.ai = provider.unwrap_or_default().apply(&config.ai);
+        let mut operation = state.operations.start(OperationKind::Analyze, &snapshot.root);
+        let batch: Vec<FileFacts> = entries.iter().map(|entry| entry.facts()).collect();
+        let total = batch.len() as u64;
+        let run = classify_in_batches(
+            &progress_emitter(&app),
+            &state,
+            &config,
+            &session_id,
+            &batch,
+            (0, total),
+            &operation,
+        )
+        .await?;
+
+        let use_cache = config.analysis.enable_caching;
+        let mut improved = 0;
+        let files: Vec<ReanalyzedFile> = entries
+            .into_iter()
+            .zip(&batch)
+            .zip(previous.into_iter().zip(run.classifications))
+            .map(|((entry, facts), (previous, retried))| {
+                let better = retried.source == ClassificationSource::Ai
+                    && retried.confidence > previous.confidence;
+                let classification = if better {
+                    improved += 1;
+                    if use_cache {
+                        state
+                            .analysis_cache
+                            .insert(facts, &retried, &config.analysis.response_language);
+                    }
+                    retried
+                } else {
+                    previous.clone()
+                };
+                ReanalyzedFile {
+                    path: entry.path,
+                    size: entry.size,
+                    previous,
+                    classification,
+                    improved: better,
+                }
+            })
+            .collect();
+
+        // Keep saved results in step so incremental runs reuse the better verdicts
+        let store = SnapshotStore::new(&config.cache_directory);
+        if let Ok(Some(mut results)) = store.load_results(&session_id) {
+            if improved > 0 && results.language == config.analysis.response_language {
+                for file in files.iter().filter(|file| file.improved) {
+                    results
+                        .classifications
+                        .insert(file.path.clone(), file.classification.clone());
+                }
+                if let Err(e) = store.save_results(&session_id, &results) {
+                    warn!("Failed to save analysis results of session {}: {}", session_id, e);
+                }
+            }
+        }
+
+        info!(
+            "Re-analyzed {} low-confidence file(s) of session {} with {}/{}: {} improved",
+            total, session_id, config.ai.provider, config.ai.model, improved
+        );
+        operation.complete(analysis_tally(
+            total,
+            files.iter().map(|file| file.size).sum(),
+            run.errors,
+        ));
+
+        Ok(Reanalysis {
+            session_id,
+            threshold,
+            provider: config.ai.provider,
+            model: config.ai.model,
+            candidates: total,
+            improved,
+            ai_requests: run.ai_requests,
+            files,
+        })
+    })
+    .await
+}
+
+/// Tally of an analysis over `files` files of `bytes` in total, where the
+/// files in `errors` fell back to heuristics
+fn analysis_tally(files: u64, bytes: u64, errors: Vec<ItemError>) -> OperationTally {
+    let failed = errors.len() as u64;
+    OperationTally {
+        succeeded: files.saturating_sub(failed),
+        failed,
+        bytes,
+        errors,
+    }
+}
+
+/// An analyzed earlier session whose results an incremental run reuses
+struct PreviousAnalysis {
+    session_id: String,
+    entries: HashMap<String, SnapshotEntry>,
+    results: SessionResults,
+}
+
+/// The session to reuse results from: `requested` if given, which must have
+/// been analyzed in `language`, otherwise the newest such session of the
+/// same root scanned before `snapshot`
+fn previous_analysis(
+    store: &SnapshotStore,
+    snapshot: &ScanSnapshot,
+    requested: Option<String>,
+    language: &str,
+) -> AppResult<Option<PreviousAnalysis>> {
+    let load = |id: &str| {
+        let results = store
+            .load_results(id)
+            .map_err(|e| AppError::FileSystemError(e.to_string()))?
+            .filter(|results| results.language == language);
+        let Some(results) = results else {
+            return Ok(None);
+        };
+        let entries = store
+            .load(id)
+            .map_err(|e| AppError::FileSystemError(e.to_string()))?
+            .entries
+            .into_iter()
+            .map(|entry| (entry.path.clone(), entry))
+            .collect();
+        Ok::<_, AppError>(Some(PreviousAnalysis {
+            session_id: id.to_string(),
+            entries,
+            results,
+        }))
+    };
+
+    if let Some(id) = requested {
+        return match load(&id)? {
+            Some(previous) => Ok(Some(previous)),
+            None => Err(AppError::ConfigError(format!(
+                "Session {} has no saved analysis in language '{}'",
+                id, language
+            ))),
+        };
+    }
+
+    for summary in store.list_summaries() {
+        if summary.root != snapshot.root || summary.created_at >= snapshot.created_at {
+            continue;
+        }
+        match load(&summary.id) {
+            Ok(Some(previous)) => return Ok(Some(previous)),
+            Ok(None) => {}
+            Err(e) => warn!("Ignoring analysis of session {}: {}", summary.id, e),
+        }
+    }
+    Ok(None)
+}
+
+/// Persist a session's classifications, before override rules, so a later
+/// incremental run can reuse them. Failure only costs that reuse.
+fn save_results<'a>(
+    config: &AppConfig,
+    session_id: &str,
+    entries: &[SnapshotEntry],
+    classifications: impl Iterator<Item = Option<&'a Classification>>,
+) {
+    let results = SessionResults {
+        language: config.analysis.response_language.clone(),
+        classifications: entries
+            .iter()
+            .zip(classifications)
+            .filter_map(|(entry, classification)| {
+                Some((entry.path.clone(), classification?.clone()))
+            })
+            .collect(),
+    };
+    if let Err(e) = SnapshotStore::new(&config.cache_directory).save_results(session_id, &results) {
+        warn!("Failed to save analysis results of session {}: {}", session_id, e);
+    }
+}
+
+/// Forwards progress to the frontend as `ANALYSIS_PROGRESS_EVENT`
+fn progress_emitter<R: Runtime>(
+    app: &AppHandle<R>,
+) -> impl Fn(AnalysisProgress) + Send + Sync + '_ {
+    move |progress| {
+        if let Err(e) = app.emit(ANALYSIS_PROGRESS_EVENT, progress) {
+            warn!("Failed to emit {} event: {}", ANALYSIS_PROGRESS_EVENT, e);
+        }
+    }
+}
+
+/// Send `facts` to the provider in batches sized by a `BatchTuner`, passing
+/// progress to `on_progress` after each batch. `(offset, total)` places these
+/// files within the whole run for the progress figures. Classifications are
+/// returned in the order of `facts`.
+async fn classify_in_batches(
+    on_progress: &ProgressSink<'_>,
+    state: &AppState,
+    config: &AppConfig,
+    session_id: &str,
+    facts: &[FileFacts],
+    (offset, total): (u64, u64),
+    operation: &OperationGuard,
+) -> AppResult<BatchRun> {
+    let mut tuner = BatchTuner::new(&config.analysis);
+    let mut classifications = Vec::with_capacity(facts.len());
+    let mut ai_requests = 0;
+    let mut errors = Vec::new();
+    while classifications.len() < facts.len() {
+        if operation.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+
+        let done = classifications.len();
+        let batch = &facts[done..(done + tuner.size()).min(facts.len())];
+        let outcome = classifier::classify_batch(config, &state.rate_limiter, batch).await;
+        let sent = !outcome.latency.is_zero()
+            && !matches!(outcome.error, Some(AiError::MissingApiKey(_)));
+        if sent {
+            ai_requests += 1;
+            state.metrics.record_ai_request(outcome.latency, outcome.error.is_some());
+        }
+        tuner.record(
+            batch.len(),
+            outcome.latency,
+            outcome.total_tokens,
+            matches!(outcome.error, Some(AiError::Timeout(_))),
+        );
+        // Without an API key heuristics are expected, not a failure
+        let failed = outcome
+            .error
+            .as_ref()
+            .filter(|error| !matches!(error, AiError::MissingApiKey(_)));
+        if let Some(error) = failed {
+            errors.extend(batch.iter().map(|facts| ItemError {
+                path: facts.path.clone(),
+                code: ErrorCode::AiUnavailable,
+                message: error.to_string(),
+            }));
+        }
+        errors.extend(outcome.invalid_replies.into_iter().map(|(index, problem)| ItemError {
+            path: batch[index].path.clone(),
+            code: ErrorCode::AiUnavailable,
+            message: problem,
+        }));
+        classifications.extend(outcome.classifications);
+
+        let analyzed = offset + classifications.len() as u64;
+        let eta_seconds = operation.report_progress(analyzed, total);
+        let progress = AnalysisProgress {
+            session_id: session_id.to_string(),
+            analyzed,
+            total,
+            batch_size: tuner.size(),
+            eta_seconds,
+        };
+        on_progress(progress);
+    }
+
+    Ok(BatchRun {
+        classifications,
+        ai_requests,
+        batch_size: tuner.size(),
+        errors,
+    })
+}
+
+/// Aggregate a scan session's files by category, largest reclaimable
+/// categories first. Files the session's analysis classified keep that
+/// verdict; only files it never reached are classified offline.
+#[command]
+pub async fn summarize_analysis(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> AppResult<AnalysisSummary> {
+    timed_command("summarize_analysis", Some(state.inner()), None, async {
+        let config = state.get_config().await;
+        let store = SnapshotStore::new(&config.cache_directory);
+        let snapshot = store
+            .load(&session_id)
+            .map_err(|e| AppError::FileSystemError(e.to_string()))?;
+        let saved = store
+            .load_results(&session_id)
+            .map_err(|e| AppError::FileSystemError(e.to_string()))?
+            .unwrap_or_default();
+        let cache = state.analysis_cache.clone();
+        let rules = state.override_rules.read().await.clone();
+        let threshold = config.analysis.high_confidence_threshold;
+
+        let mut categories = tokio::task::spawn_blocking(move || {
+            category_totals(&snapshot.entries, &saved, &config, &cache, &rules)
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))?;
+
+        let (reclaimable_bytes, high_confidence_reclaimable_bytes) =
+            rank_categories(&mut categories);
+
+        Ok(AnalysisSummary {
+            session_id,
+            high_confidence_threshold: threshold,
+            categories,
+            reclaimable_bytes,
+            high_confidence_reclaimable_bytes,
+        })
+    })
+    .await
+}
+
+/// Totals per category of `entries`, taking each file's classification from
+/// `saved` where it has one and from `classify_offline` otherwise, with
+/// override rules applied to both
+fn category_totals(
+    entries: &[SnapshotEntry],
+    saved: &SessionResults,
+    config: &AppConfig,
+    cache: &AnalysisCache,
+    rules: &OverrideRules,
+) -> Vec<CategoryTotals> {
+    let threshold = config.analysis.high_confidence_threshold;
+    let mut by_category: HashMap<FileCategory, CategoryTotals> = HashMap::new();
+    for entry in entries {
+        let classification = match saved.classifications.get(&entry.path) {
+            Some(classification) => classification.clone(),
+            None => classifier::classify_offline(config, cache, &entry.facts()),
+        };
+        let classification = rules.apply(Path::new(&entry.path), classification);
+        by_category
+            .entry(classification.category)
+            .or_insert_with(|| CategoryTotals::new(classification.category))
+            .add(entry.size, classification.confidence, threshold);
+    }
+    by_category.into_values().collect()
+}
+
+/// Merge several scan sessions into one category breakdown with per-root
+/// subtotals. A file listed by more than one session (nested or repeated
+/// selections) is counted once, under the most specific root containing it.
+#[command]
+pub async fn summarize_roots(
+    state: State<'_, AppState>,
+    session_ids: Vec<String>,
+) -> AppResult<MultiRootSummary> {
+    timed_command("summarize_roots", Some(state.inner()), None, async {
+        if session_ids.is_empty() {
+            return Err(AppError::FileSystemError("No scan sessions selected".to_string()));
+        }
+
+        let config = state.get_config().await;
+        let store = SnapshotStore::new(&config.cache_directory);
+        let snapshots = session_ids
+            .iter()
+            .map(|id| store.load(id))
+            .collect::<anyhow::Result<Vec<_>>>()
+            .map_err(|e| AppError::FileSystemError(e.to_string()))?;
+        let cache = state.analysis_cache.clone();
+        let rules = state.override_rules.read().await.clone();
+        let threshold = config.analysis.high_confidence_threshold;
+
+        let (mut categories, roots, overlapping_files) = tokio::task::spawn_blocking(move || {
+            merge_roots(&snapshots, &config, &cache, &rules)
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))?;
+
+        let (reclaimable_bytes, high_confidence_reclaimable_bytes) =
+            rank_categories(&mut categories);
+
+        Ok(MultiRootSummary {
+            high_confidence_threshold: threshold,
+            categories,
+            roots,
+            reclaimable_bytes,
+            high_confidence_reclaimable_bytes,
+            overlapping_files,
+        })
+    })
+    .await
+}
+
+/// Category totals, per-root subtotals and the number of files skipped as
+/// already counted, for `summarize_roots`. Roots are resolved first, so a
+/// file reached through a symlinked root is still recognised as the same
+/// file.
+fn merge_roots(
+    snapshots: &[ScanSnapshot],
+    config: &AppConfig,
+    cache: &AnalysisCache,
+    rules: &OverrideRules,
+) -> (Vec<CategoryTotals>, Vec<RootSubtotal>, u64) {
+    let threshold = config.analysis.high_confidence_threshold;
+    let roots: Vec<PathBuf> = snapshots
+        .iter()
+        .map(|snapshot| {
+            let root = PathBuf::from(&snapshot.root);
+            std::fs::canonicalize(&root).unwrap_or(root)
+        })
+        .collect();
+
+    // Deepest roots first so nested selections claim their own files;
+    // the stable sort keeps the caller's order between equal depths
+    let mut order: Vec<usize> = (0..snapshots.len()).collect();
+    order.sort_by_key(|&i| Reverse(roots[i].components().count()));
+
+    let mut seen: HashSet<PathBuf> = HashSet::new();
+    let mut overlapping_files = 0;
+    let mut by_category: HashMap<FileCategory, CategoryTotals> = HashMap::new();
+    let mut subtotals: Vec<Option<RootSubtotal>> = snapshots.iter().map(|_| None).collect();
+
+    for i in order {
+        let snapshot = &snapshots[i];
+        let mut subtotal = RootSubtotal {
+            session_id: snapshot.id.clone(),
+            root: snapshot.root.clone(),
+            file_count: 0,
+            total_bytes: 0,
+            reclaimable_bytes: 0,
+        };
+
+        for entry in &snapshot.entries {
+            let path = Path::new(&entry.path);
+            let resolved = match path.strip_prefix(&snapshot.root) {
+                Ok(relative) => roots[i].join(relative),
+                Err(_) => path.to_path_buf(),
+            };
+            if !seen.insert(resolved) {
+                overlapping_files += 1;
+                continue;
+            }
+
+            let classification = rules.apply(
+                path,
+                classifier::classify_offline(config, cache, &entry.facts()),
+            );
+            by_category
+                .entry(classification.category)
+                .or_insert_with(|| CategoryTotals::new(classification.category))
+                .add(entry.size, classification.confidence, threshold);
+
+            subtotal.file_count += 1;
+            subtotal.total_bytes += entry.size;
+            if classification.category.is_reclaimable() {
+                subtotal.reclaimable_bytes += entry.size;
+            }
+        }
+        subtotals[i] = Some(subtotal);
+    }
+
+    (
+        by_category.into_values().collect(),
+        subtotals.into_iter().flatten().collect(),
+        overlapping_files,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, size: u64) -> SnapshotEntry {
+        SnapshotEntry {
+            path: path.to_string(),
+            size,
+            modified: None,
+        }
+    }
+
+    fn saved(category: FileCategory, confidence: f32) -> Classification {
+        Classification {
+            category,
+            confidence,
+            reason: "saved".to_string(),
+            source: ClassificationSource::Ai,
+            overridden_by: None,
+        }
+    }
+
+    #[test]
+    fn summary_uses_the_saved_analysis_and_classifies_only_the_rest() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let cache = AnalysisCache::load(cache_dir.path());
+        let config = AppConfig::default();
+        let entries = vec![
+            entry("/data/notes.tmp", 100),
+            entry("/data/report.log", 20),
+            entry("/data/unreached.log", 5),
+        ];
+        let results = SessionResults {
+            language: config.analysis.response_language.clone(),
+            classifications: HashMap::from([
+                ("/data/notes.tmp".to_string(), saved(FileCategory::Document, 0.99)),
+                ("/data/report.log".to_string(), saved(FileCategory::Document, 0.1)),
+            ]),
+        };
+
+        let totals =
+            category_totals(&entries, &results, &config, &cache, &OverrideRules::default());
+
+        let documents = totals
+            .iter()
+            .find(|totals| totals.category == FileCategory::Document)
+            .unwrap();
+        assert_eq!(documents.file_count, 2);
+        assert_eq!(documents.total_bytes, 120);
+        assert_eq!(documents.high_confidence_bytes, 100);
+        assert_eq!(documents.low_confidence_bytes, 20);
+
+        let offline = classifier::classify_offline(&config, &cache, &entries[2].facts());
+        let rest = totals
+            .iter()
+            .find(|totals| totals.category == offline.category)
+            .unwrap();
+        assert_eq!(rest.file_count, 1);
+        assert_eq!(rest.total_bytes, 5);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sessions_overlapping_through_a_symlinked_root_count_files_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = dir.path().join("data");
+        std::fs::create_dir_all(data.join("sub")).unwrap();
+        let link = dir.path().join("link");
+        std::os::unix::fs::symlink(&data, &link).unwrap();
+        let cache = AnalysisCache::load(dir.path());
+        let config = AppConfig::default();
+
+        let session = |root: &Path, files: &[&str]| {
+            let entries = files
+                .iter()
+                .map(|file| entry(&root.join(file).to_string_lossy(), 10))
+                .collect();
+            ScanSnapshot::new(&root.to_string_lossy(), entries)
+        };
+        let snapshots = vec![
+            session(&data, &["sub/a.log", "b.log"]),
+            session(&link.join("sub"), &["a.log"]),
+        ];
+
+        let (categories, roots, overlapping_files) =
+            merge_roots(&snapshots, &config, &cache, &OverrideRules::default());
+
+        assert_eq!(overlapping_files, 1);
+        assert_eq!(categories.iter().map(|totals| totals.file_count).sum::<u64>(), 2);
+        let nested = roots.iter().find(|root| root.session_id == snapshots[1].id).unwrap();
+        assert_eq!(nested.file_count, 1);
+        let outer = roots.iter().find(|root| root.session_id == snapshots[0].id).unwrap();
+        assert_eq!(outer.file_count, 1);
+    }
+}
@@ -0,0 +1,310 @@
+use crate::utils::backups;
+use crate::utils::config::SecurityConfig;
+use crate::utils::logging::timed_command;
+use crate::utils::operations::OperationKind;
+use crate::utils::paths;
+use crate::utils::security::SecurityValidator;
+use crate::utils::snapshots::SnapshotStore;
+use crate::utils::walker::{directory_size, WalkOptions};
+use crate::{AppError, AppResult, AppState};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tauri::{command, State};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// Something the app itself keeps on disk
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AppDataKind {
+    AnalysisCache,
+    SizeCache,
+    /// File hashes kept across duplicate scans
+    HashIndex,
+    /// Backup sessions beyond `security.backup_retention`
+    Backups,
+    /// Scan sessions beyond `analysis.snapshot_retention` or whose root is gone
+    Sessions,
+    /// Contents of the app's own folder inside `temp_directory`
+    Temp,
+}
+
+const ALL_KINDS: &[AppDataKind] = &[
+    AppDataKind::AnalysisCache,
+    AppDataKind::SizeCache,
+    AppDataKind::HashIndex,
+    AppDataKind::Backups,
+    AppDataKind::Sessions,
+    AppDataKind::Temp,
+];
+
+/// Disk space taken by one kind of app data
+#[derive(Debug, Serialize)]
+pub struct AppDataUsage {
+    pub kind: AppDataKind,
+    pub path: String,
+    pub bytes: u64,
+    /// What clearing would free; less than `bytes` when some data is kept
+    pub clearable_bytes: u64,
+    pub bytes_freed: u64,
+    /// Why some or all of the data is kept
+    pub kept_reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppDataReport {
+    pub usage: Vec<AppDataUsage>,
+    pub total_bytes: u64,
+    pub clearable_bytes: u64,
+    pub bytes_freed: u64,
+    /// False when only reporting
+    pub cleared: bool,
+}
+
+/// Report the disk space of the app's caches, backups, scan sessions and
+/// temp directory, and with `clear` free what is safe to free of the
+/// `kinds` given (all by default).
+///
+/// The scan session the app has loaded and the newest
+/// `security.backup_retention` backup sessions are always kept. Backups are
+/// left alone while a delete is running, since it may be writing one, and
+/// sessions and temp files while any scan or analysis is. Only the app's
+/// own folder inside `temp_directory` is cleared, never the directory itself.
+#[command]
+pub async fn clear_app_data(
+    state: State<'_, AppState>,
+    kinds: Option<Vec<AppDataKind>>,
+    clear: Option<bool>,
+) -> AppResult<AppDataReport> {
+    timed_command("clear_app_data", Some(state.inner()), None, async {
+        let kinds = kinds.unwrap_or_else(|| ALL_KINDS.to_vec());
+        let clear = clear.unwrap_or(false);
+        let config = state.get_config().await;
+        let running: Vec<OperationKind> = state
+            .operations
+            .list()
+            .into_iter()
+            .map(|operation| operation.kind)
+            .collect();
+        let deleting = running.contains(&OperationKind::Delete);
+        let scanning = running
+            .iter()
+            .any(|kind| matches!(kind, OperationKind::Scan | OperationKind::Analyze));
+
+        let mut usage = Vec::new();
+        for kind in kinds {
+            let entry = match kind {
+                AppDataKind::AnalysisCache => {
+                    let cache = &state.analysis_cache;
+                    cache_usage(kind, cache.path(), clear, || cache.clear())
+                }
+                AppDataKind::SizeCache => {
+                    let cache = &state.dir_size_cache;
+                    cache_usage(kind, cache.path(), clear, || cache.clear())
+                }
+                AppDataKind::HashIndex => {
+                    let index = &state.hash_index;
+                    cache_usage(kind, index.path(), clear, || index.clear())
+                }
+                AppDataKind::Backups => {
+                    let retention = config.security.backup_retention.max(1);
+                    let root = paths::backup_dir();
+                    tokio::task::spawn_blocking(move || {
+                        backup_usage(&root, retention, clear && !deleting, deleting)
+                    })
+                    .await
+                    .map_err(|e| AppError::SystemError(e.to_string()))??
+                }
+                AppDataKind::Sessions => {
+                    let retention = config.analysis.snapshot_retention.max(1);
+                    let store = SnapshotStore::new(&config.cache_directory);
+                    let active = state.active_session().await;
+                    tokio::task::spawn_blocking(move || {
+                        session_usage(&store, retention, active, clear && !scanning, scanning)
+                    })
+                    .await
+                    .map_err(|e| AppError::SystemError(e.to_string()))?
+                }
+                AppDataKind::Temp => {
+                    let dir = paths::app_temp_dir(&config.temp_directory);
+                    let security = config.security.clone();
+                    tokio::task::spawn_blocking(move || {
+                        temp_usage(&dir, &security, clear && !scanning, scanning)
+                    })
+                    .await
+                    .map_err(|e| AppError::SystemError(e.to_string()))?
+                }
+            };
+            usage.push(entry);
+        }
+
+        let report = AppDataReport {
+            total_bytes: usage.iter().map(|u| u.bytes).sum(),
+            clearable_bytes: usage.iter().map(|u| u.clearable_bytes).sum(),
+            bytes_freed: usage.iter().map(|u| u.bytes_freed).sum(),
+            usage,
+            cleared: clear,
+        };
+        if clear {
+            info!("Cleared {} bytes of app data", report.bytes_freed);
+        }
+        Ok(report)
+    })
+    .await
+}
+
+/// Usage of a single-file cache, cleared with `clear_cache`
+fn cache_usage(
+    kind: AppDataKind,
+    path: &Path,
+    clear: bool,
+    clear_cache: impl FnOnce() -> std::io::Result<u64>,
+) -> AppDataUsage {
+    let bytes = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let mut usage = AppDataUsage {
+        kind,
+        path: path.to_string_lossy().to_string(),
+        bytes,
+        clearable_bytes: bytes,
+        bytes_freed: 0,
+        kept_reason: None,
+    };
+    if clear {
+        match clear_cache() {
+            Ok(freed) => usage.bytes_freed = freed,
+            Err(e) => {
+                warn!("Failed to clear {}: {}", path.display(), e);
+                usage.kept_reason = Some(format!("Could not be removed: {}", e));
+            }
+        }
+    }
+    usage
+}
+
+fn size_of(path: &Path) -> u64 {
+    directory_size(path, WalkOptions::default(), &CancellationToken::new()).allocated
+}
+
+fn backup_usage(
+    root: &Path,
+    retention: usize,
+    clear: bool,
+    deleting: bool,
+) -> AppResult<AppDataUsage> {
+    let sessions = backups::sessions(root)?;
+    let excess = sessions.len().saturating_sub(retention);
+    let mut usage = AppDataUsage {
+        kind: AppDataKind::Backups,
+        path: root.to_string_lossy().to_string(),
+        bytes: sessions.iter().map(|session| size_of(session)).sum(),
+        clearable_bytes: 0,
+        bytes_freed: 0,
+        kept_reason: (excess < sessions.len())
+            .then(|| format!("The newest {} backup sessions are kept", retention)),
+    };
+    if deleting {
+        usage.kept_reason = Some("A delete in progress may be writing a backup".to_string());
+        return Ok(usage);
+    }
+
+    for session in &sessions[..excess] {
+        let size = size_of(session);
+        usage.clearable_bytes += size;
+        if !clear {
+            continue;
+        }
+        match fs::remove_dir_all(session) {
+            Ok(()) => usage.bytes_freed += size,
+            Err(e) => warn!("Failed to remove backup session {}: {}", session.display(), e),
+        }
+    }
+    Ok(usage)
+}
+
+fn session_usage(
+    store: &SnapshotStore,
+    retention: usize,
+    active: Option<String>,
+    clear: bool,
+    scanning: bool,
+) -> AppDataUsage {
+    // Newest first
+    let summaries = store.list_summaries();
+    let mut usage = AppDataUsage {
+        kind: AppDataKind::Sessions,
+        path: store.dir().to_string_lossy().to_string(),
+        bytes: summaries
+            .iter()
+            .map(|summary| store.size_on_disk(&summary.id))
+            .sum(),
+        clearable_bytes: 0,
+        bytes_freed: 0,
+        kept_reason: active.as_ref().map(|_| "The loaded scan session is kept".to_string()),
+    };
+    if scanning {
+        usage.kept_reason = Some("A scan or analysis is using its session".to_string());
+        return usage;
+    }
+
+    for (index, summary) in summaries.iter().enumerate() {
+        if active.as_ref() == Some(&summary.id)
+            || (index < retention && Path::new(&summary.root).exists())
+        {
+            continue;
+        }
+        let size = store.size_on_disk(&summary.id);
+        usage.clearable_bytes += size;
+        if !clear {
+            continue;
+        }
+        match store.remove(&summary.id) {
+            Ok(freed) => usage.bytes_freed += freed,
+            Err(e) => warn!("Failed to remove scan session {}: {}", summary.id, e),
+        }
+    }
+    usage
+}
+
+fn temp_usage(
+    dir: &Path,
+    security: &SecurityConfig,
+    clear: bool,
+    scanning: bool,
+) -> AppDataUsage {
+    let bytes = size_of(dir);
+    let mut usage = AppDataUsage {
+        kind: AppDataKind::Temp,
+        path: dir.to_string_lossy().to_string(),
+        bytes,
+        clearable_bytes: bytes,
+        bytes_freed: 0,
+        kept_reason: None,
+    };
+    if scanning {
+        usage.clearable_bytes = 0;
+        usage.kept_reason = Some("A scan or analysis may be using temporary files".to_string());
+        return usage;
+    }
+    if !clear {
+        return usage;
+    }
+
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if let Err(reason) = SecurityValidator::validate_deletion_target(&path, security) {
+            warn!("Keeping temporary file {}: {}", path.display(), reason);
+            continue;
+        }
+        let size = size_of(&path).max(entry.metadata().map(|m| m.len()).unwrap_or(0));
+        let removed = match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => fs::remove_dir_all(&path),
+            _ => fs::remove_file(&path),
+        };
+        match removed {
+            Ok(()) => usage.bytes_freed += size,
+            Err(e) => warn!("Failed to remove temporary file {}: {}", path.display(), e),
+        }
+    }
+    usage
+}
@@ -0,0 +1,163 @@
+use crate::utils::archive::{self, ArchiveFormat};
+use crate::utils::audit::{AuditAction, AuditEntry};
+use crate::commands::cleanup::{plan_delete, DeletePlan};
+use crate::utils::delete_tokens::DeleteMode;
+use crate::utils::logging::timed_command;
+use crate::utils::operations::{OperationKind, OperationTally};
+use crate::utils::security::{validate_output_path, validate_scan_root};
+use crate::{AppError, AppResult, AppState};
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use tauri::{command, State};
+use tracing::{info, warn};
+
+#[derive(Debug, Serialize)]
+pub struct ArchiveResult {
+    pub destination: String,
+    pub format: ArchiveFormat,
+    pub file_count: u64,
+    pub original_size: u64,
+    /// Size of the finished archive
+    pub compressed_size: u64,
+    /// With `remove_originals`, the plan for deleting the archived files;
+    /// they are only removed once its token is passed to `confirm_delete`
+    pub deletion_plan: Option<DeletePlan>,
+}
+
+/// Compress `paths` into a new `.zip` or `.tar.zst` at `destination`, read it
+/// back to verify every file, and only then, with `remove_originals`, plan
+/// deleting the archived files. Folders in the selection are archived with
+/// everything below them and left in place, emptied of the archived files
+/// once the plan is confirmed. `format` defaults to the one named by the destination's
+/// extension.
+#[command]
+pub async fn archive_files(
+    state: State<'_, AppState>,
+    paths: Vec<String>,
+    destination: String,
+    format: Option<ArchiveFormat>,
+    remove_originals: Option<bool>,
+) -> AppResult<ArchiveResult> {
+    timed_command("archive_files", Some(state.inner()), Some(destination.clone()), async {
+        let destination_path = validate_output_path(&destination)?;
+        if destination_path.exists() {
+            return Err(AppError::FileSystemError(format!(
+                "{} already exists",
+                destination_path.display()
+            )));
+        }
+        let format = format
+            .or_else(|| ArchiveFormat::from_path(&destination_path))
+            .ok_or_else(|| {
+                AppError::ConfigError(
+                    "Choose an archive format or end the destination in .zip or .tar.zst"
+                        .to_string(),
+                )
+            })?;
+        if paths.is_empty() {
+            return Err(AppError::FileSystemError("No files to archive".to_string()));
+        }
+        let mut sources = Vec::with_capacity(paths.len());
+        for path in &paths {
+            let source = validate_scan_root(path)?;
+            if destination_path.starts_with(&source) {
+                return Err(AppError::SecurityError(format!(
+                    "The archive cannot be written inside {}, which is being archived",
+                    source.display()
+                )));
+            }
+            sources.push(source);
+        }
+
+        let remove_originals = remove_originals.unwrap_or(false);
+        if remove_originals {
+            state.require_scan_root().await?;
+        }
+        let mut operation = state.operations.start(OperationKind::Archive, &destination);
+        let cancel = operation.token();
+
+        let target = destination_path.clone();
+        let (archived, compressed_size) = tokio::task::spawn_blocking(move || {
+            let members = archive::collect_members(&sources)?;
+            if members.is_empty() {
+                return Err(AppError::FileSystemError(
+                    "The selection holds no regular files".to_string(),
+                ));
+            }
+
+            // Written under a temporary name so a failed or cancelled run
+            // never leaves something that looks like a finished archive
+            let mut partial = target.clone().into_os_string();
+            partial.push(".partial");
+            let partial = PathBuf::from(partial);
+            let written = archive::write_archive(&partial, format, &members, &cancel)
+                .and_then(|archived| {
+                    archive::verify_archive(&partial, format, &archived, &cancel)?;
+                    fs::rename(&partial, &target)?;
+                    Ok(archived)
+                });
+            let archived = match written {
+                Ok(archived) => archived,
+                Err(e) => {
+                    if let Err(remove) = fs::remove_file(&partial) {
+                        warn!("Failed to remove {}: {}", partial.display(), remove);
+                    }
+                    return Err(match e.kind() {
+                        io::ErrorKind::Interrupted => AppError::Cancelled,
+                        io::ErrorKind::InvalidData => AppError::FileSystemError(format!(
+                            "Archive failed verification, originals were kept: {}",
+                            e
+                        )),
+                        _ => AppError::IoError(e),
+                    });
+                }
+            };
+            let compressed_size = fs::metadata(&target)?.len();
+            Ok((archived, compressed_size))
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))??;
+
+        let original_size = archived.iter().map(|member| member.size).sum();
+        if state.get_config().await.security.enable_audit_trail {
+            for member in &archived {
+                let detail = Some(format!("Archived to {}", destination));
+                state.audit.record(AuditEntry::new(
+                    AuditAction::Archived,
+                    &member.source.to_string_lossy(),
+                    member.size,
+                    detail,
+                ));
+            }
+        }
+        let deletion_plan = if remove_originals {
+            let originals = archived
+                .iter()
+                .map(|member| member.source.to_string_lossy().to_string())
+                .collect();
+            Some(plan_delete(&state, originals, DeleteMode::default()).await?)
+        } else {
+            None
+        };
+
+        info!(
+            "Archived {} files ({} bytes) to {} ({} bytes)",
+            archived.len(),
+            original_size,
+            destination,
+            compressed_size
+        );
+        operation.complete(OperationTally::completed(archived.len() as u64, original_size));
+        Ok(ArchiveResult {
+            destination,
+            format,
+            file_count: archived.len() as u64,
+            original_size,
+            compressed_size,
+            deletion_plan,
+        })
+    })
+    .await
+}
@@ -0,0 +1,2130 @@
+use crate::utils::app_leftovers;
+use crate::utils::backups::{self, BackupVerification};
+use crate::utils::classifier::{is_broken_symlink, FileCategory};
+use crate::utils::crash_dumps;
+use crate::utils::device_backups::{self, DeviceBackupKind};
+use crate::utils::config::ConfirmationTrigger;
+use crate::utils::deletion::{
+    elevated_delete, elevation_required, purge_trash, safe_delete_with, transactional_delete,
+    tree_delete, wipe_warning, BackupProgress, DeletionReport, NO_ELEVATION_NEEDED_REASON,
+};
+use crate::utils::delete_tokens::{ApprovedDelete, DeleteBatch, DeleteMode, DeleteRequest};
+use crate::utils::free_space::{FreeSpaceProbe, Retention};
+use crate::utils::paths;
+use crate::utils::thumbnail_caches::{self, ThumbnailCacheLocation};
+use crate::utils::browser_caches::{self, BrowserCacheLocation};
+use crate::utils::config::AnalysisConfig;
+use crate::utils::dev_caches::{self, DevCacheLocation, OversizedCacheFile};
+use crate::utils::file_locks::{locking_processes_any, LockingProcess};
+use crate::utils::logging::timed_command;
+use crate::utils::operations::{OperationKind, OperationTally};
+use crate::utils::security::{validate_scan_root, SecurityValidator};
+use crate::utils::throughput::DurationEstimate;
+use crate::utils::trash_bin::{self, TrashEntry};
+use crate::utils::tree_deletion::{TreeDeleteMode, TreeDeleteProgress};
+use crate::utils::volume_locks::VolumeLockGuard;
+use crate::utils::walker::{allocated_size, DirWalker, WalkOptions};
+use crate::{AppError, AppResult, AppState};
+use chrono::{DateTime, Utc};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{command, AppHandle, Emitter, Runtime, State};
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+/// Event carrying `TreeDeleteProgress` while `delete_directory` runs
+pub const DELETE_PROGRESS_EVENT: &str = "delete-progress";
+/// Event carrying `BackupProgress` while a transactional batch is backed up
+pub const BACKUP_PROGRESS_EVENT: &str = "backup-progress";
+
+#[derive(Debug, Serialize)]
+pub struct BrokenSymlink {
+    pub link_path: String,
+    pub target: String,
+    pub is_directory_link: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BrokenSymlinkReport {
+    pub root: String,
+    pub broken_links: Vec<BrokenSymlink>,
+    pub unreadable_entries: u64,
+    /// With `delete`, the plan for removing them; nothing is deleted until
+    /// its token is passed to `confirm_delete`
+    pub deletion_plan: Option<DeletePlan>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmptyDirectory {
+    pub path: String,
+    /// Hidden or OS metadata files that were ignored when judging emptiness
+    pub ignored_files: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmptyDirectoryReport {
+    pub root: String,
+    /// Deepest directories first, the order they can be removed in
+    pub directories: Vec<EmptyDirectory>,
+    pub unreadable_entries: u64,
+    /// With `delete`, the plan for removing them; nothing is deleted until
+    /// its token is passed to `confirm_delete`
+    pub deletion_plan: Option<DeletePlan>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmptyFileReport {
+    pub root: String,
+    /// Zero-length regular files, in path order
+    pub files: Vec<String>,
+    /// Zero-byte files kept because they match `analysis.empty_file_markers`
+    pub markers_kept: u64,
+    pub unreadable_entries: u64,
+    /// With `delete`, the plan for removing them; nothing is deleted until
+    /// its token is passed to `confirm_delete`
+    pub deletion_plan: Option<DeletePlan>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DevCache {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+    pub file_count: u64,
+    pub category: FileCategory,
+    pub note: String,
+}
+
+/// A single oversized file in an app or browser cache, e.g. a multi-GB
+/// Electron cache blob that directory totals would hide
+#[derive(Debug, Serialize)]
+pub struct LargeCacheFile {
+    pub path: String,
+    pub size: u64,
+    /// Application the cache belongs to, when the path names one
+    pub app: Option<String>,
+    pub category: FileCategory,
+    pub confidence: f32,
+    pub note: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DevCacheReport {
+    pub caches: Vec<DevCache>,
+    pub total_size: u64,
+    /// Files of at least `analysis.large_cache_file_bytes` in cache folders
+    /// outside the caches listed above, largest first
+    pub large_files: Vec<LargeCacheFile>,
+    pub large_files_size: u64,
+}
+
+/// One cache folder of a browser profile
+#[derive(Debug, Serialize)]
+pub struct BrowserCache {
+    /// Profile folder name; `None` for browsers with one shared cache
+    pub profile: Option<String>,
+    pub path: String,
+    pub size: u64,
+    pub file_count: u64,
+}
+
+/// Caches of one browser across all its profiles
+#[derive(Debug, Serialize)]
+pub struct BrowserCacheGroup {
+    pub browser: String,
+    pub caches: Vec<BrowserCache>,
+    pub total_size: u64,
+    /// Processes holding cache files open; cleaning is refused until they exit
+    pub in_use_by: Vec<LockingProcess>,
+    pub category: FileCategory,
+    pub note: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BrowserCacheReport {
+    /// Largest first
+    pub browsers: Vec<BrowserCacheGroup>,
+    pub total_size: u64,
+}
+
+/// Confidence for a file in a crash folder or named like a dump
+const CRASH_DUMP_CONFIDENCE: f32 = 0.95;
+
+/// Confidence for thumbnails, which the file manager regenerates on demand
+const THUMBNAIL_CACHE_CONFIDENCE: f32 = 0.98;
+
+/// Confidence for a single large file found in a cache folder
+const LARGE_CACHE_FILE_CONFIDENCE: f32 = 0.9;
+
+/// Default age after which an app-data folder counts as unused
+const DEFAULT_LEFTOVER_MIN_AGE_DAYS: u64 = 180;
+/// Default age in months after which a device backup counts as stale
+const DEFAULT_DEVICE_BACKUP_STALE_MONTHS: u32 = 6;
+
+/// Leftover confidence when installed apps could be listed and none matched
+const LEFTOVER_CONFIDENCE: f32 = 0.5;
+/// Leftover confidence when installed apps could not be detected at all
+const LEFTOVER_CONFIDENCE_UNDETECTED: f32 = 0.3;
+
+/// An app-data folder that may belong to an uninstalled application
+#[derive(Debug, Serialize)]
+pub struct AppLeftover {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+    pub file_count: u64,
+    pub last_modified: Option<DateTime<Utc>>,
+    /// Always low to medium; this is a guess from names and dates
+    pub confidence: f32,
+    pub note: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppLeftoverReport {
+    pub leftovers: Vec<AppLeftover>,
+    pub total_size: u64,
+    pub min_age_days: u64,
+    /// Whether installed applications could be listed to rule folders out
+    pub installed_apps_detected: bool,
+}
+
+/// A backup of a phone or tablet made by iTunes, Finder or Smart Switch
+#[derive(Debug, Serialize)]
+pub struct DeviceBackup {
+    pub kind: DeviceBackupKind,
+    pub device_name: String,
+    pub path: String,
+    pub size: u64,
+    pub file_count: u64,
+    pub last_backup: Option<DateTime<Utc>>,
+    /// Not written for `stale_after_months`; a candidate for cleanup
+    pub stale: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeviceBackupReport {
+    /// Largest first
+    pub backups: Vec<DeviceBackup>,
+    pub total_size: u64,
+    pub stale_size: u64,
+    pub stale_after_months: u32,
+    pub category: FileCategory,
+    pub warning: String,
+}
+
+/// A crash dump, core file or crash report
+#[derive(Debug, Serialize)]
+pub struct CrashDump {
+    pub path: String,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+    pub category: FileCategory,
+    pub confidence: f32,
+    /// Why deleting needs administrator rights, e.g. a root-owned
+    /// `/var/crash`; such dumps are removed with `elevate`
+    pub requires_elevation: Option<String>,
+    /// Why the security settings refuse to delete the dump at all
+    pub blocked_reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CrashDumpReport {
+    /// Largest first
+    pub dumps: Vec<CrashDump>,
+    pub total_size: u64,
+    /// Part of `total_size` that only an elevated delete can free
+    pub elevated_size: u64,
+    /// Platform crash folders that exist and were searched
+    pub locations: Vec<String>,
+    pub unreadable_entries: u64,
+}
+
+/// A thumbnail cache folder, or the cache files in a shared folder
+#[derive(Debug, Serialize)]
+pub struct ThumbnailCache {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+    pub file_count: u64,
+    /// Processes holding cache files open; deleting those files will fail
+    pub in_use_by: Vec<LockingProcess>,
+}
+
+/// A `Thumbs.db` style file Explorer left in an ordinary folder
+#[derive(Debug, Serialize)]
+pub struct ThumbnailFile {
+    pub path: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ThumbnailCacheReport {
+    /// Largest first
+    pub caches: Vec<ThumbnailCache>,
+    /// Found below the `path` given to `find_thumbnail_caches`, largest first
+    pub stray_files: Vec<ThumbnailFile>,
+    pub total_size: u64,
+    pub category: FileCategory,
+    pub confidence: f32,
+    pub unreadable_entries: u64,
+    pub note: String,
+}
+
+/// A trash item considered by `empty_old_trash`
+#[derive(Debug, Serialize)]
+pub struct TrashedItem {
+    pub path: String,
+    pub size: u64,
+    /// When the item was trashed, if the platform records it
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrashPurgeReport {
+    pub older_than_days: u64,
+    /// Items old enough to purge, all covered by `plan`
+    pub eligible: Vec<TrashedItem>,
+    pub eligible_bytes: u64,
+    pub retained_count: u64,
+    pub retained_bytes: u64,
+    /// Items without a deletion date; purged only when confirmed
+    pub undated_count: u64,
+    pub note: Option<String>,
+    /// Listing was cancelled; later items were not considered
+    pub cancelled: bool,
+    /// Pass its token to `confirm_delete` to purge the eligible items
+    pub plan: DeletePlan,
+}
+
+/// A path included in a prepared deletion
+#[derive(Debug, Serialize)]
+pub struct PlannedDeletion {
+    pub path: String,
+    pub bytes: u64,
+    /// Removed through the OS elevation prompt
+    pub elevated: bool,
+}
+
+/// A path left out of a prepared deletion because it would be refused
+#[derive(Debug, Serialize)]
+pub struct RefusedDeletion {
+    pub path: String,
+    pub reason: String,
+}
+
+/// What `confirm_delete` will do when given `token`
+#[derive(Debug, Serialize)]
+pub struct DeletePlan {
+    pub token: String,
+    pub expires_in_seconds: u64,
+    pub paths: Vec<PlannedDeletion>,
+    pub refused: Vec<RefusedDeletion>,
+    pub reclaimable_bytes: u64,
+    /// File contents will be overwritten before removal; cannot be undone
+    pub secure_wipe: bool,
+    /// The batch is backed up first and rolled back if any deletion fails
+    pub transactional: bool,
+    pub warnings: Vec<String>,
+    /// The UI must ask the user before calling `confirm_delete`
+    pub requires_confirmation: bool,
+    /// What made confirmation necessary, for explaining the prompt
+    pub confirmation_triggers: Vec<ConfirmationTrigger>,
+    /// Entries that need administrator rights, under a separate token that
+    /// only `delete_elevated` accepts
+    pub elevated: Option<Box<DeletePlan>>,
+}
+
+#[command]
+pub async fn find_broken_symlinks(
+    state: State<'_, AppState>,
+    path: String,
+    delete: Option<bool>,
+) -> AppResult<BrokenSymlinkReport> {
+    timed_command("find_broken_symlinks", Some(state.inner()), Some(path.clone()), async {
+        let root = validate_scan_root(&path)?;
+        let delete = delete.unwrap_or(false);
+        if delete {
+            state.require_scan_root().await?;
+        }
+        let security = state.get_config().await.security;
+        let mut operation = state.operations.start(OperationKind::Scan, &path);
+        let cancel = operation.token();
+
+        let mut report = tokio::task::spawn_blocking(move || {
+            let walk_options = WalkOptions {
+                excluded_filesystem_types: security.excluded_filesystem_types.clone(),
+                excluded_paths: security.global_exclusion_paths(),
+                ..WalkOptions::default()
+            };
+            let mut walker = DirWalker::new(&root, walk_options);
+            let broken_links: Vec<BrokenSymlink> = walker
+                .by_ref()
+                .take_while(|_| !cancel.is_cancelled())
+                .filter(|entry| entry.is_symlink())
+                .filter_map(|entry| broken_symlink(&entry.path))
+                .collect();
+
+            if cancel.is_cancelled() {
+                return Err(AppError::Cancelled);
+            }
+
+            Ok(BrokenSymlinkReport {
+                root: path,
+                broken_links,
+                unreadable_entries: walker.error_count(),
+                deletion_plan: None,
+            })
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))??;
+
+        if delete {
+            let targets = report.broken_links.iter().map(|link| link.link_path.clone()).collect();
+            report.deletion_plan = Some(plan_delete(&state, targets, DeleteMode::default()).await?);
+        }
+
+        operation.complete(OperationTally::completed(report.broken_links.len() as u64, 0));
+        Ok(report)
+    })
+    .await
+}
+
+/// Find directories below `path` that contain no files, counting directories
+/// that only hold empty subdirectories as empty too. With `ignore_junk_files`,
+/// hidden files and OS metadata such as `.DS_Store` do not count as content.
+#[command]
+pub async fn find_empty_directories(
+    state: State<'_, AppState>,
+    path: String,
+    delete: Option<bool>,
+    ignore_junk_files: Option<bool>,
+) -> AppResult<EmptyDirectoryReport> {
+    timed_command("find_empty_directories", Some(state.inner()), Some(path.clone()), async {
+        let root = validate_scan_root(&path)?;
+        let delete = delete.unwrap_or(false);
+        if delete {
+            state.require_scan_root().await?;
+        }
+        let ignore_junk_files = ignore_junk_files.unwrap_or(false);
+        let security = state.get_config().await.security;
+        let mut operation = state.operations.start(OperationKind::Scan, &path);
+        let cancel = operation.token();
+
+        let (mut report, targets) = tokio::task::spawn_blocking(move || {
+            let walk_options = WalkOptions {
+                excluded_filesystem_types: security.excluded_filesystem_types.clone(),
+                excluded_paths: security.global_exclusion_paths(),
+                ..WalkOptions::default()
+            };
+            let mut walker = DirWalker::new(&root, walk_options);
+            let mut directories: HashMap<PathBuf, (usize, Vec<PathBuf>)> = HashMap::new();
+            let mut non_empty: HashSet<PathBuf> = HashSet::new();
+
+            for entry in walker.by_ref() {
+                if cancel.is_cancelled() {
+                    return Err(AppError::Cancelled);
+                }
+                if entry.is_dir() {
+                    directories.insert(entry.path, (entry.depth, Vec::new()));
+                    continue;
+                }
+
+                let Some(parent) = entry.path.parent().map(Path::to_path_buf) else {
+                    continue;
+                };
+                if ignore_junk_files && is_junk_file(&entry.path) {
+                    if let Some((_, junk)) = directories.get_mut(&parent) {
+                        junk.push(entry.path);
+                    }
+                    continue;
+                }
+
+                // Real content keeps every ancestor up to the root non-empty
+                for ancestor in parent.ancestors() {
+                    if !ancestor.starts_with(&root) || !non_empty.insert(ancestor.to_path_buf()) {
+                        break;
+                    }
+                }
+            }
+
+            let mut empty: Vec<(usize, PathBuf, Vec<PathBuf>)> = directories
+                .into_iter()
+                .filter(|(dir, _)| !non_empty.contains(dir))
+                .map(|(dir, (depth, junk))| (depth, dir, junk))
+                .collect();
+            empty.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+            let to_string = |p: &PathBuf| p.to_string_lossy().to_string();
+            // Junk files first, then directories deepest-first so each
+            // directory is already empty when it is removed
+            let targets: Vec<String> = empty
+                .iter()
+                .flat_map(|(_, _, junk)| junk.iter().map(to_string))
+                .chain(empty.iter().map(|(_, dir, _)| to_string(dir)))
+                .collect();
+
+            let report = EmptyDirectoryReport {
+                root: path,
+                directories: empty
+                    .iter()
+                    .map(|(_, dir, junk)| EmptyDirectory {
+                        path: to_string(dir),
+                        ignored_files: junk.iter().map(to_string).collect(),
+                    })
+                    .collect(),
+                unreadable_entries: walker.error_count(),
+                deletion_plan: None,
+            };
+            Ok((report, targets))
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))??;
+
+        if delete {
+            report.deletion_plan = Some(plan_delete(&state, targets, DeleteMode::default()).await?);
+        }
+
+        operation.complete(OperationTally::completed(report.directories.len() as u64, 0));
+        Ok(report)
+    })
+    .await
+}
+
+/// Find zero-length regular files below `path`, leaving out marker files
+/// such as `.gitkeep` or `__init__.py` listed in
+/// `analysis.empty_file_markers`. With `delete`, the report carries a plan
+/// for removing the files found, which requires a confirmed scan root.
+#[command]
+pub async fn find_empty_files(
+    state: State<'_, AppState>,
+    path: String,
+    delete: Option<bool>,
+) -> AppResult<EmptyFileReport> {
+    timed_command("find_empty_files", Some(state.inner()), Some(path.clone()), async {
+        let root = validate_scan_root(&path)?;
+        let delete = delete.unwrap_or(false);
+        if delete {
+            state.require_scan_root().await?;
+        }
+        let config = state.get_config().await;
+        let markers = empty_file_markers(&config.analysis.empty_file_markers)?;
+        let security = config.security;
+        let mut operation = state.operations.start(OperationKind::Scan, &path);
+        let cancel = operation.token();
+
+        let mut report = tokio::task::spawn_blocking(move || {
+            let walk_options = WalkOptions {
+                excluded_filesystem_types: security.excluded_filesystem_types.clone(),
+                excluded_paths: security.global_exclusion_paths(),
+                ..WalkOptions::default()
+            };
+            let mut walker = DirWalker::new(&root, walk_options);
+            let mut files = Vec::new();
+            let mut markers_kept = 0u64;
+            for entry in walker.by_ref() {
+                if cancel.is_cancelled() {
+                    return Err(AppError::Cancelled);
+                }
+                if !entry.is_file() || entry.metadata.len() != 0 {
+                    continue;
+                }
+                let is_marker = entry
+                    .path
+                    .file_name()
+                    .is_some_and(|name| markers.is_match(name));
+                if is_marker {
+                    markers_kept += 1;
+                } else {
+                    files.push(entry.path);
+                }
+            }
+            files.sort();
+
+            Ok(EmptyFileReport {
+                root: path,
+                files: files
+                    .iter()
+                    .map(|file| file.to_string_lossy().to_string())
+                    .collect(),
+                markers_kept,
+                unreadable_entries: walker.error_count(),
+                deletion_plan: None,
+            })
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))??;
+
+        if delete {
+            let targets = report.files.clone();
+            report.deletion_plan = Some(plan_delete(&state, targets, DeleteMode::default()).await?);
+        }
+
+        operation.complete(OperationTally::completed(report.files.len() as u64, 0));
+        Ok(report)
+    })
+    .await
+}
+
+/// Matcher for the configured marker file names and globs
+fn empty_file_markers(patterns: &[String]) -> AppResult<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|e| {
+            AppError::ConfigError(format!("Invalid empty file marker '{}': {}", pattern, e))
+        })?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| AppError::ConfigError(format!("Invalid empty file markers: {}", e)))
+}
+
+/// Hidden files and OS-generated folder metadata
+fn is_junk_file(path: &Path) -> bool {
+    const JUNK_NAMES: &[&str] = &[".DS_Store", "Thumbs.db", "desktop.ini"];
+
+    path.file_name()
+        .map(|name| name.to_string_lossy())
+        .is_some_and(|name| name.starts_with('.') || JUNK_NAMES.contains(&name.as_ref()))
+}
+
+/// First step of a deletion: check the paths and issue a short-lived token
+/// covering exactly those that would be deleted. Entries that need
+/// administrator rights get their own token in `elevated`, for
+/// `delete_elevated`.
+///
+/// `secure_wipe` opts in to overwriting file contents before removal. It is
+/// only available while `require_confirmation` is enabled. `transactional`
+/// backs the batch up first and restores it if any deletion fails; the two
+/// cannot be combined since a backup would defeat the wipe.
+///
+/// The plan says whether the UI must prompt before confirming, and why:
+/// with `confirm_above_bytes` or `confirm_above_count` set, only batches
+/// over those limits (and secure wipes) need the prompt.
+#[command]
+pub async fn prepare_delete(
+    state: State<'_, AppState>,
+    paths: Vec<String>,
+    secure_wipe: Option<bool>,
+    transactional: Option<bool>,
+) -> AppResult<DeletePlan> {
+    timed_command("prepare_delete", Some(state.inner()), None, async {
+        let mode = DeleteMode {
+            secure_wipe: secure_wipe.unwrap_or(false),
+            transactional: transactional.unwrap_or(false),
+            tree: None,
+        };
+        plan_delete(&state, paths, mode).await
+    })
+    .await
+}
+
+/// Check `paths` against the confirmed scan root and issue a delete token,
+/// as `prepare_delete` does. Shared by the command and headless runs.
+pub async fn plan_delete(
+    state: &AppState,
+    paths: Vec<String>,
+    mode: DeleteMode,
+) -> AppResult<DeletePlan> {
+    let scan_root = state.require_scan_root().await?;
+    let security = state.get_config().await.security;
+    if mode.secure_wipe && !security.require_confirmation {
+        return Err(AppError::SecurityError(
+            "Secure wipe requires security.require_confirmation to be enabled".to_string(),
+        ));
+    }
+    if mode.secure_wipe && mode.transactional {
+        return Err(AppError::SecurityError(
+            "Secure wipe cannot be combined with a transactional delete".to_string(),
+        ));
+    }
+
+    let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+    let (elevated, paths): (Vec<PathBuf>, Vec<PathBuf>) = tokio::task::spawn_blocking(move || {
+        paths
+            .into_iter()
+            .partition(|path| elevation_required(path).is_some())
+    })
+    .await
+    .map_err(|e| AppError::SystemError(e.to_string()))?;
+    let label = scan_root.to_string_lossy().to_string();
+
+    // Confined to the scan root by its batch, but not tied to it, so it
+    // stays valid after the main token clears the root
+    let elevated = if elevated.is_empty() {
+        None
+    } else {
+        let plan = issue_plan(
+            state,
+            DeleteRequest {
+                scan_root: None,
+                label: label.clone(),
+                batches: vec![DeleteBatch {
+                    paths: elevated,
+                    root: scan_root.clone(),
+                    elevated: true,
+                }],
+                trash: Vec::new(),
+                mode: DeleteMode::default(),
+            },
+        )
+        .await?;
+        Some(Box::new(plan))
+    };
+
+    let mut plan = issue_plan(
+        state,
+        DeleteRequest {
+            scan_root: Some(scan_root.clone()),
+            label,
+            batches: vec![DeleteBatch {
+                paths,
+                root: scan_root,
+                elevated: false,
+            }],
+            trash: Vec::new(),
+            mode,
+        },
+    )
+    .await?;
+    plan.elevated = elevated;
+    Ok(plan)
+}
+
+/// Check every path in `request` against its batch root, leave out those
+/// that would be refused and issue a token for the rest. Commands that find
+/// things to clean return this plan; nothing is deleted until its token is
+/// passed to `confirm_delete`.
+async fn issue_plan(state: &AppState, mut request: DeleteRequest) -> AppResult<DeletePlan> {
+    let security = state.get_config().await.security;
+    let check_security = security.clone();
+    let batches = std::mem::take(&mut request.batches);
+    let (batches, planned, refused) = tokio::task::spawn_blocking(move || {
+        let mut checked_batches = Vec::new();
+        let mut planned = Vec::new();
+        let mut refused = Vec::new();
+        for batch in batches {
+            let mut kept = Vec::new();
+            for path in batch.paths {
+                let checked = SecurityValidator::validate_within_root(&path, &batch.root)
+                    .and_then(|()| {
+                        SecurityValidator::validate_deletion_target(&path, &check_security)
+                    })
+                    .and_then(|()| match (elevation_required(&path), batch.elevated) {
+                        (Some(reason), false) => Err(reason),
+                        (None, true) => Err(NO_ELEVATION_NEEDED_REASON.to_string()),
+                        _ => Ok(()),
+                    });
+                match checked {
+                    Ok(()) => {
+                        let bytes = fs::symlink_metadata(&path)
+                            .map(|m| if m.is_file() { allocated_size(&path, &m) } else { 0 })
+                            .unwrap_or(0);
+                        planned.push(PlannedDeletion {
+                            path: path.to_string_lossy().to_string(),
+                            bytes,
+                            elevated: batch.elevated,
+                        });
+                        kept.push(path);
+                    }
+                    Err(reason) => refused.push(RefusedDeletion {
+                        path: path.to_string_lossy().to_string(),
+                        reason,
+                    }),
+                }
+            }
+            if !kept.is_empty() {
+                checked_batches.push(DeleteBatch {
+                    paths: kept,
+                    ..batch
+                });
+            }
+        }
+        (checked_batches, planned, refused)
+    })
+    .await
+    .map_err(|e| AppError::SystemError(e.to_string()))?;
+    request.batches = batches;
+
+    let mut planned = planned;
+    planned.extend(request.trash.iter().map(|entry| PlannedDeletion {
+        path: entry.display_path(),
+        bytes: entry.size,
+        elevated: false,
+    }));
+    let mode = request.mode;
+    let roots: Vec<PathBuf> = request.batches.iter().map(|batch| batch.root.clone()).collect();
+    let token = state.delete_tokens.issue_plan(request);
+    let reclaimable_bytes = planned.iter().map(|planned| planned.bytes).sum();
+    let confirmation_triggers = security.confirmation_triggers(
+        planned.len() as u64,
+        reclaimable_bytes,
+        mode.secure_wipe,
+    );
+    let warnings = if mode.secure_wipe {
+        tokio::task::spawn_blocking(move || roots.iter().filter_map(|root| wipe_warning(root)).collect())
+            .await
+            .map_err(|e| AppError::SystemError(e.to_string()))?
+    } else {
+        Vec::new()
+    };
+
+    Ok(DeletePlan {
+        token,
+        expires_in_seconds: state.delete_tokens.ttl().as_secs(),
+        paths: planned,
+        refused,
+        reclaimable_bytes,
+        secure_wipe: mode.secure_wipe,
+        transactional: mode.transactional,
+        warnings,
+        requires_confirmation: !confirmation_triggers.is_empty(),
+        confirmation_triggers,
+        elevated: None,
+    })
+}
+
+/// Estimate how long deleting `paths` would take, as a range, from the
+/// measured speed of recent deletes on the same volume, or from rough
+/// default rates when there is no history yet. `backup` estimates a
+/// transactional delete, which backs everything up first.
+#[command]
+pub async fn estimate_operation_duration(
+    state: State<'_, AppState>,
+    paths: Vec<String>,
+    backup: Option<bool>,
+) -> AppResult<DurationEstimate> {
+    timed_command("estimate_operation_duration", Some(state.inner()), None, async {
+        let first = paths
+            .first()
+            .map(PathBuf::from)
+            .ok_or_else(|| AppError::FileSystemError("No paths to estimate".to_string()))?;
+        let ttl_seconds = state.get_config().await.analysis.size_cache_ttl_seconds;
+        let sizes = state.dir_size_cache.clone();
+
+        let (files, bytes) = tokio::task::spawn_blocking(move || {
+            let cancel = CancellationToken::new();
+            let mut totals = (0u64, 0u64);
+            for path in paths.iter().map(Path::new) {
+                let Ok(metadata) = fs::symlink_metadata(path) else {
+                    continue;
+                };
+                if metadata.is_dir() {
+                    let size = sizes.measure(path, ttl_seconds, &cancel);
+                    totals.0 += size.files;
+                    totals.1 += size.bytes;
+                } else {
+                    totals.0 += 1;
+                    totals.1 += metadata.len();
+                }
+            }
+            totals
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))?;
+
+        Ok(state
+            .throughput
+            .estimate(&first, files, bytes, backup.unwrap_or(false)))
+    })
+    .await
+}
+
+/// Second step of a deletion: delete the paths covered by a token from
+/// `prepare_delete` or a cleanup command's plan, refusing if it expired or
+/// anything changed on disk since.
+/// Transactional batches emit `BACKUP_PROGRESS_EVENT` while backing up and
+/// can be cancelled until the backup is complete. Whole directory trees emit
+/// `DELETE_PROGRESS_EVENT` as they are removed.
+#[command]
+pub async fn confirm_delete<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, AppState>,
+    token: String,
+) -> AppResult<DeletionReport> {
+    timed_command("confirm_delete", Some(state.inner()), None, async {
+        let tree_app = app.clone();
+        let on_backup_progress = move |progress: &BackupProgress| {
+            if let Err(e) = app.emit(BACKUP_PROGRESS_EVENT, progress) {
+                warn!("Failed to emit {} event: {}", BACKUP_PROGRESS_EVENT, e);
+            }
+        };
+        let on_tree_progress = move |progress: &TreeDeleteProgress| {
+            if let Err(e) = tree_app.emit(DELETE_PROGRESS_EVENT, progress) {
+                warn!("Failed to emit {} event: {}", DELETE_PROGRESS_EVENT, e);
+            }
+        };
+        execute_delete(&state, token, on_backup_progress, on_tree_progress).await
+    })
+    .await
+}
+
+/// Delete the paths covered by a plan's token, as `confirm_delete` does,
+/// passing backup progress of transactional batches to
+/// `on_backup_progress` and progress through whole trees to
+/// `on_tree_progress`. Shared by the command and headless runs.
+pub async fn execute_delete<F, G>(
+    state: &AppState,
+    token: String,
+    mut on_backup_progress: F,
+    mut on_tree_progress: G,
+) -> AppResult<DeletionReport>
+where
+    F: FnMut(&BackupProgress) + Send + 'static,
+    G: FnMut(&TreeDeleteProgress) + Send + 'static,
+{
+    let scan_root = state.scan_root().await;
+    if state.get_config().await.security.require_backup_verification {
+        let verification = tokio::task::spawn_blocking(|| {
+            backups::verify_latest(&paths::backup_dir(), true)
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))??;
+        if !verification.passed {
+            return Err(AppError::SecurityError(format!(
+                "Latest backup failed verification ({} missing, {} corrupt); \
+                 run verify_backups for details",
+                verification.missing.len(),
+                verification.corrupt.len()
+            )));
+        }
+    }
+    let roots = state.delete_tokens.roots(&token)?;
+    let _volumes = lock_volumes(state, &roots).await?;
+    let approved = state.delete_tokens.redeem(&token, scan_root.as_deref())?;
+    let targets: Vec<PathBuf> = approved.paths().cloned().collect();
+    ensure_no_overlap(state, &targets)?;
+    let security = state.get_config().await.security;
+    let audit = state.audit.clone();
+    let label = approved.label.clone();
+    let used_scan_root = approved.scan_root.is_some();
+    let mut operation = state.operations.start(OperationKind::Delete, &label);
+    let cancel = operation.token();
+
+    let throughput = state.throughput.clone();
+    let report = tokio::task::spawn_blocking(move || {
+        let ApprovedDelete { batches, trash, mode, .. } = approved;
+        let probe = batches.first().map(|batch| FreeSpaceProbe::start(&batch.root));
+        let started = Instant::now();
+        let mut report = DeletionReport::default();
+        let mut retention = Retention::None;
+        for batch in &batches {
+            let batch_report = if batch.elevated {
+                elevated_delete(&batch.paths, &batch.root, &security, &audit)
+            } else if let Some(tree_mode) = mode.tree {
+                if tree_mode == TreeDeleteMode::Trash {
+                    retention = Retention::Trash;
+                }
+                tree_delete(
+                    &batch.paths,
+                    &batch.root,
+                    &security,
+                    &audit,
+                    tree_mode,
+                    &cancel,
+                    &mut on_tree_progress,
+                )
+            } else if mode.transactional {
+                retention = Retention::Backups;
+                transactional_delete(
+                    &batch.paths,
+                    &batch.root,
+                    &security,
+                    &audit,
+                    &paths::backup_dir(),
+                    &cancel,
+                    &mut on_backup_progress,
+                )
+            } else {
+                safe_delete_with(&batch.paths, &batch.root, &security, &audit, mode.secure_wipe)
+            };
+            report.merge(batch_report);
+        }
+        if !trash.is_empty() {
+            report.merge(purge_trash(&trash, &security, &audit, &cancel));
+        }
+        if let Some(batch) = batches.first() {
+            if !report.cancelled && !report.rolled_back {
+                throughput.record(
+                    &batch.root,
+                    report.deleted_count,
+                    report.bytes_freed,
+                    started.elapsed(),
+                    mode.transactional,
+                );
+            }
+        }
+        report.free_space = probe.and_then(|probe| probe.finish(report.bytes_freed, retention));
+        report
+    })
+    .await
+    .map_err(|e| AppError::SystemError(e.to_string()))?;
+
+    state.record_cleanup(&label, &report).await;
+    if used_scan_root {
+        state.clear_scan_root().await;
+    }
+
+    operation.complete(report.tally());
+    Ok(report)
+}
+
+/// Lock the volumes holding `paths` under `security.volume_delete_policy`.
+/// Every command that deletes holds these locks while it touches the disk.
+async fn lock_volumes(state: &AppState, paths: &[PathBuf]) -> AppResult<Vec<VolumeLockGuard>> {
+    let policy = state.get_config().await.security.volume_delete_policy;
+    state
+        .volume_locks
+        .acquire_all(paths, policy)
+        .await
+        .map_err(AppError::SecurityError)
+}
+
+/// Refuse a delete while any of `paths` is inside, or contains, the target
+/// of a running operation
+fn ensure_no_overlap(state: &AppState, paths: &[PathBuf]) -> AppResult<()> {
+    if let Some((path, operation)) = paths.iter().find_map(|path| {
+        state
+            .operations
+            .overlapping(path)
+            .into_iter()
+            .next()
+            .map(|operation| (path, operation))
+    }) {
+        return Err(AppError::SecurityError(format!(
+            "{} overlaps a running {:?} operation on {}; \
+             prepare the deletion again once it finishes",
+            path.display(),
+            operation.kind,
+            operation.target
+        )));
+    }
+    Ok(())
+}
+
+/// Check that the most recent backup session is intact: every backed-up file
+/// present with its recorded size and, unless `check_hashes` is false, its
+/// recorded digest
+#[command]
+pub async fn verify_backups(
+    state: State<'_, AppState>,
+    check_hashes: Option<bool>,
+) -> AppResult<BackupVerification> {
+    timed_command("verify_backups", Some(state.inner()), None, async {
+        let check_hashes = check_hashes.unwrap_or(true);
+        let verification = tokio::task::spawn_blocking(move || {
+            backups::verify_latest(&paths::backup_dir(), check_hashes)
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))??;
+
+        if !verification.passed {
+            warn!(
+                "Backup {} failed verification: {} missing, {} corrupt",
+                verification.session.as_deref().unwrap_or("-"),
+                verification.missing.len(),
+                verification.corrupt.len()
+            );
+        }
+        Ok(verification)
+    })
+    .await
+}
+
+/// Delete entries that need administrator rights through the OS elevation
+/// prompt. Only accepts the separate token `prepare_delete` issues for the
+/// entries it flags as needing elevation, so elevation is always an explicit
+/// request covering exactly those entries.
+#[command]
+pub async fn delete_elevated(
+    state: State<'_, AppState>,
+    token: String,
+) -> AppResult<DeletionReport> {
+    timed_command("delete_elevated", Some(state.inner()), None, async {
+        if !state.delete_tokens.is_elevated(&token)? {
+            return Err(AppError::SecurityError(
+                "Not an elevated delete token; pass the elevated plan's token from \
+                 prepare_delete"
+                    .to_string(),
+            ));
+        }
+        execute_delete(&state, token, |_| {}, |_| {}).await
+    })
+    .await
+}
+
+/// Plan removing a whole directory tree inside the confirmed scan root,
+/// permanently or to the trash. The token covers only that directory;
+/// `confirm_delete` removes it, emitting `DELETE_PROGRESS_EVENT` as it goes,
+/// and cancelling stops between entries with what was removed so far.
+#[command]
+pub async fn delete_directory(
+    state: State<'_, AppState>,
+    path: String,
+    to_trash: Option<bool>,
+) -> AppResult<DeletePlan> {
+    timed_command("delete_directory", Some(state.inner()), Some(path.clone()), async {
+        let scan_root = state.require_scan_root().await?;
+        let tree = if to_trash.unwrap_or(false) {
+            TreeDeleteMode::Trash
+        } else {
+            TreeDeleteMode::Permanent
+        };
+
+        issue_plan(
+            &state,
+            DeleteRequest {
+                scan_root: Some(scan_root.clone()),
+                label: path.clone(),
+                batches: vec![DeleteBatch {
+                    root: scan_root,
+                    paths: vec![PathBuf::from(&path)],
+                    elevated: false,
+                }],
+                trash: Vec::new(),
+                mode: DeleteMode {
+                    tree: Some(tree),
+                    ..DeleteMode::default()
+                },
+            },
+        )
+        .await
+    })
+    .await
+}
+
+/// Detect well-known package-manager caches plus any configured extras, and
+/// single oversized files in app and browser caches
+#[command]
+pub async fn find_dev_caches(state: State<'_, AppState>) -> AppResult<DevCacheReport> {
+    timed_command("find_dev_caches", Some(state.inner()), None, async {
+        let home = home::home_dir()
+            .ok_or_else(|| AppError::SystemError("Unable to determine home directory".to_string()))?;
+        let config = state.get_config().await;
+
+        let mut locations = dev_caches::known_locations(&home);
+        locations.extend(
+            config
+                .analysis
+                .extra_dev_cache_paths
+                .iter()
+                .map(|entry| dev_caches::configured_location(entry, &home)),
+        );
+
+        let mut operation = state.operations.start(OperationKind::Scan, "developer caches");
+        let ttl_seconds = config.analysis.size_cache_ttl_seconds;
+        let min_large_size = config.analysis.large_cache_file_bytes;
+        let mut large_tasks = JoinSet::new();
+        if min_large_size > 0 {
+            // Docker's data is never offered as cache, even file by file
+            let exclude: Arc<Vec<PathBuf>> = Arc::new(
+                locations
+                    .iter()
+                    .map(|location| location.path.clone())
+                    .chain(dev_caches::container_data_dirs(&home))
+                    .collect(),
+            );
+            for root in dev_caches::cache_search_roots(&home) {
+                let cancel = operation.token();
+                let exclude = exclude.clone();
+                large_tasks.spawn_blocking(move || {
+                    dev_caches::oversized_cache_files(&root, min_large_size, &exclude, &cancel)
+                });
+            }
+        }
+
+        let mut tasks = JoinSet::new();
+        for location in locations {
+            let cancel = operation.token();
+            let sizes = state.dir_size_cache.clone();
+            tasks.spawn_blocking(move || {
+                // Skip missing locations and symlinks so no cache is counted twice
+                if !fs::symlink_metadata(&location.path).is_ok_and(|m| m.is_dir()) {
+                    return None;
+                }
+                let size = sizes.measure(&location.path, ttl_seconds, &cancel);
+                Some(dev_cache(location, size.allocated, size.files))
+            });
+        }
+
+        let mut caches = Vec::new();
+        while let Some(joined) = tasks.join_next().await {
+            if let Some(cache) = joined.map_err(|e| AppError::SystemError(e.to_string()))? {
+                caches.push(cache);
+            }
+        }
+        if operation.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+
+        if let Err(e) = state.dir_size_cache.flush() {
+            warn!("Failed to save directory size cache: {}", e);
+        }
+
+        let mut large_files = Vec::new();
+        while let Some(joined) = large_tasks.join_next().await {
+            let found = joined.map_err(|e| AppError::SystemError(e.to_string()))?;
+            large_files.extend(found.into_iter().map(large_cache_file));
+        }
+        if operation.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+
+        caches.sort_by_key(|cache| Reverse(cache.size));
+        let total_size = caches.iter().map(|cache| cache.size).sum();
+        large_files.sort_by_key(|file| Reverse(file.size));
+        let large_files_size = large_files.iter().map(|file| file.size).sum();
+        operation.complete(OperationTally::completed(
+            (caches.len() + large_files.len()) as u64,
+            total_size + large_files_size,
+        ));
+
+        Ok(DevCacheReport {
+            caches,
+            total_size,
+            large_files,
+            large_files_size,
+        })
+    })
+    .await
+}
+
+/// Locate the per-profile cache folders of Chrome, Edge, Firefox and Safari
+/// plus any configured extras, with sizes per browser and the processes
+/// currently holding them open. Cookies and other profile data are never
+/// included.
+#[command]
+pub async fn find_browser_caches(state: State<'_, AppState>) -> AppResult<BrowserCacheReport> {
+    timed_command("find_browser_caches", Some(state.inner()), None, async {
+        let home = home::home_dir()
+            .ok_or_else(|| AppError::SystemError("Unable to determine home directory".to_string()))?;
+        let config = state.get_config().await;
+        let ttl_seconds = config.analysis.size_cache_ttl_seconds;
+
+        let mut operation = state.operations.start(OperationKind::Scan, "browser caches");
+        let mut tasks = JoinSet::new();
+        for (browser, locations) in browser_cache_locations(&home, &config.analysis) {
+            let cancel = operation.token();
+            let sizes = state.dir_size_cache.clone();
+            tasks.spawn_blocking(move || {
+                let mut samples = Vec::new();
+                let caches: Vec<BrowserCache> = locations
+                    .into_iter()
+                    .map(|location| {
+                        let size = sizes.measure(&location.path, ttl_seconds, &cancel);
+                        samples.extend(browser_caches::sample_files(&location.path));
+                        BrowserCache {
+                            profile: location.profile,
+                            path: location.path.to_string_lossy().to_string(),
+                            size: size.allocated,
+                            file_count: size.files,
+                        }
+                    })
+                    .collect();
+                let in_use_by = locking_processes_any(&samples).unwrap_or_else(|e| {
+                    warn!("Failed to check whether {} caches are in use: {}", browser, e);
+                    Vec::new()
+                });
+                browser_cache_group(browser, caches, in_use_by)
+            });
+        }
+
+        let mut browsers = Vec::new();
+        while let Some(joined) = tasks.join_next().await {
+            browsers.push(joined.map_err(|e| AppError::SystemError(e.to_string()))?);
+        }
+        if operation.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+
+        if let Err(e) = state.dir_size_cache.flush() {
+            warn!("Failed to save directory size cache: {}", e);
+        }
+
+        browsers.sort_by_key(|group| Reverse(group.total_size));
+        let total_size = browsers.iter().map(|group| group.total_size).sum();
+        operation.complete(OperationTally::completed(browsers.len() as u64, total_size));
+        Ok(BrowserCacheReport {
+            browsers,
+            total_size,
+        })
+    })
+    .await
+}
+
+/// Plan removing every cache folder of `browser` found by
+/// `find_browser_caches`, permanently or to the trash. Refused while any
+/// process holds the caches open, since the browser must be closed first.
+/// Nothing is deleted until the plan's token is passed to `confirm_delete`,
+/// which emits `DELETE_PROGRESS_EVENT` as each folder is removed.
+#[command]
+pub async fn clean_browser_caches(
+    state: State<'_, AppState>,
+    browser: String,
+    to_trash: Option<bool>,
+) -> AppResult<DeletePlan> {
+    timed_command("clean_browser_caches", Some(state.inner()), Some(browser.clone()), async {
+        let home = home::home_dir()
+            .ok_or_else(|| AppError::SystemError("Unable to determine home directory".to_string()))?;
+        let config = state.get_config().await;
+
+        let (browser, locations) = browser_cache_locations(&home, &config.analysis)
+            .into_iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&browser))
+            .ok_or_else(|| {
+                AppError::FileSystemError(format!("No {} caches were found", browser))
+            })?;
+
+        let samples: Vec<PathBuf> = locations
+            .iter()
+            .flat_map(|location| browser_caches::sample_files(&location.path))
+            .collect();
+        let in_use_by = tokio::task::spawn_blocking(move || locking_processes_any(&samples))
+            .await
+            .map_err(|e| AppError::SystemError(e.to_string()))?
+            .unwrap_or_else(|e| {
+                warn!("Failed to check whether {} caches are in use: {}", browser, e);
+                Vec::new()
+            });
+        if !in_use_by.is_empty() {
+            return Err(AppError::SecurityError(format!(
+                "Close {} first; its cache is in use by {}",
+                browser,
+                describe_processes(&in_use_by)
+            )));
+        }
+
+        // Each folder is removed whole, confined to the profile holding it
+        let batches = locations
+            .into_iter()
+            .filter_map(|location| {
+                let parent = location.path.parent()?;
+                // Roots are compared with canonical parents, so resolve them too
+                let root = fs::canonicalize(parent).unwrap_or_else(|_| parent.to_path_buf());
+                Some(DeleteBatch {
+                    root,
+                    paths: vec![location.path],
+                    elevated: false,
+                })
+            })
+            .collect();
+        let tree = if to_trash.unwrap_or(false) {
+            TreeDeleteMode::Trash
+        } else {
+            TreeDeleteMode::Permanent
+        };
+
+        issue_plan(
+            &state,
+            DeleteRequest {
+                scan_root: None,
+                label: format!("{} caches", browser),
+                batches,
+                trash: Vec::new(),
+                mode: DeleteMode {
+                    tree: Some(tree),
+                    ..DeleteMode::default()
+                },
+            },
+        )
+        .await
+    })
+    .await
+}
+
+/// Flag app-data folders (`~/.config`, `~/Library/Application Support`,
+/// `%APPDATA%`) untouched for `min_age_days` whose name matches no installed
+/// application. Results are suggestions only and are never deleted here.
+#[command]
+pub async fn find_app_leftovers(
+    state: State<'_, AppState>,
+    min_age_days: Option<u64>,
+) -> AppResult<AppLeftoverReport> {
+    timed_command("find_app_leftovers", Some(state.inner()), None, async {
+        let home = home::home_dir()
+            .ok_or_else(|| AppError::SystemError("Unable to determine home directory".to_string()))?;
+        let min_age_days = min_age_days.unwrap_or(DEFAULT_LEFTOVER_MIN_AGE_DAYS);
+        let cutoff = SystemTime::now()
+            .checked_sub(Duration::from_secs(min_age_days * 24 * 60 * 60))
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        let ttl_seconds = state.get_config().await.analysis.size_cache_ttl_seconds;
+
+        let mut operation = state.operations.start(OperationKind::Scan, "application leftovers");
+        let cancel = operation.token();
+        let sizes = state.dir_size_cache.clone();
+        let (mut leftovers, installed_apps_detected) = tokio::task::spawn_blocking(move || {
+            let installed = app_leftovers::installed_app_names(&home);
+            let detected = !installed.is_empty();
+            let confidence = if detected {
+                LEFTOVER_CONFIDENCE
+            } else {
+                LEFTOVER_CONFIDENCE_UNDETECTED
+            };
+
+            let mut leftovers = Vec::new();
+            for location in app_leftovers::data_locations(&home) {
+                for candidate in app_leftovers::stale_folders(&location, cutoff) {
+                    if cancel.is_cancelled() {
+                        return (leftovers, detected);
+                    }
+                    if app_leftovers::matches_installed(&candidate.name, &installed) {
+                        continue;
+                    }
+
+                    let size = sizes.measure(&candidate.path, ttl_seconds, &cancel);
+                    leftovers.push(AppLeftover {
+                        note: format!(
+                            "Possible leftover - verify before deleting: \
+                             not modified in {}+ days{}",
+                            min_age_days,
+                            if detected {
+                                " and no installed application with a matching name was found"
+                            } else {
+                                "; installed applications could not be checked"
+                            }
+                        ),
+                        name: candidate.name,
+                        path: candidate.path.to_string_lossy().to_string(),
+                        size: size.allocated,
+                        file_count: size.files,
+                        last_modified: candidate.last_modified,
+                        confidence,
+                    });
+                }
+            }
+            (leftovers, detected)
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))?;
+        if operation.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+
+        if let Err(e) = state.dir_size_cache.flush() {
+            warn!("Failed to save directory size cache: {}", e);
+        }
+
+        leftovers.sort_by_key(|leftover| Reverse(leftover.size));
+        let total_size = leftovers.iter().map(|leftover| leftover.size).sum();
+        operation.complete(OperationTally::completed(leftovers.len() as u64, total_size));
+
+        Ok(AppLeftoverReport {
+            leftovers,
+            total_size,
+            min_age_days,
+            installed_apps_detected,
+        })
+    })
+    .await
+}
+
+/// Locate device backups: iOS backups made by iTunes or Finder on Windows
+/// and macOS, and Samsung Smart Switch backups. Backups not written for
+/// `stale_after_months` months are flagged as cleanup candidates; nothing
+/// is deleted here.
+#[command]
+pub async fn find_device_backups(
+    state: State<'_, AppState>,
+    stale_after_months: Option<u32>,
+) -> AppResult<DeviceBackupReport> {
+    timed_command("find_device_backups", Some(state.inner()), None, async {
+        let home = home::home_dir()
+            .ok_or_else(|| AppError::SystemError("Unable to determine home directory".to_string()))?;
+        let stale_after_months = stale_after_months.unwrap_or(DEFAULT_DEVICE_BACKUP_STALE_MONTHS);
+        let cutoff = Utc::now() - chrono::Duration::days(i64::from(stale_after_months) * 30);
+        let ttl_seconds = state.get_config().await.analysis.size_cache_ttl_seconds;
+
+        let mut operation = state.operations.start(OperationKind::Scan, "device backups");
+        let cancel = operation.token();
+        let sizes = state.dir_size_cache.clone();
+        let mut backups = tokio::task::spawn_blocking(move || {
+            let mut backups = Vec::new();
+            for location in device_backups::locations(&home) {
+                for candidate in device_backups::backups(&location) {
+                    if cancel.is_cancelled() {
+                        return backups;
+                    }
+                    let size = sizes.measure(&candidate.path, ttl_seconds, &cancel);
+                    backups.push(DeviceBackup {
+                        kind: candidate.kind,
+                        device_name: candidate.device_name,
+                        path: candidate.path.to_string_lossy().to_string(),
+                        size: size.allocated,
+                        file_count: size.files,
+                        // A backup of unknown age is never suggested
+                        stale: candidate.last_backup.is_some_and(|last| last < cutoff),
+                        last_backup: candidate.last_backup,
+                    });
+                }
+            }
+            backups
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))?;
+        if operation.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+
+        if let Err(e) = state.dir_size_cache.flush() {
+            warn!("Failed to save directory size cache: {}", e);
+        }
+
+        backups.sort_by_key(|backup| Reverse(backup.size));
+        let total_size = backups.iter().map(|backup| backup.size).sum();
+        let stale_size = backups
+            .iter()
+            .filter(|backup| backup.stale)
+            .map(|backup| backup.size)
+            .sum();
+        operation.complete(OperationTally::completed(backups.len() as u64, total_size));
+
+        Ok(DeviceBackupReport {
+            backups,
+            total_size,
+            stale_size,
+            stale_after_months,
+            category: FileCategory::Backup,
+            warning: "Deleting a device backup cannot be undone, and it may be the only copy \
+                      of photos, messages and app data that are no longer on the device. \
+                      Make sure a newer backup exists before removing one."
+                .to_string(),
+        })
+    })
+    .await
+}
+
+/// Locate crash dumps, core files and crash reports in the platform's crash
+/// folders (`/var/crash`, `~/Library/Logs/DiagnosticReports`,
+/// `%LOCALAPPDATA%\CrashDumps` and the like), plus files named like dumps
+/// (`*.dmp`, `core.1234`, ...) below `path` when given. Dumps in root-owned
+/// folders are flagged so they can be removed with elevation.
+#[command]
+pub async fn find_crash_dumps(
+    state: State<'_, AppState>,
+    path: Option<String>,
+) -> AppResult<CrashDumpReport> {
+    timed_command("find_crash_dumps", Some(state.inner()), path.clone(), async {
+        let home = home::home_dir()
+            .ok_or_else(|| AppError::SystemError("Unable to determine home directory".to_string()))?;
+        let extra_root = path.as_deref().map(validate_scan_root).transpose()?;
+        let security = state.get_config().await.security;
+        let mut operation = state.operations.start(OperationKind::Scan, "crash dumps");
+        let cancel = operation.token();
+
+        let (mut dumps, locations, unreadable_entries) = tokio::task::spawn_blocking(move || {
+            let mut found: Vec<(PathBuf, fs::Metadata)> = Vec::new();
+            let mut searched = Vec::new();
+            let mut unreadable = 0u64;
+            let walk_options = || WalkOptions {
+                excluded_paths: security.global_exclusion_paths(),
+                ..WalkOptions::default()
+            };
+
+            for location in crash_dumps::locations(&home) {
+                let Ok(metadata) = fs::symlink_metadata(&location) else {
+                    continue;
+                };
+                searched.push(location.to_string_lossy().to_string());
+                if metadata.is_file() {
+                    found.push((location, metadata));
+                } else if metadata.is_dir() {
+                    let mut walker = DirWalker::new(&location, walk_options());
+                    for entry in walker.by_ref() {
+                        if cancel.is_cancelled() {
+                            break;
+                        }
+                        if entry.is_file() {
+                            found.push((entry.path, entry.metadata));
+                        }
+                    }
+                    unreadable += walker.error_count();
+                }
+            }
+            if let Some(root) = extra_root {
+                let mut walker = DirWalker::new(&root, walk_options());
+                for entry in walker.by_ref() {
+                    if cancel.is_cancelled() {
+                        break;
+                    }
+                    let is_dump = entry.is_file()
+                        && entry
+                            .path
+                            .file_name()
+                            .is_some_and(|name| crash_dumps::is_dump_name(&name.to_string_lossy()));
+                    if is_dump && !found.iter().any(|(path, _)| *path == entry.path) {
+                        found.push((entry.path, entry.metadata));
+                    }
+                }
+                unreadable += walker.error_count();
+            }
+
+            let dumps: Vec<CrashDump> = found
+                .into_iter()
+                .map(|(path, metadata)| CrashDump {
+                    size: allocated_size(&path, &metadata),
+                    modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+                    category: FileCategory::Log,
+                    confidence: CRASH_DUMP_CONFIDENCE,
+                    requires_elevation: elevation_required(&path),
+                    blocked_reason: SecurityValidator::validate_deletion_target(&path, &security)
+                        .err(),
+                    path: path.to_string_lossy().to_string(),
+                })
+                .collect();
+            (dumps, searched, unreadable)
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))?;
+        if operation.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+
+        dumps.sort_by_key(|dump| Reverse(dump.size));
+        let total_size = dumps.iter().map(|dump| dump.size).sum();
+        let elevated_size = dumps
+            .iter()
+            .filter(|dump| dump.requires_elevation.is_some())
+            .map(|dump| dump.size)
+            .sum();
+        operation.complete(OperationTally::completed(dumps.len() as u64, total_size));
+
+        Ok(CrashDumpReport {
+            dumps,
+            total_size,
+            elevated_size,
+            locations,
+            unreadable_entries,
+        })
+    })
+    .await
+}
+
+/// Plan deleting crash dumps reported by `find_crash_dumps`. Dumps in a
+/// platform crash folder are confined to that folder; other dumps must lie
+/// inside the confirmed scan root and be named like a dump. With `elevate`,
+/// dumps the current user cannot remove are planned for the OS elevation
+/// prompt; without it they are refused. Nothing is deleted until the plan's
+/// token is passed to `confirm_delete`.
+#[command]
+pub async fn clean_crash_dumps(
+    state: State<'_, AppState>,
+    paths: Vec<String>,
+    elevate: Option<bool>,
+) -> AppResult<DeletePlan> {
+    timed_command("clean_crash_dumps", Some(state.inner()), None, async {
+        let home = home::home_dir()
+            .ok_or_else(|| AppError::SystemError("Unable to determine home directory".to_string()))?;
+        let locations = crash_dumps::locations(&home);
+        let scan_root = state.scan_root().await;
+
+        let mut used_scan_root = false;
+        let mut batches: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+        for path in &paths {
+            let target = PathBuf::from(path);
+            let is_dump_name = target
+                .file_name()
+                .is_some_and(|name| crash_dumps::is_dump_name(&name.to_string_lossy()));
+            let root = match crash_dumps::location_root(&target, &locations) {
+                Some(root) => root,
+                None => match &scan_root {
+                    Some(root) if is_dump_name && target.starts_with(root) => {
+                        used_scan_root = true;
+                        root.clone()
+                    }
+                    _ => {
+                        return Err(AppError::SecurityError(format!(
+                            "{} is neither in a crash dump folder nor a dump inside the \
+                             confirmed scan root",
+                            path
+                        )))
+                    }
+                },
+            };
+            // Roots are compared with canonical parents, so resolve them too
+            let root = fs::canonicalize(&root).unwrap_or(root);
+            batches.entry(root).or_default().push(target);
+        }
+
+        let elevate = elevate.unwrap_or(false);
+        let batches = tokio::task::spawn_blocking(move || {
+            let mut split = Vec::new();
+            for (root, targets) in batches {
+                let (elevated, plain): (Vec<PathBuf>, Vec<PathBuf>) = targets
+                    .into_iter()
+                    .partition(|target| elevate && elevation_required(target).is_some());
+                for (paths, elevated) in [(plain, false), (elevated, true)] {
+                    if !paths.is_empty() {
+                        split.push(DeleteBatch {
+                            root: root.clone(),
+                            paths,
+                            elevated,
+                        });
+                    }
+                }
+            }
+            split
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))?;
+
+        issue_plan(
+            &state,
+            DeleteRequest {
+                scan_root: scan_root.filter(|_| used_scan_root),
+                label: "crash dumps".to_string(),
+                batches,
+                trash: Vec::new(),
+                mode: DeleteMode::default(),
+            },
+        )
+        .await
+    })
+    .await
+}
+
+/// Locate the file manager's thumbnail caches: `~/.cache/thumbnails` on
+/// Linux, the `thumbcache_*.db` files of Explorer on Windows and QuickLook's
+/// thumbnail cache on macOS, plus `Thumbs.db` files below `path` when given.
+/// Thumbnails are regenerated on demand, so all of it can be cleaned.
+#[command]
+pub async fn find_thumbnail_caches(
+    state: State<'_, AppState>,
+    path: Option<String>,
+) -> AppResult<ThumbnailCacheReport> {
+    timed_command("find_thumbnail_caches", Some(state.inner()), path.clone(), async {
+        let home = home::home_dir()
+            .ok_or_else(|| AppError::SystemError("Unable to determine home directory".to_string()))?;
+        let extra_root = path.as_deref().map(validate_scan_root).transpose()?;
+        let security = state.get_config().await.security;
+        let mut operation = state.operations.start(OperationKind::Scan, "thumbnail caches");
+        let cancel = operation.token();
+
+        let (mut caches, mut stray_files, unreadable_entries) =
+            tokio::task::spawn_blocking(move || {
+                let mut caches = Vec::new();
+                let mut unreadable = 0u64;
+                let walk_options = || WalkOptions {
+                    excluded_paths: security.global_exclusion_paths(),
+                    ..WalkOptions::default()
+                };
+
+                for location in thumbnail_caches::locations(&home) {
+                    if !fs::symlink_metadata(&location.path).is_ok_and(|m| m.is_dir()) {
+                        continue;
+                    }
+                    let (files, errors) = thumbnail_cache_files(&location, &cancel);
+                    unreadable += errors;
+                    if files.is_empty() {
+                        continue;
+                    }
+                    let samples: Vec<PathBuf> = match location.file_prefix {
+                        // The shared database files are what Explorer keeps open
+                        Some(_) => files.iter().map(|(path, _)| path.clone()).collect(),
+                        None => browser_caches::sample_files(&location.path),
+                    };
+                    let in_use_by = locking_processes_any(&samples).unwrap_or_else(|e| {
+                        warn!("Failed to check whether {} is in use: {}", location.name, e);
+                        Vec::new()
+                    });
+                    caches.push(ThumbnailCache {
+                        name: location.name.to_string(),
+                        path: location.path.to_string_lossy().to_string(),
+                        size: files
+                            .iter()
+                            .map(|(path, metadata)| allocated_size(path, metadata))
+                            .sum(),
+                        file_count: files.len() as u64,
+                        in_use_by,
+                    });
+                }
+
+                let mut stray_files = Vec::new();
+                if let Some(root) = extra_root {
+                    let mut walker = DirWalker::new(&root, walk_options());
+                    for entry in walker.by_ref() {
+                        if cancel.is_cancelled() {
+                            break;
+                        }
+                        let is_thumbs_db = entry.is_file()
+                            && entry.path.file_name().is_some_and(|name| {
+                                thumbnail_caches::is_thumbs_db(&name.to_string_lossy())
+                            });
+                        if is_thumbs_db {
+                            stray_files.push(ThumbnailFile {
+                                size: allocated_size(&entry.path, &entry.metadata),
+                                path: entry.path.to_string_lossy().to_string(),
+                            });
+                        }
+                    }
+                    unreadable += walker.error_count();
+                }
+                (caches, stray_files, unreadable)
+            })
+            .await
+            .map_err(|e| AppError::SystemError(e.to_string()))?;
+        if operation.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+
+        caches.sort_by_key(|cache| Reverse(cache.size));
+        stray_files.sort_by_key(|file| Reverse(file.size));
+        let total_size = caches.iter().map(|cache| cache.size).sum::<u64>()
+            + stray_files.iter().map(|file| file.size).sum::<u64>();
+        let file_manager = if cfg!(target_os = "windows") {
+            "Explorer"
+        } else if cfg!(target_os = "macos") {
+            "Finder"
+        } else {
+            "the file manager"
+        };
+        let in_use: Vec<LockingProcess> =
+            caches.iter().flat_map(|cache| cache.in_use_by.clone()).collect();
+        let note = if in_use.is_empty() {
+            format!(
+                "Thumbnails are regenerated on demand. Clean while {} is idle, since \
+                 cache files it holds open cannot be removed",
+                file_manager
+            )
+        } else {
+            format!(
+                "Thumbnail caches are in use by {}; files held open will fail to delete \
+                 until {} is idle or restarted",
+                describe_processes(&in_use),
+                file_manager
+            )
+        };
+        operation.complete(OperationTally::completed(
+            (caches.len() + stray_files.len()) as u64,
+            total_size,
+        ));
+
+        Ok(ThumbnailCacheReport {
+            caches,
+            stray_files,
+            total_size,
+            category: FileCategory::Cache,
+            confidence: THUMBNAIL_CACHE_CONFIDENCE,
+            unreadable_entries,
+            note,
+        })
+    })
+    .await
+}
+
+/// Plan deleting thumbnail caches reported by `find_thumbnail_caches`. A
+/// cache `path` covers every cache file in it, confined to that folder; a
+/// stray `Thumbs.db` must lie inside the confirmed scan root. Nothing is
+/// deleted until the plan's token is passed to `confirm_delete`, and files
+/// the file manager holds open then are reported as failed rather than
+/// retried.
+#[command]
+pub async fn clean_thumbnail_caches(
+    state: State<'_, AppState>,
+    paths: Vec<String>,
+) -> AppResult<DeletePlan> {
+    timed_command("clean_thumbnail_caches", Some(state.inner()), None, async {
+        let home = home::home_dir()
+            .ok_or_else(|| AppError::SystemError("Unable to determine home directory".to_string()))?;
+        let locations = thumbnail_caches::locations(&home);
+        let scan_root = state.scan_root().await;
+
+        let mut cache_locations = Vec::new();
+        let mut stray_files = Vec::new();
+        for path in &paths {
+            let target = PathBuf::from(path);
+            if let Some(location) = locations.iter().find(|location| location.path == target) {
+                cache_locations.push(location.clone());
+                continue;
+            }
+            let is_thumbs_db = target
+                .file_name()
+                .is_some_and(|name| thumbnail_caches::is_thumbs_db(&name.to_string_lossy()));
+            match &scan_root {
+                Some(root) if is_thumbs_db && target.starts_with(root) => {
+                    stray_files.push(target);
+                }
+                _ => {
+                    return Err(AppError::SecurityError(format!(
+                        "{} is neither a thumbnail cache nor a Thumbs.db inside the \
+                         confirmed scan root",
+                        path
+                    )))
+                }
+            }
+        }
+
+        let mut operation = state.operations.start(OperationKind::Scan, "thumbnail caches");
+        let cancel = operation.token();
+        let used_scan_root = !stray_files.is_empty();
+        let stray_root = scan_root.clone().filter(|_| used_scan_root);
+        let batches = tokio::task::spawn_blocking(move || {
+            let mut batches = Vec::new();
+            for location in cache_locations {
+                let (files, _) = thumbnail_cache_files(&location, &cancel);
+                // Roots are compared with canonical parents, so resolve them too
+                let root = fs::canonicalize(&location.path).unwrap_or(location.path);
+                batches.push(DeleteBatch {
+                    root,
+                    paths: files.into_iter().map(|(path, _)| path).collect(),
+                    elevated: false,
+                });
+            }
+            if let Some(root) = stray_root {
+                batches.push(DeleteBatch {
+                    root,
+                    paths: stray_files,
+                    elevated: false,
+                });
+            }
+            batches
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))?;
+        if operation.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+
+        let plan = issue_plan(
+            &state,
+            DeleteRequest {
+                scan_root: scan_root.filter(|_| used_scan_root),
+                label: "thumbnail caches".to_string(),
+                batches,
+                trash: Vec::new(),
+                mode: DeleteMode::default(),
+            },
+        )
+        .await?;
+        operation.complete(OperationTally::completed(
+            plan.paths.len() as u64,
+            plan.reclaimable_bytes,
+        ));
+        Ok(plan)
+    })
+    .await
+}
+
+/// Plan permanently removing trash items deleted more than
+/// `older_than_days` ago, keeping newer ones as a recovery window. Items
+/// whose deletion date the platform does not record (everything on macOS)
+/// are kept unless `confirm_undated` is set. Nothing is removed until the
+/// plan's token is passed to `confirm_delete`.
+#[command]
+pub async fn empty_old_trash(
+    state: State<'_, AppState>,
+    older_than_days: u64,
+    confirm_undated: Option<bool>,
+) -> AppResult<TrashPurgeReport> {
+    timed_command("empty_old_trash", Some(state.inner()), None, async {
+        let confirm_undated = confirm_undated.unwrap_or(false);
+        let cutoff = i64::try_from(older_than_days)
+            .ok()
+            .and_then(chrono::Duration::try_days)
+            .and_then(|age| Utc::now().checked_sub_signed(age))
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+
+        let mut operation = state.operations.start(OperationKind::Scan, "trash");
+        let cancel = operation.token();
+        let (entries, cancelled) = tokio::task::spawn_blocking(move || {
+            trash_bin::list(&cancel).map(|entries| (entries, cancel.is_cancelled()))
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))?
+        .map_err(AppError::FileSystemError)?;
+
+        let undated_count = entries.iter().filter(|e| e.deleted_at.is_none()).count() as u64;
+        let (eligible, retained): (Vec<TrashEntry>, Vec<TrashEntry>) =
+            entries.into_iter().partition(|entry| match entry.deleted_at {
+                Some(deleted_at) => deleted_at <= cutoff,
+                None => confirm_undated,
+            });
+        let eligible_items: Vec<TrashedItem> = eligible
+            .iter()
+            .map(|entry| TrashedItem {
+                path: entry.display_path(),
+                size: entry.size,
+                deleted_at: entry.deleted_at,
+            })
+            .collect();
+        let eligible_bytes = eligible_items.iter().map(|item| item.size).sum();
+
+        let plan = issue_plan(
+            &state,
+            DeleteRequest {
+                label: "trash".to_string(),
+                trash: eligible,
+                ..DeleteRequest::default()
+            },
+        )
+        .await?;
+        operation.complete(OperationTally::completed(
+            eligible_items.len() as u64,
+            eligible_bytes,
+        ));
+
+        Ok(TrashPurgeReport {
+            older_than_days,
+            eligible: eligible_items,
+            eligible_bytes,
+            retained_count: retained.len() as u64,
+            retained_bytes: retained.iter().map(|entry| entry.size).sum(),
+            undated_count,
+            note: (undated_count > 0 && !confirm_undated).then(|| {
+                format!(
+                    "{} items have no deletion date on this platform and were kept; \
+                     confirm to treat them as old enough",
+                    undated_count
+                )
+            }),
+            cancelled,
+            plan,
+        })
+    })
+    .await
+}
+
+fn dev_cache(location: DevCacheLocation, size: u64, file_count: u64) -> DevCache {
+    DevCache {
+        note: format!(
+            "{} cache is regenerable; it is re-downloaded or rebuilt when next needed",
+            location.name
+        ),
+        name: location.name,
+        path: location.path.to_string_lossy().to_string(),
+        size,
+        file_count,
+        category: FileCategory::DevCache,
+    }
+}
+
+/// Cache folders of every built-in and configured browser, grouped by
+/// browser name in the order the browsers are listed
+fn browser_cache_locations(
+    home: &Path,
+    analysis: &AnalysisConfig,
+) -> Vec<(String, Vec<BrowserCacheLocation>)> {
+    let mut specs = browser_caches::known_browsers(home);
+    specs.extend(analysis.extra_browser_caches.iter().cloned());
+
+    let mut seen = HashSet::new();
+    let mut groups: Vec<(String, Vec<BrowserCacheLocation>)> = Vec::new();
+    for spec in &specs {
+        for location in browser_caches::locate(spec, home) {
+            if !seen.insert(location.path.clone()) {
+                continue;
+            }
+            match groups.iter_mut().find(|(name, _)| *name == location.browser) {
+                Some((_, locations)) => locations.push(location),
+                None => groups.push((location.browser.clone(), vec![location])),
+            }
+        }
+    }
+    groups
+}
+
+fn browser_cache_group(
+    browser: String,
+    caches: Vec<BrowserCache>,
+    in_use_by: Vec<LockingProcess>,
+) -> BrowserCacheGroup {
+    let note = if in_use_by.is_empty() {
+        format!(
+            "{} cache is regenerable; close {} before cleaning so it does not hold or \
+             rewrite cache files. Cookies and other profile data are not included",
+            browser, browser
+        )
+    } else {
+        format!(
+            "{} is running ({}); close it before cleaning its cache",
+            browser,
+            describe_processes(&in_use_by)
+        )
+    };
+    BrowserCacheGroup {
+        total_size: caches.iter().map(|cache| cache.size).sum(),
+        browser,
+        caches,
+        in_use_by,
+        category: FileCategory::Cache,
+        note,
+    }
+}
+
+/// Files of the thumbnail cache at `location`, with the number of entries
+/// that could not be read
+fn thumbnail_cache_files(
+    location: &ThumbnailCacheLocation,
+    cancel: &CancellationToken,
+) -> (Vec<(PathBuf, fs::Metadata)>, u64) {
+    let mut walker = DirWalker::new(&location.path, WalkOptions::default());
+    let mut files = Vec::new();
+    for entry in walker.by_ref() {
+        if cancel.is_cancelled() {
+            break;
+        }
+        if entry.is_file() && location.holds(&entry.path) {
+            files.push((entry.path, entry.metadata));
+        }
+    }
+    (files, walker.error_count())
+}
+
+/// "name (pid)" for each process, comma separated
+fn describe_processes(processes: &[LockingProcess]) -> String {
+    processes
+        .iter()
+        .map(|process| format!("{} ({})", process.name, process.pid))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn large_cache_file(file: OversizedCacheFile) -> LargeCacheFile {
+    LargeCacheFile {
+        note: match &file.app {
+            Some(app) => format!("Large {} cache file; {} recreates it when needed", app, app),
+            None => "Large cache file; recreated by its application when needed".to_string(),
+        },
+        path: file.path.to_string_lossy().to_string(),
+        size: file.size,
+        app: file.app,
+        category: FileCategory::Cache,
+        confidence: LARGE_CACHE_FILE_CONFIDENCE,
+    }
+}
+
+/// Return link details if the symlink (or junction) target does not resolve
+fn broken_symlink(link: &Path) -> Option<BrokenSymlink> {
+    if !is_broken_symlink(link) {
+        return None;
+    }
+
+    let target = fs::read_link(link).ok()?;
+
+    Some(BrokenSymlink {
+        link_path: link.to_string_lossy().to_string(),
+        target: target.to_string_lossy().to_string(),
+        is_directory_link: is_directory_link(link),
+    })
+}
+
+#[cfg(windows)]
+fn is_directory_link(link: &Path) -> bool {
+    use std::os::windows::fs::FileTypeExt;
+
+    fs::symlink_metadata(link)
+        .map(|m| m.file_type().is_symlink_dir())
+        .unwrap_or(false)
+}
+
+#[cfg(not(windows))]
+fn is_directory_link(_link: &Path) -> bool {
+    // Unix symlinks carry no file/directory distinction of their own
+    false
+}
@@ -0,0 +1,535 @@
+use crate::commands::scan::ScanOptions;
+use crate::utils::app_bundles::{self, AncillaryKind};
+use crate::utils::logging::timed_command;
+use crate::utils::operations::{OperationKind, OperationTally};
+use crate::utils::security::validate_scan_root;
+use crate::utils::walker::{allocated_size, hardlink_id, DirWalker, SkippedPath, WalkOptions};
+use crate::{AppError, AppResult, AppState};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tauri::{command, State};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+const DEFAULT_DIRECTORY_LIMIT: usize = 20;
+/// A file counts as sparse once at least this much of its apparent size is
+/// not allocated, which block rounding alone never explains
+const SPARSE_MIN_UNALLOCATED_BYTES: u64 = 1024 * 1024;
+/// Default bucket edges in days: a week, a month, six months and a year
+const DEFAULT_AGE_BOUNDARIES_DAYS: &[u64] = &[7, 30, 180, 365];
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LargeDirectory {
+    pub path: String,
+    /// Bytes in files directly inside this directory
+    pub direct_bytes: u64,
+    pub direct_files: u64,
+    /// Bytes in this directory and all of its subdirectories
+    pub total_bytes: u64,
+    pub total_files: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LargeDirectoryReport {
+    pub root: String,
+    /// Largest directories by recursive total, biggest first
+    pub directories: Vec<LargeDirectory>,
+    /// Extra hard links to already counted files, excluded from all totals
+    pub hardlinks_deduplicated: u64,
+    /// Hidden files and directories left out because of `skip_hidden`
+    pub hidden_skipped: u64,
+    pub unreadable_entries: u64,
+    pub skipped: Vec<SkippedPath>,
+}
+
+/// A file occupying noticeably less disk than its apparent size: sparse
+/// (VM images, databases) or compressed by the filesystem
+#[derive(Debug, Serialize)]
+pub struct SparseFile {
+    pub path: String,
+    pub apparent_size: u64,
+    /// Bytes actually allocated on disk; what deleting the file frees
+    pub allocated_size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SparseFileReport {
+    pub root: String,
+    /// Biggest gap between apparent and allocated size first
+    pub files: Vec<SparseFile>,
+    pub total_apparent_size: u64,
+    pub total_allocated_size: u64,
+    /// Hidden files and directories left out because of `skip_hidden`
+    pub hidden_skipped: u64,
+    pub unreadable_entries: u64,
+    pub skipped: Vec<SkippedPath>,
+}
+
+/// Data an app keeps outside its bundle
+#[derive(Debug, Serialize)]
+pub struct AppAncillaryData {
+    pub kind: AncillaryKind,
+    pub path: String,
+    /// Bytes on disk; a container's figure leaves out its cache, which is
+    /// listed separately
+    pub size: u64,
+    /// Caches can be cleared without uninstalling the app
+    pub removable: bool,
+}
+
+/// Space one application takes: its bundle plus caches and container
+#[derive(Debug, Serialize)]
+pub struct AppFootprint {
+    pub name: String,
+    pub bundle_path: String,
+    pub bundle_id: Option<String>,
+    /// Removing the bundle is an uninstall and is never suggested here
+    pub bundle_size: u64,
+    pub ancillary: Vec<AppAncillaryData>,
+    pub removable_size: u64,
+    pub total_size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppFootprintReport {
+    pub applications_dir: String,
+    /// Largest total footprint first
+    pub apps: Vec<AppFootprint>,
+    pub total_bundle_size: u64,
+    pub total_removable_size: u64,
+}
+
+/// Files last modified within `[min_age_days, max_age_days)` days ago
+#[derive(Debug, Serialize)]
+pub struct AgeBucket {
+    pub label: String,
+    pub min_age_days: u64,
+    /// `None` for the open-ended oldest bucket
+    pub max_age_days: Option<u64>,
+    pub file_count: u64,
+    pub total_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AgeHistogram {
+    pub root: String,
+    /// Youngest bucket first
+    pub buckets: Vec<AgeBucket>,
+    /// Files whose modification time could not be read
+    pub unknown_age_files: u64,
+    pub unknown_age_bytes: u64,
+    /// Hidden files and directories left out because of `skip_hidden`
+    pub hidden_skipped: u64,
+    pub unreadable_entries: u64,
+    pub skipped: Vec<SkippedPath>,
+}
+
+/// Bucket the files below `path` by how long ago they were last modified.
+///
+/// `boundaries_days` lists the bucket edges in ascending order; files with a
+/// modification time in the future (clock skew) count as the most recent.
+#[command]
+pub async fn age_histogram(
+    state: State<'_, AppState>,
+    path: String,
+    boundaries_days: Option<Vec<u64>>,
+    options: Option<ScanOptions>,
+) -> AppResult<AgeHistogram> {
+    timed_command("age_histogram", Some(state.inner()), Some(path.clone()), async {
+        let root = validate_scan_root(&path)?;
+        let boundaries =
+            boundaries_days.unwrap_or_else(|| DEFAULT_AGE_BOUNDARIES_DAYS.to_vec());
+        if boundaries.is_empty() || boundaries.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(AppError::ConfigError(
+                "Age boundaries must be a non-empty, strictly increasing list of days".to_string(),
+            ));
+        }
+        let security = state.get_config().await.security;
+        let walk_options = options.unwrap_or_default().walk_options(&security);
+        let mut operation = state.operations.start(OperationKind::Scan, &path);
+        let cancel = operation.token();
+
+        let root_str = path.clone();
+        let histogram = tokio::task::spawn_blocking(move || {
+            bucket_by_age(&root_str, &root, walk_options, &boundaries, &cancel)
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))??;
+
+        operation.complete(OperationTally {
+            succeeded: histogram.buckets.iter().map(|b| b.file_count).sum::<u64>()
+                + histogram.unknown_age_files,
+            failed: histogram.unreadable_entries,
+            bytes: histogram.buckets.iter().map(|b| b.total_bytes).sum::<u64>()
+                + histogram.unknown_age_bytes,
+            errors: Vec::new(),
+        });
+        Ok(histogram)
+    })
+    .await
+}
+
+fn bucket_by_age(
+    root_str: &str,
+    root: &Path,
+    options: WalkOptions,
+    boundaries: &[u64],
+    cancel: &CancellationToken,
+) -> AppResult<AgeHistogram> {
+    let mut buckets: Vec<AgeBucket> = (0..=boundaries.len())
+        .map(|i| {
+            let min_age_days = if i == 0 { 0 } else { boundaries[i - 1] };
+            let max_age_days = boundaries.get(i).copied();
+            AgeBucket {
+                label: age_label(min_age_days, max_age_days),
+                min_age_days,
+                max_age_days,
+                file_count: 0,
+                total_bytes: 0,
+            }
+        })
+        .collect();
+    let mut unknown_age_files = 0u64;
+    let mut unknown_age_bytes = 0u64;
+    let now = Utc::now();
+
+    let mut walker = DirWalker::new(root, options);
+    for entry in walker.by_ref() {
+        if cancel.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+        if !entry.is_file() {
+            continue;
+        }
+
+        let size = entry.metadata.len();
+        let Some(modified) = entry.metadata.modified().ok().map(DateTime::<Utc>::from) else {
+            unknown_age_files += 1;
+            unknown_age_bytes += size;
+            continue;
+        };
+
+        // Future timestamps give a negative age, which clamps into the first bucket
+        let age_days = (now - modified).num_days().max(0) as u64;
+        let index = boundaries.partition_point(|&edge| edge <= age_days);
+        buckets[index].file_count += 1;
+        buckets[index].total_bytes += size;
+    }
+
+    Ok(AgeHistogram {
+        root: root_str.to_string(),
+        buckets,
+        unknown_age_files,
+        unknown_age_bytes,
+        hidden_skipped: walker.hidden_count(),
+        unreadable_entries: walker.error_count(),
+        skipped: walker.into_skipped(),
+    })
+}
+
+fn age_label(min_age_days: u64, max_age_days: Option<u64>) -> String {
+    match max_age_days {
+        Some(max) if min_age_days == 0 => format!("< {} days", max),
+        Some(max) => format!("{}-{} days", min_age_days, max),
+        None => format!("{}+ days", min_age_days),
+    }
+}
+
+/// Find sparse and filesystem-compressed files below `path`, reporting both
+/// their apparent size and the space they really occupy, so thin-provisioned
+/// disk images are not mistaken for huge reclaimable files
+#[command]
+pub async fn find_sparse_files(
+    state: State<'_, AppState>,
+    path: String,
+    options: Option<ScanOptions>,
+) -> AppResult<SparseFileReport> {
+    timed_command("find_sparse_files", Some(state.inner()), Some(path.clone()), async {
+        let root = validate_scan_root(&path)?;
+        let security = state.get_config().await.security;
+        let walk_options = options.unwrap_or_default().walk_options(&security);
+        let mut operation = state.operations.start(OperationKind::Scan, &path);
+        let cancel = operation.token();
+
+        let root_str = path.clone();
+        let report = tokio::task::spawn_blocking(move || {
+            collect_sparse_files(&root_str, &root, walk_options, &cancel)
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))??;
+
+        info!(
+            "Found {} sparse files under {} ({} bytes apparent, {} allocated)",
+            report.files.len(),
+            path,
+            report.total_apparent_size,
+            report.total_allocated_size
+        );
+
+        operation.complete(OperationTally {
+            succeeded: report.files.len() as u64,
+            failed: report.unreadable_entries,
+            bytes: report.total_allocated_size,
+            errors: Vec::new(),
+        });
+        Ok(report)
+    })
+    .await
+}
+
+fn collect_sparse_files(
+    root_str: &str,
+    root: &Path,
+    options: WalkOptions,
+    cancel: &CancellationToken,
+) -> AppResult<SparseFileReport> {
+    let mut files = Vec::new();
+    let mut walker = DirWalker::new(root, options);
+    for entry in walker.by_ref() {
+        if cancel.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+        if !entry.is_file() {
+            continue;
+        }
+
+        let apparent_size = entry.metadata.len();
+        let allocated_size = allocated_size(&entry.path, &entry.metadata);
+        if allocated_size.saturating_add(SPARSE_MIN_UNALLOCATED_BYTES) <= apparent_size {
+            files.push(SparseFile {
+                path: entry.path.to_string_lossy().to_string(),
+                apparent_size,
+                allocated_size,
+            });
+        }
+    }
+    files.sort_by_key(|file| Reverse(file.apparent_size - file.allocated_size));
+
+    Ok(SparseFileReport {
+        root: root_str.to_string(),
+        total_apparent_size: files.iter().map(|file| file.apparent_size).sum(),
+        total_allocated_size: files.iter().map(|file| file.allocated_size).sum(),
+        files,
+        hidden_skipped: walker.hidden_count(),
+        unreadable_entries: walker.error_count(),
+        skipped: walker.into_skipped(),
+    })
+}
+
+/// Size of each `.app` bundle in `applications_dir` (`/Applications` by
+/// default) together with its `~/Library/Caches` and `~/Library/Containers`
+/// data, so the space an app really takes is visible. Only the caches are
+/// flagged as removable. Always `None` outside macOS.
+#[command]
+pub async fn find_app_footprints(
+    state: State<'_, AppState>,
+    applications_dir: Option<String>,
+) -> AppResult<Option<AppFootprintReport>> {
+    timed_command("find_app_footprints", Some(state.inner()), applications_dir.clone(), async {
+        if !cfg!(target_os = "macos") {
+            return Ok(None);
+        }
+
+        let applications_dir = applications_dir.unwrap_or_else(|| "/Applications".to_string());
+        let dir = validate_scan_root(&applications_dir)?;
+        let home = home::home_dir()
+            .ok_or_else(|| AppError::SystemError("Unable to determine home directory".to_string()))?;
+        let ttl_seconds = state.get_config().await.analysis.size_cache_ttl_seconds;
+        let mut operation = state.operations.start(OperationKind::Scan, &applications_dir);
+        let cancel = operation.token();
+        let sizes = state.dir_size_cache.clone();
+
+        let mut apps = tokio::task::spawn_blocking(move || {
+            let mut apps = Vec::new();
+            for bundle in app_bundles::list_bundles(&dir) {
+                if cancel.is_cancelled() {
+                    break;
+                }
+                let bundle_size = sizes.measure(&bundle.path, ttl_seconds, &cancel).allocated;
+
+                let mut ancillary: Vec<AppAncillaryData> = Vec::new();
+                for (kind, path) in app_bundles::ancillary_locations(&bundle, &home) {
+                    let mut size = sizes.measure(&path, ttl_seconds, &cancel).allocated;
+                    if kind == AncillaryKind::Container {
+                        let cache_size: u64 = ancillary
+                            .iter()
+                            .filter(|data| data.kind == AncillaryKind::ContainerCache)
+                            .map(|data| data.size)
+                            .sum();
+                        size = size.saturating_sub(cache_size);
+                    }
+                    ancillary.push(AppAncillaryData {
+                        kind,
+                        path: path.to_string_lossy().to_string(),
+                        size,
+                        removable: kind.is_removable(),
+                    });
+                }
+
+                let removable_size = ancillary
+                    .iter()
+                    .filter(|data| data.removable)
+                    .map(|data| data.size)
+                    .sum();
+                let ancillary_size: u64 = ancillary.iter().map(|data| data.size).sum();
+                apps.push(AppFootprint {
+                    name: bundle.name,
+                    bundle_path: bundle.path.to_string_lossy().to_string(),
+                    bundle_id: bundle.bundle_id,
+                    bundle_size,
+                    ancillary,
+                    removable_size,
+                    total_size: bundle_size + ancillary_size,
+                });
+            }
+            apps
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))?;
+        if operation.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+
+        if let Err(e) = state.dir_size_cache.flush() {
+            warn!("Failed to save directory size cache: {}", e);
+        }
+
+        apps.sort_by_key(|app| Reverse(app.total_size));
+        let total_bundle_size = apps.iter().map(|app| app.bundle_size).sum();
+        let total_removable_size = apps.iter().map(|app| app.removable_size).sum();
+        operation.complete(OperationTally::completed(apps.len() as u64, total_removable_size));
+
+        Ok(Some(AppFootprintReport {
+            applications_dir,
+            apps,
+            total_bundle_size,
+            total_removable_size,
+        }))
+    })
+    .await
+}
+
+/// Top `limit` directories below `path` by aggregate size, counting each
+/// hard-linked file once
+#[command]
+pub async fn find_large_directories(
+    state: State<'_, AppState>,
+    path: String,
+    limit: Option<usize>,
+    options: Option<ScanOptions>,
+) -> AppResult<LargeDirectoryReport> {
+    timed_command("find_large_directories", Some(state.inner()), Some(path.clone()), async {
+        let root = validate_scan_root(&path)?;
+        let security = state.get_config().await.security;
+        let walk_options = options.unwrap_or_default().walk_options(&security);
+        let limit = limit.unwrap_or(DEFAULT_DIRECTORY_LIMIT);
+        let mut operation = state.operations.start(OperationKind::Scan, &path);
+        let cancel = operation.token();
+
+        let root_str = path.clone();
+        let report = tokio::task::spawn_blocking(move || {
+            measure_directories(&root_str, &root, walk_options, limit, &cancel)
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))??;
+
+        info!(
+            "Found {} large directories under {} ({} hard links deduplicated)",
+            report.directories.len(),
+            path,
+            report.hardlinks_deduplicated
+        );
+
+        operation.complete(OperationTally {
+            succeeded: report.directories.len() as u64,
+            failed: report.unreadable_entries,
+            ..OperationTally::default()
+        });
+        Ok(report)
+    })
+    .await
+}
+
+/// Walk `root` once, then roll direct sizes up into recursive totals from the
+/// deepest directories upwards
+fn measure_directories(
+    root_str: &str,
+    root: &Path,
+    options: WalkOptions,
+    limit: usize,
+    cancel: &CancellationToken,
+) -> AppResult<LargeDirectoryReport> {
+    let mut directories: HashMap<PathBuf, LargeDirectory> = HashMap::new();
+    let mut seen_links: HashSet<(u64, u64)> = HashSet::new();
+    let mut hardlinks_deduplicated = 0u64;
+
+    let mut walker = DirWalker::new(root, options);
+    for entry in walker.by_ref() {
+        if cancel.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+
+        if entry.is_dir() {
+            directories.entry(entry.path).or_default();
+            continue;
+        }
+        if !entry.is_file() {
+            continue;
+        }
+        if let Some(id) = hardlink_id(&entry.metadata) {
+            if !seen_links.insert(id) {
+                hardlinks_deduplicated += 1;
+                continue;
+            }
+        }
+
+        if let Some(parent) = entry.path.parent() {
+            let directory = directories.entry(parent.to_path_buf()).or_default();
+            directory.direct_bytes += entry.metadata.len();
+            directory.direct_files += 1;
+        }
+    }
+
+    let mut paths: Vec<PathBuf> = directories.keys().cloned().collect();
+    paths.sort_by_key(|path| Reverse(path.components().count()));
+
+    for path in &paths {
+        let Some(directory) = directories.get_mut(path) else {
+            continue;
+        };
+        directory.total_bytes += directory.direct_bytes;
+        directory.total_files += directory.direct_files;
+        let (bytes, files) = (directory.total_bytes, directory.total_files);
+
+        if path.as_path() == root {
+            continue;
+        }
+        if let Some(parent) = path.parent().and_then(|parent| directories.get_mut(parent)) {
+            parent.total_bytes += bytes;
+            parent.total_files += files;
+        }
+    }
+
+    let mut largest: Vec<LargeDirectory> = directories
+        .into_iter()
+        .filter(|(path, _)| path.as_path() != root)
+        .map(|(path, mut directory)| {
+            directory.path = path.to_string_lossy().to_string();
+            directory
+        })
+        .collect();
+    largest.sort_by_key(|directory| Reverse(directory.total_bytes));
+    largest.truncate(limit);
+
+    Ok(LargeDirectoryReport {
+        root: root_str.to_string(),
+        directories: largest,
+        hardlinks_deduplicated,
+        hidden_skipped: walker.hidden_count(),
+        unreadable_entries: walker.error_count(),
+        skipped: walker.into_skipped(),
+    })
+}
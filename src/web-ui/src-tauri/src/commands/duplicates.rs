@@ -0,0 +1,988 @@
+use crate::utils::config::{AnalysisConfig, RunOverrides};
+use crate::utils::dir_duplicates::DirTree;
+use crate::utils::hash_index::{HashIndex, IndexLookup};
+use crate::utils::hashing::{hamming_distance, hash_file, perceptual_hash};
+use crate::utils::keep_policy::{KeepCandidate, KeepPolicy, Keeper};
+use crate::utils::logging::timed_command;
+use crate::utils::operations::{ItemError, OperationGuard, OperationKind, OperationTally};
+use crate::utils::platform::is_case_insensitive_volume;
+use crate::utils::security::validate_scan_root;
+use crate::commands::scan::ScanOptions;
+use crate::utils::walker::{DirWalker, SkippedPath, WalkOptions};
+use crate::{AppError, AppResult, AppState};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{command, State};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// Candidate paths keyed by file size
+type SizeGroups = HashMap<u64, Vec<PathBuf>>;
+
+/// Default maximum Hamming distance between similar image hashes (of 64 bits)
+const DEFAULT_SIMILARITY_DISTANCE: u32 = 10;
+/// Similar-image groups stop accepting members beyond this size
+const MAX_SIMILAR_GROUP_SIZE: usize = 50;
+/// Each image is compared against at most this many open groups, newest
+/// first, so very large libraries stay roughly linear
+const MAX_GROUP_COMPARISONS: usize = 5000;
+/// Longest stretch of hashing between two saves of the hash index, which
+/// bounds the work an interrupted scan loses
+const HASH_INDEX_FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+/// Folder below the cache directory holding the size grouping of duplicate
+/// scans that have not finished
+const CHECKPOINT_DIR: &str = "duplicate_scans";
+/// Older checkpoints are walked again rather than trusted
+const CHECKPOINT_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Serialize)]
+pub struct DuplicateGroup {
+    pub hash: String,
+    pub size: u64,
+    pub paths: Vec<String>,
+    /// Copies the keep policy retains, best first
+    pub retained: Vec<String>,
+    /// The other copies, safe to delete once one is retained
+    pub to_remove: Vec<String>,
+    /// False when an `in_path` policy matched no copy and the newest were
+    /// retained instead
+    pub policy_applied: bool,
+    pub reclaimable_bytes: u64,
+}
+
+/// Work a duplicate scan skipped thanks to hashes kept from earlier runs
+#[derive(Debug, Default, Serialize)]
+pub struct HashReuse {
+    /// Files whose recorded hash was still valid and were not read
+    pub files: u64,
+    pub bytes: u64,
+    /// Recorded hashes dropped because the file changed since
+    pub stale: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DuplicateReport {
+    pub root: String,
+    pub groups: Vec<DuplicateGroup>,
+    pub keep: KeepPolicy,
+    pub keep_count: usize,
+    pub files_scanned: u64,
+    pub files_hashed: u64,
+    /// Files below `min_file_size`, never considered as duplicates
+    pub small_files: u64,
+    /// Hidden files and directories left out because of `skip_hidden`
+    pub hidden_skipped: u64,
+    pub reclaimable_bytes: u64,
+    pub skipped: Vec<SkippedPath>,
+    /// Whether the size grouping came from an unfinished earlier scan of the
+    /// same folder instead of a new walk
+    pub resumed_walk: bool,
+    pub hash_reuse: HashReuse,
+}
+
+/// Directories whose whole contents are identical
+#[derive(Debug, Serialize)]
+pub struct DuplicateDirectoryGroup {
+    pub hash: String,
+    /// Files in each copy, counting all subdirectories
+    pub file_count: u64,
+    /// Bytes in each copy
+    pub size: u64,
+    pub paths: Vec<String>,
+    pub reclaimable_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DuplicateDirectoryReport {
+    pub root: String,
+    pub groups: Vec<DuplicateDirectoryGroup>,
+    pub directories_scanned: u64,
+    pub files_hashed: u64,
+    pub unreadable_entries: u64,
+    pub reclaimable_bytes: u64,
+    pub skipped: Vec<SkippedPath>,
+    pub hash_reuse: HashReuse,
+}
+
+/// An image grouped with a representative, and how close it is to it
+#[derive(Debug, Serialize)]
+pub struct SimilarImage {
+    pub path: String,
+    pub size: u64,
+    pub distance: u32,
+    /// 1.0 for identical hashes, falling linearly with the Hamming distance
+    pub similarity: f32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SimilarImageGroup {
+    /// The largest image of the group, usually the one worth keeping
+    pub representative: String,
+    pub representative_size: u64,
+    pub similar: Vec<SimilarImage>,
+    pub reclaimable_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SimilarImageReport {
+    pub root: String,
+    pub max_distance: u32,
+    pub groups: Vec<SimilarImageGroup>,
+    pub images_hashed: u64,
+    /// Files that looked like images but could not be decoded
+    pub undecodable_images: u64,
+    /// Hidden files and directories left out because of `skip_hidden`
+    pub hidden_skipped: u64,
+    pub reclaimable_bytes: u64,
+    pub skipped: Vec<SkippedPath>,
+}
+
+/// A file in a name group
+#[derive(Debug, Serialize)]
+pub struct NamedFile {
+    pub path: String,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+    /// Whether the keep policy retains this file; the others are marked
+    /// for removal
+    pub retained: bool,
+}
+
+/// Files sharing a base name, whatever their contents
+#[derive(Debug, Serialize)]
+pub struct DuplicateNameGroup {
+    /// Name as spelled by the first file, in path order
+    pub name: String,
+    pub files: Vec<NamedFile>,
+    pub total_size: u64,
+    /// Bytes held by the files not retained
+    pub removable_bytes: u64,
+    /// False when an `in_path` policy matched no file and the newest were
+    /// retained instead
+    pub policy_applied: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DuplicateNameReport {
+    pub root: String,
+    /// Whether names differing only in case were grouped together
+    pub case_insensitive: bool,
+    pub groups: Vec<DuplicateNameGroup>,
+    pub keep: KeepPolicy,
+    pub keep_count: usize,
+    pub files_scanned: u64,
+    /// Files below `min_file_size`, left out of the groups
+    pub small_files: u64,
+    /// Hidden files and directories left out because of `skip_hidden`
+    pub hidden_skipped: u64,
+    pub skipped: Vec<SkippedPath>,
+}
+
+struct HashedImage {
+    path: PathBuf,
+    size: u64,
+    hash: u64,
+}
+
+/// Outcome of the hashing walk that precedes image grouping
+struct ImageScan {
+    images: Vec<HashedImage>,
+    undecodable: u64,
+    hidden_skipped: u64,
+    skipped: Vec<SkippedPath>,
+}
+
+/// Find files below `path` with identical contents.
+///
+/// Progress survives cancellation and restarts: the size grouping is kept
+/// in the cache directory until the scan finishes, and every hash is
+/// recorded in the hash index, so running the same scan again skips the
+/// walk and only reads files that were not hashed yet or have changed.
+///
+/// In each group `keep` (newest by default) picks the `keep_count` copies to
+/// retain, one by default, and the rest are listed for removal.
+#[command]
+pub async fn find_duplicates(
+    state: State<'_, AppState>,
+    path: String,
+    options: Option<ScanOptions>,
+    overrides: Option<RunOverrides>,
+    keep: Option<KeepPolicy>,
+    keep_count: Option<usize>,
+) -> AppResult<DuplicateReport> {
+    timed_command("find_duplicates", Some(state.inner()), Some(path.clone()), async {
+        let root = validate_scan_root(&path)?;
+        let keeper = Keeper::new(keep.unwrap_or_default(), keep_count.unwrap_or(1))
+            .map_err(AppError::ConfigError)?;
+        let overrides = overrides.unwrap_or_default();
+        let config = state.get_config().await;
+        let analysis = overrides
+            .apply(&config.analysis)
+            .map_err(|e| AppError::ConfigError(e.to_string()))?;
+        let options = options.unwrap_or_default();
+        let walk_options = options.walk_options(&config.security);
+        let min_file_size = options.min_file_size;
+        let checkpoint = checkpoint_path(&config.cache_directory, &root, &options);
+        let mut operation = state.operations.start(OperationKind::Analyze, &path);
+        let cancel = operation.token();
+
+        let walk_cancel = cancel.clone();
+        let walk_checkpoint = checkpoint.clone();
+        let (
+            SizeScan {
+                groups: size_groups,
+                files_scanned,
+                small_files,
+                hidden_skipped,
+                skipped,
+            },
+            resumed_walk,
+        ) = tokio::task::spawn_blocking(move || {
+            if let Some(scan) = load_checkpoint(&walk_checkpoint) {
+                return Ok((scan, true));
+            }
+            let scan = group_by_size(root, walk_options, min_file_size, &walk_cancel)?;
+            if let Err(e) = save_checkpoint(&walk_checkpoint, &scan) {
+                warn!("Failed to save duplicate scan checkpoint: {}", e);
+            }
+            Ok::<_, AppError>((scan, false))
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))??;
+        if resumed_walk {
+            info!("Resuming duplicate scan of {} from its checkpoint", path);
+        }
+
+        let candidates = size_groups
+            .into_iter()
+            .flat_map(|(size, paths)| paths.into_iter().map(move |path| (path, size)))
+            .collect();
+        let (hashed, errors, hash_reuse) =
+            hash_files(candidates, &analysis, &overrides, &state.hash_index, &operation).await;
+
+        let mut by_hash: HashMap<(String, u64), Vec<String>> = HashMap::new();
+        let files_hashed = hashed.len() as u64;
+        for (hash, size, path) in hashed {
+            by_hash
+                .entry((hash, size))
+                .or_default()
+                .push(path.to_string_lossy().to_string());
+        }
+
+        if cancel.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+        match fs::remove_file(&checkpoint) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                warn!("Failed to remove duplicate scan checkpoint: {}", e);
+            }
+            _ => {}
+        }
+
+        let (keep, keep_count) = (keeper.policy().clone(), keeper.count());
+        by_hash.retain(|_, paths| paths.len() > 1);
+        let mut groups: Vec<DuplicateGroup> = tokio::task::spawn_blocking(move || {
+            by_hash
+                .into_iter()
+                .map(|((hash, size), mut paths)| {
+                    paths.sort();
+                    keep_copies(&keeper, hash, size, paths)
+                })
+                .collect()
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))?;
+        groups.sort_by_key(|g| Reverse(g.reclaimable_bytes));
+
+        let reclaimable_bytes = groups.iter().map(|g| g.reclaimable_bytes).sum();
+        info!(
+            "Duplicate scan of {} found {} groups ({} bytes reclaimable, {} files not rehashed)",
+            path,
+            groups.len(),
+            reclaimable_bytes,
+            hash_reuse.files
+        );
+
+        operation.complete(OperationTally {
+            succeeded: files_scanned - errors.len() as u64,
+            failed: errors.len() as u64,
+            bytes: reclaimable_bytes,
+            errors,
+        });
+        Ok(DuplicateReport {
+            root: path,
+            groups,
+            keep,
+            keep_count,
+            files_scanned,
+            files_hashed,
+            small_files,
+            hidden_skipped,
+            reclaimable_bytes,
+            skipped,
+            resumed_walk,
+            hash_reuse,
+        })
+    })
+    .await
+}
+
+/// Find directories below `path` whose complete contents (file names,
+/// structure and file hashes) are identical, so a redundant copy can be
+/// removed as a whole. Nested duplicates inside a reported pair are folded
+/// into it. Directories that were only partly walked, e.g. because of
+/// `skip_hidden` or a skipped mount, are never reported; `min_file_size` is
+/// ignored since every file decides whether two trees match.
+#[command]
+pub async fn find_duplicate_directories(
+    state: State<'_, AppState>,
+    path: String,
+    options: Option<ScanOptions>,
+    overrides: Option<RunOverrides>,
+) -> AppResult<DuplicateDirectoryReport> {
+    timed_command("find_duplicate_directories", Some(state.inner()), Some(path.clone()), async {
+        let root = validate_scan_root(&path)?;
+        let overrides = overrides.unwrap_or_default();
+        let config = state.get_config().await;
+        let analysis = overrides
+            .apply(&config.analysis)
+            .map_err(|e| AppError::ConfigError(e.to_string()))?;
+        let walk_options = options.unwrap_or_default().walk_options(&config.security);
+        let mut operation = state.operations.start(OperationKind::Analyze, &path);
+        let cancel = operation.token();
+
+        let walk_cancel = cancel.clone();
+        let tree = tokio::task::spawn_blocking(move || {
+            DirTree::scan(&root, walk_options, &walk_cancel)
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))?
+        .ok_or(AppError::Cancelled)?;
+
+        // Only directories matching another in names, sizes and layout can be
+        // identical, so only their files are read
+        let candidates = tree.candidates();
+        let (hashed, errors, hash_reuse) = hash_files(
+            tree.files_below(&candidates),
+            &analysis,
+            &overrides,
+            &state.hash_index,
+            &operation,
+        )
+        .await;
+        if cancel.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+        let files_hashed = hashed.len() as u64;
+        let file_hashes: HashMap<PathBuf, String> =
+            hashed.into_iter().map(|(hash, _, path)| (path, hash)).collect();
+
+        let mut groups: Vec<DuplicateDirectoryGroup> = tree
+            .duplicate_groups(&candidates, &file_hashes)
+            .into_iter()
+            .map(|group| DuplicateDirectoryGroup {
+                reclaimable_bytes: group.total_size * (group.paths.len() as u64 - 1),
+                hash: group.hash,
+                file_count: group.file_count,
+                size: group.total_size,
+                paths: group
+                    .paths
+                    .iter()
+                    .map(|path| path.to_string_lossy().to_string())
+                    .collect(),
+            })
+            .collect();
+        groups.sort_by_key(|g| Reverse(g.reclaimable_bytes));
+
+        let reclaimable_bytes = groups.iter().map(|g| g.reclaimable_bytes).sum();
+        info!(
+            "Duplicate directory scan of {} found {} groups ({} bytes reclaimable)",
+            path,
+            groups.len(),
+            reclaimable_bytes
+        );
+
+        operation.complete(OperationTally {
+            succeeded: tree.directory_count(),
+            failed: tree.unreadable_entries + errors.len() as u64,
+            bytes: reclaimable_bytes,
+            errors,
+        });
+        Ok(DuplicateDirectoryReport {
+            root: path,
+            groups,
+            directories_scanned: tree.directory_count(),
+            files_hashed,
+            unreadable_entries: tree.unreadable_entries,
+            reclaimable_bytes,
+            skipped: tree.skipped,
+            hash_reuse,
+        })
+    })
+    .await
+}
+
+/// Group files below `path` by base name regardless of content, e.g. the
+/// many `IMG_0001.jpg` left by separate photo imports. Names are compared
+/// case-insensitively when `case_insensitive` is set, defaulting to how the
+/// volume holding `path` treats case. `keep` and `keep_count` mark the
+/// files of each group to retain as in `find_duplicates`.
+#[command]
+pub async fn find_duplicate_names(
+    state: State<'_, AppState>,
+    path: String,
+    case_insensitive: Option<bool>,
+    options: Option<ScanOptions>,
+    keep: Option<KeepPolicy>,
+    keep_count: Option<usize>,
+) -> AppResult<DuplicateNameReport> {
+    timed_command("find_duplicate_names", Some(state.inner()), Some(path.clone()), async {
+        let root = validate_scan_root(&path)?;
+        let keeper = Keeper::new(keep.unwrap_or_default(), keep_count.unwrap_or(1))
+            .map_err(AppError::ConfigError)?;
+        let config = state.get_config().await;
+        let options = options.unwrap_or_default();
+        let walk_options = options.walk_options(&config.security);
+        let min_file_size = options.min_file_size;
+        let mut operation = state.operations.start(OperationKind::Analyze, &path);
+        let cancel = operation.token();
+
+        let (case_insensitive, scan) = tokio::task::spawn_blocking(move || {
+            let case_insensitive =
+                case_insensitive.unwrap_or_else(|| is_case_insensitive_volume(&root));
+            let scan = group_by_name(&root, walk_options, min_file_size, case_insensitive, &cancel);
+            scan.map(|scan| (case_insensitive, scan))
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))??;
+
+        let mut groups: Vec<DuplicateNameGroup> = scan
+            .groups
+            .into_values()
+            .filter(|files| files.len() > 1)
+            .map(|mut files| {
+                files.sort_by(|a, b| a.path.cmp(&b.path));
+                let name = Path::new(&files[0].path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let candidates: Vec<KeepCandidate> = files
+                    .iter()
+                    .map(|file| KeepCandidate {
+                        path: &file.path,
+                        size: file.size,
+                        modified: file.modified,
+                    })
+                    .collect();
+                let selection = keeper.select(&candidates);
+                for index in selection.retained {
+                    files[index].retained = true;
+                }
+                DuplicateNameGroup {
+                    name,
+                    total_size: files.iter().map(|file| file.size).sum(),
+                    removable_bytes: files
+                        .iter()
+                        .filter(|file| !file.retained)
+                        .map(|file| file.size)
+                        .sum(),
+                    policy_applied: selection.policy_applied,
+                    files,
+                }
+            })
+            .collect();
+        groups.sort_by(|a, b| {
+            b.files
+                .len()
+                .cmp(&a.files.len())
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        info!("Duplicate name scan of {} found {} groups", path, groups.len());
+        operation.complete(OperationTally::completed(scan.files_scanned, 0));
+        Ok(DuplicateNameReport {
+            root: path,
+            case_insensitive,
+            groups,
+            keep: keeper.policy().clone(),
+            keep_count: keeper.count(),
+            files_scanned: scan.files_scanned,
+            small_files: scan.small_files,
+            hidden_skipped: scan.hidden_skipped,
+            skipped: scan.skipped,
+        })
+    })
+    .await
+}
+
+/// Outcome of the walk that groups files by name
+struct NameScan {
+    groups: HashMap<String, Vec<NamedFile>>,
+    files_scanned: u64,
+    small_files: u64,
+    hidden_skipped: u64,
+    skipped: Vec<SkippedPath>,
+}
+
+/// Group regular files of at least `min_file_size` bytes by base name
+fn group_by_name(
+    root: &Path,
+    options: WalkOptions,
+    min_file_size: u64,
+    case_insensitive: bool,
+    cancel: &CancellationToken,
+) -> AppResult<NameScan> {
+    let mut groups: HashMap<String, Vec<NamedFile>> = HashMap::new();
+    let mut files_scanned = 0u64;
+    let mut small_files = 0u64;
+
+    let mut walker = DirWalker::new(root, options);
+    for entry in walker.by_ref() {
+        if cancel.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+        if !entry.is_file() {
+            continue;
+        }
+        files_scanned += 1;
+
+        let size = entry.metadata.len();
+        if size < min_file_size {
+            small_files += 1;
+            continue;
+        }
+        let Some(name) = entry.path.file_name().map(|name| name.to_string_lossy()) else {
+            continue;
+        };
+        let key = if case_insensitive {
+            name.to_lowercase()
+        } else {
+            name.to_string()
+        };
+        groups.entry(key).or_default().push(NamedFile {
+            path: entry.path.to_string_lossy().to_string(),
+            size,
+            modified: entry.metadata.modified().ok().map(DateTime::<Utc>::from),
+            retained: false,
+        });
+    }
+
+    Ok(NameScan {
+        groups,
+        files_scanned,
+        small_files,
+        hidden_skipped: walker.hidden_count(),
+        skipped: walker.into_skipped(),
+    })
+}
+
+/// Split a group of identical copies into those `keeper` retains and those
+/// to remove, reading each copy's modification time
+fn keep_copies(keeper: &Keeper, hash: String, size: u64, paths: Vec<String>) -> DuplicateGroup {
+    let modified: Vec<Option<DateTime<Utc>>> = paths
+        .iter()
+        .map(|path| {
+            fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .map(DateTime::<Utc>::from)
+        })
+        .collect();
+    let candidates: Vec<KeepCandidate> = paths
+        .iter()
+        .zip(&modified)
+        .map(|(path, modified)| KeepCandidate {
+            path,
+            size,
+            modified: *modified,
+        })
+        .collect();
+    let selection = keeper.select(&candidates);
+
+    let retained: Vec<String> = selection
+        .retained
+        .iter()
+        .map(|&index| paths[index].clone())
+        .collect();
+    let to_remove: Vec<String> = paths
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !selection.retained.contains(index))
+        .map(|(_, path)| path.clone())
+        .collect();
+    DuplicateGroup {
+        reclaimable_bytes: size * to_remove.len() as u64,
+        hash,
+        size,
+        paths,
+        retained,
+        to_remove,
+        policy_applied: selection.policy_applied,
+    }
+}
+
+/// Where a file's hash in `hash_files` came from
+enum HashSource {
+    Index,
+    Read,
+    /// Read again because the indexed hash was stale
+    Reread,
+}
+
+/// Hash files with BLAKE3 concurrently, returning `(hash, size, path)` for
+/// each readable file. Unreadable files are logged and returned separately.
+/// Hashes still valid in `index` are reused, and new ones are saved to it
+/// as hashing goes so an interrupted run loses little work.
+async fn hash_files(
+    files: Vec<(PathBuf, u64)>,
+    analysis: &AnalysisConfig,
+    overrides: &RunOverrides,
+    index: &Arc<HashIndex>,
+    operation: &OperationGuard,
+) -> (Vec<(String, u64, PathBuf)>, Vec<ItemError>, HashReuse) {
+    // Each hashing task holds one chunk-sized buffer, so bounding the number
+    // of concurrent tasks bounds the total bytes in flight.
+    let chunk_size = analysis.hash_chunk_size.max(1);
+    let mut max_tasks = (analysis.max_inflight_bytes / chunk_size as u64).max(1) as usize;
+    // An explicit concurrency override further throttles hashing, e.g. on battery
+    if let Some(limit) = overrides.max_concurrent_requests {
+        max_tasks = max_tasks.min(limit);
+    }
+    let permits = Arc::new(Semaphore::new(max_tasks));
+
+    let mut tasks = JoinSet::new();
+    for (path, size) in files {
+        let permits = permits.clone();
+        let cancel = operation.token();
+        let index = index.clone();
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await.ok()?;
+            if cancel.is_cancelled() {
+                return None;
+            }
+            let target = path.to_string_lossy().to_string();
+            let hashed = tokio::task::spawn_blocking(move || {
+                // Read before hashing, so a change made meanwhile makes the
+                // recorded entry stale rather than wrong
+                let metadata = fs::metadata(&path)?;
+                let source = match index.lookup(&path, &metadata) {
+                    IndexLookup::Hit(hash) => return Ok(((hash, size, path), HashSource::Index)),
+                    IndexLookup::Stale => HashSource::Reread,
+                    IndexLookup::Miss => HashSource::Read,
+                };
+                let hash = hash_file(&path, chunk_size)?;
+                index.insert(&path, &metadata, &hash);
+                Ok::<_, std::io::Error>(((hash, size, path), source))
+            })
+            .await
+            .ok()?;
+
+            match hashed {
+                Ok(result) => Some(Ok(result)),
+                Err(e) => {
+                    warn!("Skipping unreadable file during duplicate scan: {}", e);
+                    Some(Err(ItemError::new(target, e.to_string())))
+                }
+            }
+        });
+    }
+
+    let mut hashed = Vec::new();
+    let mut errors = Vec::new();
+    let mut reuse = HashReuse::default();
+    let candidates = tasks.len() as u64;
+    let mut finished = 0u64;
+    let mut last_flush = Instant::now();
+    while let Some(joined) = tasks.join_next().await {
+        finished += 1;
+        operation.report_progress(finished, candidates);
+        match joined {
+            Ok(Some(Ok((result, source)))) => {
+                match source {
+                    HashSource::Index => {
+                        reuse.files += 1;
+                        reuse.bytes += result.1;
+                    }
+                    HashSource::Reread => reuse.stale += 1,
+                    HashSource::Read => {}
+                }
+                hashed.push(result);
+            }
+            Ok(Some(Err(error))) => errors.push(error),
+            _ => {}
+        }
+        if last_flush.elapsed() >= HASH_INDEX_FLUSH_INTERVAL {
+            if let Err(e) = index.flush() {
+                warn!("Failed to save file hash index: {}", e);
+            }
+            last_flush = Instant::now();
+        }
+    }
+    if let Err(e) = index.flush() {
+        warn!("Failed to save file hash index: {}", e);
+    }
+    (hashed, errors, reuse)
+}
+
+/// Outcome of the size-grouping walk that precedes hashing
+#[derive(Serialize, Deserialize)]
+struct SizeScan {
+    groups: SizeGroups,
+    files_scanned: u64,
+    small_files: u64,
+    hidden_skipped: u64,
+    skipped: Vec<SkippedPath>,
+}
+
+/// Group non-empty regular files of at least `min_file_size` bytes by size;
+/// only sizes shared by two or more files can contain duplicates, so
+/// singletons are dropped before hashing
+fn group_by_size(
+    root: PathBuf,
+    options: WalkOptions,
+    min_file_size: u64,
+    cancel: &CancellationToken,
+) -> AppResult<SizeScan> {
+    let mut by_size = SizeGroups::new();
+    let mut files_scanned = 0u64;
+    let mut small_files = 0u64;
+
+    let mut walker = DirWalker::new(&root, options);
+    for entry in walker.by_ref() {
+        if cancel.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+        if !entry.is_file() {
+            continue;
+        }
+        files_scanned += 1;
+
+        let size = entry.metadata.len();
+        if size < min_file_size {
+            small_files += 1;
+        } else if size > 0 {
+            by_size.entry(size).or_default().push(entry.path);
+        }
+    }
+
+    by_size.retain(|_, paths| paths.len() > 1);
+    Ok(SizeScan {
+        groups: by_size,
+        files_scanned,
+        small_files,
+        hidden_skipped: walker.hidden_count(),
+        skipped: walker.into_skipped(),
+    })
+}
+
+/// Checkpoint file of a duplicate scan of `root` with `options`; a scan
+/// with different options walks differently, so it gets its own
+fn checkpoint_path(cache_dir: &Path, root: &Path, options: &ScanOptions) -> PathBuf {
+    let key = format!(
+        "{}\n{}",
+        root.display(),
+        serde_json::to_string(options).unwrap_or_default()
+    );
+    cache_dir
+        .join(CHECKPOINT_DIR)
+        .join(format!("{}.json", blake3::hash(key.as_bytes()).to_hex()))
+}
+
+fn save_checkpoint(path: &Path, scan: &SizeScan) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string(scan)?)?;
+    Ok(())
+}
+
+/// The grouping saved at `path` if it is recent enough to trust. Files
+/// that were removed or changed size since are dropped, as are sizes left
+/// with a single file.
+fn load_checkpoint(path: &Path) -> Option<SizeScan> {
+    let age = fs::metadata(path).ok()?.modified().ok()?.elapsed().ok()?;
+    if age > CHECKPOINT_MAX_AGE {
+        return None;
+    }
+    let contents = fs::read_to_string(path).ok()?;
+    let mut scan: SizeScan = match serde_json::from_str(&contents) {
+        Ok(scan) => scan,
+        Err(e) => {
+            warn!("Discarding unreadable duplicate scan checkpoint: {}", e);
+            return None;
+        }
+    };
+
+    for (size, paths) in scan.groups.iter_mut() {
+        paths.retain(|path| {
+            fs::symlink_metadata(path).is_ok_and(|m| m.is_file() && m.len() == *size)
+        });
+    }
+    scan.groups.retain(|_, paths| paths.len() > 1);
+    Some(scan)
+}
+
+/// Group near-identical images by perceptual hash. Images whose hashes are
+/// within `max_distance` bits of a group's representative join that group.
+#[command]
+pub async fn find_similar_images(
+    state: State<'_, AppState>,
+    path: String,
+    max_distance: Option<u32>,
+    options: Option<ScanOptions>,
+) -> AppResult<SimilarImageReport> {
+    timed_command("find_similar_images", Some(state.inner()), Some(path.clone()), async {
+        let root = validate_scan_root(&path)?;
+        let max_distance = max_distance.unwrap_or(DEFAULT_SIMILARITY_DISTANCE).min(64);
+        let options = options.unwrap_or_default();
+        let walk_options = options.walk_options(&state.get_config().await.security);
+        let min_file_size = options.min_file_size;
+        let operation = state.operations.start(OperationKind::Analyze, &path);
+
+        let (scan, mut operation) = tokio::task::spawn_blocking(move || {
+            let scan = hash_images(&root, walk_options, min_file_size, &operation);
+            (scan, operation)
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))?;
+        let ImageScan {
+            images,
+            undecodable: undecodable_images,
+            hidden_skipped,
+            skipped,
+        } = scan?;
+
+        let images_hashed = images.len() as u64;
+        let groups = group_similar(images, max_distance);
+        let reclaimable_bytes = groups.iter().map(|g| g.reclaimable_bytes).sum();
+        info!(
+            "Similar image scan of {} found {} groups among {} images",
+            path,
+            groups.len(),
+            images_hashed
+        );
+
+        operation.complete(OperationTally {
+            succeeded: images_hashed,
+            failed: undecodable_images,
+            bytes: reclaimable_bytes,
+            errors: Vec::new(),
+        });
+        Ok(SimilarImageReport {
+            root: path,
+            max_distance,
+            groups,
+            images_hashed,
+            undecodable_images,
+            hidden_skipped,
+            reclaimable_bytes,
+            skipped,
+        })
+    })
+    .await
+}
+
+/// Perceptually hash every file whose contents identify it as an image
+fn hash_images(
+    root: &Path,
+    options: WalkOptions,
+    min_file_size: u64,
+    operation: &OperationGuard,
+) -> AppResult<ImageScan> {
+    let mut images = Vec::new();
+    let mut undecodable = 0u64;
+
+    let mut walker = DirWalker::new(root, options);
+    for entry in walker.by_ref() {
+        if operation.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+        let size = entry.metadata.len();
+        if !entry.is_file() || size == 0 || size < min_file_size {
+            continue;
+        }
+
+        match perceptual_hash(&entry.path) {
+            Ok(Some(hash)) => images.push(HashedImage {
+                path: entry.path,
+                size,
+                hash,
+            }),
+            Ok(None) => {}
+            Err(e) => {
+                undecodable += 1;
+                warn!("Skipping undecodable image {}: {}", entry.path.display(), e);
+            }
+        }
+    }
+
+    Ok(ImageScan {
+        images,
+        undecodable,
+        hidden_skipped: walker.hidden_count(),
+        skipped: walker.into_skipped(),
+    })
+}
+
+/// Greedily cluster images around the largest ones. Comparisons per image
+/// and members per group are capped to bound the work on big libraries.
+fn group_similar(mut images: Vec<HashedImage>, max_distance: u32) -> Vec<SimilarImageGroup> {
+    images.sort_by_key(|image| Reverse(image.size));
+
+    let mut groups: Vec<(HashedImage, Vec<SimilarImage>)> = Vec::new();
+    // Indices into `groups` that can still take members, oldest first
+    let mut open: Vec<usize> = Vec::new();
+
+    for image in images {
+        let closest = open
+            .iter()
+            .rev()
+            .take(MAX_GROUP_COMPARISONS)
+            .map(|&index| (index, hamming_distance(groups[index].0.hash, image.hash)))
+            .filter(|&(_, distance)| distance <= max_distance)
+            .min_by_key(|&(_, distance)| distance);
+
+        match closest {
+            Some((index, distance)) => {
+                let members = &mut groups[index].1;
+                members.push(SimilarImage {
+                    path: image.path.to_string_lossy().to_string(),
+                    size: image.size,
+                    distance,
+                    similarity: 1.0 - distance as f32 / 64.0,
+                });
+                if members.len() + 1 >= MAX_SIMILAR_GROUP_SIZE {
+                    open.retain(|&open_index| open_index != index);
+                }
+            }
+            None => {
+                open.push(groups.len());
+                groups.push((image, Vec::new()));
+            }
+        }
+    }
+
+    let mut groups: Vec<SimilarImageGroup> = groups
+        .into_iter()
+        .filter(|(_, similar)| !similar.is_empty())
+        .map(|(representative, mut similar)| {
+            similar.sort_by_key(|image| image.distance);
+            SimilarImageGroup {
+                representative: representative.path.to_string_lossy().to_string(),
+                representative_size: representative.size,
+                reclaimable_bytes: similar.iter().map(|image| image.size).sum(),
+                similar,
+            }
+        })
+        .collect();
+    groups.sort_by_key(|g| Reverse(g.reclaimable_bytes));
+    groups
+}
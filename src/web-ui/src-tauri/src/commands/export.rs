@@ -0,0 +1,352 @@
+use crate::utils::analysis_cache::AnalysisCache;
+use crate::utils::classifier::{classify_offline, Classification, FileCategory};
+use crate::utils::config::AppConfig;
+use crate::utils::logging::timed_command;
+use crate::utils::security::{validate_output_path, RiskLevel, SecurityValidator};
+use crate::utils::snapshots::{ScanSnapshot, SnapshotStore};
+use crate::{AppError, AppResult, AppState};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use tauri::{command, State};
+use tracing::info;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+/// One exported file row
+#[derive(Debug, Serialize)]
+pub struct ExportRow {
+    pub path: String,
+    pub size: u64,
+    pub category: FileCategory,
+    /// High where deleting the file would be refused, Safe otherwise
+    pub risk_level: RiskLevel,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonExport<'a> {
+    session_id: &'a str,
+    root: &'a str,
+    exported_at: chrono::DateTime<chrono::Utc>,
+    files: &'a [ExportRow],
+}
+
+/// A file of the Markdown report with its final classification
+struct AnalyzedFile<'a> {
+    path: &'a str,
+    size: u64,
+    classification: Classification,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportResult {
+    pub destination: String,
+    pub rows: u64,
+    pub bytes_written: u64,
+}
+
+/// Write a persisted scan session to a JSON or CSV file, optionally gzipped
+#[command]
+pub async fn export_scan_results(
+    state: State<'_, AppState>,
+    session_id: String,
+    destination: String,
+    format: ExportFormat,
+    compress: Option<bool>,
+) -> AppResult<ExportResult> {
+    timed_command("export_scan_results", Some(state.inner()), None, async {
+        let destination = validate_output_path(&destination)?;
+        let config = state.get_config().await;
+        let snapshot = SnapshotStore::new(&config.cache_directory)
+            .load(&session_id)
+            .map_err(|e| AppError::FileSystemError(e.to_string()))?;
+        let cache = state.analysis_cache.clone();
+
+        let result = tokio::task::spawn_blocking(move || {
+            let rows = export_rows(&snapshot, &config, &cache);
+            write_export(
+                &destination,
+                &snapshot,
+                &rows,
+                format,
+                compress.unwrap_or(false),
+            )?;
+            Ok::<_, AppError>(ExportResult {
+                bytes_written: std::fs::metadata(&destination)?.len(),
+                destination: destination.to_string_lossy().to_string(),
+                rows: rows.len() as u64,
+            })
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))??;
+
+        info!(
+            "Exported {} rows of scan {} to {}",
+            result.rows, session_id, result.destination
+        );
+        Ok(result)
+    })
+    .await
+}
+
+/// Write the analysis of a persisted scan session as a Markdown report:
+/// totals per category, then every file grouped by category with its size,
+/// confidence and the reason it was classified that way. Files come with
+/// the session's saved analysis where there is one, else with cached or
+/// heuristic verdicts, and user override rules apply as in the app.
+#[command]
+pub async fn export_analysis_markdown(
+    state: State<'_, AppState>,
+    session_id: String,
+    destination: String,
+) -> AppResult<ExportResult> {
+    timed_command("export_analysis_markdown", Some(state.inner()), None, async {
+        let destination = validate_output_path(&destination)?;
+        let config = state.get_config().await;
+        let store = SnapshotStore::new(&config.cache_directory);
+        let snapshot = store
+            .load(&session_id)
+            .map_err(|e| AppError::FileSystemError(e.to_string()))?;
+        let saved = store
+            .load_results(&session_id)
+            .map_err(|e| AppError::FileSystemError(e.to_string()))?;
+        let cache = state.analysis_cache.clone();
+        let rules = state.override_rules.read().await.clone();
+
+        let result = tokio::task::spawn_blocking(move || {
+            let analyzed = saved.is_some();
+            let mut saved = saved.map(|results| results.classifications).unwrap_or_default();
+            let files: Vec<AnalyzedFile> = snapshot
+                .entries
+                .iter()
+                .map(|entry| {
+                    let classification = saved
+                        .remove(&entry.path)
+                        .unwrap_or_else(|| classify_offline(&config, &cache, &entry.facts()));
+                    AnalyzedFile {
+                        path: &entry.path,
+                        size: entry.size,
+                        classification: rules.apply(Path::new(&entry.path), classification),
+                    }
+                })
+                .collect();
+
+            let mut file = BufWriter::new(File::create(&destination)?);
+            write_markdown(&mut file, &snapshot, analyzed, &files)?;
+            file.flush()?;
+            drop(file);
+            Ok::<_, AppError>(ExportResult {
+                bytes_written: std::fs::metadata(&destination)?.len(),
+                destination: destination.to_string_lossy().to_string(),
+                rows: files.len() as u64,
+            })
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))??;
+
+        info!(
+            "Exported the analysis of scan {} ({} files) to {}",
+            session_id, result.rows, result.destination
+        );
+        Ok(result)
+    })
+    .await
+}
+
+/// Build export rows from cached classifications or heuristics
+fn export_rows(
+    snapshot: &ScanSnapshot,
+    config: &AppConfig,
+    cache: &AnalysisCache,
+) -> Vec<ExportRow> {
+    snapshot
+        .entries
+        .iter()
+        .map(|entry| {
+            let category = classify_offline(config, cache, &entry.facts()).category;
+
+            ExportRow {
+                path: entry.path.clone(),
+                size: entry.size,
+                category,
+                risk_level: deletion_risk(Path::new(&entry.path), config),
+            }
+        })
+        .collect()
+}
+
+fn deletion_risk(path: &Path, config: &AppConfig) -> RiskLevel {
+    match SecurityValidator::validate_deletion_target(path, &config.security) {
+        Ok(()) => RiskLevel::Safe,
+        Err(_) => RiskLevel::High,
+    }
+}
+
+fn write_export(
+    destination: &Path,
+    snapshot: &ScanSnapshot,
+    rows: &[ExportRow],
+    format: ExportFormat,
+    compress: bool,
+) -> AppResult<()> {
+    let mut file = BufWriter::new(File::create(destination)?);
+
+    if compress {
+        let mut encoder = GzEncoder::new(file, Compression::default());
+        write_body(&mut encoder, snapshot, rows, format)?;
+        encoder.finish()?.flush()?;
+    } else {
+        write_body(&mut file, snapshot, rows, format)?;
+        file.flush()?;
+    }
+
+    Ok(())
+}
+
+fn write_body<W: Write>(
+    writer: &mut W,
+    snapshot: &ScanSnapshot,
+    rows: &[ExportRow],
+    format: ExportFormat,
+) -> AppResult<()> {
+    match format {
+        ExportFormat::Json => {
+            let export = JsonExport {
+                session_id: &snapshot.id,
+                root: &snapshot.root,
+                exported_at: chrono::Utc::now(),
+                files: rows,
+            };
+            serde_json::to_writer_pretty(writer, &export)
+                .map_err(|e| AppError::FileSystemError(e.to_string()))?;
+        }
+        ExportFormat::Csv => {
+            // The csv writer quotes fields containing commas, quotes or newlines
+            let mut csv = csv::Writer::from_writer(writer);
+            for row in rows {
+                csv.serialize(row)
+                    .map_err(|e| AppError::FileSystemError(e.to_string()))?;
+            }
+            csv.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_markdown<W: Write>(
+    writer: &mut W,
+    snapshot: &ScanSnapshot,
+    analyzed: bool,
+    files: &[AnalyzedFile],
+) -> std::io::Result<()> {
+    let mut by_category: HashMap<FileCategory, Vec<&AnalyzedFile>> = HashMap::new();
+    for file in files {
+        by_category
+            .entry(file.classification.category)
+            .or_default()
+            .push(file);
+    }
+    let size_of = |files: &[&AnalyzedFile]| -> u64 { files.iter().map(|file| file.size).sum() };
+    let mut categories: Vec<(FileCategory, Vec<&AnalyzedFile>)> =
+        by_category.into_iter().collect();
+    categories.sort_by_key(|(_, files)| std::cmp::Reverse(size_of(files)));
+
+    let total_size: u64 = files.iter().map(|file| file.size).sum();
+    let reclaimable: u64 = files
+        .iter()
+        .filter(|file| file.classification.category.is_reclaimable())
+        .map(|file| file.size)
+        .sum();
+
+    writeln!(writer, "# Analysis of {}", escape_markdown(&snapshot.root))?;
+    writeln!(writer)?;
+    writeln!(writer, "- Session: {}", snapshot.id)?;
+    writeln!(writer, "- Exported: {}", chrono::Utc::now().format("%Y-%m-%d %H:%M UTC"))?;
+    writeln!(writer, "- Files: {}", files.len())?;
+    writeln!(writer, "- Total size: {}", format_size(total_size))?;
+    writeln!(writer, "- Reclaimable: {}", format_size(reclaimable))?;
+    if !analyzed {
+        writeln!(
+            writer,
+            "- This session was not analyzed; classifications come from cached results \
+             and heuristics"
+        )?;
+    }
+    writeln!(writer)?;
+
+    writeln!(writer, "| Category | Files | Size |")?;
+    writeln!(writer, "| --- | ---: | ---: |")?;
+    for (category, files) in &categories {
+        writeln!(
+            writer,
+            "| {:?} | {} | {} |",
+            category,
+            files.len(),
+            format_size(size_of(files))
+        )?;
+    }
+
+    for (category, mut files) in categories {
+        files.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(b.path)));
+        writeln!(writer)?;
+        writeln!(writer, "## {:?}", category)?;
+        writeln!(writer)?;
+        writeln!(writer, "| Path | Size | Confidence | Reason |")?;
+        writeln!(writer, "| --- | ---: | ---: | --- |")?;
+        for file in files {
+            writeln!(
+                writer,
+                "| {} | {} | {:.0}% | {} |",
+                escape_markdown(file.path),
+                format_size(file.size),
+                file.classification.confidence * 100.0,
+                escape_markdown(&file.classification.reason)
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// `text` with the characters Markdown or a table cell would interpret
+/// backslash-escaped, and line breaks flattened so a row stays one line
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '{' | '}' | '[' | ']' | '<' | '>' | '(' | ')' | '#'
+            | '!' | '|' | '~' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' | '\r' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
@@ -1,7 +1,15 @@
-use crate::AppResult;
+use crate::utils::audit::{AuditAction, AuditEntry};
+use crate::utils::file_locks::{locking_processes, LockingProcess};
+use crate::utils::hashing::{hash_file_with, HashAlgorithm};
+use crate::utils::logging::timed_command;
+use crate::utils::platform::{filesystem_type, is_network_drive, prepare_path_for_io};
+use crate::utils::security::SecurityValidator;
+use crate::{AppError, AppResult, AppState};
 use serde::Serialize;
-use tauri::{command, Manager, Runtime};
+use tauri::{command, Manager, Runtime, State};
 use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
 use std::path::PathBuf;
 
 #[derive(Debug, Serialize)]
@@ -12,83 +20,160 @@ pub struct DirectoryInfo {
     pub is_writable: bool,
     pub file_count: Option<u64>,
     pub total_size: Option<u64>,
+    /// Filesystem of the containing volume, e.g. `apfs`, `ext4` or `cifs`
+    pub filesystem_type: Option<String>,
+    pub is_network: bool,
 }
 
+#[derive(Debug, Serialize)]
+pub struct FileDigest {
+    pub path: String,
+    pub algorithm: HashAlgorithm,
+    pub digest: String,
+    pub size: u64,
+}
+
+/// Default and maximum amount of a file returned by `preview_file`, in KiB
+const DEFAULT_PREVIEW_KB: u64 = 16;
+const MAX_PREVIEW_KB: u64 = 256;
+/// Bytes shown in the hexdump of a binary file
+const HEXDUMP_BYTES: usize = 256;
+
+#[derive(Debug, Serialize)]
+pub struct FilePreview {
+    pub path: String,
+    pub size: u64,
+    /// Whether the file looks binary; `text` is then omitted
+    pub binary: bool,
+    /// Leading contents decoded as UTF-8, invalid sequences replaced
+    pub text: Option<String>,
+    /// Hexdump of the leading bytes of a binary file
+    pub hexdump: Option<String>,
+    /// Whether the file continues past the preview
+    pub truncated: bool,
+}
+
+/// Default and maximum number of lines returned by `tail_file`
+const DEFAULT_TAIL_LINES: usize = 100;
+const MAX_TAIL_LINES: usize = 10_000;
+/// Bytes read per backwards seek, and the most a tail ever holds in memory
+const TAIL_CHUNK_BYTES: u64 = 64 * 1024;
+const MAX_TAIL_BYTES: usize = 1024 * 1024;
+
+#[derive(Debug, Serialize)]
+pub struct FileTail {
+    pub path: String,
+    pub size: u64,
+    /// Whether the tail looks binary; `lines` is then empty
+    pub binary: bool,
+    /// Last lines of the file, oldest first, without line endings
+    pub lines: Vec<String>,
+    /// Hexdump of the final bytes of a binary file
+    pub hexdump: Option<String>,
+    /// Whether the file has content before the returned lines
+    pub truncated: bool,
+}
+
+/// Let the user pick a folder. Without `default_path` the dialog opens in
+/// the last folder picked, when `remember_last_directory` is on and that
+/// folder still exists, and in the home directory otherwise.
 #[command]
 pub async fn select_directory<R: Runtime>(
     app: tauri::AppHandle<R>,
+    state: State<'_, AppState>,
     title: Option<String>,
     default_path: Option<String>,
 ) -> Result<Option<String>, String> {
     let dialog_title = title.unwrap_or_else(|| "Select Directory to Analyze".to_string());
+    let config = state.get_config().await;
 
+    let remembered = config
+        .last_directory
+        .filter(|path| config.remember_last_directory && path.is_dir());
     let dialog_path = if let Some(path) = default_path {
         PathBuf::from(path)
+    } else if let Some(path) = remembered {
+        path
     } else {
         home::home_dir().unwrap_or_else(|| PathBuf::from("/"))
     };
 
-    let file_dialog = app.dialog()
-        .file()
-        .set_title(dialog_title)
-        .set_directory(dialog_path)
-        .pick_folder();
+    let selected = tokio::task::spawn_blocking(move || {
+        app.dialog()
+            .file()
+            .set_title(dialog_title)
+            .set_directory(dialog_path)
+            .blocking_pick_folder()
+    })
+    .await
+    .map_err(|e| e.to_string())?;
 
-    match file_dialog {
-        Some(path) => {
-            let path_str = path.to_string_lossy().to_string();
-            Ok(Some(path_str))
+    let Some(path) = selected.and_then(|path| path.into_path().ok()) else {
+        return Ok(None);
+    };
+    if config.remember_last_directory {
+        let last = path.clone();
+        if let Err(e) = state
+            .update_config(move |config| config.last_directory = Some(last))
+            .await
+        {
+            tracing::warn!("Failed to remember picked directory: {}", e);
         }
-        None => Ok(None),
     }
+    Ok(Some(path.to_string_lossy().to_string()))
 }
 
 #[command]
 pub async fn validate_directory_access(path: String) -> AppResult<DirectoryInfo> {
-    let path_buf = PathBuf::from(&path);
+    timed_command("select_directory", None, Some(path.clone()), async {
+        let path_buf = PathBuf::from(&path);
 
-    if !path_buf.exists() {
-        return Err(crate::AppError::FileSystemError(
-            "Directory does not exist".to_string()
-        ));
-    }
+        if !path_buf.exists() {
+            return Err(crate::AppError::FileSystemError(
+                "Directory does not exist".to_string()
+            ));
+        }
 
-    if !path_buf.is_dir() {
-        return Err(crate::AppError::FileSystemError(
-            "Path is not a directory".to_string()
-        ));
-    }
+        if !path_buf.is_dir() {
+            return Err(crate::AppError::FileSystemError(
+                "Path is not a directory".to_string()
+            ));
+        }
 
-    let name = path_buf
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or(&path)
-        .to_string();
+        let name = path_buf
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(&path)
+            .to_string();
 
-    // Check read/write permissions
-    let is_readable = std::fs::read_dir(&path_buf).is_ok();
-    let is_writable = std::fs::write(path_buf.join(".write_test"), "").is_ok();
+        // Check read/write permissions
+        let is_readable = std::fs::read_dir(&path_buf).is_ok();
+        let is_writable = std::fs::write(path_buf.join(".write_test"), "").is_ok();
 
-    // Clean up test file if created
-    if is_writable {
-        let _ = std::fs::remove_file(path_buf.join(".write_test"));
-    }
+        // Clean up test file if created
+        if is_writable {
+            let _ = std::fs::remove_file(path_buf.join(".write_test"));
+        }
 
-    // Count files and calculate size (quick scan for large directories)
-    let (file_count, total_size) = if is_readable {
-        count_directory_contents(&path_buf).await
-    } else {
-        (None, None)
-    };
+        // Count files and calculate size (quick scan for large directories)
+        let (file_count, total_size) = if is_readable {
+            count_directory_contents(&path_buf).await
+        } else {
+            (None, None)
+        };
 
-    Ok(DirectoryInfo {
-        path,
-        name,
-        is_readable,
-        is_writable,
-        file_count,
-        total_size,
+        Ok(DirectoryInfo {
+            path,
+            name,
+            is_readable,
+            is_writable,
+            file_count,
+            total_size,
+            filesystem_type: filesystem_type(&path_buf),
+            is_network: is_network_drive(&path_buf),
+        })
     })
+    .await
 }
 
 async fn count_directory_contents(path: &PathBuf) -> (Option<u64>, Option<u64>) {
@@ -115,4 +200,237 @@ async fn count_directory_contents(path: &PathBuf) -> (Option<u64>, Option<u64>)
     }
 
     (Some(file_count), Some(total_size))
-}
\ No newline at end of file
+}
+
+/// Compute a streamed digest of a file, e.g. to record it before deletion or
+/// to confirm two duplicates really are identical
+#[command]
+pub async fn hash_file(
+    state: State<'_, AppState>,
+    path: String,
+    algorithm: Option<HashAlgorithm>,
+) -> AppResult<FileDigest> {
+    timed_command("hash_file", Some(state.inner()), Some(path.clone()), async {
+        let algorithm = algorithm.unwrap_or_default();
+        let config = state.get_config().await;
+        let chunk_size = config.analysis.hash_chunk_size;
+
+        let target = PathBuf::from(&path);
+        let (digest, size) = tokio::task::spawn_blocking(move || {
+            let size = std::fs::metadata(&target)?.len();
+            hash_file_with(&target, algorithm, chunk_size).map(|digest| (digest, size))
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))??;
+
+        if config.security.enable_audit_trail {
+            state.audit.record(AuditEntry::new(
+                AuditAction::Hashed,
+                &path,
+                size,
+                Some(format!("{}:{}", algorithm.as_str(), digest)),
+            ));
+        }
+
+        Ok(FileDigest {
+            path,
+            algorithm,
+            digest,
+            size,
+        })
+    })
+    .await
+}
+
+/// Processes holding a file open, so a delete that failed with "file in use"
+/// can name the application to close before retrying
+#[command]
+pub async fn find_locking_processes(
+    state: State<'_, AppState>,
+    path: String,
+) -> AppResult<Vec<LockingProcess>> {
+    timed_command("find_locking_processes", Some(state.inner()), Some(path.clone()), async {
+        let target = PathBuf::from(&path);
+        let processes = tokio::task::spawn_blocking(move || {
+            std::fs::symlink_metadata(&target)?;
+            locking_processes(&target)
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))??;
+
+        Ok(processes)
+    })
+    .await
+}
+
+/// Peek at the start of a file before deciding to delete it. Text is returned
+/// as lossy UTF-8; binary files only get a short hexdump.
+#[command]
+pub async fn preview_file(
+    state: State<'_, AppState>,
+    path: String,
+    max_kb: Option<u64>,
+) -> AppResult<FilePreview> {
+    timed_command("preview_file", Some(state.inner()), Some(path.clone()), async {
+        let security = state.get_config().await.security;
+        let limit = (max_kb.unwrap_or(DEFAULT_PREVIEW_KB).clamp(1, MAX_PREVIEW_KB) * 1024) as usize;
+
+        tokio::task::spawn_blocking(move || {
+            let target = PathBuf::from(&path);
+            SecurityValidator::validate_deletion_target(&target, &security)
+                .map_err(AppError::SecurityError)?;
+
+            let metadata = std::fs::symlink_metadata(&target)?;
+            if !metadata.is_file() {
+                return Err(AppError::FileSystemError(format!("{} is not a regular file", path)));
+            }
+
+            let file = std::fs::File::open(prepare_path_for_io(&target))?;
+            let mut contents = Vec::with_capacity(limit.min(metadata.len() as usize));
+            file.take(limit as u64).read_to_end(&mut contents)?;
+            let truncated = metadata.len() > contents.len() as u64;
+
+            let binary = looks_binary(&contents);
+            Ok(FilePreview {
+                path,
+                size: metadata.len(),
+                binary,
+                text: (!binary).then(|| String::from_utf8_lossy(&contents).into_owned()),
+                hexdump: binary.then(|| hexdump(&contents[..contents.len().min(HEXDUMP_BYTES)])),
+                truncated,
+            })
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))?
+    })
+    .await
+}
+
+/// Read the last lines of a file, e.g. to check whether a huge log is stale.
+/// The file is read backwards in chunks, so only the tail (at most 1 MiB) is
+/// ever held in memory.
+#[command]
+pub async fn tail_file(
+    state: State<'_, AppState>,
+    path: String,
+    lines: Option<usize>,
+) -> AppResult<FileTail> {
+    timed_command("tail_file", Some(state.inner()), Some(path.clone()), async {
+        let security = state.get_config().await.security;
+        let wanted = lines.unwrap_or(DEFAULT_TAIL_LINES).clamp(1, MAX_TAIL_LINES);
+
+        tokio::task::spawn_blocking(move || {
+            let target = PathBuf::from(&path);
+            SecurityValidator::validate_deletion_target(&target, &security)
+                .map_err(AppError::SecurityError)?;
+
+            let metadata = std::fs::symlink_metadata(&target)?;
+            if !metadata.is_file() {
+                return Err(AppError::FileSystemError(format!("{} is not a regular file", path)));
+            }
+
+            let mut file = File::open(prepare_path_for_io(&target))?;
+            let (tail, starts_mid_file) = read_tail(&mut file, metadata.len(), wanted)?;
+
+            if looks_binary(&tail) {
+                return Ok(FileTail {
+                    path,
+                    size: metadata.len(),
+                    binary: true,
+                    lines: Vec::new(),
+                    hexdump: Some(hexdump(&tail[tail.len().saturating_sub(HEXDUMP_BYTES)..])),
+                    truncated: metadata.len() > HEXDUMP_BYTES as u64,
+                });
+            }
+
+            let text = String::from_utf8_lossy(&tail);
+            let text = text.strip_suffix('\n').unwrap_or(&text);
+            let mut all: Vec<&str> = if text.is_empty() {
+                Vec::new()
+            } else {
+                text.split('\n').collect()
+            };
+            // The first piece is cut off unless the tail reaches the file start;
+            // keep it only if it is all there is (one very long line)
+            let mut truncated = starts_mid_file;
+            if starts_mid_file && all.len() > 1 {
+                all.remove(0);
+            }
+            if all.len() > wanted {
+                all.drain(..all.len() - wanted);
+                truncated = true;
+            }
+
+            Ok(FileTail {
+                path,
+                size: metadata.len(),
+                binary: false,
+                lines: all
+                    .into_iter()
+                    .map(|line| line.strip_suffix('\r').unwrap_or(line).to_string())
+                    .collect(),
+                hexdump: None,
+                truncated,
+            })
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))?
+    })
+    .await
+}
+
+/// Read backwards from the end of the file until the buffer holds `wanted`
+/// complete lines, the start of the file, or `MAX_TAIL_BYTES`. Returns the
+/// bytes read and whether they start part-way into the file.
+fn read_tail(file: &mut File, size: u64, wanted: usize) -> std::io::Result<(Vec<u8>, bool)> {
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut position = size;
+
+    while position > 0 && buffer.len() < MAX_TAIL_BYTES {
+        // A trailing newline ends the last line rather than starting a new one
+        let body = buffer.strip_suffix(b"\n").unwrap_or(&buffer);
+        if body.iter().filter(|&&b| b == b'\n').count() >= wanted {
+            break;
+        }
+
+        let read = TAIL_CHUNK_BYTES.min(position);
+        position -= read;
+        file.seek(SeekFrom::Start(position))?;
+        let mut chunk = vec![0; read as usize];
+        file.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&buffer);
+        buffer = chunk;
+    }
+
+    Ok((buffer, position > 0))
+}
+
+/// NUL bytes, or a high share of control characters, mark a file as binary
+fn looks_binary(contents: &[u8]) -> bool {
+    if contents.contains(&0) {
+        return true;
+    }
+
+    let control = contents
+        .iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\n' | b'\r' | b'\t' | 0x0c | 0x1b))
+        .count();
+    control * 10 > contents.len()
+}
+
+/// Classic 16-bytes-per-line hexdump with an ASCII column
+fn hexdump(bytes: &[u8]) -> String {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(line, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                .collect();
+            format!("{:08x}  {:<47}  |{}|", line * 16, hex.join(" "), ascii)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
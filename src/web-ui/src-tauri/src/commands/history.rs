@@ -0,0 +1,33 @@
+use crate::utils::history::CleanupSession;
+use crate::utils::logging::timed_command;
+use crate::{AppResult, AppState};
+use serde::Serialize;
+use tauri::{command, State};
+
+const DEFAULT_HISTORY_LIMIT: usize = 50;
+
+#[derive(Debug, Serialize)]
+pub struct CleanupHistoryReport {
+    pub recent_sessions: Vec<CleanupSession>,
+    pub lifetime_sessions: u64,
+    pub lifetime_files_removed: u64,
+    pub lifetime_bytes_freed: u64,
+}
+
+#[command]
+pub async fn get_cleanup_history(
+    state: State<'_, AppState>,
+    limit: Option<usize>,
+) -> AppResult<CleanupHistoryReport> {
+    timed_command("get_cleanup_history", Some(state.inner()), None, async {
+        let history = state.history.lock().await;
+
+        Ok(CleanupHistoryReport {
+            recent_sessions: history.recent(limit.unwrap_or(DEFAULT_HISTORY_LIMIT)),
+            lifetime_sessions: history.lifetime_sessions,
+            lifetime_files_removed: history.lifetime_files_removed,
+            lifetime_bytes_freed: history.lifetime_bytes_freed,
+        })
+    })
+    .await
+}
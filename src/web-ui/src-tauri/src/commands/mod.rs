@@ -5,9 +5,59 @@ pub mod file_system;
 pub mod system_integration;
 pub mod security;
 pub mod notifications;
+pub mod cleanup;
+pub mod analysis;
+pub mod history;
+pub mod duplicates;
+pub mod scan;
+pub mod operations;
+pub mod export;
+pub mod disk_usage;
+pub mod overrides;
+pub mod archive;
+pub mod plan;
+pub mod schedule;
+pub mod app_data;
 
 // Re-export all command functions for easy registration
-pub use file_system::select_directory;
-pub use system_integration::{get_system_info, get_platform_info};
-pub use security::validate_path_safety;
-pub use notifications::show_notification;
\ No newline at end of file
+pub use file_system::{find_locking_processes, hash_file, preview_file, select_directory, tail_file};
+pub use system_integration::{
+    convert_config_format, get_app_paths, get_capabilities, get_disk_space, get_platform_info,
+    get_purgeable_space, get_system_info, get_system_theme, open_system_storage_settings,
+    validate_config,
+};
+pub use security::{
+    add_global_exclusion, explain_path_risk, remove_global_exclusion,
+    reset_protected_patterns_to_default, set_scan_root, validate_path_safety, validate_paths,
+};
+pub use notifications::show_notification;
+pub use cleanup::{
+    clean_browser_caches, clean_crash_dumps, confirm_delete, delete_directory, delete_elevated,
+    empty_old_trash, estimate_operation_duration, find_app_leftovers, find_broken_symlinks,
+    find_browser_caches, find_crash_dumps, find_dev_caches, find_empty_directories,
+    find_empty_files, find_thumbnail_caches, clean_thumbnail_caches, prepare_delete,
+    verify_backups, find_device_backups,
+};
+pub use analysis::{
+    analyze_incremental, analyze_session, analyze_single_path, reanalyze_low_confidence,
+    summarize_analysis, summarize_roots, test_ai_connection,
+};
+pub use history::get_cleanup_history;
+pub use duplicates::{
+    find_duplicate_directories, find_duplicate_names, find_duplicates, find_similar_images,
+};
+pub use scan::{diff_scans, list_sessions, load_session, scan_directory};
+pub use operations::{
+    cancel_all_operations, get_metrics, is_path_busy, list_operations, reset_metrics,
+};
+pub use export::{export_analysis_markdown, export_scan_results};
+pub use disk_usage::{
+    age_histogram, find_app_footprints, find_large_directories, find_sparse_files,
+};
+pub use overrides::{add_override_rule, list_override_rules, remove_override_rule};
+pub use archive::archive_files;
+pub use plan::{export_cleanup_plan, import_cleanup_plan};
+pub use schedule::{
+    list_scheduled_cleanups, register_scheduled_cleanup, unregister_scheduled_cleanup,
+};
+pub use app_data::clear_app_data;
@@ -1,35 +1,201 @@
-use crate::AppResult;
-use serde::Serialize;
-use tauri::{command, AppHandle, Manager};
+use crate::commands::system_integration::SystemTheme;
+use crate::utils::config::ThemePreference;
+use crate::utils::logging::timed_command;
+use crate::utils::notification_throttle::ThrottleDecision;
+use crate::{AppResult, AppState};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle, Manager, State};
 use tauri_plugin_notification::NotificationExt;
+use tracing::warn;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct NotificationConfig {
     pub title: String,
     pub body: String,
     pub icon: Option<String>,
+    /// Picks the default icon when `icon` is not given
+    #[serde(default)]
+    pub kind: NotificationKind,
     pub sound: Option<String>,
     pub duration: Option<i32>,
 }
 
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationKind {
+    #[default]
+    Info,
+    Success,
+    Warning,
+}
+
+impl NotificationKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            NotificationKind::Info => "info",
+            NotificationKind::Success => "success",
+            NotificationKind::Warning => "warning",
+        }
+    }
+
+    /// Freedesktop icon naming spec name, resolved by the desktop's icon theme
+    fn freedesktop_icon(self) -> &'static str {
+        match self {
+            NotificationKind::Info => "dialog-information",
+            NotificationKind::Success => "emblem-default",
+            NotificationKind::Warning => "dialog-warning",
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct NotificationResult {
     pub success: bool,
     pub message: String,
+    pub status: NotificationStatus,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationStatus {
+    Sent,
+    Failed,
+    Disabled,
+    Throttled,
+    Queued,
+    Dropped,
+}
+
+impl NotificationResult {
+    fn suppressed(status: NotificationStatus, message: &str) -> Self {
+        Self {
+            success: false,
+            message: message.to_string(),
+            status,
+        }
+    }
+}
+
+/// Show a notification, subject to the minimum interval and quiet hours.
+/// Notifications queued during quiet hours are delivered once they end.
 #[command]
 pub async fn show_notification<R: tauri::Runtime>(
     app: AppHandle<R>,
+    state: State<'_, AppState>,
     config: NotificationConfig,
 ) -> AppResult<NotificationResult> {
+    timed_command("show_notification", Some(state.inner()), None, async {
+        let app_config = state.get_config().await;
+        if !app_config.enable_notifications {
+            return Ok(NotificationResult::suppressed(
+                NotificationStatus::Disabled,
+                "Notifications are disabled",
+            ));
+        }
+
+        let decision = state.notification_throttle.lock().await.check(
+            &app_config.notifications,
+            config,
+            Instant::now(),
+            chrono::Local::now().time(),
+        );
+        let (config, held) = match decision {
+            ThrottleDecision::Send { notification, held } => (notification, held),
+            ThrottleDecision::Throttled => {
+                return Ok(NotificationResult::suppressed(
+                    NotificationStatus::Throttled,
+                    "Notification suppressed by minimum interval",
+                ))
+            }
+            ThrottleDecision::Queued { deliver_in } => {
+                if let Some(delay) = deliver_in {
+                    deliver_held_after(app.clone(), state.inner().clone(), delay);
+                }
+                return Ok(NotificationResult::suppressed(
+                    NotificationStatus::Queued,
+                    "Notification held until quiet hours end",
+                ))
+            }
+            ThrottleDecision::Dropped => {
+                return Ok(NotificationResult::suppressed(
+                    NotificationStatus::Dropped,
+                    "Notification dropped during quiet hours",
+                ))
+            }
+        };
+
+        deliver_all(&app, held, &app_config.theme);
+        match deliver(&app, config, &app_config.theme) {
+            Ok(()) => Ok(NotificationResult {
+                success: true,
+                message: "Notification sent successfully".to_string(),
+                status: NotificationStatus::Sent,
+            }),
+            Err(e) => Ok(NotificationResult {
+                success: false,
+                message: format!("Failed to send notification: {}", e),
+                status: NotificationStatus::Failed,
+            }),
+        }
+    })
+    .await
+}
+
+/// Wait `delay`, then deliver whatever quiet hours held back. Waits again
+/// if the quiet hours were changed to end later in the meantime.
+fn deliver_held_after<R: tauri::Runtime>(app: AppHandle<R>, state: AppState, delay: Duration) {
+    tauri::async_runtime::spawn(async move {
+        // The end of the window is computed to the second; overshoot it
+        // slightly so the release below finds quiet hours over
+        let mut delay = delay + Duration::from_secs(1);
+        loop {
+            tokio::time::sleep(delay).await;
+            let app_config = state.get_config().await;
+            let released = state.notification_throttle.lock().await.release_held(
+                &app_config.notifications,
+                Instant::now(),
+                chrono::Local::now().time(),
+            );
+            match released {
+                Ok(held) => {
+                    if app_config.enable_notifications {
+                        deliver_all(&app, held, &app_config.theme);
+                    }
+                    return;
+                }
+                Err(wait) => delay = wait + Duration::from_secs(1),
+            }
+        }
+    });
+}
+
+fn deliver_all<R: tauri::Runtime>(
+    app: &AppHandle<R>,
+    notifications: Vec<NotificationConfig>,
+    theme: &ThemePreference,
+) {
+    for notification in notifications {
+        if let Err(e) = deliver(app, notification, theme) {
+            warn!("Failed to send a notification held during quiet hours: {}", e);
+        }
+    }
+}
+
+/// Show `config` right away
+fn deliver<R: tauri::Runtime>(
+    app: &AppHandle<R>,
+    config: NotificationConfig,
+    theme: &ThemePreference,
+) -> Result<(), String> {
     let notification = app
         .notification()
         .builder()
         .title(config.title)
         .body(config.body);
 
-    let notification = if let Some(icon) = config.icon {
+    let icon = config.icon.or_else(|| default_icon(app, config.kind, theme));
+    let notification = if let Some(icon) = icon {
         notification.icon(icon)
     } else {
         notification
@@ -41,30 +207,62 @@ pub async fn show_notification<R: tauri::Runtime>(
         notification
     };
 
-    match notification.show() {
-        Ok(_) => Ok(NotificationResult {
-            success: true,
-            message: "Notification sent successfully".to_string(),
-        }),
-        Err(e) => Ok(NotificationResult {
-            success: false,
-            message: format!("Failed to send notification: {}", e),
-        }),
+    notification.show().map_err(|e| e.to_string())
+}
+
+/// Bundled icon for `kind` matching the effective theme. Linux uses the
+/// desktop's own icon theme instead, which already follows light/dark.
+fn default_icon<R: tauri::Runtime>(
+    app: &AppHandle<R>,
+    kind: NotificationKind,
+    preference: &ThemePreference,
+) -> Option<String> {
+    if cfg!(target_os = "linux") {
+        return Some(kind.freedesktop_icon().to_string());
     }
+
+    let theme = match preference {
+        ThemePreference::Light => SystemTheme::Light,
+        ThemePreference::Dark => SystemTheme::Dark,
+        ThemePreference::System => app
+            .get_webview_window("main")
+            .and_then(|window| window.theme().ok())
+            .map(SystemTheme::from)
+            .unwrap_or(SystemTheme::Light),
+    };
+    let variant = match theme {
+        SystemTheme::Light => "light",
+        SystemTheme::Dark => "dark",
+    };
+
+    let icon = app
+        .path()
+        .resource_dir()
+        .ok()?
+        .join("icons")
+        .join("notifications")
+        .join(format!("{}-{}.png", kind.as_str(), variant));
+    icon.is_file().then(|| icon.to_string_lossy().into_owned())
 }
 
 #[command]
 pub async fn check_notification_permissions<R: tauri::Runtime>(
     app: AppHandle<R>,
 ) -> AppResult<bool> {
-    // Check if notifications are enabled on the current platform
-    Ok(true) // Simplified for now
+    timed_command("check_notification_permissions", None, None, async {
+        // Check if notifications are enabled on the current platform
+        Ok(true) // Simplified for now
+    })
+    .await
 }
 
 #[command]
 pub async fn request_notification_permissions<R: tauri::Runtime>(
     app: AppHandle<R>,
 ) -> AppResult<bool> {
-    // Request notification permissions if needed
-    Ok(true) // Simplified for now
-}
\ No newline at end of file
+    timed_command("request_notification_permissions", None, None, async {
+        // Request notification permissions if needed
+        Ok(true) // Simplified for now
+    })
+    .await
+}
@@ -0,0 +1,90 @@
+use crate::utils::logging::timed_command;
+use crate::utils::metrics::MetricsSnapshot;
+use crate::utils::operations::OperationInfo;
+use crate::{AppResult, AppState};
+use serde::Serialize;
+use std::path::Path;
+use tauri::{command, AppHandle, Emitter, Runtime, State};
+use tracing::{info, warn};
+
+/// Event carrying an `OperationResult` whenever a scan, analysis or delete
+/// ends, whether it completed, failed or was cancelled
+pub const OPERATION_COMPLETE_EVENT: &str = "operation-complete";
+
+/// Emit `OPERATION_COMPLETE_EVENT` for every operation registered in `state`
+/// and feed its result to the metrics store
+pub fn emit_operation_results<R: Runtime>(app: &AppHandle<R>, state: &AppState) {
+    let app = app.clone();
+    let metrics = state.metrics.clone();
+    state.operations.on_complete(move |result| {
+        metrics.record_operation(&result);
+        if let Err(e) = app.emit(OPERATION_COMPLETE_EVENT, result) {
+            warn!("Failed to emit {} event: {}", OPERATION_COMPLETE_EVENT, e);
+        }
+    });
+}
+
+/// Whether a path overlaps any in-flight operation
+#[derive(Debug, Serialize)]
+pub struct PathBusy {
+    pub busy: bool,
+    pub operations: Vec<OperationInfo>,
+}
+
+/// Signal every in-flight operation to stop, returning how many were signalled
+#[command]
+pub async fn cancel_all_operations(state: State<'_, AppState>) -> AppResult<usize> {
+    timed_command("cancel_all_operations", Some(state.inner()), None, async {
+        let signalled = state.operations.cancel_all();
+        if signalled > 0 {
+            info!("Cancellation requested for {} operation(s)", signalled);
+        }
+        Ok(signalled)
+    })
+    .await
+}
+
+/// Currently running operations, oldest first, for the activity panel
+#[command]
+pub async fn list_operations(state: State<'_, AppState>) -> AppResult<Vec<OperationInfo>> {
+    timed_command("list_operations", Some(state.inner()), None, async {
+        Ok(state.operations.list())
+    })
+    .await
+}
+
+/// Check whether `path` is inside, or contains, the target of a running
+/// operation so the UI can hold off on conflicting work
+#[command]
+pub async fn is_path_busy(state: State<'_, AppState>, path: String) -> AppResult<PathBusy> {
+    timed_command("is_path_busy", Some(state.inner()), Some(path.clone()), async {
+        let operations = state.operations.overlapping(Path::new(&path));
+        Ok(PathBusy {
+            busy: !operations.is_empty(),
+            operations,
+        })
+    })
+    .await
+}
+
+/// Recent throughput per operation kind, analysis cache hit rate, AI
+/// latency percentiles and failing commands, for the diagnostics panel.
+/// The figures stay on this machine.
+#[command]
+pub async fn get_metrics(state: State<'_, AppState>) -> AppResult<MetricsSnapshot> {
+    timed_command("get_metrics", Some(state.inner()), None, async {
+        Ok(state.metrics.snapshot())
+    })
+    .await
+}
+
+/// Discard every collected metric and start afresh
+#[command]
+pub async fn reset_metrics(state: State<'_, AppState>) -> AppResult<()> {
+    timed_command("reset_metrics", Some(state.inner()), None, async {
+        state.metrics.reset();
+        info!("Metrics reset");
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,57 @@
+use crate::utils::classifier::FileCategory;
+use crate::utils::logging::timed_command;
+use crate::utils::overrides::OverrideRule;
+use crate::{AppError, AppResult, AppState};
+use tauri::{command, State};
+use tracing::info;
+
+#[command]
+pub async fn list_override_rules(state: State<'_, AppState>) -> AppResult<Vec<OverrideRule>> {
+    timed_command("list_override_rules", Some(state.inner()), None, async {
+        Ok(state.override_rules.read().await.rules().to_vec())
+    })
+    .await
+}
+
+/// Add a rule that forces `category` (and optionally `confidence`) onto
+/// every file matching `pattern`, e.g. `*.psd` or `~/Downloads/*.zip`
+#[command]
+pub async fn add_override_rule(
+    state: State<'_, AppState>,
+    pattern: String,
+    category: FileCategory,
+    confidence: Option<f32>,
+    note: Option<String>,
+) -> AppResult<OverrideRule> {
+    timed_command("add_override_rule", Some(state.inner()), None, async {
+        let mut rules = state.override_rules.write().await;
+        let rule = rules
+            .add(pattern, category, confidence, note)
+            .map_err(AppError::ConfigError)?;
+        rules
+            .save()
+            .map_err(|e| AppError::ConfigError(format!("Failed to save override rules: {}", e)))?;
+
+        info!("Added override rule '{}' -> {:?}", rule.pattern, rule.category);
+        Ok(rule)
+    })
+    .await
+}
+
+/// Remove a rule by id; returns false if no such rule exists
+#[command]
+pub async fn remove_override_rule(state: State<'_, AppState>, id: String) -> AppResult<bool> {
+    timed_command("remove_override_rule", Some(state.inner()), None, async {
+        let mut rules = state.override_rules.write().await;
+        if !rules.remove(&id) {
+            return Ok(false);
+        }
+        rules
+            .save()
+            .map_err(|e| AppError::ConfigError(format!("Failed to save override rules: {}", e)))?;
+
+        info!("Removed override rule {}", id);
+        Ok(true)
+    })
+    .await
+}
@@ -0,0 +1,213 @@
+use crate::commands::scan::ScanOptions;
+use crate::utils::classifier::FileCategory;
+use crate::utils::logging::timed_command;
+use crate::utils::security::{broad_root_reason, validate_output_path, validate_scan_root};
+use crate::{AppError, AppResult, AppState};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tauri::{command, State};
+use tracing::info;
+
+/// Format version written by `export_cleanup_plan`; bump it whenever a
+/// change would make older builds misread a plan
+pub const PLAN_VERSION: u32 = 1;
+
+/// Plan files larger than this are not plans
+const MAX_PLAN_SIZE: u64 = 1024 * 1024;
+
+/// A reusable description of a cleanup: what to scan, how, and which
+/// categories to act on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CleanupPlan {
+    pub version: u32,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub roots: Vec<String>,
+    #[serde(default)]
+    pub scan_options: ScanOptions,
+    /// Folders never scanned, as in `security.global_exclusions`
+    #[serde(default)]
+    pub exclusions: Vec<String>,
+    /// Categories selected for cleanup; empty selects every reclaimable one
+    #[serde(default)]
+    pub categories: Vec<FileCategory>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlanExport {
+    pub destination: String,
+    pub bytes_written: u64,
+}
+
+/// A plan that passed validation, ready to drive `scan_directory` with its
+/// `roots` and `scan_options`
+#[derive(Debug, Serialize)]
+pub struct ImportedPlan {
+    pub plan: CleanupPlan,
+    /// Plan exclusions that were not yet global exclusions and now are
+    pub exclusions_added: Vec<String>,
+    /// Broad roots the user confirmed, each with the reason it needed it
+    pub confirmed_roots: Vec<String>,
+}
+
+/// Save the given roots, scan options and categories, together with the
+/// current global exclusions, as a versioned JSON plan at `destination`
+#[command]
+pub async fn export_cleanup_plan(
+    state: State<'_, AppState>,
+    destination: String,
+    roots: Vec<String>,
+    options: Option<ScanOptions>,
+    categories: Option<Vec<FileCategory>>,
+    name: Option<String>,
+) -> AppResult<PlanExport> {
+    timed_command("export_cleanup_plan", Some(state.inner()), Some(destination.clone()), async {
+        let destination = validate_output_path(&destination)?;
+        if roots.is_empty() {
+            return Err(AppError::ConfigError("A plan needs at least one root".to_string()));
+        }
+        for root in &roots {
+            validate_scan_root(root)?;
+        }
+
+        let plan = CleanupPlan {
+            version: PLAN_VERSION,
+            name,
+            created_at: Utc::now(),
+            roots,
+            scan_options: options.unwrap_or_default(),
+            exclusions: state.get_config().await.security.global_exclusions,
+            categories: categories.unwrap_or_default(),
+        };
+        let json = serde_json::to_string_pretty(&plan)
+            .map_err(|e| AppError::ConfigError(e.to_string()))?;
+
+        let target = destination.clone();
+        tokio::task::spawn_blocking(move || fs::write(&target, json))
+            .await
+            .map_err(|e| AppError::SystemError(e.to_string()))??;
+
+        let result = PlanExport {
+            bytes_written: fs::metadata(&destination)?.len(),
+            destination: destination.to_string_lossy().to_string(),
+        };
+        info!(
+            "Exported cleanup plan with {} roots to {}",
+            plan.roots.len(),
+            result.destination
+        );
+        Ok(result)
+    })
+    .await
+}
+
+/// Load a plan written by `export_cleanup_plan` and validate it.
+///
+/// Every root must pass the usual scan-root checks. Roots that would sweep
+/// in far more than a cleanup usually means to, such as a filesystem root or
+/// the home folder, are refused unless `confirm_unsafe` is set. The plan's
+/// exclusions are added to the global exclusions so scans started from it
+/// skip the same folders as on the machine that exported it.
+#[command]
+pub async fn import_cleanup_plan(
+    state: State<'_, AppState>,
+    path: String,
+    confirm_unsafe: Option<bool>,
+) -> AppResult<ImportedPlan> {
+    timed_command("import_cleanup_plan", Some(state.inner()), Some(path.clone()), async {
+        let source = path.clone();
+        let plan = tokio::task::spawn_blocking(move || read_plan(Path::new(&source)))
+            .await
+            .map_err(|e| AppError::SystemError(e.to_string()))??;
+
+        let mut invalid = Vec::new();
+        let mut broad = Vec::new();
+        for root in &plan.roots {
+            match validate_scan_root(root) {
+                Err(e) => invalid.push(format!("{}: {}", root, e)),
+                Ok(root_path) => {
+                    if let Some(reason) = broad_root_reason(&root_path) {
+                        broad.push(format!("{} {}", root, reason));
+                    }
+                }
+            }
+        }
+        if !invalid.is_empty() {
+            return Err(AppError::SecurityError(format!(
+                "The plan has roots that cannot be scanned: {}",
+                invalid.join("; ")
+            )));
+        }
+        if !broad.is_empty() && !confirm_unsafe.unwrap_or(false) {
+            return Err(AppError::SecurityError(format!(
+                "The plan scans unusually broad roots ({}); confirm to import it anyway",
+                broad.join("; ")
+            )));
+        }
+
+        let mut exclusions = state.get_config().await.security.global_exclusions;
+        let mut exclusions_added = Vec::new();
+        for excluded in &plan.exclusions {
+            let canonical = Path::new(excluded)
+                .canonicalize()
+                .map(|canonical| canonical.to_string_lossy().to_string())
+                .unwrap_or_else(|_| excluded.clone());
+            if !exclusions.contains(&canonical) {
+                exclusions.push(canonical.clone());
+                exclusions_added.push(canonical);
+            }
+        }
+        if !exclusions_added.is_empty() {
+            state
+                .update_config(move |config| config.security.global_exclusions = exclusions)
+                .await
+                .map_err(|e| AppError::ConfigError(e.to_string()))?;
+        }
+
+        info!(
+            "Imported cleanup plan {} with {} roots, {} new exclusions",
+            path,
+            plan.roots.len(),
+            exclusions_added.len()
+        );
+        Ok(ImportedPlan {
+            plan,
+            exclusions_added,
+            confirmed_roots: broad,
+        })
+    })
+    .await
+}
+
+/// Parse and check the structure of the plan at `path`
+pub fn read_plan(path: &Path) -> AppResult<CleanupPlan> {
+    if fs::metadata(path)?.len() > MAX_PLAN_SIZE {
+        return Err(AppError::ConfigError(format!(
+            "{} is too large to be a cleanup plan",
+            path.display()
+        )));
+    }
+    let plan: CleanupPlan = serde_json::from_str(&fs::read_to_string(path)?).map_err(|e| {
+        AppError::ConfigError(format!("{} is not a cleanup plan: {}", path.display(), e))
+    })?;
+
+    if plan.version == 0 || plan.version > PLAN_VERSION {
+        return Err(AppError::ConfigError(format!(
+            "Cleanup plan version {} is not supported; this build reads up to version {}",
+            plan.version, PLAN_VERSION
+        )));
+    }
+    if plan.roots.is_empty() {
+        return Err(AppError::ConfigError("The plan has no roots".to_string()));
+    }
+    if let Some(relative) = plan.exclusions.iter().find(|e| !Path::new(e).is_absolute()) {
+        return Err(AppError::ConfigError(format!(
+            "Plan exclusion '{}' is not an absolute path",
+            relative
+        )));
+    }
+    Ok(plan)
+}
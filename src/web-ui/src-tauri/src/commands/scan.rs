@@ -0,0 +1,517 @@
+use crate::utils::classifier::{self, Classification};
+use crate::utils::config::SecurityConfig;
+use crate::utils::gitignore::GitignoreRules;
+use crate::utils::logging::timed_command;
+use crate::utils::operations::{OperationKind, OperationTally};
+use crate::utils::security::validate_scan_root;
+use crate::utils::snapshots::{ScanSnapshot, SessionSummary, SnapshotEntry, SnapshotStore};
+use crate::utils::walker::{DirWalker, SkipReason, SkippedPath, WalkOptions};
+use crate::{AppError, AppResult, AppState};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::{command, State};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// Files collected between two progress reports of `scan_directory`
+const SCAN_PROGRESS_INTERVAL: usize = 1000;
+
+/// Running count of the files collected by every walker feeding one scan.
+/// Walkers add their counts atomically, so reports from walkers on several
+/// threads never lose updates and the highest total reported is exact.
+#[derive(Debug, Default)]
+struct ScanProgress {
+    files: AtomicU64,
+}
+
+impl ScanProgress {
+    /// Add `files` and return the new total
+    fn add(&self, files: u64) -> u64 {
+        self.files.fetch_add(files, Ordering::Relaxed) + files
+    }
+}
+
+/// User-facing options shared by recursive scan and find commands
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanOptions {
+    /// Skip subtrees that live on a different filesystem than the root
+    pub stay_on_filesystem: bool,
+    /// Skip subtrees mounted from SMB, NFS or other network filesystems
+    pub skip_network_drives: bool,
+    /// How `.gitignore` files found during the walk are applied; only
+    /// `scan_directory` honours this
+    pub gitignore: GitignoreMode,
+    /// Files smaller than this many bytes are left out of results and totals
+    /// and only counted; 0 keeps everything
+    pub min_file_size: u64,
+    /// Leave out dotfiles and hidden entries, which are usually configuration
+    /// rather than reclaimable data. On by default; pass `false` to include them.
+    pub skip_hidden: bool,
+    /// Scan folders listed in `security.global_exclusions` this time too
+    pub ignore_global_exclusions: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            stay_on_filesystem: false,
+            skip_network_drives: false,
+            gitignore: GitignoreMode::default(),
+            min_file_size: 0,
+            skip_hidden: true,
+            ignore_global_exclusions: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum GitignoreMode {
+    /// Ignore `.gitignore` files entirely
+    #[default]
+    Off,
+    /// Leave gitignored files and directories out of the results
+    Exclude,
+    /// Keep gitignored files but flag them, e.g. for "clean ignored files"
+    Annotate,
+}
+
+impl ScanOptions {
+    /// Walker options for these scan options plus the global exclusions
+    /// from `security`
+    pub fn walk_options(&self, security: &SecurityConfig) -> WalkOptions {
+        WalkOptions {
+            stay_on_filesystem: self.stay_on_filesystem,
+            skip_network_drives: self.skip_network_drives,
+            skip_hidden: self.skip_hidden,
+            excluded_filesystem_types: security.excluded_filesystem_types.clone(),
+            excluded_paths: if self.ignore_global_exclusions {
+                Vec::new()
+            } else {
+                security.global_exclusion_paths()
+            },
+            ..WalkOptions::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScannedFile {
+    pub path: String,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+    pub gitignored: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScanResult {
+    pub session_id: Option<String>,
+    pub root: String,
+    pub files: Vec<ScannedFile>,
+    pub total_files: u64,
+    pub total_size: u64,
+    pub gitignored_files: u64,
+    pub gitignored_size: u64,
+    /// Files below `min_file_size`, excluded from `files` and `total_size`
+    pub small_files: u64,
+    pub small_files_size: u64,
+    /// Hidden files and directories left out because of `skip_hidden`
+    pub hidden_skipped: u64,
+    pub unreadable_entries: u64,
+    pub skipped: Vec<SkippedPath>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionFile {
+    pub path: String,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+    /// Cached AI verdict when available, otherwise the heuristic one
+    pub classification: Classification,
+}
+
+/// A persisted scan session restored for review, e.g. after a restart
+#[derive(Debug, Serialize)]
+pub struct SessionDetail {
+    pub summary: SessionSummary,
+    pub files: Vec<SessionFile>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResizedFile {
+    pub path: String,
+    pub old_size: u64,
+    pub new_size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScanDiff {
+    pub old_session_id: String,
+    pub new_session_id: String,
+    pub added: Vec<SnapshotEntry>,
+    pub removed: Vec<SnapshotEntry>,
+    pub resized: Vec<ResizedFile>,
+    pub bytes_added: u64,
+    pub bytes_removed: u64,
+}
+
+#[command]
+pub async fn scan_directory(
+    state: State<'_, AppState>,
+    path: String,
+    options: Option<ScanOptions>,
+) -> AppResult<ScanResult> {
+    timed_command("scan_directory", Some(state.inner()), Some(path.clone()), async {
+        run_scan(&state, path, options.unwrap_or_default()).await
+    })
+    .await
+}
+
+/// Walk `path`, persist the result as a scan session and return it. Shared by
+/// `scan_directory` and headless runs.
+pub async fn run_scan(
+    state: &AppState,
+    path: String,
+    options: ScanOptions,
+) -> AppResult<ScanResult> {
+    let root = validate_scan_root(&path)?;
+    let config = state.get_config().await;
+    let operation = state.operations.start(OperationKind::Scan, &path);
+    let cancel = operation.token();
+
+    let walk_options = options.walk_options(&config.security);
+    // The previous scan of the same root is the best guess at the total,
+    // which is what makes a progress percentage and ETA possible
+    let expected_files = SnapshotStore::new(&config.cache_directory)
+        .list_summaries()
+        .into_iter()
+        .find(|summary| summary.root == path)
+        .map(|summary| summary.total_files);
+
+    let (result, mut operation) = tokio::task::spawn_blocking(move || {
+        let mut on_progress = |files: u64| {
+            if let Some(expected) = expected_files {
+                // The tree may have grown since the previous scan, in which
+                // case the total follows the count instead of capping it
+                operation.report_progress(files, expected.max(files));
+            }
+        };
+        let progress = ScanProgress::default();
+        let result = scan_tree(
+            &path,
+            &root,
+            &options,
+            walk_options,
+            &cancel,
+            &progress,
+            &mut on_progress,
+        );
+        (result, operation)
+    })
+    .await
+    .map_err(|e| AppError::SystemError(e.to_string()))?;
+    let mut result = result?;
+
+    let snapshot = ScanSnapshot::new(&result.root, snapshot_entries(&result.files));
+    let store = SnapshotStore::new(&config.cache_directory);
+    match store.save(&snapshot, config.analysis.snapshot_retention) {
+        Ok(()) => {
+            state.set_active_session(snapshot.id.clone()).await;
+            result.session_id = Some(snapshot.id)
+        }
+        Err(e) => warn!("Failed to persist scan snapshot: {}", e),
+    }
+
+    // The confirmation covers a single scan-and-review cycle
+    state.clear_scan_root().await;
+
+    info!(
+        "Scanned {}: {} files, {} bytes, {} subtrees skipped",
+        result.root,
+        result.total_files,
+        result.total_size,
+        result.skipped.len()
+    );
+
+    operation.complete(OperationTally {
+        succeeded: result.total_files,
+        failed: result.unreadable_entries,
+        bytes: result.total_size,
+        errors: Vec::new(),
+    });
+    Ok(result)
+}
+
+#[command]
+pub async fn diff_scans(
+    state: State<'_, AppState>,
+    old_session_id: String,
+    new_session_id: String,
+) -> AppResult<ScanDiff> {
+    timed_command("diff_scans", Some(state.inner()), None, async {
+        let store = SnapshotStore::new(&state.get_config().await.cache_directory);
+        let load = |id: &str| {
+            store
+                .load(id)
+                .map_err(|e| AppError::FileSystemError(e.to_string()))
+        };
+        let old = load(&old_session_id)?;
+        let new = load(&new_session_id)?;
+
+        Ok(compare_snapshots(old, new))
+    })
+    .await
+}
+
+/// Persisted scan sessions, newest first, for a "resume last session" prompt
+#[command]
+pub async fn list_sessions(state: State<'_, AppState>) -> AppResult<Vec<SessionSummary>> {
+    timed_command("list_sessions", Some(state.inner()), None, async {
+        let store = SnapshotStore::new(&state.get_config().await.cache_directory);
+
+        tokio::task::spawn_blocking(move || store.list_summaries())
+            .await
+            .map_err(|e| AppError::SystemError(e.to_string()))
+    })
+    .await
+}
+
+/// Restore a persisted scan session together with its known classifications
+#[command]
+pub async fn load_session(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> AppResult<SessionDetail> {
+    timed_command("load_session", Some(state.inner()), None, async {
+        let config = state.get_config().await;
+        let snapshot = SnapshotStore::new(&config.cache_directory)
+            .load(&session_id)
+            .map_err(|e| AppError::FileSystemError(e.to_string()))?;
+        state.set_active_session(session_id).await;
+        let cache = state.analysis_cache.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let summary = snapshot.summary();
+            let files = snapshot
+                .entries
+                .iter()
+                .map(|entry| {
+                    let facts = entry.facts();
+                    SessionFile {
+                        classification: classifier::classify_offline(&config, &cache, &facts),
+                        path: entry.path.clone(),
+                        size: entry.size,
+                        modified: facts.modified,
+                    }
+                })
+                .collect();
+
+            SessionDetail { summary, files }
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))
+    })
+    .await
+}
+
+/// Compute added, removed and resized files between two snapshots
+fn compare_snapshots(old: ScanSnapshot, new: ScanSnapshot) -> ScanDiff {
+    let mut old_entries: HashMap<String, SnapshotEntry> = old
+        .entries
+        .into_iter()
+        .map(|entry| (entry.path.clone(), entry))
+        .collect();
+
+    let mut added = Vec::new();
+    let mut resized = Vec::new();
+    for entry in new.entries {
+        match old_entries.remove(&entry.path) {
+            None => added.push(entry),
+            Some(previous) if previous.size != entry.size => resized.push(ResizedFile {
+                path: entry.path,
+                old_size: previous.size,
+                new_size: entry.size,
+            }),
+            Some(_) => {}
+        }
+    }
+
+    let mut removed: Vec<SnapshotEntry> = old_entries.into_values().collect();
+    removed.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let bytes_added = added.iter().map(|e| e.size).sum::<u64>()
+        + resized
+            .iter()
+            .map(|r| r.new_size.saturating_sub(r.old_size))
+            .sum::<u64>();
+    let bytes_removed = removed.iter().map(|e| e.size).sum::<u64>()
+        + resized
+            .iter()
+            .map(|r| r.old_size.saturating_sub(r.new_size))
+            .sum::<u64>();
+
+    ScanDiff {
+        old_session_id: old.id,
+        new_session_id: new.id,
+        added,
+        removed,
+        resized,
+        bytes_added,
+        bytes_removed,
+    }
+}
+
+fn snapshot_entries(files: &[ScannedFile]) -> Vec<SnapshotEntry> {
+    files
+        .iter()
+        .map(|file| SnapshotEntry {
+            path: file.path.clone(),
+            size: file.size,
+            modified: file.modified.map(|m| m.timestamp()),
+        })
+        .collect()
+}
+
+/// Walk a validated root and collect every regular file, adding to
+/// `progress` every `SCAN_PROGRESS_INTERVAL` files and calling `on_progress`
+/// with its total. The last report always comes after the final count is
+/// added, so for a single walker it equals `total_files`.
+fn scan_tree(
+    root_str: &str,
+    root: &Path,
+    options: &ScanOptions,
+    walk_options: WalkOptions,
+    cancel: &CancellationToken,
+    progress: &ScanProgress,
+    on_progress: &mut dyn FnMut(u64),
+) -> AppResult<ScanResult> {
+    let mut walker = DirWalker::new(root, walk_options);
+    let mut gitignore =
+        (options.gitignore != GitignoreMode::Off).then(|| GitignoreRules::new(root));
+    let mut files = Vec::new();
+    let mut total_size = 0u64;
+    let mut gitignored_files = 0u64;
+    let mut gitignored_size = 0u64;
+    let mut small_files = 0u64;
+    let mut small_files_size = 0u64;
+    let mut unreported = 0u64;
+
+    while let Some(entry) = walker.next() {
+        if cancel.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+
+        let gitignored = gitignore
+            .as_ref()
+            .is_some_and(|rules| rules.is_ignored(&entry.path, entry.is_dir()));
+
+        if entry.is_dir() {
+            if gitignored && options.gitignore == GitignoreMode::Exclude {
+                walker.skip_subtree(&entry.path, SkipReason::Gitignored);
+            } else if let Some(rules) = gitignore.as_mut() {
+                rules.load_dir(&entry.path);
+            }
+            continue;
+        }
+        if !entry.is_file() || (gitignored && options.gitignore == GitignoreMode::Exclude) {
+            continue;
+        }
+
+        let size = entry.metadata.len();
+        if size < options.min_file_size {
+            small_files += 1;
+            small_files_size += size;
+            continue;
+        }
+        total_size += size;
+        if gitignored {
+            gitignored_files += 1;
+            gitignored_size += size;
+        }
+        files.push(ScannedFile {
+            path: entry.path.to_string_lossy().to_string(),
+            size,
+            modified: entry.metadata.modified().ok().map(DateTime::<Utc>::from),
+            gitignored,
+        });
+        unreported += 1;
+        if unreported == SCAN_PROGRESS_INTERVAL as u64 {
+            on_progress(progress.add(unreported));
+            unreported = 0;
+        }
+    }
+
+    on_progress(progress.add(unreported));
+
+    let unreadable_entries = walker.error_count();
+    Ok(ScanResult {
+        session_id: None,
+        root: root_str.to_string(),
+        total_files: files.len() as u64,
+        files,
+        total_size,
+        gitignored_files,
+        gitignored_size,
+        small_files,
+        small_files_size,
+        hidden_skipped: walker.hidden_count(),
+        unreadable_entries,
+        skipped: walker.into_skipped(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn parallel_walkers_report_the_exact_total() {
+        const PARTS: usize = 8;
+        const FILES_PER_PART: usize = 1250;
+        let tree = tempfile::tempdir().unwrap();
+        for part in 0..PARTS {
+            let dir = tree.path().join(format!("part{}", part)).join("nested");
+            fs::create_dir_all(&dir).unwrap();
+            for file in 0..FILES_PER_PART {
+                fs::write(dir.join(format!("f{}.txt", file)), b"x").unwrap();
+            }
+        }
+
+        let progress = ScanProgress::default();
+        let highest = AtomicU64::new(0);
+        let cancel = CancellationToken::new();
+        let returned: u64 = std::thread::scope(|scope| {
+            let walkers: Vec<_> = (0..PARTS)
+                .map(|part| {
+                    let root = tree.path().join(format!("part{}", part));
+                    let (progress, highest, cancel) = (&progress, &highest, &cancel);
+                    scope.spawn(move || {
+                        let mut on_progress = |files: u64| {
+                            highest.fetch_max(files, Ordering::Relaxed);
+                        };
+                        scan_tree(
+                            &root.to_string_lossy(),
+                            &root,
+                            &ScanOptions::default(),
+                            WalkOptions::default(),
+                            cancel,
+                            progress,
+                            &mut on_progress,
+                        )
+                        .unwrap()
+                        .total_files
+                    })
+                })
+                .collect();
+            walkers.into_iter().map(|walker| walker.join().unwrap()).sum()
+        });
+
+        assert_eq!(returned, (PARTS * FILES_PER_PART) as u64);
+        assert_eq!(highest.load(Ordering::Relaxed), returned);
+    }
+}
@@ -0,0 +1,175 @@
+use crate::commands::plan::read_plan;
+use crate::utils::classifier::FileCategory;
+use crate::utils::logging::timed_command;
+use crate::utils::scheduler::{self, ScheduleInterval, ScheduledCleanup};
+use crate::utils::security::{broad_root_reason, validate_scan_root};
+use crate::{AppError, AppResult, AppState};
+use chrono::Utc;
+use serde::Serialize;
+use std::path::Path;
+use tauri::{command, State};
+use tracing::info;
+
+/// Categories a cleanup may act on without anyone reviewing it first:
+/// regenerable data whose loss costs at most a rebuild or re-download
+pub const UNATTENDED_CATEGORIES: &[FileCategory] = &[
+    FileCategory::Temporary,
+    FileCategory::Cache,
+    FileCategory::DevCache,
+    FileCategory::Log,
+];
+
+/// A registered cleanup and whether it is still in effect
+#[derive(Debug, Serialize)]
+pub struct ScheduledCleanupStatus {
+    #[serde(flatten)]
+    pub task: ScheduledCleanup,
+    /// The OS scheduler still has the task; it may have been removed there
+    pub installed: bool,
+    pub plan_exists: bool,
+}
+
+/// Have the OS scheduler run the plan at `plan_path` in headless mode every
+/// `interval`: Task Scheduler on Windows, a launchd agent on macOS, and a
+/// systemd user timer or, without one, a cron entry on Linux.
+///
+/// Since nobody reviews an unattended run, the plan may only select
+/// regenerable categories and no broad roots, and registering needs
+/// `confirm_unattended`. Registering the same plan again replaces its task.
+#[command]
+pub async fn register_scheduled_cleanup(
+    state: State<'_, AppState>,
+    plan_path: String,
+    interval: ScheduleInterval,
+    confirm_unattended: Option<bool>,
+) -> AppResult<ScheduledCleanup> {
+    let target = Some(plan_path.clone());
+    timed_command("register_scheduled_cleanup", Some(state.inner()), target, async {
+        if !confirm_unattended.unwrap_or(false) {
+            return Err(AppError::SecurityError(
+                "Scheduled cleanups delete files without asking; confirm to register one"
+                    .to_string(),
+            ));
+        }
+
+        let plan_path = Path::new(&plan_path).canonicalize()?;
+        let source = plan_path.clone();
+        let plan = tokio::task::spawn_blocking(move || read_plan(&source))
+            .await
+            .map_err(|e| AppError::SystemError(e.to_string()))??;
+
+        if plan.categories.is_empty() {
+            return Err(AppError::SecurityError(
+                "A scheduled plan must name the categories it cleans".to_string(),
+            ));
+        }
+        if let Some(category) = plan
+            .categories
+            .iter()
+            .find(|category| !UNATTENDED_CATEGORIES.contains(category))
+        {
+            return Err(AppError::SecurityError(format!(
+                "Category {:?} is not safe to clean unattended; scheduled plans are limited to \
+                 temporary files, caches and logs",
+                category
+            )));
+        }
+        for root in &plan.roots {
+            let root_path = validate_scan_root(root)?;
+            if let Some(reason) = broad_root_reason(&root_path) {
+                return Err(AppError::SecurityError(format!(
+                    "{} {}, which is too broad to clean unattended",
+                    root, reason
+                )));
+            }
+        }
+
+        let executable = std::env::current_exe()?;
+        let task = ScheduledCleanup {
+            id: scheduler::task_id(&plan_path),
+            plan_path,
+            interval,
+            backend: scheduler::preferred_backend(),
+            registered_at: Utc::now(),
+        };
+        let registered = task.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut tasks = scheduler::load_manifest();
+            // A task registered earlier with another backend would keep running
+            let previous = tasks
+                .iter()
+                .find(|t| t.id == task.id && t.backend != task.backend);
+            if let Some(previous) = previous {
+                scheduler::uninstall(previous)?;
+            }
+            scheduler::install(&task, &executable)?;
+            tasks.retain(|t| t.id != task.id);
+            tasks.push(task);
+            scheduler::save_manifest(&tasks)
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))?
+        .map_err(|e| AppError::SystemError(format!("Failed to schedule cleanup: {}", e)))?;
+
+        info!(
+            "Scheduled cleanup {} of {} ({:?}, {:?})",
+            registered.id,
+            registered.plan_path.display(),
+            registered.interval,
+            registered.backend
+        );
+        Ok(registered)
+    })
+    .await
+}
+
+/// Remove the scheduled cleanup `id` from the OS scheduler and forget it
+#[command]
+pub async fn unregister_scheduled_cleanup(
+    state: State<'_, AppState>,
+    id: String,
+) -> AppResult<ScheduledCleanup> {
+    timed_command("unregister_scheduled_cleanup", Some(state.inner()), Some(id.clone()), async {
+        let removed = tokio::task::spawn_blocking(move || {
+            let mut tasks = scheduler::load_manifest();
+            let Some(index) = tasks.iter().position(|task| task.id == id) else {
+                return Err(AppError::ConfigError(format!("No scheduled cleanup {}", id)));
+            };
+            scheduler::uninstall(&tasks[index]).map_err(|e| {
+                AppError::SystemError(format!("Failed to remove scheduled cleanup: {}", e))
+            })?;
+            let removed = tasks.remove(index);
+            scheduler::save_manifest(&tasks).map_err(|e| AppError::ConfigError(e.to_string()))?;
+            Ok(removed)
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))??;
+
+        info!("Removed scheduled cleanup {}", removed.id);
+        Ok(removed)
+    })
+    .await
+}
+
+/// Scheduled cleanups registered by this app, checked against the OS
+/// scheduler
+#[command]
+pub async fn list_scheduled_cleanups(
+    state: State<'_, AppState>,
+) -> AppResult<Vec<ScheduledCleanupStatus>> {
+    timed_command("list_scheduled_cleanups", Some(state.inner()), None, async {
+        tokio::task::spawn_blocking(|| {
+            scheduler::load_manifest()
+                .into_iter()
+                .map(|task| ScheduledCleanupStatus {
+                    installed: scheduler::is_installed(&task),
+                    plan_exists: task.plan_path.is_file(),
+                    task,
+                })
+                .collect()
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))
+    })
+    .await
+}
@@ -1,9 +1,20 @@
-use crate::AppResult;
+use crate::utils::config::default_protected_patterns;
+use crate::utils::logging::timed_command;
+use crate::utils::platform::{
+    is_network_drive, is_removable_drive, resolve_symlinks, NETWORK_DRIVE_WARNING,
+    REMOVABLE_MEDIA_WARNING,
+};
+use crate::utils::security::validate_scan_root;
+use crate::utils::validation_cache::ValidationCache;
+use crate::{AppError, AppResult, AppState};
 use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use tauri::command;
+use tauri::{command, State};
+use tokio::task::JoinSet;
+use tracing::info;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Clone)]
 pub struct SafetyValidation {
     pub is_safe: bool,
     pub risk_level: RiskLevel,
@@ -20,87 +31,362 @@ pub enum RiskLevel {
     Critical,
 }
 
-#[command]
-pub async fn validate_path_safety(path: String) -> AppResult<SafetyValidation> {
-    let path_buf = PathBuf::from(&path);
-    let mut warnings = Vec::new();
-    let mut blocked_reasons = Vec::new();
-
-    // Check if path exists
-    if !path_buf.exists() {
-        blocked_reasons.push("Path does not exist".to_string());
-        return Ok(SafetyValidation {
-            is_safe: false,
-            risk_level: RiskLevel::Critical,
-            warnings,
-            blocked_reasons,
-        });
-    }
+impl SafetyValidation {
+    /// Verdict of the rules that fired: a block sets the level directly,
+    /// otherwise one or two warnings make it Low and more make it Medium
+    fn from_rules(rules: &[RiskRuleResult]) -> Self {
+        let mut warnings = Vec::new();
+        let mut blocked_reasons = Vec::new();
+        let mut block_level = None;
+        for rule in rules.iter().filter(|rule| rule.fired) {
+            match &rule.effect {
+                RiskEffect::Warns => warnings.push(rule.detail.clone()),
+                RiskEffect::Blocks(level) => {
+                    blocked_reasons.push(rule.detail.clone());
+                    block_level = Some(level.clone());
+                }
+            }
+        }
 
-    // Check if it's a directory
-    if !path_buf.is_dir() {
-        blocked_reasons.push("Path is not a directory".to_string());
-        return Ok(SafetyValidation {
-            is_safe: false,
-            risk_level: RiskLevel::Critical,
+        let (is_safe, risk_level) = match block_level {
+            Some(level) => (false, level),
+            None if warnings.is_empty() => (true, RiskLevel::Safe),
+            None if warnings.len() <= 2 => (true, RiskLevel::Low),
+            None => (true, RiskLevel::Medium),
+        };
+        SafetyValidation {
+            is_safe,
+            risk_level,
             warnings,
             blocked_reasons,
-        });
+        }
     }
+}
 
-    // System directory checks
-    if is_system_directory(&path_buf) {
-        blocked_reasons.push("System directory - modification not recommended".to_string());
-        return Ok(SafetyValidation {
-            is_safe: false,
-            risk_level: RiskLevel::High,
-            warnings,
-            blocked_reasons,
-        });
-    }
+/// Individual rule evaluated by `assess_path_safety`
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskRule {
+    Missing,
+    NotDirectory,
+    SystemDirectory,
+    HomeDirectory,
+    ApplicationDirectory,
+    RemovableMedia,
+    NetworkDrive,
+    PathDepth,
+    SpecialCharacters,
+}
 
-    // User home directory checks
-    if is_user_home_directory(&path_buf) {
-        warnings.push("User home directory - review carefully before operations".to_string());
-    }
+/// What a rule does to the final assessment when it fires
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", content = "level", rename_all = "snake_case")]
+pub enum RiskEffect {
+    Warns,
+    Blocks(RiskLevel),
+}
 
-    // Application directory checks
-    if is_application_directory(&path_buf) {
-        warnings.push("Application directory - may affect installed programs".to_string());
-    }
+#[derive(Debug, Clone, Serialize)]
+pub struct RiskRuleResult {
+    pub rule: RiskRule,
+    pub fired: bool,
+    pub effect: RiskEffect,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RiskExplanation {
+    pub path: String,
+    pub is_safe: bool,
+    pub risk_level: RiskLevel,
+    pub rules: Vec<RiskRuleResult>,
+}
 
-    // Check directory depth
-    if path_buf.components().count() > 10 {
-        warnings.push("Very deep directory path - may cause performance issues".to_string());
+/// Validate a path, reusing a recent result for the same unchanged path
+/// unless `force` is set
+#[command]
+pub async fn validate_path_safety(
+    state: State<'_, AppState>,
+    path: String,
+    force: Option<bool>,
+) -> AppResult<SafetyValidation> {
+    timed_command("validate_path_safety", Some(state.inner()), Some(path.clone()), async {
+        let capacity = state.get_config().await.security.validation_cache_size;
+        let cache = state.validation_cache.clone();
+        let force = force.unwrap_or(false);
+
+        tokio::task::spawn_blocking(move || cached_path_safety(&cache, &path, capacity, force))
+            .await
+            .map_err(|e| AppError::SystemError(e.to_string()))
+    })
+    .await
+}
+
+/// Validate many paths in one round trip, returning results in input order.
+///
+/// Duplicate paths are validated once and the result is shared; recent
+/// results are reused like `validate_path_safety` unless `force` is set.
+#[command]
+pub async fn validate_paths(
+    state: State<'_, AppState>,
+    paths: Vec<String>,
+    force: Option<bool>,
+) -> AppResult<Vec<SafetyValidation>> {
+    timed_command("validate_paths", Some(state.inner()), None, async {
+        let capacity = state.get_config().await.security.validation_cache_size;
+        let force = force.unwrap_or(false);
+        let mut tasks = JoinSet::new();
+        let mut seen = HashSet::new();
+
+        for path in &paths {
+            if !seen.insert(path.clone()) {
+                continue;
+            }
+
+            let path = path.clone();
+            let cache = state.validation_cache.clone();
+            tasks.spawn_blocking(move || {
+                let validation = cached_path_safety(&cache, &path, capacity, force);
+                (path, validation)
+            });
+        }
+
+        let mut results: HashMap<String, SafetyValidation> = HashMap::new();
+        while let Some(joined) = tasks.join_next().await {
+            let (path, validation) = joined.map_err(|e| AppError::SystemError(e.to_string()))?;
+            results.insert(path, validation);
+        }
+
+        Ok(paths.iter().map(|path| results[path].clone()).collect())
+    })
+    .await
+}
+
+/// Record the directory the user explicitly selected. Delete commands refuse
+/// any path that is not a descendant of it.
+#[command]
+pub async fn set_scan_root(state: State<'_, AppState>, path: String) -> AppResult<String> {
+    timed_command("set_scan_root", Some(state.inner()), Some(path.clone()), async {
+        let root = validate_scan_root(&path)?.canonicalize()?;
+        info!("Scan root confirmed: {}", root.display());
+        state.set_scan_root(root.clone()).await;
+        Ok(root.to_string_lossy().to_string())
+    })
+    .await
+}
+
+/// Explain, rule by rule, how the risk level `validate_path_safety`
+/// reports for a path was reached
+#[command]
+pub async fn explain_path_risk(
+    state: State<'_, AppState>,
+    path: String,
+) -> AppResult<RiskExplanation> {
+    timed_command("explain_path_risk", Some(state.inner()), Some(path.clone()), async {
+        tokio::task::spawn_blocking(move || explain_path_safety(&path))
+            .await
+            .map_err(|e| AppError::SystemError(e.to_string()))
+    })
+    .await
+}
+
+/// Replace the configured protected patterns with the defaults for this OS
+#[command]
+pub async fn reset_protected_patterns_to_default(
+    state: State<'_, AppState>,
+) -> AppResult<Vec<String>> {
+    timed_command("reset_protected_patterns_to_default", Some(state.inner()), None, async {
+        let patterns = default_protected_patterns();
+        let updated = patterns.clone();
+        state
+            .update_config(move |config| config.security.protected_patterns = updated)
+            .await
+            .map_err(|e| AppError::ConfigError(e.to_string()))?;
+
+        info!("Protected patterns reset to {} platform defaults", patterns.len());
+        Ok(patterns)
+    })
+    .await
+}
+
+/// Exclude `path` from every future scan. It is stored in canonical form so
+/// it matches however a scan reaches it; returns the updated list.
+#[command]
+pub async fn add_global_exclusion(
+    state: State<'_, AppState>,
+    path: String,
+) -> AppResult<Vec<String>> {
+    timed_command("add_global_exclusion", Some(state.inner()), Some(path.clone()), async {
+        let canonical = Path::new(&path)
+            .canonicalize()
+            .map_err(|e| AppError::FileSystemError(format!("Cannot resolve {}: {}", path, e)))?
+            .to_string_lossy()
+            .to_string();
+
+        let mut exclusions = state.get_config().await.security.global_exclusions;
+        if !exclusions.contains(&canonical) {
+            exclusions.push(canonical.clone());
+            let updated = exclusions.clone();
+            state
+                .update_config(move |config| config.security.global_exclusions = updated)
+                .await
+                .map_err(|e| AppError::ConfigError(e.to_string()))?;
+            info!("Added global exclusion {}", canonical);
+        }
+        Ok(exclusions)
+    })
+    .await
+}
+
+/// Stop excluding `path` from scans, given either as stored or in a form
+/// that resolves to it; returns the updated list
+#[command]
+pub async fn remove_global_exclusion(
+    state: State<'_, AppState>,
+    path: String,
+) -> AppResult<Vec<String>> {
+    timed_command("remove_global_exclusion", Some(state.inner()), Some(path.clone()), async {
+        let canonical = Path::new(&path)
+            .canonicalize()
+            .map(|canonical| canonical.to_string_lossy().to_string())
+            .unwrap_or_else(|_| path.clone());
+
+        let mut exclusions = state.get_config().await.security.global_exclusions;
+        let before = exclusions.len();
+        exclusions.retain(|excluded| *excluded != path && *excluded != canonical);
+        if exclusions.len() != before {
+            let updated = exclusions.clone();
+            state
+                .update_config(move |config| config.security.global_exclusions = updated)
+                .await
+                .map_err(|e| AppError::ConfigError(e.to_string()))?;
+            info!("Removed global exclusion {}", path);
+        }
+        Ok(exclusions)
+    })
+    .await
+}
+
+fn cached_path_safety(
+    cache: &ValidationCache<SafetyValidation>,
+    path: &str,
+    capacity: usize,
+    force: bool,
+) -> SafetyValidation {
+    if !force {
+        if let Some(validation) = cache.get(path) {
+            return validation;
+        }
     }
 
-    // Check for special characters
-    if has_special_characters(&path) {
-        warnings.push("Path contains special characters - some operations may be limited".to_string());
+    let validation = assess_path_safety(path);
+    cache.insert(path, validation.clone(), capacity);
+    validation
+}
+
+/// Run every safety check against a single path
+pub fn assess_path_safety(path: &str) -> SafetyValidation {
+    SafetyValidation::from_rules(&path_risk_rules(path))
+}
+
+/// Explain, rule by rule, how `assess_path_safety` reached its verdict
+pub fn explain_path_safety(path: &str) -> RiskExplanation {
+    let rules = path_risk_rules(path);
+    let validation = SafetyValidation::from_rules(&rules);
+    RiskExplanation {
+        path: path.to_string(),
+        is_safe: validation.is_safe,
+        risk_level: validation.risk_level,
+        rules,
     }
+}
 
-    // Determine overall safety
-    let (is_safe, risk_level) = if blocked_reasons.is_empty() {
-        if warnings.is_empty() {
-            (true, RiskLevel::Safe)
-        } else if warnings.len() <= 2 {
-            (true, RiskLevel::Low)
-        } else {
-            (true, RiskLevel::Medium)
+/// Evaluate the safety rules in order. A rule that blocks ends the
+/// evaluation, so the rules after it are not listed.
+fn path_risk_rules(path: &str) -> Vec<RiskRuleResult> {
+    let path_buf = PathBuf::from(path);
+    let checks: [(RiskRule, RiskEffect, &str, &dyn Fn() -> bool); 9] = [
+        (
+            RiskRule::Missing,
+            RiskEffect::Blocks(RiskLevel::Critical),
+            "Path does not exist",
+            &|| !path_buf.exists(),
+        ),
+        (
+            RiskRule::NotDirectory,
+            RiskEffect::Blocks(RiskLevel::Critical),
+            "Path is not a directory",
+            &|| !path_buf.is_dir(),
+        ),
+        (
+            RiskRule::SystemDirectory,
+            RiskEffect::Blocks(RiskLevel::High),
+            "System directory - modification not recommended",
+            &|| is_system_directory(&path_buf),
+        ),
+        (
+            RiskRule::HomeDirectory,
+            RiskEffect::Warns,
+            "User home directory - review carefully before operations",
+            &|| is_user_home_directory(&path_buf),
+        ),
+        (
+            RiskRule::ApplicationDirectory,
+            RiskEffect::Warns,
+            "Application directory - may affect installed programs",
+            &|| is_application_directory(&path_buf),
+        ),
+        (
+            RiskRule::RemovableMedia,
+            RiskEffect::Warns,
+            REMOVABLE_MEDIA_WARNING,
+            &|| is_removable_drive(&path_buf),
+        ),
+        (
+            RiskRule::NetworkDrive,
+            RiskEffect::Warns,
+            NETWORK_DRIVE_WARNING,
+            &|| is_network_drive(&path_buf),
+        ),
+        (
+            RiskRule::PathDepth,
+            RiskEffect::Warns,
+            "Very deep directory path - may cause performance issues",
+            &|| path_buf.components().count() > 10,
+        ),
+        (
+            RiskRule::SpecialCharacters,
+            RiskEffect::Warns,
+            "Path contains special characters - some operations may be limited",
+            &|| has_special_characters(path),
+        ),
+    ];
+
+    let mut rules = Vec::new();
+    for (rule, effect, detail, check) in checks {
+        let fired = check();
+        let blocks = fired && matches!(effect, RiskEffect::Blocks(_));
+        rules.push(RiskRuleResult {
+            rule,
+            fired,
+            effect,
+            detail: detail.to_string(),
+        });
+        if blocks {
+            break;
         }
-    } else {
-        (false, RiskLevel::High)
-    };
-
-    Ok(SafetyValidation {
-        is_safe,
-        risk_level,
-        warnings,
-        blocked_reasons,
-    })
+    }
+    rules
 }
 
+/// Checks the symlink target as well as the literal path, since `/lib` may
+/// be a link into `/usr/lib` or a user may link a system directory elsewhere
 fn is_system_directory(path: &Path) -> bool {
+    matches_system_directory(path)
+        || resolve_symlinks(path).is_some_and(|resolved| matches_system_directory(&resolved))
+}
+
+fn matches_system_directory(path: &Path) -> bool {
     let path_str = path.to_string_lossy();
 
     // Windows system directories
@@ -185,4 +471,52 @@ fn has_special_characters(path: &str) -> bool {
         c == '<' || c == '>' || c == ':' || c == '"' ||
         c == '|' || c == '?' || c == '*'
     })
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explanation_matches_the_assessment() {
+        let dir = tempfile::tempdir().unwrap();
+        let folder = dir.path().join("node_modules").join("caf\u{e9}");
+        std::fs::create_dir_all(&folder).unwrap();
+        let file = dir.path().join("file.txt");
+        std::fs::write(&file, b"x").unwrap();
+
+        for path in [folder, file, dir.path().join("missing")] {
+            let path = path.to_string_lossy();
+            let assessment = assess_path_safety(&path);
+            let explanation = explain_path_safety(&path);
+            assert_eq!(explanation.is_safe, assessment.is_safe);
+            assert_eq!(explanation.risk_level, assessment.risk_level);
+
+            let fired = |warns: bool| -> Vec<String> {
+                explanation
+                    .rules
+                    .iter()
+                    .filter(|rule| rule.fired)
+                    .filter(|rule| matches!(rule.effect, RiskEffect::Warns) == warns)
+                    .map(|rule| rule.detail.clone())
+                    .collect()
+            };
+            assert_eq!(fired(true), assessment.warnings);
+            assert_eq!(fired(false), assessment.blocked_reasons);
+        }
+    }
+
+    #[test]
+    fn a_block_ends_the_explanation() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("file.txt");
+        std::fs::write(&file, b"x").unwrap();
+
+        let explanation = explain_path_safety(&file.to_string_lossy());
+        let last = explanation.rules.last().unwrap();
+        assert!(matches!(last.rule, RiskRule::NotDirectory));
+        assert!(last.fired);
+        assert_eq!(explanation.risk_level, RiskLevel::Critical);
+        assert!(!explanation.is_safe);
+    }
+}
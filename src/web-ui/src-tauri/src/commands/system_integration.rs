@@ -1,7 +1,19 @@
-use crate::AppResult;
+use crate::utils::config::{ConfigFormat, ConfigValidationIssue};
+use crate::utils::credentials;
+use crate::utils::logging::redact_identifier;
+use crate::utils::logging::timed_command;
+use crate::utils::paths::{self, AppPaths};
+use crate::utils::platform::{inode_usage, is_program_available, volume_space, PlatformDetection};
+use crate::{AppError, AppResult, AppState};
 use serde::Serialize;
-use tauri::{command, Runtime};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tauri::{command, Emitter, Runtime, State, Theme, Window};
 use std::process::Command;
+use tracing::{debug, info, warn};
+
+/// Event emitted to the frontend when the OS theme toggles
+pub const THEME_CHANGED_EVENT: &str = "theme-changed";
 
 #[derive(Debug, Serialize)]
 pub struct SystemInfo {
@@ -9,6 +21,8 @@ pub struct SystemInfo {
     pub os_version: String,
     pub arch: String,
     pub hostname: String,
+    pub username: String,
+    pub identifiers_redacted: bool,
     pub total_memory: Option<u64>,
     pub available_memory: Option<u64>,
     pub disk_space: Option<DiskSpaceInfo>,
@@ -16,9 +30,31 @@ pub struct SystemInfo {
 
 #[derive(Debug, Serialize)]
 pub struct DiskSpaceInfo {
+    pub path: String,
     pub total: u64,
     pub available: u64,
     pub used: u64,
+    /// Inode counts; `None` on Windows and on filesystems without a fixed
+    /// inode table. A volume can fill up on inodes with bytes to spare.
+    pub inodes_total: Option<u64>,
+    pub inodes_free: Option<u64>,
+    pub inodes_used: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SystemTheme {
+    Light,
+    Dark,
+}
+
+impl From<Theme> for SystemTheme {
+    fn from(theme: Theme) -> Self {
+        match theme {
+            Theme::Dark => SystemTheme::Dark,
+            _ => SystemTheme::Light,
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -59,60 +95,385 @@ pub struct LinuxInfo {
 }
 
 #[command]
-pub async fn get_system_info() -> AppResult<SystemInfo> {
-    let os_type = std::env::consts::OS.to_string();
-    let arch = std::env::consts::ARCH.to_string();
-
-    let hostname = gethostname::gethostname()
-        .to_string_lossy()
-        .to_string();
-
-    let os_version = get_os_version().await?;
-    let (total_memory, available_memory) = get_memory_info().await?;
-    let disk_space = get_disk_space_info().await?;
-
-    Ok(SystemInfo {
-        os_type,
-        os_version,
-        arch,
-        hostname,
-        total_memory,
-        available_memory,
-        disk_space,
+pub async fn get_system_info(state: State<'_, AppState>) -> AppResult<SystemInfo> {
+    timed_command("get_system_info", Some(state.inner()), None, async {
+        let os_type = std::env::consts::OS.to_string();
+        let arch = std::env::consts::ARCH.to_string();
+
+        let hostname = gethostname::gethostname()
+            .to_string_lossy()
+            .to_string();
+        let username = get_username();
+
+        // Unredacted identifiers only ever go to the local log
+        debug!("System identifiers: hostname={}, username={}", hostname, username);
+
+        let identifiers_redacted = state.get_config().await.redact_identifiers;
+        let (hostname, username) = if identifiers_redacted {
+            (redact_identifier(&hostname), redact_identifier(&username))
+        } else {
+            (hostname, username)
+        };
+
+        let os_version = get_os_version().await?;
+        let (total_memory, available_memory) = get_memory_info().await?;
+        let disk_space = get_disk_space_info().await?;
+
+        Ok(SystemInfo {
+            os_type,
+            os_version,
+            arch,
+            hostname,
+            username,
+            identifiers_redacted,
+            total_memory,
+            available_memory,
+            disk_space,
+        })
     })
+    .await
 }
 
 #[command]
 pub async fn get_platform_info() -> AppResult<PlatformInfo> {
-    let is_windows = cfg!(target_os = "windows");
-    let is_macos = cfg!(target_os = "macos");
-    let is_linux = cfg!(target_os = "linux");
-
-    let platform_specific = PlatformSpecific {
-        windows: WindowsInfo {
-            is_windows,
-            version: if is_windows { get_windows_version().await } else { None },
-            build_number: if is_windows { get_windows_build_number().await } else { None },
-        },
-        macos: MacOSInfo {
-            is_macos,
-            version: if is_macos { get_macos_version().await } else { None },
-            darwin_version: if is_macos { get_darwin_version().await } else { None },
-        },
-        linux: LinuxInfo {
-            is_linux,
-            distribution: if is_linux { get_linux_distribution().await } else { None },
-            desktop_environment: if is_linux { get_desktop_environment().await } else { None },
-        },
-    };
+    timed_command("get_platform_info", None, None, async {
+        let is_windows = cfg!(target_os = "windows");
+        let is_macos = cfg!(target_os = "macos");
+        let is_linux = cfg!(target_os = "linux");
+
+        let platform_specific = PlatformSpecific {
+            windows: WindowsInfo {
+                is_windows,
+                version: if is_windows { get_windows_version().await } else { None },
+                build_number: if is_windows { get_windows_build_number().await } else { None },
+            },
+            macos: MacOSInfo {
+                is_macos,
+                version: if is_macos { get_macos_version().await } else { None },
+                darwin_version: if is_macos { get_darwin_version().await } else { None },
+            },
+            linux: LinuxInfo {
+                is_linux,
+                distribution: if is_linux { get_linux_distribution().await } else { None },
+                desktop_environment: if is_linux { get_desktop_environment().await } else { None },
+            },
+        };
+
+        let detected = PlatformDetection::detect();
+        Ok(PlatformInfo {
+            is_desktop: detected.is_desktop,
+            supports_notifications: detected.supports_notifications,
+            supports_file_dialogs: detected.supports_file_dialogs,
+            supports_system_theme: detected.supports_system_theme,
+            platform_specific,
+        })
+    })
+    .await
+}
+
+/// Whether a feature works on this machine and whether it is switched on
+#[derive(Debug, Clone, Serialize)]
+pub struct Capability {
+    pub supported: bool,
+    pub enabled: bool,
+    /// Why the feature is unsupported or disabled
+    pub reason: Option<String>,
+}
+
+impl Capability {
+    fn supported(enabled: bool, disabled_reason: impl FnOnce() -> String) -> Self {
+        Self {
+            supported: true,
+            enabled,
+            reason: (!enabled).then(disabled_reason),
+        }
+    }
+
+    fn unsupported(reason: impl Into<String>) -> Self {
+        Self {
+            supported: false,
+            enabled: false,
+            reason: Some(reason.into()),
+        }
+    }
+
+    fn when(supported: bool, reason: impl Into<String>) -> Self {
+        if supported {
+            Self::supported(true, String::new)
+        } else {
+            Self::unsupported(reason)
+        }
+    }
+}
+
+/// Feature name to availability, so the UI can hide what cannot work here
+/// instead of failing after a click
+#[command]
+pub async fn get_capabilities(
+    state: State<'_, AppState>,
+) -> AppResult<BTreeMap<&'static str, Capability>> {
+    timed_command("get_capabilities", Some(state.inner()), None, async {
+        let config = state.get_config().await;
+        let platform = PlatformDetection::detect();
+
+        let provider = config.ai.provider.clone();
+        let ai_provider = tokio::task::spawn_blocking(move || {
+            let has_key = matches!(credentials::get_api_key(&provider), Ok(Some(_)));
+            Capability::supported(has_key, || {
+                format!("No API key stored for provider '{}'", provider)
+            })
+        })
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))?;
+
+        let elevated_delete = if cfg!(any(target_os = "windows", target_os = "macos")) {
+            Capability::supported(true, String::new)
+        } else {
+            Capability::when(is_program_available("pkexec"), "pkexec is not installed")
+        };
+        let storage_settings = storage_settings_handlers()
+            .iter()
+            .any(|(program, _)| is_program_available(program));
+        let secure_wipe_passes = config.security.secure_wipe_passes;
+
+        Ok(BTreeMap::from([
+            ("ai_provider", ai_provider),
+            (
+                "backups",
+                Capability::supported(config.security.backup_before_delete, || {
+                    "backup_before_delete is off".to_string()
+                }),
+            ),
+            ("elevated_delete", elevated_delete),
+            (
+                "file_dialogs",
+                Capability::when(platform.supports_file_dialogs, "No native file dialogs"),
+            ),
+            (
+                "notifications",
+                Capability::when(platform.supports_notifications, "Notifications unsupported"),
+            ),
+            (
+                "purgeable_space",
+                Capability::when(cfg!(target_os = "macos"), "Only reported on macOS"),
+            ),
+            (
+                "secure_wipe",
+                Capability::supported(secure_wipe_passes > 0, || {
+                    "secure_wipe_passes is 0".to_string()
+                }),
+            ),
+            (
+                "system_storage_settings",
+                Capability::when(storage_settings, "No storage management tool found"),
+            ),
+            (
+                "theme_events",
+                Capability::when(platform.supports_system_theme, "No system theme detection"),
+            ),
+            ("trash", Capability::when(platform.is_desktop, "No trash on this platform")),
+        ]))
+    })
+    .await
+}
+
+#[command]
+pub async fn get_app_paths(state: State<'_, AppState>) -> AppResult<AppPaths> {
+    timed_command("get_app_paths", Some(state.inner()), None, async {
+        let config = state.get_config().await;
+        Ok(AppPaths::resolve(&config))
+    })
+    .await
+}
+
+/// Save the configuration as TOML or JSON from now on. The file in the old
+/// format is moved aside as a backup; returns the new config file path.
+#[command]
+pub async fn convert_config_format(
+    state: State<'_, AppState>,
+    format: ConfigFormat,
+) -> AppResult<String> {
+    timed_command("convert_config_format", Some(state.inner()), None, async {
+        state
+            .update_config(move |config| config.config_format = format)
+            .await
+            .map_err(|e| AppError::ConfigError(e.to_string()))?;
+
+        let path = paths::config_file_for(format);
+        info!("Configuration converted to {}", path.display());
+        Ok(path.to_string_lossy().to_string())
+    })
+    .await
+}
+
+/// Every problem with the current configuration, for the settings screen
+#[command]
+pub async fn validate_config(state: State<'_, AppState>) -> AppResult<Vec<ConfigValidationIssue>> {
+    timed_command("validate_config", Some(state.inner()), None, async {
+        let config = state.get_config().await;
+        tokio::task::spawn_blocking(move || config.validate_all())
+            .await
+            .map_err(|e| AppError::SystemError(e.to_string()))
+    })
+    .await
+}
+
+/// Native storage management tool that was launched
+#[derive(Debug, Serialize)]
+pub struct StorageSettingsLaunch {
+    pub handler: String,
+}
+
+/// Launch the OS's own storage management UI for data the app should not
+/// clean itself (system restore points, OS updates, app-managed caches)
+#[command]
+pub async fn open_system_storage_settings() -> AppResult<StorageSettingsLaunch> {
+    timed_command("open_system_storage_settings", None, None, async {
+        for (program, args) in storage_settings_handlers() {
+            match Command::new(program).args(*args).spawn() {
+                Ok(_) => {
+                    info!("Opened system storage settings with {}", program);
+                    return Ok(StorageSettingsLaunch {
+                        handler: program.to_string(),
+                    });
+                }
+                Err(e) => debug!("Storage settings handler {} unavailable: {}", program, e),
+            }
+        }
+
+        Err(AppError::SystemError(format!(
+            "No storage settings handler is available on {}",
+            std::env::consts::OS
+        )))
+    })
+    .await
+}
+
+/// Candidate launchers for the native storage UI, most specific first
+fn storage_settings_handlers() -> &'static [(&'static str, &'static [&'static str])] {
+    if cfg!(target_os = "windows") {
+        &[
+            ("explorer", &["ms-settings:storagesense"]),
+            ("cleanmgr", &[]),
+        ]
+    } else if cfg!(target_os = "macos") {
+        &[
+            ("open", &["x-apple.systempreferences:com.apple.settings.Storage"]),
+            ("open", &["-b", "com.apple.SystemProfiler"]),
+        ]
+    } else if cfg!(target_os = "linux") {
+        &[("baobab", &[]), ("filelight", &[]), ("qdirstat", &[])]
+    } else {
+        &[]
+    }
+}
+
+/// Free space on an APFS volume as macOS accounts for it. `available_bytes`
+/// includes purgeable data (local snapshots, caches) the OS frees on demand,
+/// which is why Finder can show more free space than `statvfs`.
+#[derive(Debug, Serialize)]
+pub struct PurgeableSpace {
+    pub path: String,
+    /// Space free in the APFS container right now
+    pub free_bytes: u64,
+    /// Free plus purgeable space, as Finder reports it
+    pub available_bytes: u64,
+    pub purgeable_bytes: u64,
+}
 
-    Ok(PlatformInfo {
-        is_desktop: true,
-        supports_notifications: true,
-        supports_file_dialogs: true,
-        supports_system_theme: true,
-        platform_specific,
+/// Byte and inode usage of the volume holding `path` (the home directory
+/// by default); `None` if the volume cannot be identified
+#[command]
+pub async fn get_disk_space(path: Option<String>) -> AppResult<Option<DiskSpaceInfo>> {
+    timed_command("get_disk_space", None, path.clone(), async {
+        let path = match path {
+            Some(path) => PathBuf::from(path),
+            None => home::home_dir().ok_or_else(|| {
+                AppError::SystemError("Unable to determine home directory".to_string())
+            })?,
+        };
+        tokio::task::spawn_blocking(move || disk_space_info(&path))
+            .await
+            .map_err(|e| AppError::SystemError(e.to_string()))
     })
+    .await
+}
+
+/// Purgeable space on the volume holding `path` (the boot volume by
+/// default). Always `None` outside macOS.
+#[command]
+pub async fn get_purgeable_space(path: Option<String>) -> AppResult<Option<PurgeableSpace>> {
+    timed_command("get_purgeable_space", None, path.clone(), async {
+        if !cfg!(target_os = "macos") {
+            return Ok(None);
+        }
+
+        let path = path.unwrap_or_else(|| "/".to_string());
+        let output = Command::new("diskutil")
+            .args(["info", &path])
+            .output()
+            .map_err(|e| AppError::SystemError(format!("Failed to run diskutil: {}", e)))?;
+        if !output.status.success() {
+            return Err(AppError::SystemError(format!(
+                "diskutil info failed for {}: {}",
+                path,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        let info = String::from_utf8_lossy(&output.stdout);
+        let (Some(free_bytes), Some(available_bytes)) = (
+            diskutil_bytes(&info, "Container Free Space"),
+            diskutil_bytes(&info, "Volume Free Space")
+                .or_else(|| diskutil_bytes(&info, "Free Space")),
+        ) else {
+            debug!("No APFS free space figures for {}; not an APFS volume?", path);
+            return Ok(None);
+        };
+
+        Ok(Some(PurgeableSpace {
+            path,
+            free_bytes,
+            available_bytes,
+            purgeable_bytes: available_bytes.saturating_sub(free_bytes),
+        }))
+    })
+    .await
+}
+
+/// Byte count from a `diskutil info` line such as
+/// `Container Free Space:  80.5 GB (80512345088 Bytes) (exactly ...)`
+fn diskutil_bytes(info: &str, label: &str) -> Option<u64> {
+    let line = info
+        .lines()
+        .map(str::trim)
+        .find(|line| line.strip_prefix(label).is_some_and(|rest| rest.starts_with(':')))?;
+    let start = line.find('(')? + 1;
+    let end = start + line[start..].find(" Bytes")?;
+    line[start..end].trim().parse().ok()
+}
+
+#[command]
+pub async fn get_system_theme<R: Runtime>(window: Window<R>) -> AppResult<SystemTheme> {
+    timed_command("get_system_theme", None, None, async {
+        let theme = window
+            .theme()
+            .map_err(|e| AppError::SystemError(format!("Failed to read system theme: {}", e)))?;
+
+        Ok(SystemTheme::from(theme))
+    })
+    .await
+}
+
+/// Forward OS theme changes to the frontend so `ThemePreference::System`
+/// can follow them without a restart
+pub fn handle_theme_changed<R: Runtime>(window: &Window<R>, theme: Theme) {
+    let theme = SystemTheme::from(theme);
+    info!("System theme changed to {:?}", theme);
+
+    if let Err(e) = window.emit(THEME_CHANGED_EVENT, theme) {
+        warn!("Failed to emit {} event: {}", THEME_CHANGED_EVENT, e);
+    }
 }
 
 async fn get_os_version() -> AppResult<String> {
@@ -141,6 +502,12 @@ async fn get_os_version() -> AppResult<String> {
     }
 }
 
+fn get_username() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
 async fn get_memory_info() -> AppResult<(Option<u64>, Option<u64>)> {
     // This is a simplified implementation
     // In production, you'd want platform-specific memory queries
@@ -148,9 +515,26 @@ async fn get_memory_info() -> AppResult<(Option<u64>, Option<u64>)> {
 }
 
 async fn get_disk_space_info() -> AppResult<Option<DiskSpaceInfo>> {
-    // Simplified disk space detection
-    // In production, you'd want to query actual disk usage
-    Ok(None)
+    let Some(home) = home::home_dir() else {
+        return Ok(None);
+    };
+    tokio::task::spawn_blocking(move || disk_space_info(&home))
+        .await
+        .map_err(|e| AppError::SystemError(e.to_string()))
+}
+
+fn disk_space_info(path: &Path) -> Option<DiskSpaceInfo> {
+    let (total, available) = volume_space(path)?;
+    let inodes = inode_usage(path);
+    Some(DiskSpaceInfo {
+        path: path.to_string_lossy().to_string(),
+        total,
+        available,
+        used: total.saturating_sub(available),
+        inodes_total: inodes.map(|inodes| inodes.total),
+        inodes_free: inodes.map(|inodes| inodes.free),
+        inodes_used: inodes.map(|inodes| inodes.total.saturating_sub(inodes.free)),
+    })
 }
 
 // Platform-specific helper functions
@@ -159,4 +543,4 @@ async fn get_windows_build_number() -> Option<String> { None }
 async fn get_macos_version() -> Option<String> { None }
 async fn get_darwin_version() -> Option<String> { None }
 async fn get_linux_distribution() -> Option<String> { None }
-async fn get_desktop_environment() -> Option<String> { None }
\ No newline at end of file
+async fn get_desktop_environment() -> Option<String> { None }
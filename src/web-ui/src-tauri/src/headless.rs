@@ -0,0 +1,242 @@
+// Headless cleanups for power users and scheduled tasks.
+// Runs the same scan, analysis and delete steps as the Tauri commands and
+// reports on stdout instead of the UI.
+
+use crate::commands::analysis::run_session_analysis;
+use crate::commands::cleanup::{execute_delete, plan_delete};
+use crate::commands::plan::{read_plan, CleanupPlan};
+use crate::commands::scan::{run_scan, ScanOptions};
+use crate::commands::schedule::UNATTENDED_CATEGORIES;
+use crate::utils::classifier::FileCategory;
+use crate::utils::config::RunOverrides;
+use crate::utils::delete_tokens::DeleteMode;
+use crate::utils::scheduler::{HEADLESS_ARG, PLAN_ARG};
+use crate::utils::security::{broad_root_reason, validate_scan_root};
+use crate::{AppError, AppResult, AppState};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Everything found was cleaned, or would be in a dry run
+pub const EXIT_OK: i32 = 0;
+/// Some files could not be deleted
+pub const EXIT_PARTIAL: i32 = 1;
+/// The arguments or the plan were rejected
+pub const EXIT_USAGE: i32 = 2;
+/// A scan, analysis or delete step failed outright
+pub const EXIT_FAILED: i32 = 3;
+
+pub const USAGE: &str = "Usage: ai-disk-cleaner --headless [--plan <file>] [--root <path>]... \
+[--dry-run]
+
+Scans each root, classifies the files and deletes those in the plan's
+categories (temporary files, caches and logs when no plan is given) whose
+classification meets analysis.high_confidence_threshold.";
+
+#[derive(Debug, Default)]
+pub struct HeadlessArgs {
+    /// Roots to clean in addition to the plan's
+    pub roots: Vec<String>,
+    pub plan: Option<PathBuf>,
+    /// Report what would be deleted without deleting anything
+    pub dry_run: bool,
+}
+
+/// The headless arguments if the process was started with `--headless`,
+/// or why they could not be parsed
+pub fn requested_args() -> Option<Result<HeadlessArgs, String>> {
+    let mut args = std::env::args().skip(1);
+    if args.next()? != HEADLESS_ARG {
+        return None;
+    }
+    Some(parse_args(args))
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<HeadlessArgs, String> {
+    let mut parsed = HeadlessArgs::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--root" => parsed.roots.push(args.next().ok_or("--root needs a path")?),
+            "--dry-run" => parsed.dry_run = true,
+            _ if arg == PLAN_ARG => {
+                parsed.plan = Some(PathBuf::from(args.next().ok_or("--plan needs a file")?));
+            }
+            _ => return Err(format!("Unknown argument '{}'", arg)),
+        }
+    }
+    if parsed.roots.is_empty() && parsed.plan.is_none() {
+        return Err("Nothing to clean; give --plan or --root".to_string());
+    }
+    Ok(parsed)
+}
+
+/// Run a headless cleanup and return the process exit code
+pub async fn run(args: HeadlessArgs) -> i32 {
+    let state = AppState::new();
+    let code = match clean(&state, args).await {
+        Ok(code) => code,
+        Err(e @ (AppError::SecurityError(_) | AppError::ConfigError(_))) => {
+            eprintln!("error: {}", e);
+            EXIT_USAGE
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            EXIT_FAILED
+        }
+    };
+    state.shutdown().await;
+    code
+}
+
+async fn clean(state: &AppState, args: HeadlessArgs) -> AppResult<i32> {
+    let plan = match &args.plan {
+        Some(path) => {
+            let path = path.clone();
+            let plan = tokio::task::spawn_blocking(move || read_plan(&path))
+                .await
+                .map_err(|e| AppError::SystemError(e.to_string()))??;
+            Some(plan)
+        }
+        None => None,
+    };
+    let categories = selected_categories(plan.as_ref())?;
+    let options = plan
+        .as_ref()
+        .map(|plan| plan.scan_options.clone())
+        .unwrap_or_default();
+    let mut roots = plan.as_ref().map(|plan| plan.roots.clone()).unwrap_or_default();
+    roots.extend(args.roots);
+
+    if let Some(plan) = &plan {
+        add_plan_exclusions(state, plan).await?;
+    }
+
+    let threshold = state.get_config().await.analysis.high_confidence_threshold;
+    let mut failed = false;
+    for root in roots {
+        let root = validate_scan_root(&root)?.canonicalize()?;
+        if let Some(reason) = broad_root_reason(&root) {
+            return Err(AppError::SecurityError(format!(
+                "{} {}, which is too broad to clean unattended",
+                root.display(),
+                reason
+            )));
+        }
+        failed |= !clean_root(state, &root, &options, &categories, threshold, args.dry_run).await?;
+    }
+
+    Ok(if failed { EXIT_PARTIAL } else { EXIT_OK })
+}
+
+/// Categories the run deletes: the plan's, which must all be safe to clean
+/// unattended, or every such category without a plan
+fn selected_categories(plan: Option<&CleanupPlan>) -> AppResult<Vec<FileCategory>> {
+    let Some(plan) = plan.filter(|plan| !plan.categories.is_empty()) else {
+        return Ok(UNATTENDED_CATEGORIES.to_vec());
+    };
+    if let Some(category) = plan
+        .categories
+        .iter()
+        .find(|category| !UNATTENDED_CATEGORIES.contains(category))
+    {
+        return Err(AppError::SecurityError(format!(
+            "Category {:?} is not safe to clean without review",
+            category
+        )));
+    }
+    Ok(plan.categories.clone())
+}
+
+/// Make the plan's exclusions global, as importing the plan would
+async fn add_plan_exclusions(state: &AppState, plan: &CleanupPlan) -> AppResult<()> {
+    let mut exclusions = state.get_config().await.security.global_exclusions;
+    let before = exclusions.len();
+    for excluded in &plan.exclusions {
+        let canonical = Path::new(excluded)
+            .canonicalize()
+            .map(|canonical| canonical.to_string_lossy().to_string())
+            .unwrap_or_else(|_| excluded.clone());
+        if !exclusions.contains(&canonical) {
+            exclusions.push(canonical);
+        }
+    }
+    if exclusions.len() > before {
+        state
+            .update_config(move |config| config.security.global_exclusions = exclusions)
+            .await
+            .map_err(|e| AppError::ConfigError(e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Scan, analyze and clean one root; false if any deletion failed
+async fn clean_root(
+    state: &AppState,
+    root: &Path,
+    options: &ScanOptions,
+    categories: &[FileCategory],
+    threshold: f32,
+    dry_run: bool,
+) -> AppResult<bool> {
+    let scan = run_scan(state, root.to_string_lossy().to_string(), options.clone()).await?;
+    println!(
+        "Scanned {}: {} files, {} bytes",
+        scan.root, scan.total_files, scan.total_size
+    );
+    let session_id = scan.session_id.ok_or_else(|| {
+        AppError::FileSystemError("The scan session could not be saved".to_string())
+    })?;
+
+    let analysis =
+        run_session_analysis(state, session_id, RunOverrides::default(), &|_| {}).await?;
+    let selected: Vec<_> = analysis
+        .files
+        .into_iter()
+        .filter(|file| {
+            categories.contains(&file.classification.category)
+                && file.classification.confidence >= threshold
+        })
+        .collect();
+    let selected_bytes: u64 = selected.iter().map(|file| file.size).sum();
+    println!(
+        "Selected {} files, {} bytes, classified {:?} with confidence >= {}",
+        selected.len(),
+        selected_bytes,
+        categories,
+        threshold
+    );
+
+    if dry_run {
+        for file in &selected {
+            println!("would delete {} ({} bytes)", file.path, file.size);
+        }
+        return Ok(true);
+    }
+    if selected.is_empty() {
+        return Ok(true);
+    }
+
+    state.set_scan_root(root.to_path_buf()).await;
+    let paths = selected.into_iter().map(|file| file.path).collect();
+    let plan = plan_delete(state, paths, DeleteMode::default()).await?;
+    for refused in &plan.refused {
+        println!("skipped {}: {}", refused.path, refused.reason);
+    }
+    let report = execute_delete(state, plan.token, |_| {}, |_| {}).await?;
+    for outcome in report.outcomes.iter().filter(|outcome| !outcome.deleted) {
+        println!(
+            "failed {}: {}",
+            outcome.path,
+            outcome.error.as_deref().unwrap_or("unknown error")
+        );
+    }
+    println!(
+        "Deleted {} files, freed {} bytes, {} failed",
+        report.deleted_count, report.bytes_freed, report.failed_count
+    );
+    info!(
+        "Headless cleanup of {} deleted {} files",
+        root.display(),
+        report.deleted_count
+    );
+    Ok(report.failed_count == 0)
+}
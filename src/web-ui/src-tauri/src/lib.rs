@@ -4,6 +4,7 @@
 pub mod commands;
 pub mod utils;
 pub mod app_state;
+pub mod headless;
 
 // Re-export commonly used types and functions
 pub use app_state::AppState;
@@ -26,6 +27,9 @@ pub enum AppError {
     #[error("Configuration error: {0}")]
     ConfigError(String),
 
+    #[error("Operation cancelled")]
+    Cancelled,
+
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 }
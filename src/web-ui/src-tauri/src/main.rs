@@ -1,22 +1,37 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use ai_disk_cleaner_lib::utils::elevation;
+use ai_disk_cleaner_lib::{commands, headless, AppState};
 use anyhow::Result;
-use tracing::{error, info, Level};
-use tracing_subscriber;
-
-mod commands;
-mod utils;
-
-// Tauri command modules
-use commands::{
-    file_system::select_directory,
-    system_integration::{get_system_info, show_notification},
-    security::validate_path_safety,
-};
+use tauri::{AppHandle, Manager, RunEvent, WindowEvent};
+use tracing::{info, Level};
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    // Relaunched through a UAC prompt to delete files; never starts the UI
+    if let Some(code) = elevation::run_helper_mode() {
+        std::process::exit(code);
+    }
+
+    // Started from a terminal or a scheduled task; never starts the UI.
+    // Logs go to stderr so stdout carries only the report.
+    if let Some(args) = headless::requested_args() {
+        tracing_subscriber::fmt()
+            .with_max_level(Level::INFO)
+            .with_target(false)
+            .with_writer(std::io::stderr)
+            .init();
+        let code = match args {
+            Ok(args) => headless::run(args).await,
+            Err(e) => {
+                eprintln!("{}\n\n{}", e, headless::USAGE);
+                headless::EXIT_USAGE
+            }
+        };
+        std::process::exit(code);
+    }
+
     // Initialize logging
     tracing_subscriber::fmt()
         .with_max_level(Level::INFO)
@@ -29,51 +44,130 @@ async fn main() -> Result<()> {
     tauri::Builder::default()
         // Tauri commands for file system operations
         .invoke_handler(tauri::generate_handler![
-            select_directory,
-            get_system_info,
-            show_notification,
-            validate_path_safety
+            commands::file_system::select_directory,
+            commands::file_system::hash_file,
+            commands::file_system::find_locking_processes,
+            commands::file_system::preview_file,
+            commands::file_system::tail_file,
+            commands::system_integration::get_system_info,
+            commands::system_integration::get_system_theme,
+            commands::system_integration::get_app_paths,
+            commands::system_integration::open_system_storage_settings,
+            commands::system_integration::get_purgeable_space,
+            commands::system_integration::get_disk_space,
+            commands::system_integration::get_capabilities,
+            commands::system_integration::validate_config,
+            commands::system_integration::convert_config_format,
+            commands::notifications::show_notification,
+            commands::security::validate_path_safety,
+            commands::security::validate_paths,
+            commands::security::reset_protected_patterns_to_default,
+            commands::security::add_global_exclusion,
+            commands::security::remove_global_exclusion,
+            commands::security::explain_path_risk,
+            commands::security::set_scan_root,
+            commands::cleanup::find_broken_symlinks,
+            commands::cleanup::find_dev_caches,
+            commands::cleanup::find_browser_caches,
+            commands::cleanup::clean_browser_caches,
+            commands::cleanup::empty_old_trash,
+            commands::cleanup::find_app_leftovers,
+            commands::cleanup::find_empty_directories,
+            commands::cleanup::find_empty_files,
+            commands::cleanup::find_crash_dumps,
+            commands::cleanup::clean_crash_dumps,
+            commands::cleanup::find_thumbnail_caches,
+            commands::cleanup::clean_thumbnail_caches,
+            commands::cleanup::find_device_backups,
+            commands::cleanup::prepare_delete,
+            commands::cleanup::estimate_operation_duration,
+            commands::cleanup::confirm_delete,
+            commands::cleanup::delete_directory,
+            commands::cleanup::delete_elevated,
+            commands::cleanup::verify_backups,
+            commands::analysis::test_ai_connection,
+            commands::analysis::analyze_single_path,
+            commands::analysis::analyze_session,
+            commands::analysis::analyze_incremental,
+            commands::analysis::reanalyze_low_confidence,
+            commands::analysis::summarize_analysis,
+            commands::analysis::summarize_roots,
+            commands::overrides::list_override_rules,
+            commands::overrides::add_override_rule,
+            commands::overrides::remove_override_rule,
+            commands::archive::archive_files,
+            commands::history::get_cleanup_history,
+            commands::duplicates::find_duplicates,
+            commands::duplicates::find_duplicate_directories,
+            commands::duplicates::find_duplicate_names,
+            commands::duplicates::find_similar_images,
+            commands::scan::scan_directory,
+            commands::scan::diff_scans,
+            commands::scan::list_sessions,
+            commands::scan::load_session,
+            commands::export::export_scan_results,
+            commands::export::export_analysis_markdown,
+            commands::plan::export_cleanup_plan,
+            commands::plan::import_cleanup_plan,
+            commands::schedule::register_scheduled_cleanup,
+            commands::schedule::unregister_scheduled_cleanup,
+            commands::schedule::list_scheduled_cleanups,
+            commands::disk_usage::find_large_directories,
+            commands::disk_usage::age_histogram,
+            commands::disk_usage::find_sparse_files,
+            commands::disk_usage::find_app_footprints,
+            commands::operations::cancel_all_operations,
+            commands::operations::list_operations,
+            commands::operations::is_path_busy,
+            commands::operations::get_metrics,
+            commands::operations::reset_metrics,
+            commands::app_data::clear_app_data
         ])
         // Application state
-        .manage( AppState::new() )
+        .manage(AppState::new())
         .setup(|app| {
+            commands::operations::emit_operation_results(app.handle(), &app.state::<AppState>());
             info!("Application setup completed");
             Ok(())
         })
-        .run(tauri::generate_context!())?;
+        .on_window_event(|window, event| match event {
+            WindowEvent::ThemeChanged(theme) => {
+                commands::system_integration::handle_theme_changed(window, *theme);
+            }
+            // Keep the window open until caches and logs are flushed
+            WindowEvent::CloseRequested { api, .. }
+                if !window.state::<AppState>().is_shut_down() =>
+            {
+                api.prevent_close();
+                begin_shutdown(window.app_handle());
+            }
+            _ => {}
+        })
+        .build(tauri::generate_context!())?
+        .run(|app, event| {
+            if let RunEvent::ExitRequested { api, .. } = event {
+                if !app.state::<AppState>().is_shut_down() {
+                    api.prevent_exit();
+                    begin_shutdown(app);
+                }
+            }
+        });
 
     info!("Application shutdown complete");
     Ok(())
 }
 
-/// Application state shared across all Tauri commands
-#[derive(Debug, Clone)]
-pub struct AppState {
-    pub config: AppConfig,
-}
-
-impl AppState {
-    pub fn new() -> Self {
-        Self {
-            config: AppConfig::default(),
-        }
+/// Run the graceful shutdown hook once, then exit the application
+fn begin_shutdown(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    if !state.request_shutdown() {
+        return;
     }
-}
 
-/// Application configuration
-#[derive(Debug, Clone)]
-pub struct AppConfig {
-    pub max_file_size: u64,
-    pub default_timeout: u64,
-    pub enable_logging: bool,
+    let state = state.inner().clone();
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        state.shutdown().await;
+        app.exit(0);
+    });
 }
-
-impl Default for AppConfig {
-    fn default() -> Self {
-        Self {
-            max_file_size: 1_000_000_000, // 1GB
-            default_timeout: 30, // 30 seconds
-            enable_logging: true,
-        }
-    }
-}
\ No newline at end of file
@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+
+use crate::utils::config::AiConfig;
+use crate::utils::credentials;
+use crate::utils::rate_limiter::RateLimiter;
+
+/// Failure modes when talking to the AI provider
+#[derive(Debug, Clone, Serialize, thiserror::Error)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum AiError {
+    #[error("No API key configured for provider '{0}'")]
+    MissingApiKey(String),
+
+    #[error("Authentication failed: {0}")]
+    Authentication(String),
+
+    #[error("Network error: {0}")]
+    Network(String),
+
+    #[error("Request timed out after {0} seconds")]
+    Timeout(u64),
+
+    #[error("Provider error: {0}")]
+    Provider(String),
+}
+
+/// A single chat message sent to the provider
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    model: Option<String>,
+    choices: Vec<ChatChoice>,
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatUsage {
+    total_tokens: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoice {
+    message: ChatResponseMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponseMessage {
+    content: Option<String>,
+}
+
+/// Response returned by a completed chat request
+#[derive(Debug, Clone)]
+pub struct ChatCompletion {
+    pub model: String,
+    pub content: String,
+    /// Prompt plus completion tokens, when the provider reports usage
+    pub total_tokens: Option<u64>,
+}
+
+/// Minimal client for OpenAI-compatible chat completion APIs
+pub struct AiClient {
+    http: reqwest::Client,
+    config: AiConfig,
+    api_key: String,
+    timeout_secs: u64,
+}
+
+impl AiClient {
+    /// Build a client using the API key stored in the platform keychain
+    pub fn from_config(config: &AiConfig, timeout_secs: u64) -> Result<Self, AiError> {
+        let api_key = credentials::get_api_key(&config.provider)
+            .map_err(|e| AiError::Provider(format!("Failed to read keychain: {}", e)))?
+            .ok_or_else(|| AiError::MissingApiKey(config.provider.clone()))?;
+
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(timeout_secs))
+            .build()
+            .map_err(|e| AiError::Network(e.to_string()))?;
+
+        Ok(Self {
+            http,
+            config: config.clone(),
+            api_key,
+            timeout_secs,
+        })
+    }
+
+    /// Send the smallest possible request to verify the key, endpoint and model.
+    ///
+    /// Probes bypass the rate limiter so a "Test connection" click never
+    /// consumes the analysis request budget.
+    pub async fn probe(&self) -> Result<String, AiError> {
+        let messages = vec![ChatMessage {
+            role: "user".to_string(),
+            content: "ping".to_string(),
+        }];
+
+        self.send(&messages, 1).await.map(|completion| completion.model)
+    }
+
+    /// Send a chat completion request, waiting for a rate-limit slot first
+    pub async fn chat_completion(
+        &self,
+        limiter: &RateLimiter,
+        messages: &[ChatMessage],
+        max_tokens: u32,
+    ) -> Result<ChatCompletion, AiError> {
+        limiter.acquire().await;
+        self.send(messages, max_tokens).await
+    }
+
+    async fn send(&self, messages: &[ChatMessage], max_tokens: u32) -> Result<ChatCompletion, AiError> {
+        let url = format!("{}/chat/completions", self.config.base_url.trim_end_matches('/'));
+        let body = json!({
+            "model": self.config.model,
+            "messages": messages,
+            "max_tokens": max_tokens,
+        });
+
+        let response = self
+            .http
+            .post(url)
+            .bearer_auth(&self.api_key)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| self.classify_transport_error(e))?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            return Err(AiError::Authentication(format!(
+                "Provider rejected the API key ({})",
+                status
+            )));
+        }
+        if !status.is_success() {
+            let detail = response.text().await.unwrap_or_default();
+            return Err(AiError::Provider(format!("{}: {}", status, detail)));
+        }
+
+        let parsed: ChatResponse = response
+            .json()
+            .await
+            .map_err(|e| self.classify_transport_error(e))?;
+
+        let content = parsed
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.content)
+            .unwrap_or_default();
+
+        Ok(ChatCompletion {
+            model: parsed.model.unwrap_or_else(|| self.config.model.clone()),
+            content,
+            total_tokens: parsed.usage.and_then(|usage| usage.total_tokens),
+        })
+    }
+
+    fn classify_transport_error(&self, error: reqwest::Error) -> AiError {
+        if error.is_timeout() {
+            AiError::Timeout(self.timeout_secs)
+        } else if error.is_decode() {
+            AiError::Provider(format!("Malformed response: {}", error))
+        } else {
+            AiError::Network(error.to_string())
+        }
+    }
+}
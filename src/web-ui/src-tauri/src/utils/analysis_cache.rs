@@ -0,0 +1,144 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::warn;
+
+use crate::utils::classifier::{Classification, FileFacts};
+use crate::utils::messages;
+
+const CACHE_FILE_NAME: &str = "analysis_cache.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedClassification {
+    size: u64,
+    modified: Option<i64>,
+    classified_at: DateTime<Utc>,
+    /// Language the reason was requested in; entries from before reasons
+    /// were localized are English
+    #[serde(default = "default_language")]
+    language: String,
+    classification: Classification,
+}
+
+fn default_language() -> String {
+    messages::DEFAULT_LANGUAGE.to_string()
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<String, CachedClassification>,
+    dirty: bool,
+}
+
+/// Persistent cache of classifications keyed by path.
+///
+/// An entry is only reused while the file's size and modification time are
+/// unchanged and it is younger than the configured TTL.
+#[derive(Debug)]
+pub struct AnalysisCache {
+    path: PathBuf,
+    state: Mutex<CacheState>,
+}
+
+impl AnalysisCache {
+    /// Load the cache stored under the given cache directory, starting empty
+    /// if it is missing or unreadable
+    pub fn load(cache_dir: &Path) -> Self {
+        let path = cache_dir.join(CACHE_FILE_NAME);
+        let entries = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                warn!("Discarding unreadable analysis cache: {}", e);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+
+        Self {
+            path,
+            state: Mutex::new(CacheState {
+                entries,
+                dirty: false,
+            }),
+        }
+    }
+
+    /// Return a cached classification if it is still valid for these facts
+    /// and its reason is in `language`
+    pub fn get(
+        &self,
+        facts: &FileFacts,
+        ttl_seconds: u64,
+        language: &str,
+    ) -> Option<Classification> {
+        let state = self.lock();
+        let cached = state.entries.get(&facts.path)?;
+
+        let fresh = Utc::now()
+            .signed_duration_since(cached.classified_at)
+            .num_seconds()
+            < ttl_seconds as i64;
+        let unchanged =
+            cached.size == facts.size && cached.modified == facts.modified.map(|m| m.timestamp());
+
+        let same_language = messages::primary_language(&cached.language)
+            == messages::primary_language(language);
+
+        (fresh && unchanged && same_language).then(|| cached.classification.clone())
+    }
+
+    pub fn insert(&self, facts: &FileFacts, classification: &Classification, language: &str) {
+        let mut state = self.lock();
+        state.entries.insert(
+            facts.path.clone(),
+            CachedClassification {
+                size: facts.size,
+                modified: facts.modified.map(|m| m.timestamp()),
+                classified_at: Utc::now(),
+                language: language.to_string(),
+                classification: classification.clone(),
+            },
+        );
+        state.dirty = true;
+    }
+
+    /// Write the cache to disk if anything changed since the last flush
+    pub fn flush(&self) -> anyhow::Result<()> {
+        let mut state = self.lock();
+        if !state.dirty {
+            return Ok(());
+        }
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string(&state.entries)?)?;
+        state.dirty = false;
+
+        Ok(())
+    }
+
+    /// File the entries are persisted to
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Drop every entry and delete the file, returning the bytes it took
+    pub fn clear(&self) -> std::io::Result<u64> {
+        let mut state = self.lock();
+        state.entries.clear();
+        state.dirty = false;
+        let size = match std::fs::metadata(&self.path) {
+            Ok(metadata) => metadata.len(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+        std::fs::remove_file(&self.path)?;
+        Ok(size)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
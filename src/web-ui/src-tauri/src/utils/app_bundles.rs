@@ -0,0 +1,106 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// An application bundle (`Something.app`) found in an applications folder
+#[derive(Debug, Clone)]
+pub struct AppBundle {
+    /// Bundle name without the `.app` suffix
+    pub name: String,
+    pub path: PathBuf,
+    /// `CFBundleIdentifier` from the bundle's `Info.plist`, e.g.
+    /// `com.tinyspeck.slackmacgap`
+    pub bundle_id: Option<String>,
+}
+
+/// Where an app keeps data outside its bundle
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AncillaryKind {
+    /// `~/Library/Caches/<bundle id or name>`
+    Cache,
+    /// `~/Library/Containers/<bundle id>/Data/Library/Caches`
+    ContainerCache,
+    /// The rest of the sandbox container, which holds the app's documents
+    /// and settings
+    Container,
+}
+
+impl AncillaryKind {
+    /// Caches are rebuilt on demand; container data is the user's
+    pub fn is_removable(self) -> bool {
+        matches!(self, Self::Cache | Self::ContainerCache)
+    }
+}
+
+/// Bundles directly inside `dir`, sorted by name. Symlinked bundles are
+/// skipped so an app is never counted twice.
+pub fn list_bundles(dir: &Path) -> Vec<AppBundle> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut bundles: Vec<AppBundle> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let name = file_name.strip_suffix(".app")?.to_string();
+            let path = entry.path();
+            Some(AppBundle {
+                name,
+                bundle_id: bundle_identifier(&path),
+                path,
+            })
+        })
+        .collect();
+    bundles.sort_by_key(|bundle| bundle.name.to_lowercase());
+    bundles
+}
+
+/// Read `CFBundleIdentifier` with `plutil`, which handles both the XML and
+/// the binary property list formats
+fn bundle_identifier(bundle: &Path) -> Option<String> {
+    let info = bundle.join("Contents").join("Info.plist");
+    let output = Command::new("plutil")
+        .args(["-extract", "CFBundleIdentifier", "raw", "-o", "-"])
+        .arg(&info)
+        .output()
+        .ok()?;
+    let id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !id.is_empty()).then_some(id)
+}
+
+/// Existing data folders of `bundle` below `home`. Caches are looked up by
+/// bundle id and by app name, since both conventions are common; the
+/// container is found by bundle id only.
+pub fn ancillary_locations(bundle: &AppBundle, home: &Path) -> Vec<(AncillaryKind, PathBuf)> {
+    let library = home.join("Library");
+    let caches = library.join("Caches");
+    let mut locations = Vec::new();
+
+    let mut cache_names = vec![bundle.name.as_str()];
+    if let Some(id) = &bundle.bundle_id {
+        cache_names.insert(0, id);
+        let container = library.join("Containers").join(id);
+        if is_real_dir(&container) {
+            let container_cache = container.join("Data").join("Library").join("Caches");
+            if is_real_dir(&container_cache) {
+                locations.push((AncillaryKind::ContainerCache, container_cache));
+            }
+            locations.push((AncillaryKind::Container, container));
+        }
+    }
+    for name in cache_names {
+        let cache = caches.join(name);
+        if is_real_dir(&cache) && !locations.iter().any(|(_, path)| *path == cache) {
+            locations.push((AncillaryKind::Cache, cache));
+        }
+    }
+    locations
+}
+
+fn is_real_dir(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|m| m.is_dir())
+}
@@ -0,0 +1,198 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Shortest normalized name compared by substring; shorter names such as
+/// "qt" or "x" would match almost any folder
+const MIN_FUZZY_NAME_LEN: usize = 4;
+
+/// Folders in the app-data locations that belong to the OS or desktop, not
+/// to an installable application
+const SHARED_FOLDERS: &[&str] = &[
+    "autostart",
+    "dconf",
+    "fontconfig",
+    "fonts",
+    "gtk-2.0",
+    "gtk-3.0",
+    "gtk-4.0",
+    "ibus",
+    "menus",
+    "mime",
+    "pulse",
+    "systemd",
+    "user-dirs.dirs",
+    "applications",
+    "icons",
+    "keyrings",
+    "recently-used.xbel",
+    "trash",
+    "apple",
+    "crashreporter",
+    "microsoft",
+    "mobilesync",
+    "addressbook",
+];
+
+/// An app-data folder that looks abandoned
+#[derive(Debug, Clone)]
+pub struct LeftoverCandidate {
+    pub name: String,
+    pub path: PathBuf,
+    /// Newest modification time of the folder or its direct entries
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+/// Per-user locations where applications keep their settings and data
+pub fn data_locations(home: &Path) -> Vec<PathBuf> {
+    if cfg!(target_os = "windows") {
+        vec![home.join("AppData").join("Roaming")]
+    } else if cfg!(target_os = "macos") {
+        vec![home.join("Library").join("Application Support")]
+    } else {
+        vec![home.join(".config"), home.join(".local").join("share")]
+    }
+}
+
+/// Lowercased alphanumeric form used to compare folder and app names, so
+/// "Visual Studio Code", "visual-studio-code" and "VisualStudioCode" agree
+pub fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Names of applications that appear to be installed, normalized. Empty
+/// when nothing could be detected, in which case no folder can be ruled
+/// out by name.
+pub fn installed_app_names(home: &Path) -> HashSet<String> {
+    let mut names = HashSet::new();
+    let mut add = |name: &str| {
+        let normalized = normalize_name(name);
+        if !normalized.is_empty() {
+            names.insert(normalized);
+        }
+    };
+
+    if cfg!(target_os = "windows") {
+        let mut dirs = vec![home.join("AppData").join("Local").join("Programs")];
+        for var in ["ProgramFiles", "ProgramFiles(x86)"] {
+            if let Some(dir) = std::env::var_os(var) {
+                dirs.push(PathBuf::from(dir));
+            }
+        }
+        for dir in dirs {
+            for name in child_names(&dir) {
+                add(&name);
+            }
+        }
+    } else if cfg!(target_os = "macos") {
+        for dir in [
+            PathBuf::from("/Applications"),
+            PathBuf::from("/System/Applications"),
+            home.join("Applications"),
+        ] {
+            for name in child_names(&dir) {
+                add(name.strip_suffix(".app").unwrap_or(&name));
+            }
+        }
+    } else {
+        for dir in [
+            PathBuf::from("/usr/share/applications"),
+            PathBuf::from("/usr/local/share/applications"),
+            PathBuf::from("/var/lib/flatpak/exports/share/applications"),
+            PathBuf::from("/var/lib/snapd/desktop/applications"),
+            home.join(".local").join("share").join("applications"),
+        ] {
+            for name in child_names(&dir) {
+                if let Some(stem) = name.strip_suffix(".desktop") {
+                    // Reverse-DNS ids such as org.gnome.Nautilus name the app last
+                    add(stem);
+                    add(stem.rsplit('.').next().unwrap_or(stem));
+                }
+            }
+        }
+    }
+
+    // Command-line tools keep dot folders too
+    if let Some(path) = std::env::var_os("PATH") {
+        for dir in std::env::split_paths(&path) {
+            for name in child_names(&dir) {
+                add(name.strip_suffix(".exe").unwrap_or(&name));
+            }
+        }
+    }
+
+    names
+}
+
+/// Whether a data folder name plausibly belongs to one of `installed`
+pub fn matches_installed(folder: &str, installed: &HashSet<String>) -> bool {
+    let mut candidates = vec![normalize_name(folder)];
+    // com.vendor.App bundle ids on macOS
+    if let Some(last) = folder.rsplit('.').next() {
+        candidates.push(normalize_name(last));
+    }
+
+    candidates.iter().filter(|name| !name.is_empty()).any(|name| {
+        installed.contains(name)
+            || (name.len() >= MIN_FUZZY_NAME_LEN
+                && installed.iter().any(|app| {
+                    app.len() >= MIN_FUZZY_NAME_LEN && (app.contains(name) || name.contains(app))
+                }))
+    })
+}
+
+/// Folders directly below `location` not modified since `cutoff`, skipping
+/// links and well-known OS folders
+pub fn stale_folders(location: &Path, cutoff: SystemTime) -> Vec<LeftoverCandidate> {
+    let Ok(entries) = fs::read_dir(location) else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let lowered = name.to_lowercase();
+            if SHARED_FOLDERS.contains(&lowered.as_str()) || lowered.starts_with("com.apple.") {
+                return None;
+            }
+
+            let path = entry.path();
+            let last_modified = newest_modification(&path);
+            if last_modified.is_some_and(|modified| modified >= cutoff) {
+                return None;
+            }
+
+            Some(LeftoverCandidate {
+                name,
+                path,
+                last_modified: last_modified.map(DateTime::<Utc>::from),
+            })
+        })
+        .collect()
+}
+
+/// Apps touch their own files, not always the folder, so look one level in
+fn newest_modification(dir: &Path) -> Option<SystemTime> {
+    let own = fs::symlink_metadata(dir).and_then(|m| m.modified()).ok();
+    let children = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok()?.metadata().ok()?.modified().ok());
+    own.into_iter().chain(children).max()
+}
+
+fn child_names(dir: &Path) -> Vec<String> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect()
+}
@@ -0,0 +1,456 @@
+use chrono::{DateTime, Datelike, Local, Timelike};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio_util::sync::CancellationToken;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::utils::platform::prepare_path_for_io;
+use crate::utils::walker::{DirWalker, WalkOptions};
+
+const COPY_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// zstd's own default, a good balance of speed and ratio
+const ZSTD_LEVEL: i32 = 3;
+
+/// Container written by `archive_files`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveFormat {
+    Zip,
+    TarZst,
+}
+
+impl ArchiveFormat {
+    /// Format named by the extension of `path`, if it names one
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_ascii_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+            Some(Self::TarZst)
+        } else {
+            None
+        }
+    }
+}
+
+
+/// A file to store in an archive under `name`
+#[derive(Debug, Clone)]
+pub struct ArchiveMember {
+    pub source: PathBuf,
+    /// `/`-separated path inside the archive
+    pub name: String,
+}
+
+/// A member as it was written, with the digest its copy must match
+#[derive(Debug, Clone)]
+pub struct ArchivedMember {
+    pub source: PathBuf,
+    pub name: String,
+    pub size: u64,
+    digest: blake3::Hash,
+}
+
+/// Regular files making up `paths`: files as they are, folders by every
+/// regular file below them. Names are relative to the folder the selection
+/// shares, so they never collide; symlinks and special files are left out.
+pub fn collect_members(paths: &[PathBuf]) -> io::Result<Vec<ArchiveMember>> {
+    let base = paths
+        .iter()
+        .filter_map(|path| path.parent())
+        .reduce(common_ancestor)
+        .unwrap_or(Path::new(""))
+        .to_path_buf();
+
+    let mut files = Vec::new();
+    for path in paths {
+        let metadata = fs::symlink_metadata(path)?;
+        if metadata.is_file() {
+            files.push(path.clone());
+        } else if metadata.is_dir() {
+            files.extend(
+                DirWalker::new(path, WalkOptions::default())
+                    .filter(|entry| entry.is_file())
+                    .map(|entry| entry.path),
+            );
+        }
+    }
+    files.sort();
+    files.dedup();
+
+    Ok(files
+        .into_iter()
+        .map(|source| {
+            let relative = source.strip_prefix(&base).unwrap_or(&source);
+            let name = relative
+                .iter()
+                .map(|part| part.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            ArchiveMember { source, name }
+        })
+        .collect())
+}
+
+fn common_ancestor<'a>(a: &'a Path, b: &'a Path) -> &'a Path {
+    a.ancestors()
+        .find(|ancestor| b.starts_with(ancestor))
+        .unwrap_or(Path::new(""))
+}
+
+/// Write `members` to a new archive at `path`. Stops with an `Interrupted`
+/// error if `cancel` fires; the caller removes the partial file.
+pub fn write_archive(
+    path: &Path,
+    format: ArchiveFormat,
+    members: &[ArchiveMember],
+    cancel: &CancellationToken,
+) -> io::Result<Vec<ArchivedMember>> {
+    let file = File::create_new(path)?;
+    let archived = match format {
+        ArchiveFormat::Zip => {
+            let mut zip = ZipWriter::new(BufWriter::new(file));
+            let archived = write_zip(&mut zip, members, cancel)?;
+            zip.finish()?.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+            archived
+        }
+        ArchiveFormat::TarZst => {
+            let encoder = zstd::Encoder::new(BufWriter::new(file), ZSTD_LEVEL)?;
+            let mut tar = tar::Builder::new(encoder);
+            let archived = write_tar(&mut tar, members, cancel)?;
+            tar.into_inner()?
+                .finish()?
+                .into_inner()
+                .map_err(|e| e.into_error())?
+                .sync_all()?;
+            archived
+        }
+    };
+    Ok(archived)
+}
+
+/// Read the archive at `path` back and check it holds exactly `expected`,
+/// in order, with matching names, sizes and contents
+pub fn verify_archive(
+    path: &Path,
+    format: ArchiveFormat,
+    expected: &[ArchivedMember],
+    cancel: &CancellationToken,
+) -> io::Result<()> {
+    let reader = BufReader::new(File::open(path)?);
+    match format {
+        ArchiveFormat::Zip => verify_zip(reader, expected, cancel),
+        ArchiveFormat::TarZst => verify_tar(zstd::Decoder::new(reader)?, expected, cancel),
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn cancelled() -> io::Error {
+    io::Error::new(io::ErrorKind::Interrupted, "Archiving was cancelled")
+}
+
+/// Copy `source` into `sink`, returning its length and BLAKE3 digest
+fn copy_hashed(
+    source: &mut impl Read,
+    sink: &mut impl Write,
+    cancel: &CancellationToken,
+) -> io::Result<(u64, blake3::Hash)> {
+    let mut buffer = vec![0u8; COPY_CHUNK_SIZE];
+    let mut hasher = blake3::Hasher::new();
+    let mut length = 0u64;
+    loop {
+        if cancel.is_cancelled() {
+            return Err(cancelled());
+        }
+        let read = match source.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        hasher.update(&buffer[..read]);
+        sink.write_all(&buffer[..read])?;
+        length += read as u64;
+    }
+    Ok((length, hasher.finalize()))
+}
+
+/// Hashes what passes through it, and ends early once `cancel` fires so
+/// `tar` stops copying; the caller checks the token afterwards
+struct HashingReader<'a, R> {
+    inner: R,
+    hasher: blake3::Hasher,
+    length: u64,
+    cancel: &'a CancellationToken,
+}
+
+impl<R: Read> Read for HashingReader<'_, R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if self.cancel.is_cancelled() {
+            return Ok(0);
+        }
+        let read = self.inner.read(buffer)?;
+        self.hasher.update(&buffer[..read]);
+        self.length += read as u64;
+        Ok(read)
+    }
+}
+
+fn open_member(member: &ArchiveMember) -> io::Result<(File, Option<SystemTime>)> {
+    let file = File::open(prepare_path_for_io(&member.source))?;
+    let modified = file.metadata()?.modified().ok();
+    Ok((file, modified))
+}
+
+fn check_member(
+    member: &ArchivedMember,
+    name: &str,
+    size: u64,
+    digest: blake3::Hash,
+) -> io::Result<()> {
+    if name != member.name {
+        return Err(invalid(format!("expected {} but found {}", member.name, name)));
+    }
+    if size != member.size || digest != member.digest {
+        return Err(invalid(format!("{} does not match the original", member.name)));
+    }
+    Ok(())
+}
+
+/// `modified` in local time, as zip stores it; times zip cannot hold
+/// fall back to its 1980 epoch
+fn zip_datetime(modified: Option<SystemTime>) -> zip::DateTime {
+    let local: DateTime<Local> = modified.map(DateTime::from).unwrap_or_else(Local::now);
+    zip::DateTime::from_date_and_time(
+        u16::try_from(local.year()).unwrap_or(0),
+        local.month() as u8,
+        local.day() as u8,
+        local.hour() as u8,
+        local.minute() as u8,
+        local.second() as u8,
+    )
+    .unwrap_or_default()
+}
+
+/// Deflated zip; `large_file` makes every entry Zip64 so members past
+/// 4 GiB need no special casing
+fn write_zip<W: Write + io::Seek>(
+    zip: &mut ZipWriter<W>,
+    members: &[ArchiveMember],
+    cancel: &CancellationToken,
+) -> io::Result<Vec<ArchivedMember>> {
+    let mut archived = Vec::with_capacity(members.len());
+    for member in members {
+        let (mut source, modified) = open_member(member)?;
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .large_file(true)
+            .last_modified_time(zip_datetime(modified))
+            .unix_permissions(0o644);
+        zip.start_file(member.name.as_str(), options)?;
+        let (size, digest) = copy_hashed(&mut source, zip, cancel)?;
+        archived.push(ArchivedMember {
+            source: member.source.clone(),
+            name: member.name.clone(),
+            size,
+            digest,
+        });
+    }
+    Ok(archived)
+}
+
+/// Reading each entry to its end also has `zip` check its CRC-32
+fn verify_zip(
+    reader: BufReader<File>,
+    expected: &[ArchivedMember],
+    cancel: &CancellationToken,
+) -> io::Result<()> {
+    let mut zip = ZipArchive::new(reader)?;
+    if zip.len() != expected.len() {
+        return Err(invalid(format!(
+            "archive holds {} entries but {} were written",
+            zip.len(),
+            expected.len()
+        )));
+    }
+    for (index, member) in expected.iter().enumerate() {
+        let mut entry = zip.by_index(index)?;
+        let name = entry.name().to_string();
+        let (size, digest) = copy_hashed(&mut entry, &mut io::sink(), cancel)?;
+        check_member(member, &name, size, digest)?;
+    }
+    Ok(())
+}
+
+/// GNU tar, which takes long names and sizes past 8 GiB in its stride
+fn write_tar<W: Write>(
+    tar: &mut tar::Builder<W>,
+    members: &[ArchiveMember],
+    cancel: &CancellationToken,
+) -> io::Result<Vec<ArchivedMember>> {
+    let mut archived = Vec::with_capacity(members.len());
+    for member in members {
+        let (source, modified) = open_member(member)?;
+        let size = source.metadata()?.len();
+        let mtime = modified
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |since| since.as_secs());
+
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_size(size);
+        header.set_mtime(mtime);
+        header.set_mode(0o644);
+
+        // The header already promises `size` bytes, so a file that changed
+        // length meanwhile cannot be stored faithfully
+        let mut reader = HashingReader {
+            inner: source.take(size),
+            hasher: blake3::Hasher::new(),
+            length: 0,
+            cancel,
+        };
+        tar.append_data(&mut header, &member.name, &mut reader)?;
+        if cancel.is_cancelled() {
+            return Err(cancelled());
+        }
+        if reader.length != size {
+            return Err(invalid(format!("{} changed while being archived", member.name)));
+        }
+
+        archived.push(ArchivedMember {
+            source: member.source.clone(),
+            name: member.name.clone(),
+            size,
+            digest: reader.hasher.finalize(),
+        });
+    }
+    tar.finish()?;
+    Ok(archived)
+}
+
+fn verify_tar(
+    reader: impl Read,
+    expected: &[ArchivedMember],
+    cancel: &CancellationToken,
+) -> io::Result<()> {
+    let mut tar = tar::Archive::new(reader);
+    let mut remaining = expected.iter();
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let name = String::from_utf8_lossy(&entry.path_bytes()).to_string();
+        let member = remaining
+            .next()
+            .ok_or_else(|| invalid("archive holds more entries than were written".to_string()))?;
+        let (size, digest) = copy_hashed(&mut entry, &mut io::sink(), cancel)?;
+        check_member(member, &name, size, digest)?;
+    }
+
+    match remaining.next() {
+        Some(member) => Err(invalid(format!("missing entry for {}", member.name))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_tree(dir: &Path) -> Vec<PathBuf> {
+        let folder = dir.join("photos");
+        let deep = folder.join("a".repeat(60)).join("b".repeat(60));
+        fs::create_dir_all(&deep).unwrap();
+        fs::write(folder.join("one.txt"), b"first file").unwrap();
+        fs::write(deep.join("long-name.bin"), vec![7u8; 300_000]).unwrap();
+        fs::write(dir.join("empty.log"), b"").unwrap();
+        vec![folder, dir.join("empty.log")]
+    }
+
+    fn round_trip(format: ArchiveFormat, file_name: &str) {
+        let dir = tempfile::tempdir().unwrap();
+        let members = collect_members(&sample_tree(dir.path())).unwrap();
+        assert_eq!(members.len(), 3);
+        assert!(members.iter().any(|member| member.name.len() > 100));
+
+        let path = dir.path().join(file_name);
+        assert_eq!(ArchiveFormat::from_path(&path), Some(format));
+        let cancel = CancellationToken::new();
+        let archived = write_archive(&path, format, &members, &cancel).unwrap();
+        verify_archive(&path, format, &archived, &cancel).unwrap();
+
+        let mut missing = archived.clone();
+        missing.pop();
+        assert!(verify_archive(&path, format, &missing, &cancel).is_err());
+    }
+
+    #[test]
+    fn zip_round_trips_and_verifies() {
+        round_trip(ArchiveFormat::Zip, "backup.zip");
+    }
+
+    #[test]
+    fn tar_zst_round_trips_and_verifies() {
+        round_trip(ArchiveFormat::TarZst, "backup.tar.zst");
+    }
+
+    #[test]
+    fn tar_zst_output_is_a_zstd_stream() {
+        let dir = tempfile::tempdir().unwrap();
+        let members = collect_members(&sample_tree(dir.path())).unwrap();
+        let path = dir.path().join("backup.tar.zst");
+        write_archive(&path, ArchiveFormat::TarZst, &members, &CancellationToken::new()).unwrap();
+
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(&bytes[..4], &[0x28, 0xb5, 0x2f, 0xfd]);
+        let mut tar = tar::Archive::new(zstd::Decoder::new(&bytes[..]).unwrap());
+        let mut entry = tar
+            .entries()
+            .unwrap()
+            .map(Result::unwrap)
+            .find(|entry| &*entry.path_bytes() == b"photos/one.txt")
+            .unwrap();
+        let mut contents = Vec::new();
+        entry.read_to_end(&mut contents).unwrap();
+        assert_eq!(contents, b"first file");
+    }
+
+    #[test]
+    fn verification_catches_a_changed_original() {
+        for (format, file_name) in [
+            (ArchiveFormat::Zip, "backup.zip"),
+            (ArchiveFormat::TarZst, "backup.tar.zst"),
+        ] {
+            let dir = tempfile::tempdir().unwrap();
+            let members = collect_members(&sample_tree(dir.path())).unwrap();
+            let path = dir.path().join(file_name);
+            let cancel = CancellationToken::new();
+            let mut archived = write_archive(&path, format, &members, &cancel).unwrap();
+            archived[0].digest = blake3::hash(b"something else");
+
+            let error = verify_archive(&path, format, &archived, &cancel).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn cancelling_stops_with_interrupted() {
+        for format in [ArchiveFormat::Zip, ArchiveFormat::TarZst] {
+            let dir = tempfile::tempdir().unwrap();
+            let members = collect_members(&sample_tree(dir.path())).unwrap();
+            let cancel = CancellationToken::new();
+            cancel.cancel();
+
+            let error = write_archive(&dir.path().join("out"), format, &members, &cancel)
+                .unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::Interrupted);
+        }
+    }
+}
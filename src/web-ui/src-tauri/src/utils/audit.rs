@@ -0,0 +1,120 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::warn;
+
+use crate::utils::paths;
+
+const AUDIT_FILE_NAME: &str = "audit.jsonl";
+
+/// Buffered entries are written out once this many accumulate
+const FLUSH_THRESHOLD: usize = 64;
+
+/// Kind of action recorded in the audit trail
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Deleted,
+    /// Contents were overwritten before removal; irreversible
+    Wiped,
+    DeleteFailed,
+    /// A deletion undone by restoring its backup after its batch failed
+    RolledBack,
+    /// A file's digest was recorded; `detail` holds `algorithm:hex`
+    Hashed,
+    /// A backend command finished; `detail` carries its name and timing
+    Command,
+    /// A file was stored in a verified archive; `detail` names the archive
+    Archived,
+}
+
+/// A single per-file audit record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub action: AuditAction,
+    pub path: String,
+    pub bytes: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl AuditEntry {
+    pub fn new(action: AuditAction, path: &str, bytes: u64, detail: Option<String>) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            action,
+            path: path.to_string(),
+            bytes,
+            detail,
+        }
+    }
+}
+
+/// Append-only JSON-lines audit trail with an in-memory write buffer
+#[derive(Debug)]
+pub struct AuditLog {
+    path: PathBuf,
+    buffer: Mutex<Vec<AuditEntry>>,
+}
+
+impl AuditLog {
+    pub fn new() -> Self {
+        Self {
+            path: paths::log_dir().join(AUDIT_FILE_NAME),
+            buffer: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Buffer an entry, flushing to disk once the buffer is large enough
+    pub fn record(&self, entry: AuditEntry) {
+        let should_flush = {
+            let mut buffer = self.lock();
+            buffer.push(entry);
+            buffer.len() >= FLUSH_THRESHOLD
+        };
+
+        if should_flush {
+            if let Err(e) = self.flush() {
+                warn!("Failed to flush audit log: {}", e);
+            }
+        }
+    }
+
+    /// Write all buffered entries to disk
+    pub fn flush(&self) -> anyhow::Result<()> {
+        let mut buffer = self.lock();
+        if buffer.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+
+        for entry in buffer.iter() {
+            writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        }
+        file.sync_data()?;
+        buffer.clear();
+
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<AuditEntry>> {
+        self.buffer.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,177 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::utils::hashing::hash_file;
+use crate::utils::security::safe_join;
+
+const MANIFEST_FILE_NAME: &str = "manifest.json";
+const HASH_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// One backed-up file of a deletion batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupRecord {
+    /// Where the file lived before it was deleted
+    pub original: String,
+    /// File name of the copy inside the backup session directory
+    pub file: String,
+    pub size: u64,
+    /// BLAKE3 digest taken when the backup was made
+    pub blake3: String,
+}
+
+/// Index of a backup session, written once its batch has been deleted
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupManifest {
+    created_at: DateTime<Utc>,
+    entries: Vec<BackupRecord>,
+}
+
+/// Describe a backed-up copy for the session manifest
+pub fn record_backup(original: &Path, backup: &Path) -> io::Result<BackupRecord> {
+    Ok(BackupRecord {
+        original: original.to_string_lossy().to_string(),
+        file: backup
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        size: fs::metadata(backup)?.len(),
+        blake3: hash_file(backup, HASH_CHUNK_SIZE)?,
+    })
+}
+
+pub fn write_manifest(session_dir: &Path, entries: Vec<BackupRecord>) -> io::Result<()> {
+    let manifest = BackupManifest {
+        created_at: Utc::now(),
+        entries,
+    };
+    let json = serde_json::to_string_pretty(&manifest).map_err(io::Error::other)?;
+    fs::write(session_dir.join(MANIFEST_FILE_NAME), json)
+}
+
+/// A backed-up file that is gone or no longer matches its record
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupIssue {
+    pub original: String,
+    pub backup: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupVerification {
+    /// Name of the session checked; `None` when there are no backups yet
+    pub session: Option<String>,
+    pub checked: u64,
+    pub missing: Vec<BackupIssue>,
+    pub corrupt: Vec<BackupIssue>,
+    pub passed: bool,
+}
+
+/// Backup session directories below `backup_root`, oldest first. Session
+/// directories are named by timestamp, so the newest sorts last.
+pub fn sessions(backup_root: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut sessions: Vec<PathBuf> = match fs::read_dir(backup_root) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e),
+    };
+    sessions.sort();
+    Ok(sessions)
+}
+
+/// Check the newest backup session below `backup_root`: every recorded file
+/// must exist with its recorded size and, with `check_hashes`, its digest.
+pub fn verify_latest(backup_root: &Path, check_hashes: bool) -> io::Result<BackupVerification> {
+    let mut sessions = sessions(backup_root)?;
+    let Some(session) = sessions.pop() else {
+        return Ok(BackupVerification {
+            session: None,
+            checked: 0,
+            missing: Vec::new(),
+            corrupt: Vec::new(),
+            passed: true,
+        });
+    };
+    let session_name = session
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string());
+
+    let manifest_path = session.join(MANIFEST_FILE_NAME);
+    let manifest: BackupManifest = match fs::read_to_string(&manifest_path)
+        .map_err(|e| e.to_string())
+        .and_then(|json| serde_json::from_str(&json).map_err(|e| e.to_string()))
+    {
+        Ok(manifest) => manifest,
+        Err(reason) => return Ok(unreadable_manifest(session_name, &manifest_path, reason)),
+    };
+
+    let mut missing = Vec::new();
+    let mut corrupt = Vec::new();
+    for record in &manifest.entries {
+        // A tampered manifest must not make verification read outside the session
+        let backup = match safe_join(&session, &record.file) {
+            Ok(backup) => backup,
+            Err(reason) => {
+                corrupt.push(BackupIssue {
+                    original: record.original.clone(),
+                    backup: record.file.clone(),
+                    reason,
+                });
+                continue;
+            }
+        };
+        let issue = |reason: String| BackupIssue {
+            original: record.original.clone(),
+            backup: backup.to_string_lossy().to_string(),
+            reason,
+        };
+
+        match fs::metadata(&backup) {
+            Err(e) => missing.push(issue(e.to_string())),
+            Ok(metadata) if metadata.len() != record.size => corrupt.push(issue(format!(
+                "size is {} bytes, expected {}",
+                metadata.len(),
+                record.size
+            ))),
+            Ok(_) if check_hashes => match hash_file(&backup, HASH_CHUNK_SIZE) {
+                Ok(digest) if digest == record.blake3 => {}
+                Ok(_) => corrupt.push(issue("contents do not match the recorded digest".into())),
+                Err(e) => corrupt.push(issue(e.to_string())),
+            },
+            Ok(_) => {}
+        }
+    }
+
+    Ok(BackupVerification {
+        session: session_name,
+        checked: manifest.entries.len() as u64,
+        passed: missing.is_empty() && corrupt.is_empty(),
+        missing,
+        corrupt,
+    })
+}
+
+/// A session whose manifest is gone or unreadable cannot vouch for anything
+fn unreadable_manifest(
+    session: Option<String>,
+    manifest: &Path,
+    reason: String,
+) -> BackupVerification {
+    BackupVerification {
+        session,
+        checked: 0,
+        missing: Vec::new(),
+        corrupt: vec![BackupIssue {
+            original: String::new(),
+            backup: manifest.to_string_lossy().to_string(),
+            reason: format!("Backup manifest unreadable: {}", reason),
+        }],
+        passed: false,
+    }
+}
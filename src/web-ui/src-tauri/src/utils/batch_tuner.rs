@@ -0,0 +1,91 @@
+use std::time::Duration;
+use tracing::debug;
+
+use crate::utils::config::AnalysisConfig;
+
+/// Starting size for adaptive batching; small enough for slow providers
+const INITIAL_ADAPTIVE_BATCH_SIZE: usize = 25;
+/// Upper bound on the tokens one batched request is expected to use
+pub const MAX_BATCH_TOKENS: u64 = 32_000;
+
+/// Chooses how many files go into the next AI request.
+///
+/// In static mode the configured `batch_size` is always used. In adaptive
+/// mode each finished batch's latency and token usage steer the next size
+/// towards half of `ai_timeout`, leaving headroom for slow responses, while
+/// staying under `MAX_BATCH_TOKENS`. A size never more than doubles or halves
+/// in one step, except after a timeout, which always halves it.
+#[derive(Debug, Clone)]
+pub struct BatchTuner {
+    size: usize,
+    max_size: usize,
+    target_latency: Duration,
+    adaptive: bool,
+}
+
+impl BatchTuner {
+    pub fn new(config: &AnalysisConfig) -> Self {
+        let max_size = config.batch_size.max(1);
+        let adaptive = config.adaptive_batch_size;
+        Self {
+            size: if adaptive {
+                INITIAL_ADAPTIVE_BATCH_SIZE.min(max_size)
+            } else {
+                max_size
+            },
+            max_size,
+            target_latency: Duration::from_secs(config.ai_timeout) / 2,
+            adaptive,
+        }
+    }
+
+    /// Number of files to put in the next batch
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Feed back how a batch of `files` went
+    pub fn record(
+        &mut self,
+        files: usize,
+        latency: Duration,
+        total_tokens: Option<u64>,
+        timed_out: bool,
+    ) {
+        if !self.adaptive || files == 0 {
+            return;
+        }
+
+        let previous = self.size;
+        let next = if timed_out {
+            previous / 2
+        } else {
+            let per_file = latency.as_secs_f64() / files as f64;
+            let by_latency = if per_file > 0.0 {
+                (self.target_latency.as_secs_f64() / per_file) as usize
+            } else {
+                previous * 2
+            };
+            let by_tokens = total_tokens
+                .filter(|&tokens| tokens > 0)
+                .map(|tokens| (MAX_BATCH_TOKENS * files as u64 / tokens) as usize)
+                .unwrap_or(usize::MAX);
+            by_latency
+                .min(by_tokens)
+                .clamp(previous.div_ceil(2), previous * 2)
+        };
+
+        self.size = next.clamp(1, self.max_size);
+        if self.size != previous {
+            debug!(
+                "Batch size {} -> {} ({} files in {:?}, {:?} tokens{})",
+                previous,
+                self.size,
+                files,
+                latency,
+                total_tokens,
+                if timed_out { ", timed out" } else { "" }
+            );
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::utils::config::BrowserCacheSpec;
+
+/// Files sampled from each cache folder to see whether the browser has it open
+const LOCK_SAMPLE_FILES: usize = 8;
+
+/// Cache folders inside a Chromium profile; cookies, history and other
+/// profile data sit next to them and are never touched
+const CHROMIUM_CACHE_DIRS: &[&str] = &["Cache", "Code Cache", "GPUCache"];
+/// Cache folders inside a Firefox profile
+const FIREFOX_CACHE_DIRS: &[&str] = &["cache2", "startupCache"];
+
+/// A cache folder of one browser profile
+#[derive(Debug, Clone)]
+pub struct BrowserCacheLocation {
+    pub browser: String,
+    /// Profile folder name; `None` when the browser keeps one shared cache
+    pub profile: Option<String>,
+    pub path: PathBuf,
+}
+
+fn spec(browser: &str, root: PathBuf, per_profile: bool, cache_dirs: &[&str]) -> BrowserCacheSpec {
+    BrowserCacheSpec {
+        browser: browser.to_string(),
+        root: root.to_string_lossy().to_string(),
+        per_profile,
+        cache_dirs: cache_dirs.iter().map(|dir| dir.to_string()).collect(),
+    }
+}
+
+/// Built-in browser cache layouts for the current platform relative to `home`
+pub fn known_browsers(home: &Path) -> Vec<BrowserCacheSpec> {
+    if cfg!(target_os = "windows") {
+        let local = home.join("AppData").join("Local");
+        vec![
+            spec(
+                "Chrome",
+                local.join("Google").join("Chrome").join("User Data"),
+                true,
+                CHROMIUM_CACHE_DIRS,
+            ),
+            spec(
+                "Edge",
+                local.join("Microsoft").join("Edge").join("User Data"),
+                true,
+                CHROMIUM_CACHE_DIRS,
+            ),
+            // The profile itself lives under Roaming; only its cache is local
+            spec(
+                "Firefox",
+                local.join("Mozilla").join("Firefox").join("Profiles"),
+                true,
+                FIREFOX_CACHE_DIRS,
+            ),
+        ]
+    } else if cfg!(target_os = "macos") {
+        let caches = home.join("Library").join("Caches");
+        vec![
+            spec(
+                "Chrome",
+                caches.join("Google").join("Chrome"),
+                true,
+                CHROMIUM_CACHE_DIRS,
+            ),
+            spec(
+                "Edge",
+                caches.join("Microsoft Edge"),
+                true,
+                CHROMIUM_CACHE_DIRS,
+            ),
+            spec(
+                "Firefox",
+                caches.join("Firefox").join("Profiles"),
+                true,
+                FIREFOX_CACHE_DIRS,
+            ),
+            spec(
+                "Safari",
+                caches.join("com.apple.Safari"),
+                false,
+                &["WebKitCache", "fsCachedData"],
+            ),
+        ]
+    } else {
+        let cache = home.join(".cache");
+        vec![
+            spec(
+                "Chrome",
+                cache.join("google-chrome"),
+                true,
+                CHROMIUM_CACHE_DIRS,
+            ),
+            spec(
+                "Edge",
+                cache.join("microsoft-edge"),
+                true,
+                CHROMIUM_CACHE_DIRS,
+            ),
+            spec(
+                "Firefox",
+                cache.join("mozilla").join("firefox"),
+                true,
+                FIREFOX_CACHE_DIRS,
+            ),
+        ]
+    }
+}
+
+/// `root` of `spec` with a leading `~` expanded to `home`
+fn expand_root(root: &str, home: &Path) -> PathBuf {
+    match root.strip_prefix("~") {
+        Some(rest) => home.join(rest.trim_start_matches(['/', '\\'])),
+        None => PathBuf::from(root),
+    }
+}
+
+/// Whether `dir` names a folder inside its parent, never the parent itself,
+/// an ancestor or an absolute path
+pub fn is_plain_subfolder(dir: &str) -> bool {
+    let mut components = Path::new(dir).components();
+    components.clone().count() > 0 && components.all(|c| matches!(c, Component::Normal(_)))
+}
+
+fn is_real_dir(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|m| m.is_dir())
+}
+
+/// Existing cache folders described by `spec`. Only the configured cache
+/// subfolders are returned, never a profile itself; symlinks are skipped
+/// so nothing outside the browser's own folders is reached.
+pub fn locate(spec: &BrowserCacheSpec, home: &Path) -> Vec<BrowserCacheLocation> {
+    let root = expand_root(&spec.root, home);
+    if !is_real_dir(&root) {
+        return Vec::new();
+    }
+
+    let profiles: Vec<(Option<String>, PathBuf)> = if spec.per_profile {
+        let Ok(entries) = fs::read_dir(&root) else {
+            return Vec::new();
+        };
+        let mut profiles: Vec<_> = entries
+            .flatten()
+            .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+            .map(|entry| {
+                (
+                    Some(entry.file_name().to_string_lossy().to_string()),
+                    entry.path(),
+                )
+            })
+            .collect();
+        profiles.sort();
+        profiles
+    } else {
+        vec![(None, root)]
+    };
+
+    let mut locations = Vec::new();
+    for (profile, dir) in profiles {
+        for cache_dir in spec.cache_dirs.iter().filter(|dir| is_plain_subfolder(dir)) {
+            let path = dir.join(cache_dir);
+            if is_real_dir(&path) {
+                locations.push(BrowserCacheLocation {
+                    browser: spec.browser.clone(),
+                    profile: profile.clone(),
+                    path,
+                });
+            }
+        }
+    }
+    locations
+}
+
+/// A few files from directly inside `dir` and its subfolders, enough to
+/// tell whether a running browser holds the cache open
+pub fn sample_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if files.len() >= LOCK_SAMPLE_FILES {
+                return files;
+            }
+            match entry.file_type() {
+                Ok(t) if t.is_file() => files.push(entry.path()),
+                Ok(t) if t.is_dir() && pending.len() < LOCK_SAMPLE_FILES => {
+                    pending.push(entry.path())
+                }
+                _ => {}
+            }
+        }
+    }
+    files
+}
@@ -0,0 +1,836 @@
+use chrono::{DateTime, Utc};
+use serde::de::value::{Error as ValueError, StrDeserializer};
+use serde::de::IntoDeserializer;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+use crate::utils::ai_client::{AiClient, AiError, ChatMessage};
+use crate::utils::analysis_cache::AnalysisCache;
+use crate::utils::batch_tuner::MAX_BATCH_TOKENS;
+use crate::utils::config::{AppConfig, SizeWeighting};
+use crate::utils::dev_caches::container_data_dirs;
+use crate::utils::logging::redact_identifier;
+use crate::utils::messages::{self, Message};
+use crate::utils::overrides::AppliedOverride;
+use crate::utils::platform::{is_network_drive, is_removable_drive};
+use crate::utils::rate_limiter::RateLimiter;
+use crate::utils::security::SecurityValidator;
+
+/// Cleanup-oriented category assigned to a file
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum FileCategory {
+    Temporary,
+    Cache,
+    /// Package-manager or build-tool cache that can be re-downloaded
+    DevCache,
+    Log,
+    Backup,
+    Development,
+    System,
+    Media,
+    Document,
+    Archive,
+    Working,
+    Personal,
+    /// Symlink whose target no longer exists
+    BrokenLink,
+    Unknown,
+}
+
+impl FileCategory {
+    /// Categories whose files can normally be removed without losing user data
+    pub fn is_reclaimable(self) -> bool {
+        matches!(
+            self,
+            Self::Temporary
+                | Self::Cache
+                | Self::DevCache
+                | Self::Log
+                | Self::Backup
+                | Self::Development
+                | Self::BrokenLink
+        )
+    }
+}
+
+/// Where a classification came from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClassificationSource {
+    Ai,
+    Heuristic,
+}
+
+/// Result of classifying a single file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Classification {
+    pub category: FileCategory,
+    /// Confidence in the range 0.0..=1.0
+    pub confidence: f32,
+    pub reason: String,
+    pub source: ClassificationSource,
+    /// Set when a user override rule replaced the verdict
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overridden_by: Option<AppliedOverride>,
+}
+
+/// Metadata the classifier looks at; never includes file contents
+#[derive(Debug, Clone, Serialize)]
+pub struct FileFacts {
+    pub path: String,
+    pub file_name: String,
+    pub extension: String,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+    pub is_hidden: bool,
+    /// Whether the file lives on removable or external media
+    pub on_removable_media: bool,
+    /// Whether the file lives on an SMB, NFS or other network filesystem
+    pub on_network_drive: bool,
+    /// Whether the path is a symlink whose target no longer exists
+    pub is_broken_symlink: bool,
+}
+
+impl FileFacts {
+    /// Gather facts from the filesystem without following symlinks
+    pub fn from_path(path: &Path) -> std::io::Result<Self> {
+        let metadata = fs::symlink_metadata(path)?;
+        Ok(Self {
+            on_removable_media: is_removable_drive(path),
+            on_network_drive: is_network_drive(path),
+            is_broken_symlink: is_broken_symlink(path),
+            ..Self::from_metadata(path, &metadata)
+        })
+    }
+
+    pub fn from_metadata(path: &Path, metadata: &fs::Metadata) -> Self {
+        Self::new(
+            path,
+            metadata.len(),
+            metadata.modified().ok().map(DateTime::<Utc>::from),
+        )
+    }
+
+    /// Build facts from already-known size and modification time
+    pub fn new(path: &Path, size: u64, modified: Option<DateTime<Utc>>) -> Self {
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        Self {
+            path: path.to_string_lossy().to_string(),
+            extension: path
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default(),
+            is_hidden: file_name.starts_with('.'),
+            file_name,
+            size,
+            modified,
+            on_removable_media: false,
+            on_network_drive: false,
+            is_broken_symlink: false,
+        }
+    }
+}
+
+/// Whether `path` is a symlink whose target cannot be reached
+pub fn is_broken_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_symlink())
+        && fs::metadata(path).is_err()
+}
+
+const TEMP_EXTENSIONS: &[&str] = &["tmp", "temp", "swp", "swo", "part", "crdownload"];
+const TEMP_NAMES: &[&str] = &[".ds_store", "thumbs.db", "desktop.ini"];
+const CACHE_EXTENSIONS: &[&str] = &["cache", "cch", "sqlite-shm", "sqlite-wal"];
+const LOG_EXTENSIONS: &[&str] = &["log", "trace", "out"];
+const BACKUP_EXTENSIONS: &[&str] = &["bak", "old", "orig", "backup"];
+const DEVELOPMENT_EXTENSIONS: &[&str] =
+    &["o", "obj", "pyc", "pyo", "class", "pdb", "rlib", "rmeta"];
+const DEVELOPMENT_DIRS: &[&str] = &["node_modules", "__pycache__", "target", "build", "dist"];
+const DEV_CACHE_DIRS: &[&str] = &[
+    ".npm",
+    ".m2",
+    ".gradle",
+    ".yarn",
+    ".pnpm-store",
+    "pip",
+    "go-build",
+];
+const SYSTEM_EXTENSIONS: &[&str] = &["sys", "dll", "so", "dylib", "drv", "kext"];
+const ARCHIVE_EXTENSIONS: &[&str] = &[
+    "zip", "tar", "gz", "tgz", "bz2", "xz", "7z", "rar", "dmg", "iso",
+];
+const MEDIA_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "gif", "heic", "webp", "bmp", "tiff", "mp4", "mov", "mkv", "avi", "mp3",
+    "wav", "flac", "aac", "ogg",
+];
+const DOCUMENT_EXTENSIONS: &[&str] = &[
+    "pdf", "doc", "docx", "xls", "xlsx", "ppt", "pptx", "odt", "ods", "txt", "md", "rtf",
+];
+
+/// Lowercased directory components of the path, excluding the file name
+fn directory_components(facts: &FileFacts) -> Vec<String> {
+    let path = Path::new(&facts.path);
+    path.parent()
+        .map(|parent| {
+            parent
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_lowercase())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Rule-based classification used when AI analysis is unavailable, with the
+/// reason given in `language` when the message table has it
+pub fn classify_heuristic(facts: &FileFacts, language: &str) -> Classification {
+    let name = facts.file_name.to_lowercase();
+    let ext = facts.extension.as_str();
+    let dirs = directory_components(facts);
+    let in_dir = |names: &[&str]| dirs.iter().any(|d| names.contains(&d.as_str()));
+
+    let (category, confidence, reason) = if SYSTEM_EXTENSIONS.contains(&ext) {
+        (FileCategory::System, 0.9, Message::SystemFile)
+    } else if TEMP_EXTENSIONS.contains(&ext)
+        || TEMP_NAMES.contains(&name.as_str())
+        || name.starts_with('~')
+    {
+        (
+            FileCategory::Temporary,
+            0.95,
+            Message::TemporaryFile,
+        )
+    } else if in_dir(&["tmp", "temp"]) {
+        (
+            FileCategory::Temporary,
+            0.85,
+            Message::TemporaryDirectory,
+        )
+    } else if in_dir(DEV_CACHE_DIRS) {
+        (
+            FileCategory::DevCache,
+            0.85,
+            Message::PackageCache,
+        )
+    } else if CACHE_EXTENSIONS.contains(&ext) || in_dir(&["cache", "caches", ".cache", "cache2"]) {
+        (
+            FileCategory::Cache,
+            0.9,
+            Message::CacheFile,
+        )
+    } else if LOG_EXTENSIONS.contains(&ext) || in_dir(&["logs", "log"]) {
+        (
+            FileCategory::Log,
+            0.85,
+            Message::LogFile,
+        )
+    } else if BACKUP_EXTENSIONS.contains(&ext) || name.ends_with('~') {
+        (FileCategory::Backup, 0.8, Message::BackupCopy)
+    } else if DEVELOPMENT_EXTENSIONS.contains(&ext) || in_dir(DEVELOPMENT_DIRS) {
+        (
+            FileCategory::Development,
+            0.75,
+            Message::BuildArtifact,
+        )
+    } else if ARCHIVE_EXTENSIONS.contains(&ext) {
+        (
+            FileCategory::Archive,
+            0.8,
+            Message::Archive,
+        )
+    } else if MEDIA_EXTENSIONS.contains(&ext) {
+        (FileCategory::Media, 0.8, Message::MediaFile)
+    } else if DOCUMENT_EXTENSIONS.contains(&ext) {
+        (FileCategory::Document, 0.8, Message::DocumentFile)
+    } else {
+        (
+            FileCategory::Unknown,
+            0.3,
+            Message::NoRuleMatched,
+        )
+    };
+
+    Classification {
+        category,
+        confidence,
+        reason: messages::text(reason, language).to_string(),
+        source: ClassificationSource::Heuristic,
+        overridden_by: None,
+    }
+}
+
+/// Classify without contacting the provider: a still-valid cached result if
+/// there is one, otherwise the heuristic rules weighted for location and
+/// size the same way `classify` weights them
+pub fn classify_offline(
+    config: &AppConfig,
+    cache: &AnalysisCache,
+    facts: &FileFacts,
+) -> Classification {
+    local_classification(config, facts)
+        .or_else(|| cache.get(facts, config.analysis.cache_ttl_seconds, language(config)))
+        .unwrap_or_else(|| {
+            let classification = classify_heuristic(facts, language(config));
+            let classification = adjust_for_location(classification, facts, language(config));
+            adjust_for_size(classification, facts, &config.analysis.size_weighting)
+        })
+}
+
+/// Verdicts that never need the provider: files matching a protected
+/// pattern and Docker's data are always system files, and symlinks whose
+/// target is gone are always broken links
+fn local_classification(config: &AppConfig, facts: &FileFacts) -> Option<Classification> {
+    if facts.is_broken_symlink {
+        return Some(Classification {
+            category: FileCategory::BrokenLink,
+            confidence: 1.0,
+            reason: messages::text(Message::BrokenLink, language(config)).to_string(),
+            source: ClassificationSource::Heuristic,
+            overridden_by: None,
+        });
+    }
+
+    if in_container_data(&facts.path) {
+        return Some(Classification {
+            category: FileCategory::System,
+            confidence: 1.0,
+            reason: messages::text(Message::ContainerData, language(config)).to_string(),
+            source: ClassificationSource::Heuristic,
+            overridden_by: None,
+        });
+    }
+
+    let pattern = SecurityValidator::matching_protected_pattern(
+        Path::new(&facts.path),
+        &config.security.protected_patterns,
+    )?;
+
+    Some(Classification {
+        category: FileCategory::System,
+        confidence: 1.0,
+        reason: format!("Matches protected pattern '{}'", pattern),
+        source: ClassificationSource::Heuristic,
+        overridden_by: None,
+    })
+}
+
+/// Shape of the JSON object the AI provider is asked to return. Every field
+/// is required; a category this build does not know reads as `Unknown`.
+#[derive(Debug, Deserialize)]
+struct AiVerdict {
+    #[serde(deserialize_with = "lenient_category")]
+    category: FileCategory,
+    confidence: f32,
+    reason: String,
+}
+
+/// Read a category name case-insensitively, mapping names outside
+/// `FileCategory` to `Unknown` instead of rejecting the verdict
+fn lenient_category<'de, D: Deserializer<'de>>(deserializer: D) -> Result<FileCategory, D::Error> {
+    let name = String::deserialize(deserializer)?.trim().to_lowercase();
+    let name: StrDeserializer<'_, ValueError> = name.as_str().into_deserializer();
+    Ok(FileCategory::deserialize(name).unwrap_or(FileCategory::Unknown))
+}
+
+/// A parsed batch reply
+#[derive(Debug)]
+struct BatchVerdicts {
+    /// One per file in input order; `None` where the entry was malformed
+    verdicts: Vec<Option<AiVerdict>>,
+    /// Index within the batch and problem of each malformed entry
+    invalid: Vec<(usize, String)>,
+}
+
+/// Folders below the home directory that hold the user's own files
+const USER_FOLDERS: &[&str] = &["Documents", "Desktop", "Pictures", "Music", "Videos", "Movies"];
+
+const REMOVABLE_MEDIA_CONFIDENCE_FACTOR: f32 = 0.7;
+/// Network shares are often shared with other users and machines, so they
+/// are treated even more cautiously than removable media
+const NETWORK_DRIVE_CONFIDENCE_FACTOR: f32 = 0.6;
+
+const SYSTEM_PROMPT: &str = "You classify files for a disk cleanup tool. \
+Reply with only a JSON object {\"category\": string, \"confidence\": number, \"reason\": string}. \
+category is one of: temporary, cache, dev_cache, log, backup, development, system, media, document, \
+archive, working, personal, unknown. confidence is between 0 and 1. reason is one short sentence. \
+Be conservative with files on removable media, which may be the only copy, and more so with files \
+on network drives, which other users and machines may depend on.";
+
+const BATCH_SYSTEM_PROMPT: &str = "You classify files for a disk cleanup tool. \
+You receive a JSON array of files. Reply with only a JSON array holding one object \
+{\"category\": string, \"confidence\": number, \"reason\": string} per input file, in the same \
+order. category is one of: temporary, cache, dev_cache, log, backup, development, system, media, \
+document, archive, working, personal, unknown. confidence is between 0 and 1. reason is one short \
+sentence. Be conservative with files on removable media, which may be the only copy, and more so \
+with files on network drives, which other users and machines may depend on.";
+
+/// Ask for reasons in the configured language. The hint is only a request:
+/// providers that ignore it still reply with valid, if English, reasons.
+fn system_prompt(base: &str, language: &str) -> String {
+    if messages::primary_language(language) == messages::DEFAULT_LANGUAGE {
+        return base.to_string();
+    }
+    format!(
+        "{} Write every reason in {}; keep the JSON keys and category values in English.",
+        base,
+        messages::language_name(language)
+    )
+}
+
+fn language(config: &AppConfig) -> &str {
+    &config.analysis.response_language
+}
+
+/// Reply tokens budgeted per file in a batched request
+const BATCH_TOKENS_PER_FILE: u64 = 80;
+
+/// Classifications for one batch plus what the request cost
+#[derive(Debug)]
+pub struct BatchClassification {
+    /// One per input file, in input order
+    pub classifications: Vec<Classification>,
+    /// Time spent on the provider request; zero if none was sent
+    pub latency: Duration,
+    pub total_tokens: Option<u64>,
+    /// Why the batch fell back to heuristics, if it did
+    pub error: Option<AiError>,
+    /// Files whose entry in an otherwise valid reply was malformed and that
+    /// were classified with heuristics instead: index in the batch and why
+    pub invalid_replies: Vec<(usize, String)>,
+}
+
+/// Classify several files with a single AI request. Protected files and
+/// broken symlinks are answered locally and never sent; if the request or its reply fails, the
+/// whole batch falls back to heuristics.
+pub async fn classify_batch(
+    config: &AppConfig,
+    limiter: &RateLimiter,
+    facts: &[FileFacts],
+) -> BatchClassification {
+    let protected: Vec<Option<Classification>> = facts
+        .iter()
+        .map(|facts| local_classification(config, facts))
+        .collect();
+    let to_send: Vec<&FileFacts> = facts
+        .iter()
+        .zip(&protected)
+        .filter(|(_, protected)| protected.is_none())
+        .map(|(facts, _)| facts)
+        .collect();
+
+    let started = Instant::now();
+    let mut invalid_replies = Vec::new();
+    let (verdicts, total_tokens, error) = if to_send.is_empty() {
+        (None, None, None)
+    } else {
+        match classify_batch_with_ai(config, limiter, &to_send).await {
+            Ok((verdicts, tokens, invalid)) => {
+                // Indices count sent files only; map them back to the batch
+                let sent_indices: Vec<usize> = protected
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, protected)| protected.is_none())
+                    .map(|(index, _)| index)
+                    .collect();
+                invalid_replies = invalid
+                    .into_iter()
+                    .map(|(index, problem)| (sent_indices[index], problem))
+                    .collect();
+                (Some(verdicts), tokens, None)
+            }
+            Err(e) => {
+                if !matches!(e, AiError::MissingApiKey(_)) {
+                    warn!(
+                        "AI classification failed for a batch of {} files, using heuristics: {}",
+                        to_send.len(),
+                        e
+                    );
+                }
+                (None, None, Some(e))
+            }
+        }
+    };
+    let latency = if to_send.is_empty() {
+        Duration::ZERO
+    } else {
+        started.elapsed()
+    };
+
+    let mut verdicts = verdicts.map(Vec::into_iter);
+    let classifications = facts
+        .iter()
+        .zip(protected)
+        .map(|(facts, protected)| match protected {
+            Some(classification) => classification,
+            None => {
+                let classification = match verdicts.as_mut().and_then(Iterator::next).flatten() {
+                    Some(classification) => classification,
+                    None => classify_heuristic(facts, language(config)),
+                };
+                let classification = adjust_for_location(classification, facts, language(config));
+                adjust_for_size(classification, facts, &config.analysis.size_weighting)
+            }
+        })
+        .collect();
+
+    BatchClassification {
+        classifications,
+        latency,
+        total_tokens,
+        error,
+        invalid_replies,
+    }
+}
+
+async fn classify_batch_with_ai(
+    config: &AppConfig,
+    limiter: &RateLimiter,
+    facts: &[&FileFacts],
+) -> Result<(Vec<Option<Classification>>, Option<u64>, Vec<(usize, String)>), AiError> {
+    let client = AiClient::from_config(&config.ai, config.analysis.ai_timeout)?;
+    let facts_json = serde_json::to_string(facts).map_err(|e| AiError::Provider(e.to_string()))?;
+    let messages = [
+        ChatMessage {
+            role: "system".to_string(),
+            content: system_prompt(BATCH_SYSTEM_PROMPT, language(config)),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: facts_json,
+        },
+    ];
+
+    let max_tokens = (BATCH_TOKENS_PER_FILE * facts.len() as u64).min(MAX_BATCH_TOKENS) as u32;
+    let completion = client.chat_completion(limiter, &messages, max_tokens).await?;
+    debug!(
+        "AI batch response for {} files: {} bytes",
+        facts.len(),
+        completion.content.len()
+    );
+    let parsed = parse_batch_verdicts(&completion.content, facts.len())
+        .inspect_err(|_| log_unparsed_reply(&completion.content, facts))?;
+    if !parsed.invalid.is_empty() {
+        warn!(
+            "AI batch reply had {} malformed entries of {}",
+            parsed.invalid.len(),
+            facts.len()
+        );
+        log_unparsed_reply(&completion.content, facts);
+    }
+
+    let classifications = parsed
+        .verdicts
+        .into_iter()
+        .map(|verdict| {
+            verdict.map(|verdict| Classification {
+                category: verdict.category,
+                confidence: verdict.confidence.clamp(0.0, 1.0),
+                reason: verdict.reason,
+                source: ClassificationSource::Ai,
+                overridden_by: None,
+            })
+        })
+        .collect();
+    Ok((classifications, completion.total_tokens, parsed.invalid))
+}
+
+/// Log a reply that failed to parse at debug level, with the paths and names
+/// of the files it describes redacted
+fn log_unparsed_reply(content: &str, facts: &[&FileFacts]) {
+    if !tracing::enabled!(tracing::Level::DEBUG) {
+        return;
+    }
+    let mut redacted = content.to_string();
+    for facts in facts {
+        for identifier in [&facts.path, &facts.file_name] {
+            if identifier.is_empty() {
+                continue;
+            }
+            let replacement = redact_identifier(identifier);
+            // The reply is JSON, so paths may appear with escaped separators
+            let escaped = serde_json::to_string(identifier).unwrap_or_default();
+            let escaped = escaped.trim_matches('"');
+            redacted = redacted
+                .replace(escaped, &replacement)
+                .replace(identifier.as_str(), &replacement);
+        }
+    }
+    debug!("Unparseable AI reply: {}", redacted);
+}
+
+/// Extract the JSON array from a batch reply. It must hold one entry per
+/// file; entries that are not valid verdicts are reported per file instead
+/// of failing the whole batch.
+fn parse_batch_verdicts(content: &str, expected: usize) -> Result<BatchVerdicts, AiError> {
+    let start = content.find('[');
+    let end = content.rfind(']');
+    let json = match (start, end) {
+        (Some(start), Some(end)) if start < end => &content[start..=end],
+        _ => {
+            return Err(AiError::Provider(
+                "Response did not contain a JSON array".to_string(),
+            ))
+        }
+    };
+
+    let entries: Vec<serde_json::Value> = serde_json::from_str(json)
+        .map_err(|e| AiError::Provider(format!("Malformed batch classification: {}", e)))?;
+    if entries.len() != expected {
+        return Err(AiError::Provider(format!(
+            "Batch reply classified {} of {} files",
+            entries.len(),
+            expected
+        )));
+    }
+
+    let mut invalid = Vec::new();
+    let verdicts = entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            serde_json::from_value(entry)
+                .inspect_err(|e| invalid.push((index, format!("Malformed classification: {}", e))))
+                .ok()
+        })
+        .collect();
+    Ok(BatchVerdicts { verdicts, invalid })
+}
+
+/// Classify a file with the configured AI provider, falling back to
+/// heuristics when no key is configured or the provider misbehaves.
+///
+/// Files matching a protected pattern are always reported as system files
+/// and broken symlinks as broken links; neither is sent to the provider.
+pub async fn classify(
+    config: &AppConfig,
+    limiter: &RateLimiter,
+    facts: &FileFacts,
+) -> Classification {
+    if let Some(classification) = local_classification(config, facts) {
+        return classification;
+    }
+
+    let classification = match classify_with_ai(config, limiter, facts).await {
+        Ok(classification) => classification,
+        Err(AiError::MissingApiKey(_)) => classify_heuristic(facts, language(config)),
+        Err(e) => {
+            warn!(
+                "AI classification failed for {}, using heuristics: {}",
+                facts.path, e
+            );
+            classify_heuristic(facts, language(config))
+        }
+    };
+
+    let classification = adjust_for_location(classification, facts, language(config));
+    adjust_for_size(classification, facts, &config.analysis.size_weighting)
+}
+
+/// Files on removable media may be the only copy (backups, camera cards) and
+/// files on network drives may be used by others, so reclaimable verdicts
+/// there are reported with reduced confidence
+fn adjust_for_location(
+    mut classification: Classification,
+    facts: &FileFacts,
+    language: &str,
+) -> Classification {
+    if !classification.category.is_reclaimable() {
+        return classification;
+    }
+
+    if facts.on_network_drive {
+        classification.confidence *= NETWORK_DRIVE_CONFIDENCE_FACTOR;
+        let note = messages::text(Message::OnNetworkDrive, language);
+        classification.reason = format!("{} ({})", classification.reason, note);
+    } else if facts.on_removable_media {
+        classification.confidence *= REMOVABLE_MEDIA_CONFIDENCE_FACTOR;
+        let note = messages::text(Message::OnRemovableMedia, language);
+        classification.reason = format!("{} ({})", classification.reason, note);
+    }
+    classification
+}
+
+/// Conservative weighting for large files: more confidence that big, stale
+/// temporary and cache files can go, less that big files in the user's own
+/// folders can, whatever the classifier said. Only the confidence changes.
+fn adjust_for_size(
+    mut classification: Classification,
+    facts: &FileFacts,
+    weighting: &SizeWeighting,
+) -> Classification {
+    if !weighting.enabled || facts.size < weighting.large_file_bytes {
+        return classification;
+    }
+
+    let stale = facts.modified.is_some_and(|modified| {
+        (Utc::now() - modified).num_days() >= weighting.old_file_days as i64
+    });
+    let is_cache = matches!(
+        classification.category,
+        FileCategory::Temporary | FileCategory::Cache | FileCategory::DevCache
+    );
+    if classification.category.is_reclaimable() && in_user_folder(&facts.path) {
+        classification.confidence *= weighting.user_folder_damping.clamp(0.0, 1.0);
+    } else if is_cache && stale {
+        classification.confidence =
+            (classification.confidence * weighting.stale_cache_boost.max(1.0)).min(1.0);
+    }
+    classification
+}
+
+fn in_container_data(path: &str) -> bool {
+    let Some(home) = home::home_dir() else {
+        return false;
+    };
+    let path = Path::new(path);
+    container_data_dirs(&home)
+        .iter()
+        .any(|dir| path.starts_with(dir))
+}
+
+fn in_user_folder(path: &str) -> bool {
+    let Some(home) = home::home_dir() else {
+        return false;
+    };
+    let path = Path::new(path);
+    USER_FOLDERS
+        .iter()
+        .any(|folder| path.starts_with(home.join(folder)))
+}
+
+async fn classify_with_ai(
+    config: &AppConfig,
+    limiter: &RateLimiter,
+    facts: &FileFacts,
+) -> Result<Classification, AiError> {
+    let client = AiClient::from_config(&config.ai, config.analysis.ai_timeout)?;
+    let facts_json = serde_json::to_string(facts).map_err(|e| AiError::Provider(e.to_string()))?;
+    let messages = [
+        ChatMessage {
+            role: "system".to_string(),
+            content: system_prompt(SYSTEM_PROMPT, language(config)),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: facts_json,
+        },
+    ];
+
+    let completion = client.chat_completion(limiter, &messages, 200).await?;
+    debug!(
+        "AI classification response for {}: {}",
+        facts.path, completion.content
+    );
+    let verdict = parse_verdict(&completion.content)
+        .inspect_err(|_| log_unparsed_reply(&completion.content, &[facts]))?;
+
+    Ok(Classification {
+        category: verdict.category,
+        confidence: verdict.confidence.clamp(0.0, 1.0),
+        reason: verdict.reason,
+        source: ClassificationSource::Ai,
+        overridden_by: None,
+    })
+}
+
+/// Extract the JSON object from a reply, tolerating surrounding prose or code fences
+fn parse_verdict(content: &str) -> Result<AiVerdict, AiError> {
+    let start = content.find('{');
+    let end = content.rfind('}');
+    let json = match (start, end) {
+        (Some(start), Some(end)) if start < end => &content[start..=end],
+        _ => {
+            return Err(AiError::Provider(
+                "Response did not contain a JSON object".to_string(),
+            ))
+        }
+    };
+
+    serde_json::from_str(json)
+        .map_err(|e| AiError::Provider(format!("Malformed classification: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offline_fallback_is_weighted_for_location_and_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = AppConfig::default();
+        let cache = AnalysisCache::load(dir.path());
+        let language = language(&config);
+
+        let mut facts = FileFacts::new(&dir.path().join("download.tmp"), 1024, None);
+        facts.on_network_drive = true;
+        let plain = classify_heuristic(&facts, language);
+        let offline = classify_offline(&config, &cache, &facts);
+        assert_eq!(offline.category, plain.category);
+        assert!(offline.category.is_reclaimable());
+        let expected = plain.confidence * NETWORK_DRIVE_CONFIDENCE_FACTOR;
+        assert!((offline.confidence - expected).abs() < 1e-6);
+        assert!(offline.reason.contains(messages::text(Message::OnNetworkDrive, language)));
+
+        let weighting = &config.analysis.size_weighting;
+        let stale = Utc::now() - chrono::Duration::days(weighting.old_file_days as i64 + 1);
+        let facts = FileFacts::new(
+            &dir.path().join("old.tmp"),
+            weighting.large_file_bytes,
+            Some(stale),
+        );
+        let plain = classify_heuristic(&facts, language);
+        let offline = classify_offline(&config, &cache, &facts);
+        assert!(offline.confidence > plain.confidence);
+    }
+
+    #[test]
+    fn docker_data_is_never_reclaimable() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = AppConfig::default();
+        let cache = AnalysisCache::load(dir.path());
+        let home = home::home_dir().unwrap();
+
+        for data_dir in container_data_dirs(&home) {
+            let layer = data_dir.join("overlay2").join("3f2a").join("diff").join("app.cache");
+            let classification =
+                classify_offline(&config, &cache, &FileFacts::new(&layer, 1 << 30, None));
+            assert_eq!(classification.category, FileCategory::System);
+            assert!(!classification.category.is_reclaimable());
+        }
+
+        let data_dirs = container_data_dirs(&home);
+        let known = crate::utils::dev_caches::known_locations(&home);
+        assert!(!known
+            .iter()
+            .any(|location| data_dirs.iter().any(|dir| location.path.starts_with(dir))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dangling_symlinks_are_broken_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = AppConfig::default();
+        let cache = AnalysisCache::load(dir.path());
+        let target = dir.path().join("report.pdf");
+        let link = dir.path().join("report-link.pdf");
+        fs::write(&target, b"x").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        let facts = FileFacts::from_path(&link).unwrap();
+        assert!(!facts.is_broken_symlink);
+        assert_ne!(classify_offline(&config, &cache, &facts).category, FileCategory::BrokenLink);
+
+        fs::remove_file(&target).unwrap();
+        let facts = FileFacts::from_path(&link).unwrap();
+        assert!(facts.is_broken_symlink);
+        let classification = classify_offline(&config, &cache, &facts);
+        assert_eq!(classification.category, FileCategory::BrokenLink);
+        assert!(classification.category.is_reclaimable());
+    }
+}
@@ -1,8 +1,25 @@
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::utils::browser_caches::is_plain_subfolder;
+use crate::utils::messages;
+use crate::utils::paths;
+
+/// Attempts at replacing the config file; antivirus and indexers on Windows
+/// can briefly hold it open and make the rename fail
+const SAVE_RENAME_ATTEMPTS: u32 = 3;
+const SAVE_RENAME_RETRY_DELAY: Duration = Duration::from_millis(50);
 
 /// Application configuration structure
+///
+/// Every section defaults missing fields so config files written by older
+/// versions keep loading as new settings are added.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppConfig {
     pub max_file_size: u64,
     pub default_timeout: u64,
@@ -11,28 +28,311 @@ pub struct AppConfig {
     pub cache_directory: PathBuf,
     pub temp_directory: PathBuf,
     pub enable_notifications: bool,
+    pub redact_identifiers: bool,
     pub theme: ThemePreference,
+    /// Open the directory picker where the user last picked a folder instead
+    /// of in the home directory
+    pub remember_last_directory: bool,
+    /// Folder most recently chosen in the directory picker
+    pub last_directory: Option<PathBuf>,
+    /// Format `save` writes the config file in
+    pub config_format: ConfigFormat,
     pub analysis: AnalysisConfig,
     pub security: SecurityConfig,
+    pub ai: AiConfig,
+    pub notifications: NotificationSettings,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AnalysisConfig {
+    /// Files sent to the AI provider per request; the upper bound when
+    /// `adaptive_batch_size` is on
     pub batch_size: usize,
+    /// Resize batches from measured latency and token usage so each request
+    /// stays well inside `ai_timeout`
+    pub adaptive_batch_size: bool,
     pub parallel_processing: bool,
     pub ai_timeout: u64,
     pub max_concurrent_requests: usize,
     pub enable_caching: bool,
+    /// How long cached AI classifications are reused
     pub cache_ttl_seconds: u64,
+    /// How long a cached directory listing is trusted while its mtime is
+    /// unchanged. Kept separate from `cache_ttl_seconds` and much shorter,
+    /// since files growing in place leave the directory mtime alone.
+    pub size_cache_ttl_seconds: u64,
+    pub hash_chunk_size: usize,
+    pub max_inflight_bytes: u64,
+    pub snapshot_retention: usize,
+    /// Extra package-manager cache directories checked by `find_dev_caches`;
+    /// a leading `~` expands to the home directory
+    pub extra_dev_cache_paths: Vec<String>,
+    /// Single files in app and browser caches at least this large are
+    /// reported by `find_dev_caches` on their own; 0 turns this off
+    pub large_cache_file_bytes: u64,
+    /// Browser cache layouts checked by `find_browser_caches` besides the
+    /// built-in Chrome, Edge, Firefox and Safari ones
+    pub extra_browser_caches: Vec<BrowserCacheSpec>,
+    /// File names or globs of zero-byte files `find_empty_files` leaves
+    /// alone because their presence is the point, e.g. `.gitkeep`
+    pub empty_file_markers: Vec<String>,
+    /// Classifications at or above this confidence count as high-confidence
+    pub high_confidence_threshold: f32,
+    /// Language tag, e.g. `de` or `pt-BR`, that classification reasons are
+    /// requested in; heuristic reasons are translated for en, de, fr and es
+    pub response_language: String,
+    pub size_weighting: SizeWeighting,
+}
+
+/// Where a browser keeps its caches. Only `cache_dirs` are ever cleaned;
+/// cookies, history and the rest of a profile are left alone.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrowserCacheSpec {
+    pub browser: String,
+    /// Folder holding the profiles, or the cache itself when `per_profile`
+    /// is off; a leading `~` expands to the home directory
+    pub root: String,
+    /// Whether `root` holds one subfolder per profile
+    #[serde(default)]
+    pub per_profile: bool,
+    /// Cache folders relative to each profile, or to `root`
+    pub cache_dirs: Vec<String>,
+}
+
+/// Confidence adjustments for large files, applied after classification.
+///
+/// These only scale the confidence of a verdict; the category is never
+/// changed, so a file the classifier wants kept is never turned into a
+/// removal candidate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SizeWeighting {
+    pub enabled: bool,
+    /// Files at least this large are weighted
+    pub large_file_bytes: u64,
+    /// Temporary and cache files not modified for this many days count as old
+    pub old_file_days: u64,
+    /// Multiplier (at least 1) for large, old temporary and cache files
+    pub stale_cache_boost: f32,
+    /// Multiplier (0 to 1) for large reclaimable verdicts in the user's
+    /// document, desktop and media folders
+    pub user_folder_damping: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AiConfig {
+    pub provider: String,
+    pub base_url: String,
+    pub model: String,
+    pub requests_per_minute: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SecurityConfig {
     pub allow_system_directories: bool,
     pub require_confirmation: bool,
+    /// With `require_confirmation`, only prompt for deletes larger than this
+    /// many bytes (or `confirm_above_count` entries); with neither set,
+    /// every delete prompts
+    pub confirm_above_bytes: Option<u64>,
+    pub confirm_above_count: Option<u64>,
     pub enable_audit_trail: bool,
     pub backup_before_delete: bool,
+    /// Refuse backed-up deletes unless the most recent backup verifies intact
+    pub require_backup_verification: bool,
+    /// Backup sessions `clear_app_data` keeps, newest first; at least one
+    pub backup_retention: usize,
+    pub exclude_locked_files: bool,
     pub protected_patterns: Vec<String>,
+    /// Overwrite passes made before a secure-wipe deletion removes a file
+    pub secure_wipe_passes: u32,
+    /// Recent `validate_path_safety` results kept in memory; 0 disables caching
+    pub validation_cache_size: usize,
+    /// Filesystem types no recursive scan enters, e.g. `nfs`, `exfat`, or the
+    /// aliases `network`, `removable` and `readonly`
+    pub excluded_filesystem_types: Vec<String>,
+    /// Folders every scan leaves out unless told to ignore them, matched as
+    /// prefixes of the canonical path
+    pub global_exclusions: Vec<String>,
+    /// What a delete does when another is already running on the same volume
+    pub volume_delete_policy: VolumeDeletePolicy,
+}
+
+/// Why a delete needs the user's explicit confirmation
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum ConfirmationTrigger {
+    /// `require_confirmation` is on and no thresholds are configured
+    Always,
+    /// More bytes than `confirm_above_bytes`
+    Bytes { limit: u64, planned: u64 },
+    /// More entries than `confirm_above_count`
+    Count { limit: u64, planned: u64 },
+    /// Secure wipes cannot be undone and always prompt
+    SecureWipe,
+}
+
+impl SecurityConfig {
+    /// What makes a delete of `count` entries totalling `bytes` need
+    /// confirmation; empty when it may proceed without a prompt
+    pub fn confirmation_triggers(
+        &self,
+        count: u64,
+        bytes: u64,
+        secure_wipe: bool,
+    ) -> Vec<ConfirmationTrigger> {
+        let mut triggers = Vec::new();
+        if secure_wipe {
+            triggers.push(ConfirmationTrigger::SecureWipe);
+        }
+        if !self.require_confirmation {
+            return triggers;
+        }
+
+        match (self.confirm_above_bytes, self.confirm_above_count) {
+            (None, None) => triggers.push(ConfirmationTrigger::Always),
+            (bytes_limit, count_limit) => {
+                if let Some(limit) = bytes_limit.filter(|&limit| bytes > limit) {
+                    triggers.push(ConfirmationTrigger::Bytes { limit, planned: bytes });
+                }
+                if let Some(limit) = count_limit.filter(|&limit| count > limit) {
+                    triggers.push(ConfirmationTrigger::Count { limit, planned: count });
+                }
+            }
+        }
+        triggers
+    }
+
+    /// `global_exclusions` resolved to canonical paths; entries that do not
+    /// exist right now are kept as written
+    pub fn global_exclusion_paths(&self) -> Vec<PathBuf> {
+        self.global_exclusions
+            .iter()
+            .map(|path| fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path)))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum VolumeDeletePolicy {
+    /// Fail with an error naming the running operation
+    #[default]
+    Reject,
+    /// Wait for the running delete to finish, then start
+    Queue,
+    /// Let deletes on the same volume run side by side
+    Concurrent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    /// Minimum number of seconds between two delivered notifications;
+    /// 0 delivers every one
+    pub min_interval_seconds: u64,
+    pub quiet_hours: Option<QuietHours>,
+    pub quiet_hours_behavior: QuietHoursBehavior,
+}
+
+/// Local-time window during which notifications are held back.
+/// Times use `HH:MM`; a window whose end precedes its start spans midnight.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: String,
+    pub end: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum QuietHoursBehavior {
+    /// Hold notifications and deliver them once quiet hours end
+    Queue,
+    /// Discard notifications raised during quiet hours
+    Drop,
+}
+
+/// Upper bound accepted for a per-run `max_concurrent_requests` override
+pub const MAX_CONCURRENCY_OVERRIDE: usize = 64;
+/// Upper bound accepted for a per-run `ai_timeout` override, in seconds
+pub const MAX_AI_TIMEOUT_OVERRIDE: u64 = 600;
+
+/// Per-invocation overrides of analysis limits, e.g. for a gentler run on
+/// battery. Omitted fields keep the configured values and the stored config
+/// is never modified.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct RunOverrides {
+    pub max_concurrent_requests: Option<usize>,
+    pub ai_timeout: Option<u64>,
+}
+
+impl RunOverrides {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(limit) = self.max_concurrent_requests {
+            if !(1..=MAX_CONCURRENCY_OVERRIDE).contains(&limit) {
+                anyhow::bail!(
+                    "max_concurrent_requests override must be between 1 and {}",
+                    MAX_CONCURRENCY_OVERRIDE
+                );
+            }
+        }
+
+        if let Some(timeout) = self.ai_timeout {
+            if !(1..=MAX_AI_TIMEOUT_OVERRIDE).contains(&timeout) {
+                anyhow::bail!(
+                    "ai_timeout override must be between 1 and {} seconds",
+                    MAX_AI_TIMEOUT_OVERRIDE
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validate the overrides and apply them to a copy of the analysis settings
+    pub fn apply(&self, analysis: &AnalysisConfig) -> anyhow::Result<AnalysisConfig> {
+        self.validate()?;
+
+        let mut analysis = analysis.clone();
+        if let Some(limit) = self.max_concurrent_requests {
+            analysis.max_concurrent_requests = limit;
+        }
+        if let Some(timeout) = self.ai_timeout {
+            analysis.ai_timeout = timeout;
+        }
+        Ok(analysis)
+    }
+}
+
+/// Provider settings replacing the configured ones for a single run, e.g.
+/// retrying uncertain files with a more capable model. The key for
+/// `provider` must already be stored in the keychain.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AiOverride {
+    pub provider: Option<String>,
+    pub base_url: Option<String>,
+    pub model: Option<String>,
+}
+
+impl AiOverride {
+    pub fn apply(&self, ai: &AiConfig) -> AiConfig {
+        let mut ai = ai.clone();
+        if let Some(provider) = &self.provider {
+            ai.provider = provider.clone();
+        }
+        if let Some(base_url) = &self.base_url {
+            ai.base_url = base_url.clone();
+        }
+        if let Some(model) = &self.model {
+            ai.model = model.clone();
+        }
+        ai
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,22 +342,90 @@ pub enum ThemePreference {
     System,
 }
 
+/// File format of the config file; both hold exactly the same settings
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigFormat {
+    #[default]
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Toml => "toml",
+            Self::Json => "json",
+        }
+    }
+
+    fn other(self) -> Self {
+        match self {
+            Self::Toml => Self::Json,
+            Self::Json => Self::Toml,
+        }
+    }
+
+    /// Format of the config file at `path`, from its extension or, failing
+    /// that, from whether `contents` looks like a JSON object
+    pub fn detect(path: &Path, contents: &str) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => Self::Toml,
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::Json,
+            _ if contents.trim_start().starts_with('{') => Self::Json,
+            _ => Self::Toml,
+        }
+    }
+
+    /// Parse a config written in this format. Files that do not name a
+    /// format keep the one they were written in, so existing JSON configs
+    /// are not converted behind the user's back.
+    fn parse(self, contents: &str) -> anyhow::Result<AppConfig> {
+        let (mut config, names_format): (AppConfig, bool) = match self {
+            Self::Toml => {
+                let table: toml::Table = toml::from_str(contents)?;
+                let names_format = table.contains_key("config_format");
+                (table.try_into()?, names_format)
+            }
+            Self::Json => {
+                let value: serde_json::Value = serde_json::from_str(contents)?;
+                let names_format = value.get("config_format").is_some();
+                (serde_json::from_value(value)?, names_format)
+            }
+        };
+        if !names_format {
+            config.config_format = self;
+        }
+        Ok(config)
+    }
+
+    fn render(self, config: &AppConfig) -> anyhow::Result<String> {
+        Ok(match self {
+            Self::Toml => toml::to_string_pretty(config)?,
+            Self::Json => serde_json::to_string_pretty(config)?,
+        })
+    }
+}
+
 impl Default for AppConfig {
     fn default() -> Self {
-        let cache_dir = std::env::temp_dir().join("ai-disk-cleaner-cache");
-        let temp_dir = std::env::temp_dir().join("ai-disk-cleaner-temp");
-
         Self {
             max_file_size: 1_000_000_000, // 1GB
             default_timeout: 30, // 30 seconds
             enable_logging: true,
             log_level: "info".to_string(),
-            cache_directory: cache_dir,
-            temp_directory: temp_dir,
+            cache_directory: paths::default_cache_dir(),
+            temp_directory: paths::default_temp_dir(),
             enable_notifications: true,
+            redact_identifiers: false,
             theme: ThemePreference::System,
+            remember_last_directory: true,
+            last_directory: None,
+            config_format: ConfigFormat::default(),
             analysis: AnalysisConfig::default(),
             security: SecurityConfig::default(),
+            ai: AiConfig::default(),
+            notifications: NotificationSettings::default(),
         }
     }
 }
@@ -66,11 +434,56 @@ impl Default for AnalysisConfig {
     fn default() -> Self {
         Self {
             batch_size: 1000,
+            adaptive_batch_size: false,
             parallel_processing: true,
             ai_timeout: 60, // 60 seconds
             max_concurrent_requests: 5,
             enable_caching: true,
             cache_ttl_seconds: 3600, // 1 hour
+            size_cache_ttl_seconds: 300, // 5 minutes
+            hash_chunk_size: 1024 * 1024, // 1MB
+            max_inflight_bytes: 64 * 1024 * 1024, // 64MB
+            snapshot_retention: 10,
+            extra_dev_cache_paths: Vec::new(),
+            large_cache_file_bytes: 100 * 1024 * 1024, // 100MB
+            extra_browser_caches: Vec::new(),
+            empty_file_markers: default_empty_file_markers(),
+            high_confidence_threshold: 0.8,
+            response_language: messages::DEFAULT_LANGUAGE.to_string(),
+            size_weighting: SizeWeighting::default(),
+        }
+    }
+}
+
+impl Default for SizeWeighting {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            large_file_bytes: 500 * 1024 * 1024, // 500MB
+            old_file_days: 90,
+            stale_cache_boost: 1.15,
+            user_folder_damping: 0.6,
+        }
+    }
+}
+
+impl Default for AiConfig {
+    fn default() -> Self {
+        Self {
+            provider: "openai".to_string(),
+            base_url: "https://api.openai.com/v1".to_string(),
+            model: "gpt-4".to_string(),
+            requests_per_minute: 60,
+        }
+    }
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            min_interval_seconds: 0,
+            quiet_hours: None,
+            quiet_hours_behavior: QuietHoursBehavior::Queue,
         }
     }
 }
@@ -80,47 +493,428 @@ impl Default for SecurityConfig {
         Self {
             allow_system_directories: false,
             require_confirmation: true,
+            confirm_above_bytes: None,
+            confirm_above_count: None,
             enable_audit_trail: true,
             backup_before_delete: true,
-            protected_patterns: vec![
-                "*.exe".to_string(),
-                "*.dll".to_string(),
-                "*.sys".to_string(),
-                "*.app".to_string(),
-            ],
+            require_backup_verification: false,
+            backup_retention: 10,
+            exclude_locked_files: true,
+            protected_patterns: default_protected_patterns(),
+            secure_wipe_passes: 3,
+            validation_cache_size: 256,
+            excluded_filesystem_types: Vec::new(),
+            global_exclusions: Vec::new(),
+            volume_delete_policy: VolumeDeletePolicy::default(),
         }
     }
 }
 
+/// Zero-byte files that mark a folder or package rather than being leftovers
+pub fn default_empty_file_markers() -> Vec<String> {
+    [
+        ".keep",
+        ".gitkeep",
+        ".npmignore",
+        ".nomedia",
+        "__init__.py",
+        "py.typed",
+        "*.lock",
+        "*.pid",
+    ]
+    .iter()
+    .map(|marker| marker.to_string())
+    .collect()
+}
+
+/// Protected patterns appropriate for the platform the app is running on
+pub fn default_protected_patterns() -> Vec<String> {
+    let patterns: &[&str] = if cfg!(target_os = "windows") {
+        &["*.exe", "*.dll", "*.sys", "*.msi", "*.drv"]
+    } else if cfg!(target_os = "macos") {
+        &["*.app", "*.dylib", "*.kext", "*.framework", "*.plist"]
+    } else {
+        &[
+            "*.so",
+            "*.so.*",
+            "*.ko",
+            "/var/lib/dpkg/*",
+            "/var/lib/apt/*",
+            "/var/lib/rpm/*",
+            "/var/lib/pacman/*",
+            "/var/lib/flatpak/*",
+            "/snap/*",
+            "/nix/store/*",
+        ]
+    };
+
+    patterns.iter().map(|p| p.to_string()).collect()
+}
+
 impl AppConfig {
-    /// Load configuration from file or create default
+    /// Load configuration from file or create default. Either `config.toml`
+    /// or `config.json` is read, whichever exists; new installs get TOML.
     pub fn load_or_create() -> Self {
-        // For now, return default config
-        // In production, this would load from a config file
-        Self::default()
+        let path = paths::config_file();
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => Self::parse_or_backup(&path, &contents),
+            Err(_) => {
+                let config = Self::default();
+                if let Err(e) = config.save() {
+                    warn!("Failed to write default configuration: {}", e);
+                }
+                config
+            }
+        }
+    }
+
+    /// Parse `contents` read from `path`, falling back to the backup kept by
+    /// `save` and then to the defaults when it is invalid
+    fn parse_or_backup(path: &Path, contents: &str) -> Self {
+        match ConfigFormat::detect(path, contents).parse(contents) {
+            Ok(config) => {
+                info!("Loaded configuration from {}", path.display());
+                config
+            }
+            Err(e) => {
+                warn!("Invalid configuration at {}: {}", path.display(), e);
+                Self::load_backup(path).unwrap_or_default()
+            }
+        }
+    }
+
+    /// The previous version kept by `save`, if it exists and parses
+    fn load_backup(path: &Path) -> Option<Self> {
+        let backup = backup_path(path);
+        let contents = fs::read_to_string(&backup).ok()?;
+        let config = ConfigFormat::detect(path, &contents).parse(&contents).ok()?;
+        info!("Loaded previous configuration from {}", backup.display());
+        Some(config)
     }
 
-    /// Save configuration to file
+    /// Save configuration to file.
+    ///
+    /// The new contents are written and synced to a temp file in the same
+    /// directory and then renamed over the config, so a crash at any point
+    /// leaves either the old or the new file, never a partial one. The
+    /// replaced version is kept alongside as a backup. The file is written
+    /// in `config_format`; a config left in the other format is moved to its
+    /// backup path so only one is ever loaded.
+    ///
+    /// This blocks, retrying a failed rename after a short sleep, so async
+    /// code must call it through `spawn_blocking`.
     pub fn save(&self) -> anyhow::Result<()> {
-        // For now, this is a no-op
-        // In production, this would save to a config file
+        let format = self.config_format;
+        self.save_to(&paths::config_file_for(format), &paths::config_file_for(format.other()))
+    }
+
+    /// `save` to `path`, moving a config at `stale` aside
+    fn save_to(&self, path: &Path, stale: &Path) -> anyhow::Result<()> {
+        let format = self.config_format;
+        let parent = path
+            .parent()
+            .ok_or_else(|| anyhow::anyhow!("Config path has no parent directory"))?;
+        fs::create_dir_all(parent)?;
+
+        let temp = path.with_extension(format!("{}.tmp", format.extension()));
+        {
+            let mut file = fs::File::create(&temp)?;
+            file.write_all(format.render(self)?.as_bytes())?;
+            file.sync_all()?;
+        }
+
+        if path.exists() {
+            if let Err(e) = fs::copy(path, backup_path(path)) {
+                warn!("Failed to back up previous configuration: {}", e);
+            }
+        }
+
+        let mut attempt = 1;
+        while let Err(e) = fs::rename(&temp, path) {
+            if attempt >= SAVE_RENAME_ATTEMPTS {
+                let _ = fs::remove_file(&temp);
+                return Err(e.into());
+            }
+            attempt += 1;
+            std::thread::sleep(SAVE_RENAME_RETRY_DELAY);
+        }
+
+        if stale.exists() {
+            match fs::rename(stale, backup_path(stale)) {
+                Ok(()) => {
+                    info!("Config saved as {}; moved {} aside", path.display(), stale.display())
+                }
+                Err(e) => warn!("Failed to move aside {}: {}", stale.display(), e),
+            }
+        }
+
+        // Persist the rename itself; directories can't be opened for this on Windows
+        #[cfg(unix)]
+        if let Err(e) = fs::File::open(parent).and_then(|dir| dir.sync_all()) {
+            warn!("Failed to sync config directory: {}", e);
+        }
+
         Ok(())
     }
 
-    /// Validate configuration settings
+    /// Validate configuration settings, failing on the first error
     pub fn validate(&self) -> anyhow::Result<()> {
-        if self.max_file_size == 0 {
-            anyhow::bail!("max_file_size must be greater than 0");
+        match self
+            .validate_all()
+            .into_iter()
+            .find(|issue| issue.severity == IssueSeverity::Error)
+        {
+            Some(issue) => anyhow::bail!("{} {}", issue.field, issue.message),
+            None => Ok(()),
         }
+    }
+
+    /// Check every setting and report all problems, keyed by field path, so a
+    /// settings UI can show each one next to its field
+    pub fn validate_all(&self) -> Vec<ConfigValidationIssue> {
+        let mut issues = Vec::new();
+        let mut error = |field: &str, message: String| {
+            issues.push(ConfigValidationIssue::new(field, message, IssueSeverity::Error));
+        };
 
+        if self.max_file_size == 0 {
+            error("max_file_size", "must be greater than 0".to_string());
+        }
         if self.default_timeout == 0 {
-            anyhow::bail!("default_timeout must be greater than 0");
+            error("default_timeout", "must be greater than 0".to_string());
+        }
+        if !LOG_LEVELS.contains(&self.log_level.to_ascii_lowercase().as_str()) {
+            error(
+                "log_level",
+                format!("must be one of {}, got '{}'", LOG_LEVELS.join(", "), self.log_level),
+            );
         }
-
         if self.analysis.batch_size == 0 {
-            anyhow::bail!("analysis.batch_size must be greater than 0");
+            error("analysis.batch_size", "must be greater than 0".to_string());
+        }
+        if self.analysis.ai_timeout == 0 {
+            error("analysis.ai_timeout", "must be greater than 0".to_string());
+        }
+        if self.analysis.max_concurrent_requests == 0 {
+            error("analysis.max_concurrent_requests", "must be greater than 0".to_string());
+        }
+        for (i, spec) in self.analysis.extra_browser_caches.iter().enumerate() {
+            if spec.cache_dirs.is_empty() {
+                error(
+                    &format!("analysis.extra_browser_caches[{}].cache_dirs", i),
+                    "must list at least one cache folder".to_string(),
+                );
+            }
+            if let Some(dir) = spec.cache_dirs.iter().find(|dir| !is_plain_subfolder(dir)) {
+                error(
+                    &format!("analysis.extra_browser_caches[{}].cache_dirs", i),
+                    format!("'{}' must be a relative path without '..'", dir),
+                );
+            }
+        }
+        if self.analysis.hash_chunk_size == 0 {
+            error("analysis.hash_chunk_size", "must be greater than 0".to_string());
+        }
+        if self.analysis.max_inflight_bytes < self.analysis.hash_chunk_size as u64 {
+            error(
+                "analysis.max_inflight_bytes",
+                "must be at least analysis.hash_chunk_size".to_string(),
+            );
+        }
+        if !(0.0..=1.0).contains(&self.analysis.high_confidence_threshold) {
+            error("analysis.high_confidence_threshold", "must be between 0 and 1".to_string());
+        }
+        if self.analysis.response_language.trim().is_empty() {
+            error("analysis.response_language", "must not be empty".to_string());
+        }
+        if self.analysis.size_weighting.stale_cache_boost < 1.0 {
+            error("analysis.size_weighting.stale_cache_boost", "must be at least 1".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.analysis.size_weighting.user_folder_damping) {
+            error(
+                "analysis.size_weighting.user_folder_damping",
+                "must be between 0 and 1".to_string(),
+            );
+        }
+        if self.security.secure_wipe_passes == 0 {
+            error("security.secure_wipe_passes", "must be greater than 0".to_string());
+        }
+        for marker in &self.analysis.empty_file_markers {
+            if let Err(e) = globset::Glob::new(marker) {
+                error(
+                    "analysis.empty_file_markers",
+                    format!("'{}' is not a valid pattern: {}", marker, e),
+                );
+            }
+        }
+        for path in &self.security.global_exclusions {
+            if !Path::new(path).is_absolute() {
+                error(
+                    "security.global_exclusions",
+                    format!("'{}' must be an absolute path", path),
+                );
+            }
+        }
+        if self.ai.requests_per_minute == 0 {
+            error("ai.requests_per_minute", "must be greater than 0".to_string());
+        }
+        if let Some(quiet_hours) = &self.notifications.quiet_hours {
+            for (field, time) in [
+                ("notifications.quiet_hours.start", &quiet_hours.start),
+                ("notifications.quiet_hours.end", &quiet_hours.end),
+            ] {
+                if chrono::NaiveTime::parse_from_str(time, "%H:%M").is_err() {
+                    error(field, format!("must use HH:MM, got '{}'", time));
+                }
+            }
         }
 
-        Ok(())
+        let mut warning = |field: &str, message: String| {
+            issues.push(ConfigValidationIssue::new(field, message, IssueSeverity::Warning));
+        };
+        if self.analysis.ai_timeout > MAX_AI_TIMEOUT_OVERRIDE {
+            warning(
+                "analysis.ai_timeout",
+                format!("is unusually long; more than {} seconds", MAX_AI_TIMEOUT_OVERRIDE),
+            );
+        }
+        if self.analysis.max_concurrent_requests > MAX_CONCURRENCY_OVERRIDE {
+            warning(
+                "analysis.max_concurrent_requests",
+                format!("is unusually high; more than {}", MAX_CONCURRENCY_OVERRIDE),
+            );
+        }
+        if !self.analysis.response_language.trim().is_empty()
+            && !messages::is_supported(&self.analysis.response_language)
+        {
+            warning(
+                "analysis.response_language",
+                format!(
+                    "'{}' is only passed to the AI provider; heuristic reasons stay in English",
+                    self.analysis.response_language
+                ),
+            );
+        }
+        if self.analysis.snapshot_retention == 0 {
+            warning(
+                "analysis.snapshot_retention",
+                "is 0; the most recent scan is still kept".to_string(),
+            );
+        }
+
+        for (field, dir) in [
+            ("cache_directory", &self.cache_directory),
+            ("temp_directory", &self.temp_directory),
+        ] {
+            if let Some(issue) = directory_issue(field, dir) {
+                issues.push(issue);
+            }
+        }
+
+        issues
+    }
+}
+
+/// Log levels accepted by `log_level`
+const LOG_LEVELS: &[&str] = &["trace", "debug", "info", "warn", "error"];
+
+/// How serious a configuration problem is
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueSeverity {
+    /// The setting is invalid and must be fixed
+    Error,
+    /// The setting works but is probably not what the user wants
+    Warning,
+}
+
+/// A problem with one configuration field
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigValidationIssue {
+    /// Dotted path of the field, e.g. `analysis.batch_size`
+    pub field: String,
+    pub message: String,
+    pub severity: IssueSeverity,
+}
+
+impl ConfigValidationIssue {
+    fn new(field: &str, message: String, severity: IssueSeverity) -> Self {
+        Self {
+            field: field.to_string(),
+            message,
+            severity,
+        }
+    }
+}
+
+/// Check that a configured directory is usable; a missing one is only a
+/// warning because it is created on first use
+fn directory_issue(field: &str, dir: &Path) -> Option<ConfigValidationIssue> {
+    let issue = |message: &str, severity| {
+        Some(ConfigValidationIssue::new(field, message.to_string(), severity))
+    };
+
+    match std::fs::metadata(dir) {
+        Err(_) => issue("does not exist yet and will be created", IssueSeverity::Warning),
+        Ok(metadata) if !metadata.is_dir() => issue("is not a directory", IssueSeverity::Error),
+        Ok(_) => {
+            let probe = dir.join(".write_test");
+            if std::fs::write(&probe, "").is_ok() {
+                let _ = std::fs::remove_file(&probe);
+                None
+            } else {
+                issue("is not writable", IssueSeverity::Error)
+            }
+        }
+    }
+}
+
+/// Where `AppConfig::save` keeps the previous version of the config file
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".bak");
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_at(dir: &Path, last_directory: &str) -> AppConfig {
+        AppConfig {
+            last_directory: Some(dir.join(last_directory)),
+            ..AppConfig::default()
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn interrupted_save_keeps_the_previous_config_readable() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let stale = dir.path().join("config.json");
+        let first = config_at(dir.path(), "first");
+        first.save_to(&path, &stale).unwrap();
+
+        // The temp file cannot be written, so the save stops before the rename
+        fs::create_dir(path.with_extension("toml.tmp")).unwrap();
+        assert!(config_at(dir.path(), "second").save_to(&path, &stale).is_err());
+        let loaded = AppConfig::parse_or_backup(&path, &fs::read_to_string(&path).unwrap());
+        assert_eq!(loaded.last_directory, first.last_directory);
+    }
+
+    #[test]
+    fn torn_config_falls_back_to_the_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let stale = dir.path().join("config.json");
+        let first = config_at(dir.path(), "first");
+        first.save_to(&path, &stale).unwrap();
+        config_at(dir.path(), "second").save_to(&path, &stale).unwrap();
+
+        // A write cut short outside `save`, e.g. by a full disk or an editor
+        fs::write(&path, "[secur").unwrap();
+        let loaded = AppConfig::parse_or_backup(&path, &fs::read_to_string(&path).unwrap());
+        assert_eq!(loaded.last_directory, first.last_directory);
+    }
+}
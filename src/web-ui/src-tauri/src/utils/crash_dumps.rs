@@ -0,0 +1,73 @@
+use std::path::{Path, PathBuf};
+
+/// Extensions of crash dumps and crash reports wherever they are found
+const DUMP_EXTENSIONS: &[&str] = &[
+    "dmp", "mdmp", "hdmp", "crash", "ips", "panic", "spin", "hang", "core",
+];
+
+/// Folders where the OS or its crash reporter collects dumps; everything
+/// inside them is a dump or report. Entries may also be single files.
+pub fn locations(home: &Path) -> Vec<PathBuf> {
+    if cfg!(target_os = "windows") {
+        let windows = std::env::var_os("SystemRoot")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("C:\\Windows"));
+        let program_data = std::env::var_os("ProgramData")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("C:\\ProgramData"));
+        let local = home.join("AppData").join("Local");
+        let wer = |base: &Path| base.join("Microsoft").join("Windows").join("WER");
+        vec![
+            local.join("CrashDumps"),
+            wer(&local).join("ReportArchive"),
+            wer(&local).join("ReportQueue"),
+            wer(&program_data).join("ReportArchive"),
+            wer(&program_data).join("ReportQueue"),
+            windows.join("Minidump"),
+            windows.join("MEMORY.DMP"),
+        ]
+    } else if cfg!(target_os = "macos") {
+        vec![
+            home.join("Library").join("Logs").join("DiagnosticReports"),
+            PathBuf::from("/Library/Logs/DiagnosticReports"),
+            PathBuf::from("/cores"),
+        ]
+    } else {
+        vec![
+            PathBuf::from("/var/crash"),
+            PathBuf::from("/var/lib/systemd/coredump"),
+            PathBuf::from("/var/lib/apport/coredump"),
+        ]
+    }
+}
+
+/// Whether a file named `name` looks like a crash dump by name alone:
+/// a dump extension, or a Unix core file such as `core` or `core.1234`
+pub fn is_dump_name(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    if name == "core" {
+        return true;
+    }
+    if let Some(pid) = name.strip_prefix("core.") {
+        if !pid.is_empty() && pid.chars().all(|c| c.is_ascii_digit()) {
+            return true;
+        }
+    }
+    Path::new(&name)
+        .extension()
+        .is_some_and(|ext| DUMP_EXTENSIONS.contains(&ext.to_string_lossy().as_ref()))
+}
+
+/// Folder of `locations` that holds `path`, to confine deleting it to: the
+/// location itself, or its parent for a location that is a single file
+pub fn location_root(path: &Path, locations: &[PathBuf]) -> Option<PathBuf> {
+    locations.iter().find_map(|location| {
+        if path == location {
+            location.parent().map(Path::to_path_buf)
+        } else if path.starts_with(location) {
+            Some(location.clone())
+        } else {
+            None
+        }
+    })
+}
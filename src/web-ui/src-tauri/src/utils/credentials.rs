@@ -0,0 +1,23 @@
+use keyring::Entry;
+
+/// Keychain service name under which provider API keys are stored
+const KEYCHAIN_SERVICE: &str = "ai-disk-cleaner";
+
+fn api_key_entry(provider: &str) -> keyring::Result<Entry> {
+    Entry::new(KEYCHAIN_SERVICE, &format!("api_key_{}", provider))
+}
+
+/// Read the API key for a provider from the platform credential store
+pub fn get_api_key(provider: &str) -> anyhow::Result<Option<String>> {
+    match api_key_entry(provider)?.get_password() {
+        Ok(key) => Ok(Some(key)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Store the API key for a provider in the platform credential store
+pub fn set_api_key(provider: &str, api_key: &str) -> anyhow::Result<()> {
+    api_key_entry(provider)?.set_password(api_key)?;
+    Ok(())
+}
@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::debug;
+
+use crate::utils::trash_bin::TrashEntry;
+use crate::utils::tree_deletion::TreeDeleteMode;
+use crate::{AppError, AppResult};
+
+/// On-disk state of a path at prepare time. Directories change mtime when
+/// entries are added or removed, so this also catches new or vanished files.
+#[derive(Debug, Clone, PartialEq)]
+struct Fingerprint {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl Fingerprint {
+    fn capture(path: &Path) -> Option<Self> {
+        let metadata = fs::symlink_metadata(path).ok()?;
+        Some(Self {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct PendingBatch {
+    root: PathBuf,
+    elevated: bool,
+    paths: Vec<(PathBuf, Option<Fingerprint>)>,
+}
+
+#[derive(Debug)]
+struct PendingDelete {
+    /// Scan root the token is tied to, if any batch relies on it
+    scan_root: Option<PathBuf>,
+    label: String,
+    batches: Vec<PendingBatch>,
+    trash: Vec<TrashEntry>,
+    mode: DeleteMode,
+    issued_at: Instant,
+}
+
+/// Paths to delete while confined to `root`
+#[derive(Debug, Clone)]
+pub struct DeleteBatch {
+    pub root: PathBuf,
+    pub paths: Vec<PathBuf>,
+    /// Delete through the OS elevation prompt
+    pub elevated: bool,
+}
+
+/// Everything a token covers, as handed to `DeleteTokens::issue_plan`
+#[derive(Debug, Default)]
+pub struct DeleteRequest {
+    /// The confirmed scan root, when any batch is confined to it
+    pub scan_root: Option<PathBuf>,
+    /// What the delete is reported as, e.g. the scan root or "crash dumps"
+    pub label: String,
+    pub batches: Vec<DeleteBatch>,
+    /// Trash items to purge
+    pub trash: Vec<TrashEntry>,
+    pub mode: DeleteMode,
+}
+
+/// How an approved batch is to be deleted
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DeleteMode {
+    /// Overwrite file contents before removal
+    pub secure_wipe: bool,
+    /// Back everything up first and roll back if any deletion fails
+    pub transactional: bool,
+    /// Remove each path with everything below it, permanently or to the
+    /// trash, rather than only files and empty directories
+    pub tree: Option<TreeDeleteMode>,
+}
+
+/// A redeemed token: exactly what the user approved
+#[derive(Debug)]
+pub struct ApprovedDelete {
+    pub scan_root: Option<PathBuf>,
+    pub label: String,
+    pub batches: Vec<DeleteBatch>,
+    pub trash: Vec<TrashEntry>,
+    pub mode: DeleteMode,
+}
+
+impl ApprovedDelete {
+    /// Every approved path, across batches
+    pub fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.batches.iter().flat_map(|batch| batch.paths.iter())
+    }
+}
+
+/// Short-lived tokens that tie a confirmed delete to the exact set of paths
+/// the user reviewed. A token can be redeemed once.
+#[derive(Debug)]
+pub struct DeleteTokens {
+    ttl: Duration,
+    pending: Mutex<HashMap<String, PendingDelete>>,
+}
+
+impl DeleteTokens {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Fingerprint `paths` and issue a token for deleting them under
+    /// `scan_root` in the given mode
+    pub fn issue(&self, scan_root: &Path, paths: &[PathBuf], mode: DeleteMode) -> String {
+        self.issue_plan(DeleteRequest {
+            scan_root: Some(scan_root.to_path_buf()),
+            label: scan_root.to_string_lossy().to_string(),
+            batches: vec![DeleteBatch {
+                root: scan_root.to_path_buf(),
+                paths: paths.to_vec(),
+                elevated: false,
+            }],
+            trash: Vec::new(),
+            mode,
+        })
+    }
+
+    /// Fingerprint every path in `request` and issue a token for it
+    pub fn issue_plan(&self, request: DeleteRequest) -> String {
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        let pending = PendingDelete {
+            scan_root: request.scan_root,
+            label: request.label,
+            batches: request
+                .batches
+                .into_iter()
+                .map(|batch| PendingBatch {
+                    paths: batch
+                        .paths
+                        .into_iter()
+                        .map(|path| {
+                            let fingerprint = Fingerprint::capture(&path);
+                            (path, fingerprint)
+                        })
+                        .collect(),
+                    root: batch.root,
+                    elevated: batch.elevated,
+                })
+                .collect(),
+            trash: request.trash,
+            mode: request.mode,
+            issued_at: Instant::now(),
+        };
+
+        let mut tokens = self.lock();
+        let ttl = self.ttl;
+        tokens.retain(|_, pending| pending.issued_at.elapsed() < ttl);
+        tokens.insert(token.clone(), pending);
+        token
+    }
+
+    /// Batch roots and trash locations `token` covers, so their volumes can
+    /// be locked before it is redeemed
+    pub fn roots(&self, token: &str) -> AppResult<Vec<PathBuf>> {
+        let tokens = self.lock();
+        let pending = tokens.get(token).ok_or_else(|| {
+            AppError::SecurityError("Unknown or already used delete token".to_string())
+        })?;
+        Ok(pending
+            .batches
+            .iter()
+            .map(|batch| batch.root.clone())
+            .chain(pending.trash.iter().filter_map(TrashEntry::location))
+            .collect())
+    }
+
+    /// Whether `token` covers only entries to delete through the elevation
+    /// prompt, as the tokens `delete_elevated` accepts do
+    pub fn is_elevated(&self, token: &str) -> AppResult<bool> {
+        let tokens = self.lock();
+        let pending = tokens.get(token).ok_or_else(|| {
+            AppError::SecurityError("Unknown or already used delete token".to_string())
+        })?;
+        Ok(!pending.batches.is_empty()
+            && pending.trash.is_empty()
+            && pending.batches.iter().all(|batch| batch.elevated))
+    }
+
+    /// Consume a token, returning what it approved if it is still valid for
+    /// the confirmed `scan_root` and nothing on disk changed since it was
+    /// issued. Tokens not tied to a scan root ignore `scan_root`.
+    pub fn redeem(&self, token: &str, scan_root: Option<&Path>) -> AppResult<ApprovedDelete> {
+        let pending = self.lock().remove(token).ok_or_else(|| {
+            AppError::SecurityError("Unknown or already used delete token".to_string())
+        })?;
+
+        if pending.issued_at.elapsed() >= self.ttl {
+            return Err(AppError::SecurityError(
+                "Delete token expired; prepare the deletion again".to_string(),
+            ));
+        }
+
+        match (pending.scan_root.as_deref(), scan_root) {
+            (Some(_), None) => {
+                return Err(AppError::SecurityError(
+                    "Deletion requires a confirmed scan root; call set_scan_root first"
+                        .to_string(),
+                ))
+            }
+            (Some(issued), Some(current)) if issued != current => {
+                return Err(AppError::SecurityError(
+                    "Delete token was issued for a different scan root".to_string(),
+                ))
+            }
+            _ => {}
+        }
+
+        if let Some((path, _)) = pending
+            .batches
+            .iter()
+            .flat_map(|batch| batch.paths.iter())
+            .find(|(path, fingerprint)| Fingerprint::capture(path) != *fingerprint)
+        {
+            debug!("Delete token invalidated by change to {}", path.display());
+            return Err(AppError::SecurityError(format!(
+                "{} changed since the deletion was prepared; review it again",
+                path.display()
+            )));
+        }
+
+        Ok(ApprovedDelete {
+            scan_root: pending.scan_root,
+            label: pending.label,
+            batches: pending
+                .batches
+                .into_iter()
+                .map(|batch| DeleteBatch {
+                    root: batch.root,
+                    paths: batch.paths.into_iter().map(|(path, _)| path).collect(),
+                    elevated: batch.elevated,
+                })
+                .collect(),
+            trash: pending.trash,
+            mode: pending.mode,
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, PendingDelete>> {
+        // A poisoned map is still structurally valid; keep using it
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
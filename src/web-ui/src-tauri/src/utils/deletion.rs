@@ -0,0 +1,1193 @@
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::utils::audit::{AuditAction, AuditEntry, AuditLog};
+use crate::utils::backups::{record_backup, write_manifest};
+use crate::utils::config::SecurityConfig;
+use crate::utils::elevation;
+use crate::utils::free_space::FreeSpaceChange;
+use crate::utils::file_locks::{OpenFileIndex, FILE_IN_USE_REASON};
+use crate::utils::operations::{ItemError, OperationTally};
+use crate::utils::platform::{filesystem_type, is_copy_on_write_filesystem, prepare_path_for_io};
+use crate::utils::security::SecurityValidator;
+use crate::utils::trash_bin::{self, TrashEntry};
+use crate::utils::tree_deletion::{delete_tree, TreeDeleteMode, TreeDeleteProgress};
+use crate::utils::walker::allocated_size;
+
+/// Byte patterns written on successive secure-wipe passes
+const WIPE_PATTERNS: &[u8] = &[0x00, 0xFF, 0xAA, 0x55];
+const WIPE_BUFFER_SIZE: usize = 1024 * 1024;
+/// Backups that cannot be hard-linked are copied in chunks of this size,
+/// with a cancellation check between chunks
+const BACKUP_CHUNK_SIZE: usize = 1024 * 1024;
+/// Minimum time between two backup progress reports
+const BACKUP_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Reason reported for entries the current user cannot remove without elevation
+pub const ELEVATION_REQUIRED_REASON: &str = "Requires elevated permissions";
+pub const NO_ELEVATION_NEEDED_REASON: &str =
+    "Does not require elevated permissions; delete it normally";
+
+/// Result of attempting to delete a single path
+#[derive(Debug, Clone, Serialize)]
+pub struct DeletionOutcome {
+    pub path: String,
+    pub deleted: bool,
+    pub bytes_freed: u64,
+    /// Whether the contents were overwritten before removal
+    pub wiped: bool,
+    /// Deleted, then restored from backup when its transactional batch failed
+    pub rolled_back: bool,
+    pub error: Option<String>,
+}
+
+impl DeletionOutcome {
+    fn failed(path: String, reason: impl Into<String>) -> Self {
+        Self {
+            path,
+            deleted: false,
+            bytes_freed: 0,
+            wiped: false,
+            rolled_back: false,
+            error: Some(reason.into()),
+        }
+    }
+}
+
+/// Aggregate result of a safe-delete batch
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DeletionReport {
+    pub outcomes: Vec<DeletionOutcome>,
+    pub deleted_count: u64,
+    pub failed_count: u64,
+    pub bytes_freed: u64,
+    /// Caveats about the batch, e.g. a secure wipe on a copy-on-write volume
+    pub warnings: Vec<String>,
+    /// A transactional batch failed and its deletions were undone
+    pub rolled_back: bool,
+    /// The batch was cancelled: while being backed up, so nothing was
+    /// deleted, or part way through a trash purge
+    pub cancelled: bool,
+    /// Bytes copied into backups; hard-linked backups copy nothing
+    pub backup_bytes_copied: u64,
+    /// Free space on the volume measured around the batch
+    pub free_space: Option<FreeSpaceChange>,
+}
+
+impl DeletionReport {
+    /// Counts and per-path errors for the operation-complete event
+    pub fn tally(&self) -> OperationTally {
+        OperationTally {
+            succeeded: self.deleted_count,
+            failed: self.failed_count,
+            bytes: self.bytes_freed,
+            errors: self
+                .outcomes
+                .iter()
+                .filter_map(|outcome| {
+                    let error = outcome.error.as_ref()?;
+                    (!outcome.deleted).then(|| ItemError::new(&outcome.path, error))
+                })
+                .collect(),
+        }
+    }
+
+    /// Fold in the report of another batch, e.g. one confined to a
+    /// different root; the free-space measurement of this one is kept
+    pub fn merge(&mut self, other: DeletionReport) {
+        self.outcomes.extend(other.outcomes);
+        self.deleted_count += other.deleted_count;
+        self.failed_count += other.failed_count;
+        self.bytes_freed += other.bytes_freed;
+        self.warnings.extend(other.warnings);
+        self.rolled_back |= other.rolled_back;
+        self.cancelled |= other.cancelled;
+        self.backup_bytes_copied += other.backup_bytes_copied;
+        self.free_space = self.free_space.take().or(other.free_space);
+    }
+
+    fn record(&mut self, outcome: DeletionOutcome) {
+        if outcome.deleted {
+            self.deleted_count += 1;
+            self.bytes_freed += outcome.bytes_freed;
+        } else {
+            self.failed_count += 1;
+        }
+        self.outcomes.push(outcome);
+    }
+}
+
+/// Safely delete a batch of files, symlinks and empty directories.
+///
+/// Every path is re-validated against the security configuration before it
+/// is touched. Non-empty directories are never removed here, and files held
+/// open by another process are skipped when `exclude_locked_files` is set.
+/// Paths outside `scan_root`, the directory the user confirmed, are always
+/// refused. When the audit trail is enabled every outcome is recorded in `audit`.
+pub fn safe_delete(
+    paths: &[PathBuf],
+    scan_root: &Path,
+    config: &SecurityConfig,
+    audit: &AuditLog,
+) -> DeletionReport {
+    safe_delete_with(paths, scan_root, config, audit, false)
+}
+
+/// `safe_delete`, optionally overwriting each file's contents
+/// `config.secure_wipe_passes` times before unlinking it.
+///
+/// Wiping is best effort: SSD wear levelling and copy-on-write filesystems
+/// can keep old blocks around, which is reported in the report's warnings.
+/// Files with more than one hard link are only unlinked, never overwritten,
+/// since their data is shared with links that may lie outside `scan_root`.
+pub fn safe_delete_with(
+    paths: &[PathBuf],
+    scan_root: &Path,
+    config: &SecurityConfig,
+    audit: &AuditLog,
+    secure_wipe: bool,
+) -> DeletionReport {
+    let mut report = DeletionReport::default();
+    let open_files = config.exclude_locked_files.then(OpenFileIndex::capture);
+    let wipe_passes = secure_wipe.then_some(config.secure_wipe_passes.max(1));
+    if secure_wipe {
+        report.warnings.extend(wipe_warning(scan_root));
+    }
+
+    for path in paths {
+        let (outcome, warning) =
+            delete_checked(path, scan_root, config, open_files.as_ref(), wipe_passes);
+        if config.enable_audit_trail {
+            audit.record(audit_entry(&outcome));
+        }
+        report.warnings.extend(warning);
+        report.record(outcome);
+    }
+
+    info!(
+        "Safe delete finished: {} deleted, {} failed, {} bytes freed",
+        report.deleted_count, report.failed_count, report.bytes_freed
+    );
+
+    report
+}
+
+/// Delete entries that `elevation_required` flags, through the OS elevation
+/// prompt.
+///
+/// Only flagged entries that pass every other check reach the elevated
+/// helper; anything that the normal batch could delete is refused, so this
+/// never widens an unprivileged delete. Elevated outcomes are always audited,
+/// with the helper noted, even when the audit trail is otherwise disabled.
+pub fn elevated_delete(
+    paths: &[PathBuf],
+    scan_root: &Path,
+    config: &SecurityConfig,
+    audit: &AuditLog,
+) -> DeletionReport {
+    let mut report = DeletionReport::default();
+    let mut approved = Vec::new();
+
+    for path in paths {
+        let checked = SecurityValidator::validate_within_root(path, scan_root)
+            .and_then(|()| SecurityValidator::validate_deletion_target(path, config))
+            .and_then(|()| match elevation_required(path) {
+                Some(_) => Ok(()),
+                None => Err(NO_ELEVATION_NEEDED_REASON.to_string()),
+            });
+
+        match checked {
+            Ok(()) => {
+                let bytes = fs::symlink_metadata(path)
+                    .map(|m| if m.is_file() { m.len() } else { 0 })
+                    .unwrap_or(0);
+                approved.push((path, bytes));
+            }
+            Err(reason) => {
+                warn!("Refusing elevated delete of {}: {}", path.display(), reason);
+                let outcome = DeletionOutcome::failed(path.to_string_lossy().to_string(), reason);
+                if config.enable_audit_trail {
+                    audit.record(audit_entry(&outcome));
+                }
+                report.record(outcome);
+            }
+        }
+    }
+
+    if approved.is_empty() {
+        return report;
+    }
+
+    let targets: Vec<PathBuf> = approved.iter().map(|(path, _)| path.to_path_buf()).collect();
+    let helper = elevation::delete_elevated(&targets, scan_root, config);
+    for (path, bytes) in approved {
+        let path_str = path.to_string_lossy().to_string();
+        let (outcome, detail) = match &helper {
+            Err(e) => (
+                DeletionOutcome::failed(path_str, format!("Elevated delete failed: {}", e)),
+                "elevation failed".to_string(),
+            ),
+            Ok(helper) if fs::symlink_metadata(path).is_err() => (
+                DeletionOutcome {
+                    path: path_str,
+                    deleted: true,
+                    bytes_freed: bytes,
+                    wiped: false,
+                    rolled_back: false,
+                    error: None,
+                },
+                format!("elevated via {}", helper),
+            ),
+            Ok(helper) => (
+                DeletionOutcome::failed(path_str, "Still present after elevated delete"),
+                format!("elevated via {}", helper),
+            ),
+        };
+
+        let mut entry = audit_entry(&outcome);
+        entry.detail = Some(match entry.detail {
+            Some(error) => format!("{}: {}", detail, error),
+            None => detail,
+        });
+        audit.record(entry);
+        report.record(outcome);
+    }
+
+    info!(
+        "Elevated delete finished: {} deleted, {} failed, {} bytes freed",
+        report.deleted_count, report.failed_count, report.bytes_freed
+    );
+
+    report
+}
+
+/// Remove each of `paths` with everything below it through `delete_tree`,
+/// after the same checks as any other delete. Counts cover every entry
+/// removed; each tree gets one outcome, failed unless the tree is gone.
+pub fn tree_delete(
+    paths: &[PathBuf],
+    scan_root: &Path,
+    config: &SecurityConfig,
+    audit: &AuditLog,
+    mode: TreeDeleteMode,
+    cancel: &CancellationToken,
+    on_progress: &mut dyn FnMut(&TreeDeleteProgress),
+) -> DeletionReport {
+    let mut report = DeletionReport::default();
+
+    for path in paths {
+        if cancel.is_cancelled() {
+            report.cancelled = true;
+            break;
+        }
+        let path_str = path.to_string_lossy().to_string();
+        let checked = SecurityValidator::validate_within_root(path, scan_root)
+            .and_then(|()| SecurityValidator::validate_deletion_target(path, config))
+            .and_then(|()| elevation_required(path).map_or(Ok(()), Err));
+        if let Err(reason) = checked {
+            warn!("Refusing to delete {}: {}", path.display(), reason);
+            let outcome = DeletionOutcome::failed(path_str, reason);
+            if config.enable_audit_trail {
+                audit.record(audit_entry(&outcome));
+            }
+            report.record(outcome);
+            continue;
+        }
+
+        let tree = delete_tree(path, mode, &config.protected_patterns, cancel, on_progress);
+        let detail = format!(
+            "{} files and {} directories removed{}, {} failed{}",
+            tree.files_removed,
+            tree.directories_removed,
+            if tree.to_trash { " to trash" } else { "" },
+            tree.failed_count,
+            if tree.cancelled { ", cancelled" } else { "" }
+        );
+        let error = match tree.errors.first() {
+            Some(first) => Some(format!("{}; first failure: {}", detail, first)),
+            None if !tree.root_removed => Some(detail.clone()),
+            None => None,
+        };
+        if config.enable_audit_trail {
+            let action = if tree.root_removed {
+                AuditAction::Deleted
+            } else {
+                AuditAction::DeleteFailed
+            };
+            audit.record(AuditEntry::new(action, &path_str, tree.bytes_freed, Some(detail)));
+        }
+
+        report.deleted_count += tree.files_removed + tree.directories_removed;
+        report.failed_count += tree.failed_count;
+        report.bytes_freed += tree.bytes_freed;
+        report.cancelled |= tree.cancelled;
+        report.outcomes.push(DeletionOutcome {
+            path: path_str,
+            deleted: tree.root_removed,
+            bytes_freed: tree.bytes_freed,
+            wiped: false,
+            rolled_back: false,
+            error,
+        });
+        if tree.cancelled {
+            break;
+        }
+    }
+
+    info!(
+        "Tree delete finished: {} entries removed, {} failed, {} bytes freed",
+        report.deleted_count, report.failed_count, report.bytes_freed
+    );
+
+    report
+}
+
+/// Permanently remove `entries` from the trash, stopping between items if
+/// `cancel` fires. Items purged before a cancel are reported and audited like
+/// the rest, with the report marked cancelled.
+pub fn purge_trash(
+    entries: &[TrashEntry],
+    config: &SecurityConfig,
+    audit: &AuditLog,
+    cancel: &CancellationToken,
+) -> DeletionReport {
+    let mut report = DeletionReport::default();
+    for entry in entries {
+        if cancel.is_cancelled() {
+            report.cancelled = true;
+            break;
+        }
+        let path = entry.display_path();
+        let (outcome, detail) = match trash_bin::purge(entry) {
+            Ok(()) => (
+                DeletionOutcome {
+                    path,
+                    deleted: true,
+                    bytes_freed: entry.size,
+                    wiped: false,
+                    rolled_back: false,
+                    error: None,
+                },
+                "Purged from trash".to_string(),
+            ),
+            Err(e) => (
+                DeletionOutcome::failed(path, e.clone()),
+                format!("Purge from trash failed: {}", e),
+            ),
+        };
+        if config.enable_audit_trail {
+            let mut entry = audit_entry(&outcome);
+            entry.detail = Some(detail);
+            audit.record(entry);
+        }
+        report.record(outcome);
+    }
+
+    info!(
+        "Trash purge finished: {} purged, {} failed, {} bytes freed",
+        report.deleted_count, report.failed_count, report.bytes_freed
+    );
+    report
+}
+
+/// How an entry was preserved so a transactional batch can undo its deletion
+#[derive(Debug)]
+enum Backup {
+    File(PathBuf),
+    Link { target: PathBuf, is_dir: bool },
+    EmptyDir,
+}
+
+/// Delete a batch all-or-nothing, as far as a filesystem allows.
+///
+/// Every path is checked and backed up below `backup_root` before anything
+/// is removed. If a deletion then fails, the entries already deleted are
+/// restored from their backups and the report is marked as rolled back.
+/// This is not atomic: other processes can observe the half-deleted state,
+/// and a restore can itself fail, which is reported on that path's outcome.
+/// Backups of a successful batch are kept, like `backup_before_delete`.
+///
+/// Copying backups reports through `on_progress` and stops when `cancel`
+/// fires, removing the partial copy and deleting nothing.
+pub fn transactional_delete(
+    paths: &[PathBuf],
+    scan_root: &Path,
+    config: &SecurityConfig,
+    audit: &AuditLog,
+    backup_root: &Path,
+    cancel: &CancellationToken,
+    on_progress: &mut dyn FnMut(&BackupProgress),
+) -> DeletionReport {
+    let open_files = config.exclude_locked_files.then(OpenFileIndex::capture);
+    let mut copier = BackupCopier {
+        cancel,
+        on_progress,
+        bytes_copied: 0,
+        last_progress: Instant::now(),
+    };
+    let outcomes = run_transaction(
+        paths,
+        scan_root,
+        config,
+        open_files.as_ref(),
+        backup_root,
+        &mut copier,
+    );
+
+    let mut report = DeletionReport {
+        rolled_back: outcomes.iter().any(|outcome| outcome.rolled_back),
+        cancelled: cancel.is_cancelled() && outcomes.iter().all(|outcome| !outcome.deleted),
+        backup_bytes_copied: copier.bytes_copied,
+        ..DeletionReport::default()
+    };
+    for outcome in outcomes {
+        if config.enable_audit_trail {
+            audit.record(audit_entry(&outcome));
+        }
+        report.record(outcome);
+    }
+
+    info!(
+        "Transactional delete finished: {} deleted, {} failed, rolled back: {}",
+        report.deleted_count, report.failed_count, report.rolled_back
+    );
+
+    report
+}
+
+fn run_transaction(
+    paths: &[PathBuf],
+    scan_root: &Path,
+    config: &SecurityConfig,
+    open_files: Option<&OpenFileIndex>,
+    backup_root: &Path,
+    copier: &mut BackupCopier,
+) -> Vec<DeletionOutcome> {
+    let abort = |failed_index: usize, reason: String, others: &str| -> Vec<DeletionOutcome> {
+        paths
+            .iter()
+            .enumerate()
+            .map(|(i, path)| {
+                let reason = if i == failed_index { reason.clone() } else { others.to_string() };
+                DeletionOutcome::failed(path.to_string_lossy().to_string(), reason)
+            })
+            .collect()
+    };
+
+    let mut checked = Vec::with_capacity(paths.len());
+    for (i, path) in paths.iter().enumerate() {
+        match check_target(path, scan_root, config, open_files) {
+            Ok(entry) => checked.push(entry),
+            Err(reason) => {
+                return abort(i, reason, "Not deleted: another path in the batch was refused")
+            }
+        }
+    }
+
+    let batch_dir = backup_root.join(format!(
+        "{}-{}",
+        chrono::Utc::now().format("%Y%m%d%H%M%S"),
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    ));
+    let mut backups = Vec::with_capacity(paths.len());
+    for (i, path) in paths.iter().enumerate() {
+        match backup_entry(path, &batch_dir, i, copier) {
+            Ok(backup) => backups.push(backup),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {
+                let _ = fs::remove_dir_all(&batch_dir);
+                return abort(
+                    i,
+                    format!("Backup cancelled after {} bytes copied", copier.bytes_copied),
+                    "Not deleted: the backup was cancelled",
+                );
+            }
+            Err(e) => {
+                let _ = fs::remove_dir_all(&batch_dir);
+                return abort(
+                    i,
+                    format!("Backup failed: {}", e),
+                    "Not deleted: backing up another path in the batch failed",
+                );
+            }
+        }
+    }
+
+    let mut deleted = Vec::new();
+    let mut failure = None;
+    for (i, (path, entry)) in paths.iter().zip(&checked).enumerate() {
+        // Backing up takes a while, which widens the window for a swap
+        if let Err(reason) = recheck_target(path, *entry, scan_root, config) {
+            failure = Some((i, reason));
+            break;
+        }
+        match delete_single(path, None) {
+            Ok(removal) => deleted.push(removal.bytes_freed),
+            Err(e) => {
+                failure = Some((i, e.to_string()));
+                break;
+            }
+        }
+    }
+
+    let Some((failed_index, error)) = failure else {
+        // The backups are kept, so index them for `verify_backups`
+        let records: std::io::Result<Vec<_>> = paths
+            .iter()
+            .zip(&backups)
+            .filter_map(|(path, backup)| match backup {
+                Backup::File(file) => Some(record_backup(path, file)),
+                _ => None,
+            })
+            .collect();
+        let written = records.and_then(|records| {
+            if records.is_empty() {
+                Ok(())
+            } else {
+                write_manifest(&batch_dir, records)
+            }
+        });
+        if let Err(e) = written {
+            warn!("Failed to write backup manifest in {}: {}", batch_dir.display(), e);
+        }
+
+        return paths
+            .iter()
+            .zip(deleted)
+            .map(|(path, bytes_freed)| DeletionOutcome {
+                path: path.to_string_lossy().to_string(),
+                deleted: true,
+                bytes_freed,
+                wiped: false,
+                rolled_back: false,
+                error: None,
+            })
+            .collect();
+    };
+
+    warn!(
+        "Deleting {} failed, rolling back {} deletion(s)",
+        paths[failed_index].display(),
+        deleted.len()
+    );
+    let mut all_restored = true;
+    let mut outcomes: Vec<DeletionOutcome> = deleted
+        .iter()
+        .enumerate()
+        .map(|(i, &bytes_freed)| {
+            let path_str = paths[i].to_string_lossy().to_string();
+            match restore_entry(&paths[i], &backups[i]) {
+                Ok(()) => DeletionOutcome {
+                    rolled_back: true,
+                    ..DeletionOutcome::failed(path_str, "Restored after the batch failed")
+                },
+                Err(e) => {
+                    all_restored = false;
+                    DeletionOutcome {
+                        path: path_str,
+                        deleted: true,
+                        bytes_freed,
+                        wiped: false,
+                        rolled_back: false,
+                        error: Some(format!(
+                            "Restore failed: {}; backup kept in {}",
+                            e,
+                            batch_dir.display()
+                        )),
+                    }
+                }
+            }
+        })
+        .collect();
+
+    outcomes.extend(paths.iter().enumerate().skip(failed_index).map(|(i, path)| {
+        let reason = if i == failed_index {
+            error.clone()
+        } else {
+            "Not attempted: the batch was rolled back".to_string()
+        };
+        DeletionOutcome::failed(path.to_string_lossy().to_string(), reason)
+    }));
+
+    if all_restored {
+        let _ = fs::remove_dir_all(&batch_dir);
+    }
+    outcomes
+}
+
+/// Preserve one entry before deletion. Files are hard-linked into the batch
+/// directory when possible, which costs no space, and copied otherwise.
+fn backup_entry(
+    path: &Path,
+    batch_dir: &Path,
+    index: usize,
+    copier: &mut BackupCopier,
+) -> std::io::Result<Backup> {
+    if copier.cancel.is_cancelled() {
+        return Err(io::Error::new(io::ErrorKind::Interrupted, "Backup cancelled"));
+    }
+    let io_path = prepare_path_for_io(path);
+    let metadata = fs::symlink_metadata(&io_path)?;
+    let file_type = metadata.file_type();
+
+    if file_type.is_symlink() {
+        let is_dir = fs::metadata(&io_path).is_ok_and(|target| target.is_dir());
+        return Ok(Backup::Link {
+            target: fs::read_link(&io_path)?,
+            is_dir,
+        });
+    }
+    if file_type.is_dir() {
+        return Ok(Backup::EmptyDir);
+    }
+
+    fs::create_dir_all(batch_dir)?;
+    let backup = batch_dir.join(format!("{:06}", index));
+    if fs::hard_link(&io_path, &backup).is_err() {
+        copier.copy(path, &io_path, &backup, metadata.len())?;
+    }
+    Ok(Backup::File(backup))
+}
+
+/// Running totals reported while a transactional batch is backed up
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupProgress {
+    /// File being copied
+    pub path: String,
+    pub file_bytes_copied: u64,
+    pub file_size: u64,
+    /// Bytes copied for the whole batch so far
+    pub bytes_copied: u64,
+}
+
+/// Streams backup copies so large files can report progress and be cancelled
+struct BackupCopier<'a> {
+    cancel: &'a CancellationToken,
+    on_progress: &'a mut dyn FnMut(&BackupProgress),
+    bytes_copied: u64,
+    last_progress: Instant,
+}
+
+impl BackupCopier<'_> {
+    /// Copy `source` to `backup`, removing the partial copy on cancellation
+    /// or failure so it is never mistaken for a complete backup
+    fn copy(&mut self, path: &Path, source: &Path, backup: &Path, size: u64) -> io::Result<()> {
+        let copied = self.copy_chunks(path, source, backup, size);
+        if copied.is_err() {
+            let _ = fs::remove_file(backup);
+        }
+        copied
+    }
+
+    fn copy_chunks(
+        &mut self,
+        path: &Path,
+        source: &Path,
+        backup: &Path,
+        size: u64,
+    ) -> io::Result<()> {
+        let mut reader = File::open(source)?;
+        let mut writer = File::create(backup)?;
+        let mut buffer = vec![0u8; BACKUP_CHUNK_SIZE];
+        let mut file_bytes_copied = 0u64;
+        loop {
+            if self.cancel.is_cancelled() {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "Backup cancelled"));
+            }
+            let read = match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            writer.write_all(&buffer[..read])?;
+            file_bytes_copied += read as u64;
+            self.bytes_copied += read as u64;
+
+            if self.last_progress.elapsed() >= BACKUP_PROGRESS_INTERVAL {
+                self.last_progress = Instant::now();
+                (self.on_progress)(&BackupProgress {
+                    path: path.to_string_lossy().to_string(),
+                    file_bytes_copied,
+                    file_size: size,
+                    bytes_copied: self.bytes_copied,
+                });
+            }
+        }
+        writer.sync_all()?;
+        fs::set_permissions(backup, reader.metadata()?.permissions())
+    }
+}
+
+fn restore_entry(path: &Path, backup: &Backup) -> std::io::Result<()> {
+    let io_path = prepare_path_for_io(path);
+    match backup {
+        Backup::File(backup) => {
+            if fs::rename(backup, &io_path).is_err() {
+                fs::copy(backup, &io_path)?;
+            }
+            Ok(())
+        }
+        Backup::Link { target, is_dir } => create_link(target, &io_path, *is_dir),
+        Backup::EmptyDir => fs::create_dir(&io_path),
+    }
+}
+
+#[cfg(unix)]
+fn create_link(target: &Path, link: &Path, _is_dir: bool) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn create_link(target: &Path, link: &Path, is_dir: bool) -> std::io::Result<()> {
+    if is_dir {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+/// Validate and delete one path, turning every refusal into a failed
+/// outcome. Also returns a warning when a requested wipe was skipped.
+fn delete_checked(
+    path: &Path,
+    scan_root: &Path,
+    config: &SecurityConfig,
+    open_files: Option<&OpenFileIndex>,
+    wipe_passes: Option<u32>,
+) -> (DeletionOutcome, Option<String>) {
+    let path_str = path.to_string_lossy().to_string();
+
+    let entry = match check_target(path, scan_root, config, open_files) {
+        Ok(entry) => entry,
+        Err(reason) => return (DeletionOutcome::failed(path_str, reason), None),
+    };
+    if let Err(reason) = recheck_target(path, entry, scan_root, config) {
+        return (DeletionOutcome::failed(path_str, reason), None);
+    }
+
+    match delete_single(path, wipe_passes) {
+        Ok(removal) => (
+            DeletionOutcome {
+                path: path_str,
+                deleted: true,
+                bytes_freed: removal.bytes_freed,
+                wiped: removal.wiped,
+                rolled_back: false,
+                error: None,
+            },
+            removal.warning,
+        ),
+        Err(e) => (DeletionOutcome::failed(path_str, e.to_string()), None),
+    }
+}
+
+/// What `check_target` saw of an entry, so `recheck_target` can tell
+/// whether it was swapped for something else since
+#[derive(Debug, Clone, Copy)]
+struct CheckedEntry {
+    is_symlink: bool,
+    is_dir: bool,
+}
+
+/// Every check a path must pass before it may be deleted
+fn check_target(
+    path: &Path,
+    scan_root: &Path,
+    config: &SecurityConfig,
+    open_files: Option<&OpenFileIndex>,
+) -> Result<CheckedEntry, String> {
+    let refuse = |reason: String| {
+        warn!("Refusing to delete {}: {}", path.display(), reason);
+        Err(reason)
+    };
+
+    if let Err(reason) = SecurityValidator::validate_within_root(path, scan_root) {
+        return refuse(reason);
+    }
+
+    if let Err(reason) = SecurityValidator::validate_deletion_target(path, config) {
+        return refuse(reason);
+    }
+
+    if let Some(reason) = elevation_required(path) {
+        return refuse(reason);
+    }
+
+    if let Some(open_files) = open_files {
+        if path.is_file() && !path.is_symlink() && open_files.is_in_use(path) {
+            return refuse(FILE_IN_USE_REASON.to_string());
+        }
+    }
+
+    match fs::symlink_metadata(path) {
+        Ok(metadata) => Ok(CheckedEntry {
+            is_symlink: metadata.file_type().is_symlink(),
+            is_dir: metadata.is_dir(),
+        }),
+        Err(e) => refuse(e.to_string()),
+    }
+}
+
+/// Validate `path` once more as it resolves right now, immediately before
+/// it is removed.
+///
+/// Between `check_target` and the removal, the entry or one of its parent
+/// folders may have been replaced by a symlink, e.g. one pointing into a
+/// system directory. The entry must still be of the kind that was checked,
+/// and its canonical location must still pass the scan-root and deletion
+/// checks; otherwise it is refused rather than followed.
+fn recheck_target(
+    path: &Path,
+    checked: CheckedEntry,
+    scan_root: &Path,
+    config: &SecurityConfig,
+) -> Result<(), String> {
+    let refuse = |reason: String| {
+        warn!("Refusing to delete {}: {}", path.display(), reason);
+        Err(reason)
+    };
+
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) => return refuse(e.to_string()),
+    };
+    if metadata.file_type().is_symlink() != checked.is_symlink
+        || metadata.is_dir() != checked.is_dir
+    {
+        return refuse("Path was replaced since it was checked".to_string());
+    }
+
+    let resolved = path
+        .parent()
+        .and_then(|parent| parent.canonicalize().ok())
+        .zip(path.file_name())
+        .map(|(parent, name)| parent.join(name));
+    let Some(resolved) = resolved else {
+        return refuse("Path can no longer be resolved".to_string());
+    };
+    if let Err(reason) = SecurityValidator::validate_within_root(&resolved, scan_root)
+        .and_then(|()| SecurityValidator::validate_deletion_target(&resolved, config))
+    {
+        return refuse(reason);
+    }
+    Ok(())
+}
+
+fn audit_entry(outcome: &DeletionOutcome) -> AuditEntry {
+    let action = match (outcome.deleted, outcome.wiped, outcome.rolled_back) {
+        (_, _, true) => AuditAction::RolledBack,
+        (true, true, _) => AuditAction::Wiped,
+        (true, false, _) => AuditAction::Deleted,
+        (false, _, _) => AuditAction::DeleteFailed,
+    };
+    AuditEntry::new(action, &outcome.path, outcome.bytes_freed, outcome.error.clone())
+}
+
+/// What `delete_single` did to an entry
+#[derive(Debug, Default)]
+struct Removal {
+    bytes_freed: u64,
+    wiped: bool,
+    /// Why a requested wipe was skipped
+    warning: Option<String>,
+}
+
+/// Remove one entry
+fn delete_single(path: &Path, wipe_passes: Option<u32>) -> std::io::Result<Removal> {
+    let io_path = prepare_path_for_io(path);
+    let io_path = io_path.as_ref();
+    let metadata = fs::symlink_metadata(io_path)?;
+    let file_type = metadata.file_type();
+
+    if file_type.is_symlink() {
+        remove_link(io_path)?;
+        Ok(Removal::default())
+    } else if file_type.is_dir() {
+        fs::remove_dir(io_path)?;
+        Ok(Removal::default())
+    } else {
+        // Measured first, since wiping fills in the holes of a sparse file
+        let mut removal = Removal {
+            bytes_freed: allocated_size(io_path, &metadata),
+            ..Removal::default()
+        };
+        if let Some(passes) = wipe_passes {
+            match wipe_contents(io_path, metadata.len(), passes)? {
+                None => removal.wiped = true,
+                Some(links) => {
+                    warn!("Not wiping {}: it has {} hard links", path.display(), links);
+                    removal.warning = Some(format!(
+                        "{} has {} hard links; it was unlinked without overwriting so the \
+                         other links keep their data",
+                        path.display(),
+                        links
+                    ));
+                }
+            }
+        }
+        fs::remove_file(io_path)?;
+        Ok(removal)
+    }
+}
+
+/// Overwrite a file in place with fixed patterns, syncing after each pass,
+/// then truncate it.
+///
+/// A file with several hard links shares its data with all of them, so it
+/// is left untouched and its link count returned instead. The count is read
+/// from the handle that would be written through, so the file cannot be
+/// swapped between the check and the overwrite.
+fn wipe_contents(path: &Path, len: u64, passes: u32) -> std::io::Result<Option<u64>> {
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    let links = link_count(&file)?;
+    if links > 1 {
+        return Ok(Some(links));
+    }
+
+    for pass in 0..passes as usize {
+        let buffer = vec![WIPE_PATTERNS[pass % WIPE_PATTERNS.len()]; WIPE_BUFFER_SIZE];
+        file.seek(SeekFrom::Start(0))?;
+        let mut remaining = len;
+        while remaining > 0 {
+            let chunk = remaining.min(WIPE_BUFFER_SIZE as u64) as usize;
+            file.write_all(&buffer[..chunk])?;
+            remaining -= chunk as u64;
+        }
+        file.sync_all()?;
+    }
+
+    file.set_len(0)?;
+    file.sync_all()?;
+    Ok(None)
+}
+
+/// Number of hard links to an open file
+#[cfg(unix)]
+fn link_count(file: &File) -> std::io::Result<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Ok(file.metadata()?.nlink())
+}
+
+/// Number of hard links to an open file, from `GetFileInformationByHandle`
+#[cfg(windows)]
+fn link_count(file: &File) -> std::io::Result<u64> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Storage::FileSystem::{
+        GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION,
+    };
+
+    // SAFETY: BY_HANDLE_FILE_INFORMATION is plain data, valid when zeroed
+    let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { std::mem::zeroed() };
+    // SAFETY: the handle is open for the lifetime of `file` and `info` is a
+    // valid out-pointer
+    if unsafe { GetFileInformationByHandle(file.as_raw_handle() as _, &mut info) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(u64::from(info.nNumberOfLinks))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn link_count(_file: &File) -> std::io::Result<u64> {
+    Ok(1)
+}
+
+/// Why removing `path` would fail for the current user, so it can be
+/// reported up front instead of as a raw OS error. Removing an entry needs
+/// write and search access to its parent directory; in a sticky directory
+/// such as `/tmp` the entry or the directory must also belong to the user.
+#[cfg(unix)]
+pub fn elevation_required(path: &Path) -> Option<String> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::MetadataExt;
+
+    const STICKY_BIT: u32 = 0o1000;
+
+    // SAFETY: geteuid has no preconditions and cannot fail
+    let uid = unsafe { libc::geteuid() };
+    if uid == 0 {
+        return None;
+    }
+
+    let metadata = fs::symlink_metadata(path).ok()?;
+    let parent = path.parent().filter(|parent| !parent.as_os_str().is_empty())?;
+    let parent_metadata = fs::metadata(parent).ok()?;
+    let parent_c = CString::new(parent.as_os_str().as_bytes()).ok()?;
+
+    // SAFETY: `parent_c` is a valid NUL-terminated path that outlives the call
+    let writable = unsafe { libc::access(parent_c.as_ptr(), libc::W_OK | libc::X_OK) } == 0;
+    if !writable {
+        return Some(format!(
+            "{}: {} is not writable by the current user",
+            ELEVATION_REQUIRED_REASON,
+            parent.display()
+        ));
+    }
+
+    let sticky = parent_metadata.mode() & STICKY_BIT != 0;
+    if sticky && metadata.uid() != uid && parent_metadata.uid() != uid {
+        return Some(format!(
+            "{}: owned by another user (uid {}) in a shared directory",
+            ELEVATION_REQUIRED_REASON,
+            metadata.uid()
+        ));
+    }
+
+    None
+}
+
+/// Access checks on Windows depend on ACLs that only the delete itself
+/// evaluates reliably, so failures there surface from the OS
+#[cfg(not(unix))]
+pub fn elevation_required(_path: &Path) -> Option<String> {
+    None
+}
+
+/// Warning for wipes on volumes where overwriting likely leaves old data behind
+pub fn wipe_warning(root: &Path) -> Option<String> {
+    let fs_type = filesystem_type(root)?;
+    is_copy_on_write_filesystem(&fs_type).then(|| {
+        format!(
+            "{} is a copy-on-write filesystem; overwritten data may survive in old \
+             blocks or snapshots",
+            fs_type
+        )
+    })
+}
+
+/// Remove a symlink (or Windows junction) without touching its target
+pub fn remove_link(path: &Path) -> std::io::Result<()> {
+    let path = prepare_path_for_io(path);
+    let path = path.as_ref();
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::FileTypeExt;
+
+        // Directory symlinks and junctions must be removed as directories
+        if fs::symlink_metadata(path)?.file_type().is_symlink_dir() {
+            return fs::remove_dir(path);
+        }
+    }
+
+    fs::remove_file(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> SecurityConfig {
+        SecurityConfig {
+            enable_audit_trail: false,
+            exclude_locked_files: false,
+            ..SecurityConfig::default()
+        }
+    }
+
+    #[test]
+    fn secure_wipe_leaves_hard_links_outside_root_intact() {
+        let root = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let root_path = root.path().canonicalize().unwrap();
+        let target = root_path.join("secret.txt");
+        let other_link = outside.path().join("keep.txt");
+        fs::write(&target, b"shared contents").unwrap();
+        fs::hard_link(&target, &other_link).unwrap();
+
+        let report = safe_delete_with(
+            std::slice::from_ref(&target),
+            &root_path,
+            &test_config(),
+            &AuditLog::new(),
+            true,
+        );
+
+        assert_eq!(report.deleted_count, 1);
+        assert!(!report.outcomes[0].wiped);
+        assert!(report.warnings.iter().any(|w| w.contains("hard links")));
+        assert!(!target.exists());
+        assert_eq!(fs::read(&other_link).unwrap(), b"shared contents");
+    }
+
+    #[test]
+    fn tree_delete_removes_whole_trees_only_inside_root() {
+        let root = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let root_path = root.path().canonicalize().unwrap();
+        let tree = root_path.join("build");
+        fs::create_dir_all(tree.join("nested")).unwrap();
+        fs::write(tree.join("nested/out.bin"), b"artifact").unwrap();
+        let stray = outside.path().join("keep");
+        fs::create_dir(&stray).unwrap();
+
+        let report = tree_delete(
+            &[tree.clone(), stray.clone()],
+            &root_path,
+            &test_config(),
+            &AuditLog::new(),
+            TreeDeleteMode::Permanent,
+            &CancellationToken::new(),
+            &mut |_| {},
+        );
+
+        assert!(!tree.exists());
+        assert!(stray.exists());
+        assert_eq!(report.deleted_count, 3);
+        assert!(report.outcomes[0].deleted);
+        assert!(!report.outcomes[1].deleted);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn recheck_refuses_targets_swapped_for_system_symlinks() {
+        let root = tempfile::tempdir().unwrap();
+        let root_path = root.path().canonicalize().unwrap();
+        let config = test_config();
+
+        // The entry itself replaced by a link into /etc
+        let file = root_path.join("cache.bin");
+        fs::write(&file, b"cache").unwrap();
+        let checked = check_target(&file, &root_path, &config, None).unwrap();
+        fs::remove_file(&file).unwrap();
+        std::os::unix::fs::symlink("/etc/passwd", &file).unwrap();
+        assert!(recheck_target(&file, checked, &root_path, &config).is_err());
+
+        // A parent folder replaced, so the same name now resolves into /etc
+        let sub = root_path.join("sub");
+        let nested = sub.join("passwd");
+        fs::create_dir(&sub).unwrap();
+        fs::write(&nested, b"not really").unwrap();
+        let checked = check_target(&nested, &root_path, &config, None).unwrap();
+        fs::remove_file(&nested).unwrap();
+        fs::remove_dir(&sub).unwrap();
+        std::os::unix::fs::symlink("/etc", &sub).unwrap();
+        assert!(recheck_target(&nested, checked, &root_path, &config).is_err());
+    }
+
+    #[test]
+    fn deletes_files_below_paths_longer_than_max_path() {
+        let root = tempfile::tempdir().unwrap();
+        let root_path = root.path().canonicalize().unwrap();
+        let mut dir = root_path.clone();
+        while dir.to_string_lossy().len() < 260 {
+            dir.push("n".repeat(50));
+        }
+        fs::create_dir_all(prepare_path_for_io(&dir)).unwrap();
+        let target = dir.join("file.txt");
+        fs::write(prepare_path_for_io(&target), b"deep").unwrap();
+        assert!(target.to_string_lossy().len() > 260);
+
+        let config = test_config();
+        let report = safe_delete(std::slice::from_ref(&target), &root_path, &config, &AuditLog::new());
+
+        assert_eq!(report.deleted_count, 1, "{:?}", report.outcomes);
+        assert!(fs::symlink_metadata(prepare_path_for_io(&target)).is_err());
+    }
+}
+
@@ -0,0 +1,218 @@
+use std::path::{Path, PathBuf};
+use tokio_util::sync::CancellationToken;
+
+use crate::utils::walker::{DirWalker, WalkOptions};
+
+/// Directory names, lowercased, whose contents are cache or crash data
+const CACHE_DIR_NAMES: &[&str] = &[
+    "cache",
+    "caches",
+    ".cache",
+    "cache_data",
+    "code cache",
+    "gpucache",
+    "shadercache",
+    "grshadercache",
+    "cachestorage",
+    "crashpad",
+    "crash reports",
+    "crashdumps",
+    "minidumps",
+];
+
+/// A well-known package-manager or build-tool cache location
+#[derive(Debug, Clone)]
+pub struct DevCacheLocation {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl DevCacheLocation {
+    fn new(name: &str, path: PathBuf) -> Self {
+        Self {
+            name: name.to_string(),
+            path,
+        }
+    }
+}
+
+/// Built-in cache locations for the current platform relative to `home`
+pub fn known_locations(home: &Path) -> Vec<DevCacheLocation> {
+    let mut locations = vec![
+        DevCacheLocation::new("Cargo registry", home.join(".cargo").join("registry")),
+        DevCacheLocation::new("Cargo git checkouts", home.join(".cargo").join("git")),
+        DevCacheLocation::new("npm", home.join(".npm")),
+        DevCacheLocation::new("Maven", home.join(".m2").join("repository")),
+        DevCacheLocation::new("Gradle", home.join(".gradle").join("caches")),
+        DevCacheLocation::new("pnpm store", home.join(".pnpm-store")),
+    ];
+
+    if cfg!(target_os = "windows") {
+        let local = home.join("AppData").join("Local");
+        locations.extend([
+            DevCacheLocation::new("pip", local.join("pip").join("Cache")),
+            DevCacheLocation::new("Yarn", local.join("Yarn").join("Cache")),
+            DevCacheLocation::new("NuGet", home.join(".nuget").join("packages")),
+            DevCacheLocation::new("Go build", local.join("go-build")),
+        ]);
+    } else if cfg!(target_os = "macos") {
+        let caches = home.join("Library").join("Caches");
+        locations.extend([
+            DevCacheLocation::new("pip", caches.join("pip")),
+            DevCacheLocation::new("Yarn", caches.join("Yarn")),
+            DevCacheLocation::new("Homebrew", caches.join("Homebrew")),
+            DevCacheLocation::new("Go build", caches.join("go-build")),
+        ]);
+    } else {
+        let cache = home.join(".cache");
+        locations.extend([
+            DevCacheLocation::new("pip", cache.join("pip")),
+            DevCacheLocation::new("Yarn", cache.join("yarn")),
+            DevCacheLocation::new("Go build", cache.join("go-build")),
+        ]);
+    }
+
+    locations
+}
+
+/// Where Docker keeps images, containers and volumes, including the disk
+/// image of Docker Desktop's VM. Deleting below these loses data and can
+/// leave Docker unable to start, so none of it is treated as a cache.
+pub fn container_data_dirs(home: &Path) -> Vec<PathBuf> {
+    if cfg!(target_os = "windows") {
+        vec![
+            home.join("AppData").join("Local").join("Docker"),
+            PathBuf::from(r"C:\ProgramData\Docker"),
+            PathBuf::from(r"C:\ProgramData\DockerDesktop"),
+        ]
+    } else if cfg!(target_os = "macos") {
+        vec![
+            home.join("Library").join("Containers").join("com.docker.docker"),
+            home.join("Library").join("Group Containers").join("group.com.docker"),
+        ]
+    } else {
+        vec![
+            PathBuf::from("/var/lib/docker"),
+            PathBuf::from("/var/lib/containerd"),
+            home.join(".local").join("share").join("docker"),
+            home.join(".docker").join("desktop"),
+        ]
+    }
+}
+
+/// Turn a user-configured cache path into a location, expanding a leading `~`
+pub fn configured_location(entry: &str, home: &Path) -> DevCacheLocation {
+    let path = match entry.strip_prefix("~") {
+        Some(rest) => home.join(rest.trim_start_matches(['/', '\\'])),
+        None => PathBuf::from(entry),
+    };
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| entry.to_string());
+
+    DevCacheLocation { name, path }
+}
+
+/// A directory searched for oversized cache files
+#[derive(Debug, Clone)]
+pub struct CacheSearchRoot {
+    pub path: PathBuf,
+    /// Everything below is cache data, not just cache-named subdirectories
+    pub all_cache: bool,
+}
+
+/// A single file large enough to report on its own
+#[derive(Debug, Clone)]
+pub struct OversizedCacheFile {
+    pub path: PathBuf,
+    pub size: u64,
+    /// Application owning the cache, inferred from the folder below the root
+    pub app: Option<String>,
+}
+
+/// The per-user cache directory plus the app-data folders where browsers
+/// and Electron apps keep their caches
+pub fn cache_search_roots(home: &Path) -> Vec<CacheSearchRoot> {
+    let root = |path: PathBuf, all_cache| CacheSearchRoot { path, all_cache };
+    if cfg!(target_os = "windows") {
+        let app_data = home.join("AppData");
+        vec![
+            root(app_data.join("Local"), false),
+            root(app_data.join("Roaming"), false),
+        ]
+    } else if cfg!(target_os = "macos") {
+        let library = home.join("Library");
+        vec![
+            root(library.join("Caches"), true),
+            root(library.join("Application Support"), false),
+        ]
+    } else {
+        vec![root(home.join(".cache"), true), root(home.join(".config"), false)]
+    }
+}
+
+/// Files of at least `min_size` bytes in cache directories below `root`,
+/// leaving out anything under `exclude` (caches already reported whole).
+/// Stops early, returning what was found so far, once `cancel` fires.
+pub fn oversized_cache_files(
+    root: &CacheSearchRoot,
+    min_size: u64,
+    exclude: &[PathBuf],
+    cancel: &CancellationToken,
+) -> Vec<OversizedCacheFile> {
+    let options = WalkOptions {
+        stay_on_filesystem: true,
+        skip_network_drives: true,
+        ..WalkOptions::default()
+    };
+
+    let mut found = Vec::new();
+    for entry in DirWalker::new(&root.path, options) {
+        if cancel.is_cancelled() {
+            break;
+        }
+        let size = entry.metadata.len();
+        if !entry.is_file() || size < min_size {
+            continue;
+        }
+        if exclude.iter().any(|excluded| entry.path.starts_with(excluded)) {
+            continue;
+        }
+        let Ok(relative) = entry.path.strip_prefix(&root.path) else {
+            continue;
+        };
+        let in_cache_dir = root.all_cache
+            || relative.parent().is_some_and(|dirs| {
+                dirs.components().any(|dir| {
+                    let name = dir.as_os_str().to_string_lossy().to_lowercase();
+                    CACHE_DIR_NAMES.contains(&name.as_str())
+                })
+            });
+        if !in_cache_dir {
+            continue;
+        }
+
+        found.push(OversizedCacheFile {
+            app: owning_app(relative),
+            path: entry.path,
+            size,
+        });
+    }
+    found
+}
+
+/// The folder directly below the search root names the app, e.g. `Slack`
+/// or `com.spotify.client` (reduced to its last segment)
+fn owning_app(relative: &Path) -> Option<String> {
+    let mut components = relative.components();
+    let first = components.next()?.as_os_str().to_string_lossy().to_string();
+    // A file directly in the root has no owning folder
+    components.next()?;
+
+    let name = match first.rsplit_once('.') {
+        Some((prefix, last)) if prefix.contains('.') && !last.is_empty() => last.to_string(),
+        _ => first,
+    };
+    Some(name.trim_start_matches('.').to_string()).filter(|name| !name.is_empty())
+}
@@ -0,0 +1,137 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Who wrote a device backup
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceBackupKind {
+    /// iTunes, Finder or Apple Devices
+    Ios,
+    /// Samsung Smart Switch
+    Android,
+}
+
+/// A folder holding one subfolder per backed-up device
+#[derive(Debug, Clone)]
+pub struct DeviceBackupLocation {
+    pub kind: DeviceBackupKind,
+    pub path: PathBuf,
+}
+
+/// One device backup folder
+#[derive(Debug, Clone)]
+pub struct DeviceBackupCandidate {
+    pub kind: DeviceBackupKind,
+    pub path: PathBuf,
+    /// The device name recorded in the backup, else the folder name
+    pub device_name: String,
+    /// When the backup was last written: the date iOS records in
+    /// `Info.plist`, else the newest modification of the folder or its
+    /// direct entries
+    pub last_backup: Option<DateTime<Utc>>,
+}
+
+/// Backup folders for the current platform relative to `home`. Locations
+/// that do not exist are included; callers skip them.
+pub fn locations(home: &Path) -> Vec<DeviceBackupLocation> {
+    let ios = |path: PathBuf| DeviceBackupLocation {
+        kind: DeviceBackupKind::Ios,
+        path,
+    };
+    let smart_switch = DeviceBackupLocation {
+        kind: DeviceBackupKind::Android,
+        path: home
+            .join("Documents")
+            .join("Samsung")
+            .join("SmartSwitch")
+            .join("backup"),
+    };
+
+    if cfg!(target_os = "windows") {
+        vec![
+            // iTunes from apple.com
+            ios(home
+                .join("AppData")
+                .join("Roaming")
+                .join("Apple Computer")
+                .join("MobileSync")
+                .join("Backup")),
+            // iTunes and Apple Devices from the Microsoft Store
+            ios(home.join("Apple").join("MobileSync").join("Backup")),
+            smart_switch,
+        ]
+    } else if cfg!(target_os = "macos") {
+        vec![
+            ios(home
+                .join("Library")
+                .join("Application Support")
+                .join("MobileSync")
+                .join("Backup")),
+            smart_switch,
+        ]
+    } else {
+        Vec::new()
+    }
+}
+
+/// The backups directly below `location`
+pub fn backups(location: &DeviceBackupLocation) -> Vec<DeviceBackupCandidate> {
+    let Ok(entries) = fs::read_dir(&location.path) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|entry| {
+            let path = entry.path();
+            let folder_name = entry.file_name().to_string_lossy().to_string();
+            let info = match location.kind {
+                DeviceBackupKind::Ios => fs::read_to_string(path.join("Info.plist")).ok(),
+                DeviceBackupKind::Android => None,
+            };
+            let device_name = info
+                .as_deref()
+                .and_then(|info| plist_value(info, "Device Name", "string"))
+                .unwrap_or(folder_name);
+            let last_backup = info
+                .as_deref()
+                .and_then(|info| plist_value(info, "Last Backup Date", "date"))
+                .and_then(|date| DateTime::parse_from_rfc3339(&date).ok())
+                .map(|date| date.with_timezone(&Utc))
+                .or_else(|| newest_modification(&path).map(DateTime::<Utc>::from));
+            DeviceBackupCandidate {
+                kind: location.kind,
+                path,
+                device_name,
+                last_backup,
+            }
+        })
+        .collect()
+}
+
+/// The `<tag>` value following `<key>key</key>` in an XML property list.
+/// Binary property lists yield `None`.
+fn plist_value(plist: &str, key: &str, tag: &str) -> Option<String> {
+    let after_key = &plist[plist.find(&format!("<key>{}</key>", key))?..];
+    let open = format!("<{}>", tag);
+    let start = after_key.find(&open)? + open.len();
+    let end = start + after_key[start..].find(&format!("</{}>", tag))?;
+    // The value must belong to this key, not a later one
+    if after_key[..start].matches("<key>").count() > 1 {
+        return None;
+    }
+    Some(after_key[start..end].trim().to_string())
+}
+
+/// Backups are written into subfolders, so look one level in
+fn newest_modification(dir: &Path) -> Option<SystemTime> {
+    let own = fs::symlink_metadata(dir).and_then(|m| m.modified()).ok();
+    let children = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok()?.metadata().ok()?.modified().ok());
+    own.into_iter().chain(children).max()
+}
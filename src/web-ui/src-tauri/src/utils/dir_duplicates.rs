@@ -0,0 +1,287 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio_util::sync::CancellationToken;
+
+use crate::utils::walker::{DirWalker, SkippedPath, WalkOptions};
+
+/// One directory of the scanned tree. Children are recorded by index, and a
+/// parent always has a lower index than its children.
+struct DirNode {
+    path: PathBuf,
+    parent: Option<usize>,
+    /// Entries the walk yielded directly inside this directory
+    seen: usize,
+    /// Entries actually listed by the filesystem; `None` if unreadable
+    listed: Option<usize>,
+    files: Vec<(String, u64, PathBuf)>,
+    links: Vec<(String, String)>,
+    subdirs: Vec<usize>,
+    /// Every entry of the subtree was seen, so its hash describes all of it
+    complete: bool,
+    file_count: u64,
+    total_size: u64,
+    /// Hash of names, sizes and structure, without file contents
+    shape: Option<blake3::Hash>,
+}
+
+/// Directories whose whole subtrees are identical
+#[derive(Debug, Clone)]
+pub struct DirectoryDuplicates {
+    pub hash: String,
+    pub file_count: u64,
+    pub total_size: u64,
+    pub paths: Vec<PathBuf>,
+}
+
+/// Directory tree below a root, hashed bottom-up to find identical subtrees.
+///
+/// A directory is only ever compared if every entry below it was walked:
+/// subtrees that were skipped, hidden, too deep, unreadable or that contain
+/// special files make it and all its ancestors incomplete. Hashes cover the
+/// names, sizes and count of every entry, so two directories holding a
+/// different number of files never hash alike even when the files they
+/// share are identical.
+pub struct DirTree {
+    nodes: Vec<DirNode>,
+    pub skipped: Vec<SkippedPath>,
+    pub unreadable_entries: u64,
+}
+
+impl DirTree {
+    /// Walk `root`, returning `None` if cancelled
+    pub fn scan(root: &Path, options: WalkOptions, cancel: &CancellationToken) -> Option<Self> {
+        let mut nodes = vec![DirNode::new(root.to_path_buf(), None)];
+        let mut index: HashMap<PathBuf, usize> = HashMap::from([(root.to_path_buf(), 0)]);
+
+        let mut walker = DirWalker::new(root, options);
+        for entry in walker.by_ref() {
+            if cancel.is_cancelled() {
+                return None;
+            }
+            let Some(&parent) = entry.path.parent().and_then(|parent| index.get(parent)) else {
+                continue;
+            };
+            let name = entry
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            if entry.is_symlink() {
+                let target = fs::read_link(&entry.path)
+                    .map(|target| target.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                nodes[parent].links.push((name, target));
+            } else if entry.is_dir() {
+                let child = nodes.len();
+                index.insert(entry.path.clone(), child);
+                nodes.push(DirNode::new(entry.path, Some(parent)));
+                nodes[parent].subdirs.push(child);
+            } else if entry.is_file() {
+                nodes[parent]
+                    .files
+                    .push((name, entry.metadata.len(), entry.path));
+            } else {
+                // Sockets, pipes and devices cannot be compared
+                continue;
+            }
+            nodes[parent].seen += 1;
+        }
+
+        let mut tree = Self {
+            nodes,
+            unreadable_entries: walker.error_count(),
+            skipped: walker.into_skipped(),
+        };
+        tree.compute_shapes();
+        Some(tree)
+    }
+
+    pub fn directory_count(&self) -> u64 {
+        self.nodes.len() as u64
+    }
+
+    /// Complete, non-empty directories whose shape matches another's; only
+    /// these can be duplicates, so only their files need hashing
+    pub fn candidates(&self) -> Vec<usize> {
+        let mut by_shape: HashMap<blake3::Hash, Vec<usize>> = HashMap::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            if let (Some(shape), true) = (node.shape, node.total_size > 0) {
+                by_shape.entry(shape).or_default().push(i);
+            }
+        }
+        let mut candidates: Vec<usize> = by_shape
+            .into_values()
+            .filter(|dirs| dirs.len() > 1)
+            .flatten()
+            .collect();
+        candidates.sort_unstable();
+        candidates
+    }
+
+    /// Files anywhere below the given directories, each listed once
+    pub fn files_below(&self, dirs: &[usize]) -> Vec<(PathBuf, u64)> {
+        let mut included = vec![false; self.nodes.len()];
+        for &dir in dirs {
+            included[dir] = true;
+        }
+        let mut files = Vec::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            if let Some(parent) = node.parent {
+                included[i] |= included[parent];
+            }
+            if included[i] {
+                files.extend(node.files.iter().map(|(_, size, path)| (path.clone(), *size)));
+            }
+        }
+        files
+    }
+
+    /// Group `candidates` by content, given the hash of each of their files.
+    /// Groups whose members all sit inside larger duplicated directories
+    /// are left out, since removing the outer copy covers them.
+    pub fn duplicate_groups(
+        &self,
+        candidates: &[usize],
+        file_hashes: &HashMap<PathBuf, String>,
+    ) -> Vec<DirectoryDuplicates> {
+        let mut wanted = vec![false; self.nodes.len()];
+        for &dir in candidates {
+            wanted[dir] = true;
+        }
+        for i in 0..self.nodes.len() {
+            if let Some(parent) = self.nodes[i].parent {
+                wanted[i] |= wanted[parent];
+            }
+        }
+
+        let mut content: Vec<Option<blake3::Hash>> = vec![None; self.nodes.len()];
+        for i in (0..self.nodes.len()).rev() {
+            if wanted[i] && self.nodes[i].shape.is_some() {
+                let digest = |path: &Path, _| file_hashes.get(path).map(String::as_str);
+                content[i] = self.hash_node(i, digest, &content);
+            }
+        }
+
+        let mut by_content: HashMap<blake3::Hash, Vec<usize>> = HashMap::new();
+        for &dir in candidates {
+            if let Some(hash) = content[dir] {
+                by_content.entry(hash).or_default().push(dir);
+            }
+        }
+        let duplicated: HashSet<usize> = by_content
+            .values()
+            .filter(|dirs| dirs.len() > 1)
+            .flatten()
+            .copied()
+            .collect();
+
+        by_content
+            .into_iter()
+            .filter(|(_, dirs)| dirs.len() > 1)
+            .filter(|(_, dirs)| {
+                !dirs.iter().all(|&dir| {
+                    self.nodes[dir]
+                        .parent
+                        .is_some_and(|parent| duplicated.contains(&parent))
+                })
+            })
+            .map(|(hash, dirs)| {
+                let first = &self.nodes[dirs[0]];
+                let mut paths: Vec<PathBuf> =
+                    dirs.iter().map(|&dir| self.nodes[dir].path.clone()).collect();
+                paths.sort();
+                DirectoryDuplicates {
+                    hash: hash.to_hex().to_string(),
+                    file_count: first.file_count,
+                    total_size: first.total_size,
+                    paths,
+                }
+            })
+            .collect()
+    }
+
+    /// Children are always after their parent, so a reverse pass sees every
+    /// subdirectory before the directory containing it
+    fn compute_shapes(&mut self) {
+        let mut shapes: Vec<Option<blake3::Hash>> = vec![None; self.nodes.len()];
+        for i in (0..self.nodes.len()).rev() {
+            let node = &self.nodes[i];
+            let complete = node.listed == Some(node.seen)
+                && node.subdirs.iter().all(|&child| self.nodes[child].complete);
+            let file_count = node.files.len() as u64
+                + node.subdirs.iter().map(|&c| self.nodes[c].file_count).sum::<u64>();
+            let total_size = node.files.iter().map(|(_, size, _)| size).sum::<u64>()
+                + node.subdirs.iter().map(|&c| self.nodes[c].total_size).sum::<u64>();
+
+            let shape = if complete {
+                self.hash_node(i, |_, _| Some(""), &shapes)
+            } else {
+                None
+            };
+            shapes[i] = shape;
+
+            let node = &mut self.nodes[i];
+            node.complete = complete;
+            node.file_count = file_count;
+            node.total_size = total_size;
+            node.shape = shape;
+        }
+    }
+
+    /// Hash a directory's entries in name order. Files contribute their name,
+    /// size and whatever `file_digest` returns; `None` from it, or a missing
+    /// subdirectory hash, leaves the directory unhashed.
+    fn hash_node<'a>(
+        &self,
+        i: usize,
+        file_digest: impl Fn(&Path, u64) -> Option<&'a str>,
+        subdir_hashes: &[Option<blake3::Hash>],
+    ) -> Option<blake3::Hash> {
+        let node = &self.nodes[i];
+        let mut entries = Vec::with_capacity(node.seen);
+        for (name, size, path) in &node.files {
+            entries.push(format!("f\0{}\0{}\0{}", name, size, file_digest(path, *size)?));
+        }
+        for (name, target) in &node.links {
+            entries.push(format!("l\0{}\0{}", name, target));
+        }
+        for &child in &node.subdirs {
+            let name = self.nodes[child]
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            entries.push(format!("d\0{}\0{}", name, subdir_hashes[child]?.to_hex()));
+        }
+        entries.sort_unstable();
+
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&(entries.len() as u64).to_le_bytes());
+        for entry in &entries {
+            hasher.update(entry.as_bytes());
+            hasher.update(b"\n");
+        }
+        Some(hasher.finalize())
+    }
+}
+
+impl DirNode {
+    fn new(path: PathBuf, parent: Option<usize>) -> Self {
+        let listed = fs::read_dir(&path).ok().map(|entries| entries.count());
+        Self {
+            path,
+            parent,
+            seen: 0,
+            listed,
+            files: Vec::new(),
+            links: Vec::new(),
+            subdirs: Vec::new(),
+            complete: false,
+            file_count: 0,
+            total_size: 0,
+            shape: None,
+        }
+    }
+}
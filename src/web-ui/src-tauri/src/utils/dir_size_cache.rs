@@ -0,0 +1,261 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+use crate::utils::walker::{allocated_size, DirectorySize};
+
+const CACHE_FILE_NAME: &str = "dir_sizes.json";
+
+/// Entries not revalidated for this long are dropped on flush
+const PRUNE_AFTER_SECS: i64 = 30 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedDirectory {
+    /// Directory mtime in nanoseconds since the Unix epoch
+    mtime: i128,
+    /// Size and count of the regular files directly inside the directory
+    own_bytes: u64,
+    /// Disk space of those files; absent in entries written before it was
+    /// tracked, which are re-read
+    #[serde(default)]
+    own_allocated: Option<u64>,
+    own_files: u64,
+    /// Names of the real (non-symlink) subdirectories
+    subdirs: Vec<String>,
+    computed_at: i64,
+    checked_at: i64,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<String, CachedDirectory>,
+    dirty: bool,
+}
+
+/// Persistent per-directory size cache keyed on directory mtime.
+///
+/// A directory's mtime changes when entries are added, removed or renamed,
+/// so an unchanged directory can reuse its cached file listing without being
+/// read again. Totals are always re-summed bottom-up from the children, so a
+/// change deep in the tree invalidates every ancestor's total automatically.
+/// Files that grow in place do not touch the directory mtime; entries older
+/// than `analysis.size_cache_ttl_seconds` are therefore re-read to bound
+/// that staleness.
+#[derive(Debug)]
+pub struct DirSizeCache {
+    path: PathBuf,
+    state: Mutex<CacheState>,
+}
+
+impl DirSizeCache {
+    pub fn load(cache_dir: &Path) -> Self {
+        let path = cache_dir.join(CACHE_FILE_NAME);
+        let entries = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                warn!("Discarding unreadable directory size cache: {}", e);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+
+        Self {
+            path,
+            state: Mutex::new(CacheState {
+                entries,
+                dirty: false,
+            }),
+        }
+    }
+
+    /// Total size of the regular files under `root`, reusing cached listings
+    /// for directories whose mtime is unchanged. Symlinks are never followed.
+    pub fn measure(
+        &self,
+        root: &Path,
+        ttl_seconds: u64,
+        cancel: &CancellationToken,
+    ) -> DirectorySize {
+        self.measure_dir(root, ttl_seconds, cancel)
+            .unwrap_or_default()
+    }
+
+    fn measure_dir(
+        &self,
+        dir: &Path,
+        ttl_seconds: u64,
+        cancel: &CancellationToken,
+    ) -> Option<DirectorySize> {
+        if cancel.is_cancelled() {
+            return None;
+        }
+
+        let metadata = fs::symlink_metadata(dir).ok()?;
+        if !metadata.is_dir() {
+            return None;
+        }
+        let mtime = mtime_nanos(&metadata)?;
+        let key = dir.to_string_lossy().to_string();
+        let now = Utc::now().timestamp();
+
+        let cached = self.lock().entries.get_mut(&key).and_then(|entry| {
+            let fresh = now - entry.computed_at < ttl_seconds as i64;
+            let complete = entry.own_allocated.is_some();
+            (entry.mtime == mtime && fresh && complete).then(|| {
+                entry.checked_at = now;
+                entry.clone()
+            })
+        });
+
+        let listing = match cached {
+            Some(entry) => entry,
+            None => {
+                let entry = read_listing(dir, mtime, now)?;
+                let mut state = self.lock();
+                state.entries.insert(key, entry.clone());
+                state.dirty = true;
+                entry
+            }
+        };
+
+        let mut size = DirectorySize {
+            bytes: listing.own_bytes,
+            allocated: listing.own_allocated.unwrap_or(listing.own_bytes),
+            files: listing.own_files,
+        };
+        for subdir in &listing.subdirs {
+            if let Some(child) = self.measure_dir(&dir.join(subdir), ttl_seconds, cancel) {
+                size.bytes += child.bytes;
+                size.allocated += child.allocated;
+                size.files += child.files;
+            }
+        }
+
+        Some(size)
+    }
+
+    /// Persist the cache if anything changed, dropping long-unused entries
+    pub fn flush(&self) -> anyhow::Result<()> {
+        let mut state = self.lock();
+        if !state.dirty {
+            return Ok(());
+        }
+
+        let cutoff = Utc::now().timestamp() - PRUNE_AFTER_SECS;
+        state.entries.retain(|_, entry| entry.checked_at >= cutoff);
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string(&state.entries)?)?;
+        state.dirty = false;
+
+        Ok(())
+    }
+
+    /// File the entries are persisted to
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Drop every entry and delete the file, returning the bytes it took
+    pub fn clear(&self) -> std::io::Result<u64> {
+        let mut state = self.lock();
+        state.entries.clear();
+        state.dirty = false;
+        let size = match fs::metadata(&self.path) {
+            Ok(metadata) => metadata.len(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+        fs::remove_file(&self.path)?;
+        Ok(size)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Modification time in nanoseconds since the Unix epoch, negative before it
+pub fn mtime_nanos(metadata: &fs::Metadata) -> Option<i128> {
+    let modified = metadata.modified().ok()?;
+    let nanos = match modified.duration_since(UNIX_EPOCH) {
+        Ok(after) => after.as_nanos() as i128,
+        Err(before) => -(before.duration().as_nanos() as i128),
+    };
+    Some(nanos)
+}
+
+/// Read one directory level, summing files and collecting real subdirectories
+fn read_listing(dir: &Path, mtime: i128, now: i64) -> Option<CachedDirectory> {
+    let mut listing = CachedDirectory {
+        mtime,
+        own_bytes: 0,
+        own_allocated: Some(0),
+        own_files: 0,
+        subdirs: Vec::new(),
+        computed_at: now,
+        checked_at: now,
+    };
+
+    for entry in fs::read_dir(dir).ok()?.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            listing
+                .subdirs
+                .push(entry.file_name().to_string_lossy().to_string());
+        } else if file_type.is_file() {
+            if let Ok(metadata) = entry.metadata() {
+                listing.own_bytes += metadata.len();
+                let allocated = allocated_size(&entry.path(), &metadata);
+                listing.own_allocated = listing.own_allocated.map(|total| total + allocated);
+                listing.own_files += 1;
+            }
+        }
+    }
+
+    Some(listing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: u64 = 3600;
+
+    #[test]
+    fn deep_change_updates_every_ancestor_total() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let tree = tempfile::tempdir().unwrap();
+        let deep = tree.path().join("a/b/c");
+        fs::create_dir_all(&deep).unwrap();
+        fs::write(tree.path().join("top.txt"), vec![0u8; 10]).unwrap();
+        fs::write(deep.join("old.txt"), vec![0u8; 100]).unwrap();
+        let cache = DirSizeCache::load(cache_dir.path());
+        let cancel = CancellationToken::new();
+        for dir in [tree.path(), &tree.path().join("a"), &tree.path().join("a/b")] {
+            cache.measure(dir, TTL, &cancel);
+        }
+
+        fs::write(deep.join("new.txt"), vec![0u8; 1000]).unwrap();
+
+        let expected = [
+            (tree.path().to_path_buf(), 1110),
+            (tree.path().join("a"), 1100),
+            (tree.path().join("a/b"), 1100),
+        ];
+        for (dir, bytes) in expected {
+            let size = cache.measure(&dir, TTL, &cancel);
+            assert_eq!(size.bytes, bytes, "stale total for {}", dir.display());
+        }
+        assert_eq!(cache.measure(tree.path(), TTL, &cancel).files, 3);
+    }
+}
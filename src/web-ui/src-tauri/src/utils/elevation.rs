@@ -0,0 +1,207 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{info, warn};
+
+use crate::utils::config::SecurityConfig;
+use crate::utils::deletion::remove_link;
+#[cfg(windows)]
+use crate::utils::paths;
+use crate::utils::security::SecurityValidator;
+
+/// Argument that makes the executable act as the elevated delete helper
+/// instead of starting the UI; followed by the request file and its SHA-256
+/// digest
+pub const ELEVATED_DELETE_ARG: &str = "--elevated-delete";
+
+/// What the elevated helper is asked to delete, with the root and settings
+/// it re-checks every path against before touching it
+#[derive(Debug, Serialize, Deserialize)]
+struct HelperRequest {
+    scan_root: PathBuf,
+    security: SecurityConfig,
+    paths: Vec<PathBuf>,
+}
+
+/// pkexec exit codes for a dismissed or failed authentication dialog
+#[cfg(all(unix, not(target_os = "macos")))]
+const PKEXEC_NOT_AUTHORIZED: [i32; 2] = [126, 127];
+
+/// Delete `paths` with administrator rights after an OS elevation prompt,
+/// returning the name of the helper that ran.
+///
+/// This blocks until the prompt is answered and the helper exits. Partial
+/// failures are not reported here; callers check which paths are gone.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn delete_elevated(
+    paths: &[PathBuf],
+    _scan_root: &Path,
+    _config: &SecurityConfig,
+) -> io::Result<&'static str> {
+    // Absolute path so the elevated side never resolves `rm` through PATH
+    let status = Command::new("pkexec")
+        .args(["/bin/rm", "-f", "-d", "--"])
+        .args(paths)
+        .status()?;
+
+    match status.code() {
+        Some(code) if PKEXEC_NOT_AUTHORIZED.contains(&code) => Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "Elevation was cancelled or not authorized",
+        )),
+        _ => Ok("pkexec"),
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn delete_elevated(
+    paths: &[PathBuf],
+    _scan_root: &Path,
+    _config: &SecurityConfig,
+) -> io::Result<&'static str> {
+    let mut script = String::from("/bin/rm -f -d --");
+    for path in paths {
+        script.push(' ');
+        script.push_str(&shell_quote(path));
+    }
+    let apple_script = format!(
+        "do shell script \"{}\" with administrator privileges",
+        script.replace('\\', "\\\\").replace('"', "\\\"")
+    );
+
+    let output = Command::new("osascript")
+        .args(["-e", &apple_script])
+        .output()?;
+    // -128 is AppleScript's "User canceled"
+    if String::from_utf8_lossy(&output.stderr).contains("-128") {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "Elevation was cancelled",
+        ));
+    }
+    Ok("osascript")
+}
+
+/// Relaunch this executable through a UAC prompt in helper mode, handing it
+/// the request in a file under the user's own local app data. The file's
+/// digest travels on the command line, so the helper refuses a request that
+/// was changed after it was written.
+#[cfg(windows)]
+pub fn delete_elevated(
+    paths: &[PathBuf],
+    scan_root: &Path,
+    config: &SecurityConfig,
+) -> io::Result<&'static str> {
+    let exe = std::env::current_exe()?;
+    // Unlike the temp folder, local app data is only writable by this user
+    let dir = paths::project_dirs()
+        .map(|dirs| dirs.data_local_dir().join("elevation"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No local app data folder"))?;
+    fs::create_dir_all(&dir)?;
+    let request = HelperRequest {
+        scan_root: scan_root.to_path_buf(),
+        security: config.clone(),
+        paths: paths.to_vec(),
+    };
+    let contents = serde_json::to_vec(&request)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let list = dir.join(format!("elevated-delete-{}.json", uuid::Uuid::new_v4().simple()));
+    fs::write(&list, &contents)?;
+
+    let quote = |path: &Path| path.to_string_lossy().replace('\'', "''");
+    let script = format!(
+        "Start-Process -FilePath '{}' -Verb RunAs -Wait -ArgumentList '{}','\"{}\"','{}'",
+        quote(&exe),
+        ELEVATED_DELETE_ARG,
+        quote(&list),
+        digest(&contents)
+    );
+    let status = Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .status();
+    let _ = fs::remove_file(&list);
+
+    // Start-Process throws, failing the shell, when the UAC prompt is declined
+    if status?.success() {
+        Ok("uac")
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "Elevation was cancelled or not authorized",
+        ))
+    }
+}
+
+/// Lowercase hex SHA-256 of a helper request file
+fn digest(contents: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(contents))
+}
+
+#[cfg(target_os = "macos")]
+fn shell_quote(path: &Path) -> String {
+    format!("'{}'", path.to_string_lossy().replace('\'', "'\\''"))
+}
+
+/// Entry point for helper mode: when the process was started with
+/// `ELEVATED_DELETE_ARG`, delete the requested paths and return the exit
+/// code so `main` can exit without starting the UI.
+///
+/// The request is refused unless it matches the digest it was started with,
+/// and each path is checked against the scan root and protected patterns
+/// again here, since the unelevated side cannot be trusted to have done so.
+pub fn run_helper_mode() -> Option<i32> {
+    let mut args = std::env::args_os().skip(1);
+    if args.next()? != ELEVATED_DELETE_ARG {
+        return None;
+    }
+    let list = PathBuf::from(args.next()?);
+    let expected = args.next().map(|arg| arg.to_string_lossy().to_lowercase());
+
+    let contents = match fs::read(&list) {
+        Ok(contents) => contents,
+        Err(e) => {
+            warn!("Cannot read elevated delete request {}: {}", list.display(), e);
+            return Some(2);
+        }
+    };
+    if expected.as_deref() != Some(digest(&contents).as_str()) {
+        warn!("Elevated delete request {} does not match its digest", list.display());
+        return Some(2);
+    }
+    let request: HelperRequest = match serde_json::from_slice(&contents) {
+        Ok(request) => request,
+        Err(e) => {
+            warn!("Invalid elevated delete request {}: {}", list.display(), e);
+            return Some(2);
+        }
+    };
+
+    let mut failed = 0;
+    for path in &request.paths {
+        let result = SecurityValidator::validate_within_root(path, &request.scan_root)
+            .and_then(|()| SecurityValidator::validate_deletion_target(path, &request.security))
+            .map_err(|reason| io::Error::new(io::ErrorKind::PermissionDenied, reason))
+            .and_then(|()| remove_entry(path));
+        if let Err(e) = result {
+            warn!("Elevated delete of {} failed: {}", path.display(), e);
+            failed += 1;
+        }
+    }
+    info!("Elevated delete helper finished with {} failure(s)", failed);
+    Some(if failed == 0 { 0 } else { 1 })
+}
+
+/// Remove a file, link or empty directory, never recursing
+fn remove_entry(path: &Path) -> io::Result<()> {
+    let file_type = fs::symlink_metadata(path)?.file_type();
+    if file_type.is_symlink() {
+        remove_link(path)
+    } else if file_type.is_dir() {
+        fs::remove_dir(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
@@ -0,0 +1,53 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Throughput is averaged over this much recent history
+const WINDOW: Duration = Duration::from_secs(30);
+/// No estimate until progress has been observed for this long; early rates
+/// are dominated by startup work and swing wildly
+const MIN_ELAPSED: Duration = Duration::from_secs(3);
+const MIN_SAMPLES: usize = 3;
+/// Samples closer together than this replace each other instead of piling up
+const MIN_SAMPLE_SPACING: Duration = Duration::from_millis(250);
+
+/// Estimates time remaining from a rolling average of recent throughput
+#[derive(Debug, Clone, Default)]
+pub struct EtaEstimator {
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl EtaEstimator {
+    /// Record that `done` of `total` units are finished and return the
+    /// estimated seconds remaining, if there is enough history for one
+    pub fn record(&mut self, done: u64, total: u64) -> Option<u64> {
+        let now = Instant::now();
+        if let Some(&(last, _)) = self.samples.back() {
+            if now.duration_since(last) < MIN_SAMPLE_SPACING && self.samples.len() > 1 {
+                self.samples.pop_back();
+            }
+        }
+        self.samples.push_back((now, done));
+        while self
+            .samples
+            .front()
+            .is_some_and(|&(at, _)| now.duration_since(at) > WINDOW)
+            && self.samples.len() > MIN_SAMPLES
+        {
+            self.samples.pop_front();
+        }
+
+        self.eta_seconds(total)
+    }
+
+    fn eta_seconds(&self, total: u64) -> Option<u64> {
+        let (&(first_at, first_done), &(last_at, last_done)) =
+            (self.samples.front()?, self.samples.back()?);
+        let elapsed = last_at.duration_since(first_at);
+        if self.samples.len() < MIN_SAMPLES || elapsed < MIN_ELAPSED || last_done > total {
+            return None;
+        }
+
+        let rate = last_done.saturating_sub(first_done) as f64 / elapsed.as_secs_f64();
+        (rate > 0.0).then(|| ((total - last_done) as f64 / rate).ceil() as u64)
+    }
+}
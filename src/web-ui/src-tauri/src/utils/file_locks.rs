@@ -0,0 +1,308 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+#[cfg(target_os = "linux")]
+use std::collections::HashSet;
+
+/// Reason reported for files skipped because another process holds them open
+pub const FILE_IN_USE_REASON: &str = "File in use by another process";
+
+/// A process with an open handle to a file
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct LockingProcess {
+    pub pid: u32,
+    pub name: String,
+}
+
+/// Processes other than this one holding `path` open, via the Restart
+/// Manager on Windows, `/proc/*/fd` on Linux and `lsof` on macOS. Processes
+/// of other users are only visible with enough privileges.
+pub fn locking_processes(path: &Path) -> std::io::Result<Vec<LockingProcess>> {
+    locking_processes_any(&[path.to_path_buf()])
+}
+
+/// Processes holding any of `paths` open, each listed once
+pub fn locking_processes_any(paths: &[PathBuf]) -> std::io::Result<Vec<LockingProcess>> {
+    if paths.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        Ok(linux_locking_processes(paths))
+    }
+
+    #[cfg(windows)]
+    {
+        windows_locking_processes(paths)
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        lsof_locking_processes(paths)
+    }
+
+    #[cfg(not(any(target_os = "linux", windows, target_os = "macos")))]
+    {
+        Ok(Vec::new())
+    }
+}
+
+/// Snapshot of files currently held open by other processes.
+///
+/// Built once per delete batch so the per-file check stays cheap. On Linux
+/// this indexes `/proc/*/fd`; Windows and macOS probe each file on demand.
+#[derive(Debug, Default)]
+pub struct OpenFileIndex {
+    #[cfg(target_os = "linux")]
+    open_files: HashSet<PathBuf>,
+}
+
+impl OpenFileIndex {
+    /// Capture the set of currently open files
+    pub fn capture() -> Self {
+        #[cfg(target_os = "linux")]
+        {
+            Self {
+                open_files: linux_open_files(),
+            }
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            Self::default()
+        }
+    }
+
+    /// Check whether a regular file is open or locked by another process
+    pub fn is_in_use(&self, path: &Path) -> bool {
+        #[cfg(target_os = "linux")]
+        {
+            let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+            self.open_files.contains(&canonical)
+        }
+
+        #[cfg(windows)]
+        {
+            windows_is_locked(path)
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            lsof_reports_open(path)
+        }
+
+        #[cfg(not(any(target_os = "linux", windows, target_os = "macos")))]
+        {
+            let _ = path;
+            false
+        }
+    }
+}
+
+/// Collect every file referenced by an open descriptor of another process
+#[cfg(target_os = "linux")]
+fn linux_open_files() -> HashSet<PathBuf> {
+    let own_pid = std::process::id().to_string();
+    let mut open_files = HashSet::new();
+
+    let Ok(processes) = std::fs::read_dir("/proc") else {
+        return open_files;
+    };
+
+    for process in processes.flatten() {
+        let name = process.file_name();
+        let name = name.to_string_lossy();
+        if name == own_pid || !name.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+
+        // Descriptors of other users' processes are unreadable; skip them
+        let Ok(descriptors) = std::fs::read_dir(process.path().join("fd")) else {
+            continue;
+        };
+
+        for descriptor in descriptors.flatten() {
+            if let Ok(target) = std::fs::read_link(descriptor.path()) {
+                if target.is_absolute() {
+                    open_files.insert(target);
+                }
+            }
+        }
+    }
+
+    open_files
+}
+
+/// Attempt an exclusive open; a sharing violation means another handle exists
+#[cfg(windows)]
+fn windows_is_locked(path: &Path) -> bool {
+    use std::os::windows::fs::OpenOptionsExt;
+
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    const ERROR_LOCK_VIOLATION: i32 = 33;
+
+    match std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .share_mode(0)
+        .open(path)
+    {
+        Ok(_) => false,
+        Err(e) => matches!(
+            e.raw_os_error(),
+            Some(ERROR_SHARING_VIOLATION) | Some(ERROR_LOCK_VIOLATION)
+        ),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn lsof_reports_open(path: &Path) -> bool {
+    std::process::Command::new("lsof")
+        .arg("-t")
+        .arg("--")
+        .arg(path)
+        .output()
+        .map(|output| output.status.success() && !output.stdout.is_empty())
+        .unwrap_or(false)
+}
+
+#[cfg(target_os = "linux")]
+fn linux_locking_processes(paths: &[PathBuf]) -> Vec<LockingProcess> {
+    let canonical: HashSet<PathBuf> = paths
+        .iter()
+        .map(|path| path.canonicalize().unwrap_or_else(|_| path.clone()))
+        .collect();
+    let own_pid = std::process::id();
+
+    let Ok(processes) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    processes
+        .flatten()
+        .filter_map(|process| {
+            let pid: u32 = process.file_name().to_string_lossy().parse().ok()?;
+            if pid == own_pid {
+                return None;
+            }
+            let holds_file = std::fs::read_dir(process.path().join("fd"))
+                .ok()?
+                .flatten()
+                .any(|descriptor| {
+                    std::fs::read_link(descriptor.path())
+                        .is_ok_and(|target| canonical.contains(&target))
+                });
+            holds_file.then(|| LockingProcess {
+                pid,
+                name: std::fs::read_to_string(process.path().join("comm"))
+                    .map(|name| name.trim().to_string())
+                    .unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/// Ask the Restart Manager which processes use the file
+#[cfg(windows)]
+fn windows_locking_processes(paths: &[PathBuf]) -> std::io::Result<Vec<LockingProcess>> {
+    use std::io;
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Foundation::{ERROR_MORE_DATA, ERROR_SUCCESS};
+    use windows_sys::Win32::System::RestartManager::{
+        RmEndSession, RmGetList, RmRegisterResources, RmStartSession, CCH_RM_SESSION_KEY,
+        RM_PROCESS_INFO,
+    };
+
+    let check = |status: u32| {
+        if status == ERROR_SUCCESS {
+            Ok(())
+        } else {
+            Err(io::Error::from_raw_os_error(status as i32))
+        }
+    };
+
+    let mut session = 0u32;
+    let mut key = [0u16; CCH_RM_SESSION_KEY as usize + 1];
+    // SAFETY: both out-pointers are valid for the duration of the call
+    check(unsafe { RmStartSession(&mut session, 0, key.as_mut_ptr()) })?;
+
+    let listed = (|| {
+        let wide: Vec<Vec<u16>> = paths
+            .iter()
+            .map(|path| path.as_os_str().encode_wide().chain(Some(0)).collect())
+            .collect();
+        let files: Vec<*const u16> = wide.iter().map(|name| name.as_ptr()).collect();
+        // SAFETY: `files` points at NUL-terminated strings that outlive the call
+        check(unsafe {
+            RmRegisterResources(
+                session,
+                files.len() as u32,
+                files.as_ptr(),
+                0,
+                std::ptr::null(),
+                0,
+                std::ptr::null(),
+            )
+        })?;
+
+        let mut infos: Vec<RM_PROCESS_INFO> = Vec::new();
+        loop {
+            let mut needed = 0u32;
+            let mut count = infos.len() as u32;
+            let mut reasons = 0u32;
+            // SAFETY: `infos` has room for `count` entries
+            let status = unsafe {
+                RmGetList(session, &mut needed, &mut count, infos.as_mut_ptr(), &mut reasons)
+            };
+            if status == ERROR_MORE_DATA {
+                // SAFETY: RM_PROCESS_INFO is plain data, valid when zeroed
+                infos = vec![unsafe { std::mem::zeroed() }; needed as usize];
+                continue;
+            }
+            check(status)?;
+            infos.truncate(count as usize);
+            return Ok(infos);
+        }
+    })();
+    // SAFETY: `session` was started above
+    unsafe { RmEndSession(session) };
+
+    Ok(listed?
+        .iter()
+        .map(|info| {
+            let name = &info.strAppName;
+            let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+            LockingProcess {
+                pid: info.Process.dwProcessId,
+                name: String::from_utf16_lossy(&name[..len]),
+            }
+        })
+        .collect())
+}
+
+/// Parse `lsof -F pc` output: `p<pid>` and `c<command>` lines per process
+#[cfg(target_os = "macos")]
+fn lsof_locking_processes(paths: &[PathBuf]) -> std::io::Result<Vec<LockingProcess>> {
+    let output = std::process::Command::new("lsof")
+        .args(["-F", "pc", "--"])
+        .args(paths)
+        .output()?;
+    let own_pid = std::process::id();
+
+    let mut processes: Vec<LockingProcess> = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(pid) = line.strip_prefix('p').and_then(|pid| pid.parse().ok()) {
+            processes.push(LockingProcess {
+                pid,
+                name: String::new(),
+            });
+        } else if let (Some(name), Some(process)) = (line.strip_prefix('c'), processes.last_mut())
+        {
+            process.name = name.to_string();
+        }
+    }
+    processes.retain(|process| process.pid != own_pid);
+    processes.sort_by_key(|process| process.pid);
+    processes.dedup_by_key(|process| process.pid);
+    Ok(processes)
+}
@@ -0,0 +1,110 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::utils::platform::{available_space, filesystem_type, is_copy_on_write_filesystem};
+
+/// Smallest gap between measured and expected space worth explaining;
+/// other programs change free space by this much all the time
+const MIN_DIVERGENCE_BYTES: i64 = 1024 * 1024;
+/// Gaps within this fraction of the expected amount are not explained
+const DIVERGENCE_RATIO: f64 = 0.1;
+
+/// Why freed space may stay in use on the volume after a deletion
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Retention {
+    /// Nothing known keeps the data around
+    None,
+    /// Entries were moved to the trash
+    Trash,
+    /// Backups of the entries were kept first
+    Backups,
+}
+
+/// Free space on the volume of a cleanup, measured before and after it
+#[derive(Debug, Clone, Serialize)]
+pub struct FreeSpaceChange {
+    pub before: u64,
+    pub after: u64,
+    /// Measured change in available space; negative if the volume filled
+    /// up in the meantime
+    pub delta: i64,
+    /// Summed sizes of the deleted entries
+    pub expected: u64,
+    /// Why `delta` differs noticeably from `expected`, if it does
+    pub explanation: Option<String>,
+}
+
+/// Free space recorded on the volume holding a path before a deletion
+#[derive(Debug, Clone)]
+pub struct FreeSpaceProbe {
+    path: PathBuf,
+    before: Option<u64>,
+}
+
+impl FreeSpaceProbe {
+    pub fn start(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            before: available_space(path),
+        }
+    }
+
+    /// Measure again and compare with the `expected` bytes freed. `None`
+    /// when the volume could not be measured either time.
+    pub fn finish(self, expected: u64, retention: Retention) -> Option<FreeSpaceChange> {
+        let before = self.before?;
+        // The target itself may be gone, so fall back to its parents
+        let after = self.path.ancestors().find_map(available_space)?;
+        let delta = after as i64 - before as i64;
+
+        let explanation =
+            diverges(delta, expected).then(|| explain(&self.path, delta, expected, retention));
+        Some(FreeSpaceChange {
+            before,
+            after,
+            delta,
+            expected,
+            explanation,
+        })
+    }
+}
+
+fn diverges(delta: i64, expected: u64) -> bool {
+    let expected = expected as i64;
+    let tolerance = MIN_DIVERGENCE_BYTES.max((expected as f64 * DIVERGENCE_RATIO) as i64);
+    (delta - expected).abs() > tolerance
+}
+
+fn explain(path: &Path, delta: i64, expected: u64, retention: Retention) -> String {
+    if delta > expected as i64 && retention == Retention::Trash {
+        return "The trash is on another volume, so moving entries there freed space here; \
+                trashed entries are not counted as freed"
+            .to_string();
+    }
+    if delta > expected as i64 {
+        return "More space was freed than the deleted files add up to: files occupy whole \
+                disk blocks, and other programs may have released space at the same time"
+            .to_string();
+    }
+
+    match retention {
+        Retention::Trash => "Entries were moved to the trash, which still holds their \
+                             data; the space is reclaimed once the trash is emptied"
+            .to_string(),
+        Retention::Backups => "Backups of the deleted files are kept on this volume (as hard \
+                               links where possible); the space is reclaimed once those \
+                               backups are removed"
+            .to_string(),
+        Retention::None
+            if filesystem_type(path).is_some_and(|fs| is_copy_on_write_filesystem(&fs)) =>
+        {
+            "Less space was freed than expected: snapshots on this copy-on-write volume may \
+             still reference the deleted data, and other programs may have written to it"
+                .to_string()
+        }
+        Retention::None => "Less space was freed than expected: some files may have had other \
+                            hard links or still be open in another program, and other programs \
+                            may have written to the volume meanwhile"
+            .to_string(),
+    }
+}
@@ -0,0 +1,69 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+const GITIGNORE_FILE_NAME: &str = ".gitignore";
+
+/// `.gitignore` rules discovered while walking a tree.
+///
+/// Rules are loaded per directory as the walk reaches it; because the walk
+/// yields a directory before reading its children, a directory's rules are
+/// always in place before any of its entries are checked.
+pub struct GitignoreRules {
+    root: PathBuf,
+    matchers: HashMap<PathBuf, Gitignore>,
+}
+
+impl GitignoreRules {
+    /// Start with the rules in `root/.gitignore`, if any
+    pub fn new(root: &Path) -> Self {
+        let mut rules = Self {
+            root: root.to_path_buf(),
+            matchers: HashMap::new(),
+        };
+        rules.load_dir(root);
+        rules
+    }
+
+    /// Load `dir/.gitignore` if the directory has one
+    pub fn load_dir(&mut self, dir: &Path) {
+        let file = dir.join(GITIGNORE_FILE_NAME);
+        if !file.is_file() {
+            return;
+        }
+
+        let mut builder = GitignoreBuilder::new(dir);
+        if let Some(e) = builder.add(&file) {
+            debug!("Partially invalid {}: {}", file.display(), e);
+        }
+        match builder.build() {
+            Ok(matcher) => {
+                self.matchers.insert(dir.to_path_buf(), matcher);
+            }
+            Err(e) => debug!("Ignoring unreadable {}: {}", file.display(), e),
+        }
+    }
+
+    /// Whether the nearest applicable `.gitignore` excludes `path`, either
+    /// directly or through an ignored parent directory
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let Some(parent) = path.parent() else {
+            return false;
+        };
+
+        // Deeper .gitignore files take precedence over shallower ones
+        for dir in parent.ancestors().take_while(|dir| dir.starts_with(&self.root)) {
+            if let Some(matcher) = self.matchers.get(dir) {
+                match matcher.matched_path_or_any_parents(path, is_dir) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => return false,
+                    Match::None => {}
+                }
+            }
+        }
+
+        false
+    }
+}
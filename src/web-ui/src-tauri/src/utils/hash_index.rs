@@ -0,0 +1,150 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::warn;
+
+use crate::utils::dir_size_cache::mtime_nanos;
+
+const INDEX_FILE_NAME: &str = "file_hashes.json";
+
+/// Entries not used for this long are dropped on flush
+const PRUNE_AFTER_SECS: i64 = 30 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedHash {
+    size: u64,
+    /// File mtime in nanoseconds since the Unix epoch, read before hashing
+    mtime: i128,
+    hash: String,
+    checked_at: i64,
+}
+
+#[derive(Debug, Default)]
+struct IndexState {
+    entries: HashMap<String, IndexedHash>,
+    dirty: bool,
+}
+
+/// Result of looking a file up in the index
+#[derive(Debug)]
+pub enum IndexLookup {
+    /// The file is unchanged since it was hashed
+    Hit(String),
+    /// The file was hashed before but has changed since; the entry is gone
+    Stale,
+    Miss,
+}
+
+/// Persistent content hashes of files keyed by path, valid while the
+/// file's size and mtime are unchanged.
+///
+/// Duplicate scans record every hash here as they go, so a scan that is
+/// cancelled or interrupted picks up where it stopped instead of reading
+/// every file again.
+#[derive(Debug)]
+pub struct HashIndex {
+    path: PathBuf,
+    state: Mutex<IndexState>,
+}
+
+impl HashIndex {
+    pub fn load(cache_dir: &Path) -> Self {
+        let path = cache_dir.join(INDEX_FILE_NAME);
+        let entries = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                warn!("Discarding unreadable file hash index: {}", e);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+
+        Self {
+            path,
+            state: Mutex::new(IndexState {
+                entries,
+                dirty: false,
+            }),
+        }
+    }
+
+    /// The recorded hash of `path` if its size and mtime still match
+    /// `metadata`; a mismatching entry is dropped
+    pub fn lookup(&self, path: &Path, metadata: &fs::Metadata) -> IndexLookup {
+        let key = path.to_string_lossy();
+        let mut state = self.lock();
+        let Some(entry) = state.entries.get_mut(key.as_ref()) else {
+            return IndexLookup::Miss;
+        };
+
+        if entry.size == metadata.len() && Some(entry.mtime) == mtime_nanos(metadata) {
+            entry.checked_at = Utc::now().timestamp();
+            return IndexLookup::Hit(entry.hash.clone());
+        }
+        state.entries.remove(key.as_ref());
+        state.dirty = true;
+        IndexLookup::Stale
+    }
+
+    /// Record `hash` for `path` as it was when `metadata` was read
+    pub fn insert(&self, path: &Path, metadata: &fs::Metadata, hash: &str) {
+        let Some(mtime) = mtime_nanos(metadata) else {
+            return;
+        };
+        let mut state = self.lock();
+        state.entries.insert(
+            path.to_string_lossy().to_string(),
+            IndexedHash {
+                size: metadata.len(),
+                mtime,
+                hash: hash.to_string(),
+                checked_at: Utc::now().timestamp(),
+            },
+        );
+        state.dirty = true;
+    }
+
+    /// Persist the index if anything changed, dropping long-unused entries
+    pub fn flush(&self) -> anyhow::Result<()> {
+        let mut state = self.lock();
+        if !state.dirty {
+            return Ok(());
+        }
+
+        let cutoff = Utc::now().timestamp() - PRUNE_AFTER_SECS;
+        state.entries.retain(|_, entry| entry.checked_at >= cutoff);
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string(&state.entries)?)?;
+        state.dirty = false;
+
+        Ok(())
+    }
+
+    /// File the entries are persisted to
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Drop every entry and delete the file, returning the bytes it took
+    pub fn clear(&self) -> std::io::Result<u64> {
+        let mut state = self.lock();
+        state.entries.clear();
+        state.dirty = false;
+        let size = match fs::metadata(&self.path) {
+            Ok(metadata) => metadata.len(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+        fs::remove_file(&self.path)?;
+        Ok(size)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, IndexState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,190 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Digest algorithms available for file hashing
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    #[default]
+    Blake3,
+    Sha256,
+}
+
+impl HashAlgorithm {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Blake3 => "blake3",
+            Self::Sha256 => "sha256",
+        }
+    }
+}
+
+/// Hash a file with BLAKE3 by streaming it in fixed-size chunks.
+///
+/// Only a single `chunk_size` buffer is held in memory regardless of the
+/// file's size.
+pub fn hash_file(path: &Path, chunk_size: usize) -> io::Result<String> {
+    hash_file_with(path, HashAlgorithm::Blake3, chunk_size)
+}
+
+/// Hash a file with the given algorithm, streaming it like `hash_file`
+pub fn hash_file_with(
+    path: &Path,
+    algorithm: HashAlgorithm,
+    chunk_size: usize,
+) -> io::Result<String> {
+    match algorithm {
+        HashAlgorithm::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            stream_file(path, chunk_size, |chunk| {
+                hasher.update(chunk);
+            })?;
+            Ok(hasher.finalize().to_hex().to_string())
+        }
+        HashAlgorithm::Sha256 => {
+            let mut hasher = Sha256::new();
+            stream_file(path, chunk_size, |chunk| hasher.update(chunk))?;
+            Ok(hex_string(&hasher.finalize()))
+        }
+    }
+}
+
+/// Width and height of the grayscale thumbnail compared by `perceptual_hash`;
+/// one extra column yields eight horizontal differences per row
+const DHASH_WIDTH: u32 = 9;
+const DHASH_HEIGHT: u32 = 8;
+
+/// 64-bit difference hash (dHash) of an image, or `None` if the file's
+/// contents are not a recognized image format.
+///
+/// Each bit records whether a pixel of a small grayscale thumbnail is
+/// brighter than its right-hand neighbour, so resized, recompressed or
+/// slightly edited copies of a picture hash to nearby values.
+pub fn perceptual_hash(path: &Path) -> io::Result<Option<u64>> {
+    let reader = image::ImageReader::open(path)?.with_guessed_format()?;
+    if reader.format().is_none() {
+        return Ok(None);
+    }
+
+    let image = reader
+        .decode()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let thumbnail = image
+        .resize_exact(DHASH_WIDTH, DHASH_HEIGHT, image::imageops::FilterType::Triangle)
+        .into_luma8();
+
+    let mut hash = 0u64;
+    for y in 0..DHASH_HEIGHT {
+        for x in 0..DHASH_WIDTH - 1 {
+            let brighter = thumbnail.get_pixel(x, y)[0] > thumbnail.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | brighter as u64;
+        }
+    }
+    Ok(Some(hash))
+}
+
+/// Number of differing bits between two perceptual hashes
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+fn stream_file(path: &Path, chunk_size: usize, mut update: impl FnMut(&[u8])) -> io::Result<()> {
+    let mut file = File::open(path)?;
+    let mut buffer = vec![0u8; chunk_size.max(1)];
+
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(());
+        }
+        update(&buffer[..read]);
+    }
+}
+
+fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    /// Tracks the bytes allocated by each thread, so a test can measure the
+    /// peak memory of the work it runs on its own thread
+    struct PeakTracking;
+
+    thread_local! {
+        static LIVE: Cell<isize> = const { Cell::new(0) };
+        static PEAK: Cell<isize> = const { Cell::new(0) };
+    }
+
+    fn track(delta: isize) {
+        let _ = LIVE.try_with(|live| {
+            live.set(live.get() + delta);
+            let _ = PEAK.try_with(|peak| peak.set(peak.get().max(live.get())));
+        });
+    }
+
+    // SAFETY: every call is forwarded unchanged to the system allocator
+    unsafe impl GlobalAlloc for PeakTracking {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc(layout);
+            if !ptr.is_null() {
+                track(layout.size() as isize);
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+            track(-(layout.size() as isize));
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new = System.realloc(ptr, layout, new_size);
+            if !new.is_null() {
+                track(new_size as isize - layout.size() as isize);
+            }
+            new
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: PeakTracking = PeakTracking;
+
+    /// Peak bytes allocated by this thread while `work` runs
+    fn peak_allocation(work: impl FnOnce()) -> usize {
+        let start = LIVE.with(Cell::get);
+        PEAK.with(|peak| peak.set(start));
+        work();
+        (PEAK.with(Cell::get) - start).max(0) as usize
+    }
+
+    #[test]
+    fn hashing_memory_stays_at_one_chunk_regardless_of_file_size() {
+        const CHUNK: usize = 64 * 1024;
+        const FILE_SIZE: u64 = 32 * 1024 * 1024;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("large.bin");
+        // Sparse, so the test reads 32 MB without writing it first
+        File::create(&path).unwrap().set_len(FILE_SIZE).unwrap();
+
+        for algorithm in [HashAlgorithm::Blake3, HashAlgorithm::Sha256] {
+            let peak = peak_allocation(|| {
+                hash_file_with(&path, algorithm, CHUNK).unwrap();
+            });
+            assert!(
+                peak < 2 * CHUNK,
+                "{} hashing of a {} byte file peaked at {} bytes",
+                algorithm.as_str(),
+                FILE_SIZE,
+                peak
+            );
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::utils::paths;
+
+/// Oldest sessions are pruned once the history grows past this size
+pub const MAX_HISTORY_ENTRIES: usize = 500;
+
+const HISTORY_FILE_NAME: &str = "cleanup_history.json";
+
+/// Summary of one completed cleanup session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CleanupSession {
+    pub id: String,
+    pub completed_at: DateTime<Utc>,
+    pub directory: String,
+    pub files_removed: u64,
+    pub bytes_freed: u64,
+}
+
+/// Persistent per-session cleanup history.
+///
+/// Lifetime totals are tracked separately so pruning old sessions never
+/// reduces the reported amount of space reclaimed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CleanupHistory {
+    pub sessions: Vec<CleanupSession>,
+    pub lifetime_sessions: u64,
+    pub lifetime_files_removed: u64,
+    pub lifetime_bytes_freed: u64,
+}
+
+impl CleanupHistory {
+    /// Location of the history file under the config directory
+    pub fn file_path() -> PathBuf {
+        paths::config_dir().join(HISTORY_FILE_NAME)
+    }
+
+    /// Load history from disk, starting fresh if missing or unreadable
+    pub fn load() -> Self {
+        Self::load_from(&Self::file_path())
+    }
+
+    fn load_from(path: &Path) -> Self {
+        let Ok(contents) = std::fs::read_to_string(path) else {
+            return Self::default();
+        };
+
+        serde_json::from_str(&contents).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable cleanup history {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    /// Persist history to disk
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = Self::file_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Append a completed session, pruning the oldest beyond the cap
+    pub fn record(&mut self, directory: &str, files_removed: u64, bytes_freed: u64) {
+        self.sessions.push(CleanupSession {
+            id: uuid::Uuid::new_v4().to_string(),
+            completed_at: Utc::now(),
+            directory: directory.to_string(),
+            files_removed,
+            bytes_freed,
+        });
+
+        self.lifetime_sessions += 1;
+        self.lifetime_files_removed += files_removed;
+        self.lifetime_bytes_freed += bytes_freed;
+
+        if self.sessions.len() > MAX_HISTORY_ENTRIES {
+            let excess = self.sessions.len() - MAX_HISTORY_ENTRIES;
+            self.sessions.drain(..excess);
+        }
+    }
+
+    /// Most recent sessions first
+    pub fn recent(&self, limit: usize) -> Vec<CleanupSession> {
+        self.sessions.iter().rev().take(limit).cloned().collect()
+    }
+}
@@ -0,0 +1,108 @@
+use chrono::{DateTime, Utc};
+use globset::{Glob, GlobMatcher};
+use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
+use std::path::Path;
+
+/// Which files of a group of copies to keep when the rest are removed
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case", tag = "kind", content = "pattern")]
+pub enum KeepPolicy {
+    /// The most recently modified copies
+    #[default]
+    Newest,
+    Oldest,
+    Largest,
+    /// Copies whose full path matches the glob, e.g. `**/Photos/**`; the
+    /// newest of them when several match
+    InPath(String),
+}
+
+/// A group member as the policy sees it
+#[derive(Debug, Clone, Copy)]
+pub struct KeepCandidate<'a> {
+    pub path: &'a str,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+}
+
+/// The files a policy keeps out of one group
+#[derive(Debug)]
+pub struct KeepSelection {
+    /// Indices into the group, best first
+    pub retained: Vec<usize>,
+    /// False for `InPath` when no file matched the pattern, in which case
+    /// the newest files were kept instead
+    pub policy_applied: bool,
+}
+
+/// A `KeepPolicy` ready to apply, with its pattern compiled
+#[derive(Debug)]
+pub struct Keeper {
+    policy: KeepPolicy,
+    count: usize,
+    pattern: Option<GlobMatcher>,
+}
+
+impl Keeper {
+    /// Keep `count` files per group, at least one, chosen by `policy`
+    pub fn new(policy: KeepPolicy, count: usize) -> Result<Self, String> {
+        let pattern = match &policy {
+            KeepPolicy::InPath(pattern) => Some(
+                Glob::new(pattern)
+                    .map_err(|e| format!("Invalid keep pattern '{}': {}", pattern, e))?
+                    .compile_matcher(),
+            ),
+            _ => None,
+        };
+        Ok(Self {
+            policy,
+            count: count.max(1),
+            pattern,
+        })
+    }
+
+    pub fn policy(&self) -> &KeepPolicy {
+        &self.policy
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Pick the files to keep from `group`. Ties fall back to path order so
+    /// the same group always keeps the same files; files whose modification
+    /// time is unknown count as oldest.
+    pub fn select(&self, group: &[KeepCandidate]) -> KeepSelection {
+        let matches = |candidate: &KeepCandidate| {
+            self.pattern
+                .as_ref()
+                .is_some_and(|pattern| pattern.is_match(Path::new(candidate.path)))
+        };
+        let newest_first = |a: &KeepCandidate, b: &KeepCandidate| b.modified.cmp(&a.modified);
+        let preference = |a: &KeepCandidate, b: &KeepCandidate| -> Ordering {
+            let ordering = match &self.policy {
+                KeepPolicy::Newest => newest_first(a, b),
+                // `None` sorts first, but unknown times should not win
+                KeepPolicy::Oldest => a
+                    .modified
+                    .map(Reverse)
+                    .cmp(&b.modified.map(Reverse))
+                    .reverse(),
+                KeepPolicy::Largest => b.size.cmp(&a.size).then_with(|| newest_first(a, b)),
+                KeepPolicy::InPath(_) => matches(b)
+                    .cmp(&matches(a))
+                    .then_with(|| newest_first(a, b)),
+            };
+            ordering.then_with(|| a.path.cmp(b.path))
+        };
+
+        let mut order: Vec<usize> = (0..group.len()).collect();
+        order.sort_by(|&a, &b| preference(&group[a], &group[b]));
+        order.truncate(self.count);
+        KeepSelection {
+            policy_applied: self.pattern.is_none() || group.iter().any(matches),
+            retained: order,
+        }
+    }
+}
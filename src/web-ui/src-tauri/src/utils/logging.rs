@@ -1,5 +1,16 @@
-use tracing::{Level, Subscriber};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::future::Future;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::Instant;
+use tracing::{info, info_span, warn, Instrument, Level, Subscriber};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use uuid::Uuid;
+
+use crate::utils::audit::{AuditAction, AuditEntry};
+use crate::utils::paths::redaction_salt_file;
+use crate::{AppError, AppResult, AppState};
 
 /// Initialize logging for the application
 pub fn init_logging() -> anyhow::Result<()> {
@@ -32,4 +43,197 @@ pub fn setup_tracing(level: Level) -> anyhow::Result<()> {
 pub fn get_log_level() -> Level {
     // This would read from configuration in a real implementation
     Level::INFO
-}
\ No newline at end of file
+}
+
+/// Replace an identifier with a stable, non-reversible hash so the same
+/// machine always reports the same value without revealing the original.
+/// The hash is salted per install, so a guessed user or host name cannot be
+/// confirmed by hashing it elsewhere.
+pub fn redact_identifier(value: &str) -> String {
+    static SALT: OnceLock<Vec<u8>> = OnceLock::new();
+    let salt = SALT.get_or_init(|| load_or_create_salt(&redaction_salt_file()));
+    salted_redaction(salt, value)
+}
+
+fn salted_redaction(salt: &[u8], value: &str) -> String {
+    let digest = Sha256::new()
+        .chain_update(salt)
+        .chain_update(value.as_bytes())
+        .finalize();
+    let hex: String = digest.iter().take(6).map(|b| format!("{:02x}", b)).collect();
+    format!("redacted-{}", hex)
+}
+
+/// Read the install's salt, creating it on first use. If it cannot be saved
+/// the new salt still serves this run; redacted values then change on the
+/// next start.
+fn load_or_create_salt(path: &Path) -> Vec<u8> {
+    if let Ok(salt) = fs::read(path) {
+        if salt.len() >= REDACTION_SALT_LEN {
+            return salt;
+        }
+    }
+
+    let salt: Vec<u8> = [Uuid::new_v4(), Uuid::new_v4()]
+        .iter()
+        .flat_map(|uuid| *uuid.as_bytes())
+        .collect();
+    let saved = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|()| fs::write(path, &salt));
+    if let Err(e) = saved {
+        warn!("Failed to save the redaction salt to {}: {}", path.display(), e);
+    }
+    salt
+}
+
+/// Bytes of random salt mixed into every redacted identifier
+const REDACTION_SALT_LEN: usize = 32;
+
+/// Commands that change files, configuration or app state. Only these are
+/// recorded in the audit trail; reads and scans would drown them out.
+const STATE_MODIFYING_COMMANDS: &[&str] = &[
+    "add_global_exclusion",
+    "add_override_rule",
+    "archive_files",
+    "cancel_all_operations",
+    "clear_app_data",
+    "confirm_delete",
+    "convert_config_format",
+    "delete_directory",
+    "delete_elevated",
+    "export_analysis_markdown",
+    "export_cleanup_plan",
+    "export_scan_results",
+    "import_cleanup_plan",
+    "register_scheduled_cleanup",
+    "remove_global_exclusion",
+    "remove_override_rule",
+    "reset_metrics",
+    "reset_protected_patterns_to_default",
+    "select_directory",
+    "set_scan_root",
+    "unregister_scheduled_cleanup",
+];
+
+/// What went wrong without the message, which usually names the path or
+/// host involved
+fn error_kind(error: &AppError) -> &'static str {
+    match error {
+        AppError::FileSystemError(_) => "File system error",
+        AppError::SecurityError(_) => "Security validation failed",
+        AppError::SystemError(_) => "System integration error",
+        AppError::ConfigError(_) => "Configuration error",
+        AppError::Cancelled => "Operation cancelled",
+        AppError::IoError(e) => match e.kind() {
+            std::io::ErrorKind::NotFound => "IO error: not found",
+            std::io::ErrorKind::PermissionDenied => "IO error: permission denied",
+            _ => "IO error",
+        },
+    }
+}
+
+/// Run a command body inside a `command` span and log its elapsed time.
+///
+/// The target (usually the path argument) and any error message are
+/// redacted when `redact_identifiers` is set. Commands with access to the
+/// app state that change something also record their outcome in the audit
+/// trail when it is enabled.
+pub async fn timed_command<T, F>(
+    name: &'static str,
+    state: Option<&AppState>,
+    target: Option<String>,
+    body: F,
+) -> AppResult<T>
+where
+    F: Future<Output = AppResult<T>>,
+{
+    let (redact, audit) = match state {
+        Some(state) => {
+            let config = state.get_config().await;
+            let audit = config.security.enable_audit_trail
+                && STATE_MODIFYING_COMMANDS.contains(&name);
+            (config.redact_identifiers, audit)
+        }
+        None => (false, false),
+    };
+    let target = target.map(|t| if redact { redact_identifier(&t) } else { t });
+
+    let span = info_span!("command", name, target = target.as_deref().unwrap_or(""));
+    let started = Instant::now();
+    let result = body.instrument(span.clone()).await;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+
+    let detail = span.in_scope(|| match &result {
+        Ok(_) => {
+            info!(elapsed_ms, "{} completed", name);
+            format!("{} completed in {} ms", name, elapsed_ms)
+        }
+        Err(e) => {
+            let error = if redact {
+                error_kind(e).to_string()
+            } else {
+                e.to_string()
+            };
+            warn!(elapsed_ms, error = %error, "{} failed", name);
+            format!("{} failed after {} ms: {}", name, elapsed_ms, error)
+        }
+    });
+
+    if let (Some(state), Err(_)) = (state, &result) {
+        state.metrics.record_command_error(name);
+    }
+    if let (Some(state), true) = (state, audit) {
+        state.audit.record(AuditEntry::new(
+            AuditAction::Command,
+            target.as_deref().unwrap_or(""),
+            0,
+            Some(detail),
+        ));
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacted_errors_leave_out_the_message() {
+        let secret = "/home/alice/Taxes/2025.pdf";
+        for error in [
+            AppError::FileSystemError(format!("{} is locked", secret)),
+            AppError::SecurityError(format!("{} is protected", secret)),
+            AppError::IoError(std::io::Error::new(std::io::ErrorKind::NotFound, secret)),
+        ] {
+            assert!(error.to_string().contains(secret));
+            assert!(!error_kind(&error).contains("alice"));
+        }
+    }
+
+    #[test]
+    fn redaction_salt_is_kept_per_install() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config").join("redaction-salt");
+
+        let salt = load_or_create_salt(&path);
+        assert_eq!(salt.len(), REDACTION_SALT_LEN);
+        assert_eq!(load_or_create_salt(&path), salt);
+        assert_eq!(
+            salted_redaction(&salt, "alice"),
+            salted_redaction(&load_or_create_salt(&path), "alice")
+        );
+
+        let other_install = load_or_create_salt(&dir.path().join("other"));
+        assert_ne!(other_install, salt);
+        assert_ne!(
+            salted_redaction(&salt, "alice"),
+            salted_redaction(&other_install, "alice")
+        );
+        let unsalted = Sha256::digest(b"alice");
+        let unsalted: String = unsalted.iter().take(6).map(|b| format!("{:02x}", b)).collect();
+        assert_ne!(salted_redaction(&salt, "alice"), format!("redacted-{}", unsalted));
+    }
+}
@@ -0,0 +1,154 @@
+/// Language used when `analysis.response_language` is not set
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Languages the built-in message table is translated into, with the name
+/// used to ask an AI provider for that language
+const LANGUAGES: &[(&str, &str)] = &[
+    ("en", "English"),
+    ("de", "German"),
+    ("fr", "French"),
+    ("es", "Spanish"),
+];
+
+/// Messages produced locally rather than by the AI provider
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Message {
+    SystemFile,
+    TemporaryFile,
+    TemporaryDirectory,
+    PackageCache,
+    CacheFile,
+    LogFile,
+    BackupCopy,
+    BuildArtifact,
+    Archive,
+    MediaFile,
+    DocumentFile,
+    NoRuleMatched,
+    ContainerData,
+    BrokenLink,
+    OnNetworkDrive,
+    OnRemovableMedia,
+}
+
+/// Primary subtag of a language tag, so `de-AT` and `de_CH` both mean `de`
+pub fn primary_language(language: &str) -> String {
+    language
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+/// Whether the message table has translations for `language`
+pub fn is_supported(language: &str) -> bool {
+    let primary = primary_language(language);
+    LANGUAGES.iter().any(|(code, _)| *code == primary)
+}
+
+/// English name of `language` for prompts; unknown tags are passed through
+/// as given, which most providers still understand
+pub fn language_name(language: &str) -> String {
+    let primary = primary_language(language);
+    LANGUAGES
+        .iter()
+        .find(|(code, _)| *code == primary)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| language.trim().to_string())
+}
+
+/// `message` in `language`, falling back to English for languages without
+/// a translation
+pub fn text(message: Message, language: &str) -> &'static str {
+    match primary_language(language).as_str() {
+        "de" => german(message),
+        "fr" => french(message),
+        "es" => spanish(message),
+        _ => english(message),
+    }
+}
+
+fn english(message: Message) -> &'static str {
+    match message {
+        Message::SystemFile => "System library or driver file",
+        Message::TemporaryFile => "Temporary file that can be safely deleted",
+        Message::TemporaryDirectory => "File inside a temporary directory",
+        Message::PackageCache => "Package-manager cache that is re-downloaded on demand",
+        Message::CacheFile => "Cache file that can be regenerated",
+        Message::LogFile => "Log file; usually only needed for troubleshooting",
+        Message::BackupCopy => "Backup copy of another file",
+        Message::BuildArtifact => "Build artifact or dependency that can be rebuilt",
+        Message::Archive => "Compressed archive or disk image",
+        Message::MediaFile => "Image, audio or video file",
+        Message::DocumentFile => "Document file",
+        Message::NoRuleMatched => "No heuristic rule matched this file",
+        Message::ContainerData => "Docker image, container or volume data",
+        Message::BrokenLink => "Symbolic link whose target no longer exists",
+        Message::OnNetworkDrive => "on a network drive; review before deleting",
+        Message::OnRemovableMedia => "on removable media; review before deleting",
+    }
+}
+
+fn german(message: Message) -> &'static str {
+    match message {
+        Message::SystemFile => "Systembibliothek oder Treiberdatei",
+        Message::TemporaryFile => "Temporäre Datei, die gefahrlos gelöscht werden kann",
+        Message::TemporaryDirectory => "Datei in einem temporären Verzeichnis",
+        Message::PackageCache => "Paketmanager-Cache, der bei Bedarf neu geladen wird",
+        Message::CacheFile => "Cache-Datei, die neu erzeugt werden kann",
+        Message::LogFile => "Protokolldatei; meist nur zur Fehlersuche nötig",
+        Message::BackupCopy => "Sicherungskopie einer anderen Datei",
+        Message::BuildArtifact => "Build-Artefakt oder Abhängigkeit, die neu gebaut werden kann",
+        Message::Archive => "Komprimiertes Archiv oder Festplattenabbild",
+        Message::MediaFile => "Bild-, Audio- oder Videodatei",
+        Message::DocumentFile => "Dokumentdatei",
+        Message::NoRuleMatched => "Keine Heuristik-Regel passt auf diese Datei",
+        Message::ContainerData => "Docker-Abbild, -Container oder -Volume",
+        Message::BrokenLink => "Symbolischer Link, dessen Ziel nicht mehr existiert",
+        Message::OnNetworkDrive => "auf einem Netzlaufwerk; vor dem Löschen prüfen",
+        Message::OnRemovableMedia => "auf einem Wechseldatenträger; vor dem Löschen prüfen",
+    }
+}
+
+fn french(message: Message) -> &'static str {
+    match message {
+        Message::SystemFile => "Bibliothèque système ou fichier de pilote",
+        Message::TemporaryFile => "Fichier temporaire pouvant être supprimé sans risque",
+        Message::TemporaryDirectory => "Fichier situé dans un dossier temporaire",
+        Message::PackageCache => "Cache de gestionnaire de paquets, retéléchargé au besoin",
+        Message::CacheFile => "Fichier de cache pouvant être régénéré",
+        Message::LogFile => "Fichier journal ; utile surtout pour le dépannage",
+        Message::BackupCopy => "Copie de sauvegarde d'un autre fichier",
+        Message::BuildArtifact => "Artefact de compilation ou dépendance reconstructible",
+        Message::Archive => "Archive compressée ou image disque",
+        Message::MediaFile => "Fichier image, audio ou vidéo",
+        Message::DocumentFile => "Fichier document",
+        Message::NoRuleMatched => "Aucune règle heuristique ne correspond à ce fichier",
+        Message::ContainerData => "Image, conteneur ou volume Docker",
+        Message::BrokenLink => "Lien symbolique dont la cible n'existe plus",
+        Message::OnNetworkDrive => "sur un lecteur réseau ; à vérifier avant suppression",
+        Message::OnRemovableMedia => "sur un support amovible ; à vérifier avant suppression",
+    }
+}
+
+fn spanish(message: Message) -> &'static str {
+    match message {
+        Message::SystemFile => "Biblioteca del sistema o archivo de controlador",
+        Message::TemporaryFile => "Archivo temporal que se puede eliminar con seguridad",
+        Message::TemporaryDirectory => "Archivo dentro de una carpeta temporal",
+        Message::PackageCache => "Caché del gestor de paquetes que se vuelve a descargar",
+        Message::CacheFile => "Archivo de caché que se puede regenerar",
+        Message::LogFile => "Archivo de registro; normalmente solo sirve para diagnosticar",
+        Message::BackupCopy => "Copia de seguridad de otro archivo",
+        Message::BuildArtifact => "Artefacto de compilación o dependencia que se puede reconstruir",
+        Message::Archive => "Archivo comprimido o imagen de disco",
+        Message::MediaFile => "Archivo de imagen, audio o vídeo",
+        Message::DocumentFile => "Archivo de documento",
+        Message::NoRuleMatched => "Ninguna regla heurística coincide con este archivo",
+        Message::ContainerData => "Imagen, contenedor o volumen de Docker",
+        Message::BrokenLink => "Enlace simbólico cuyo destino ya no existe",
+        Message::OnNetworkDrive => "en una unidad de red; revísalo antes de eliminar",
+        Message::OnRemovableMedia => "en un medio extraíble; revísalo antes de eliminar",
+    }
+}
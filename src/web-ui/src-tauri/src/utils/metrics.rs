@@ -0,0 +1,244 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::utils::operations::{OperationKind, OperationOutcome, OperationResult};
+
+/// Finished operations kept for throughput figures
+const MAX_OPERATIONS: usize = 200;
+/// AI requests kept for latency percentiles
+const MAX_AI_REQUESTS: usize = 500;
+/// Failed commands kept for error counts
+const MAX_COMMAND_ERRORS: usize = 200;
+/// Analysis cache lookups kept for the hit rate
+const MAX_CACHE_LOOKUPS: usize = 5000;
+
+#[derive(Debug, Clone, Copy)]
+struct OperationSample {
+    kind: OperationKind,
+    outcome: OperationOutcome,
+    items: u64,
+    bytes: u64,
+    seconds: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct AiRequestSample {
+    latency_ms: u64,
+    failed: bool,
+}
+
+#[derive(Debug)]
+struct MetricsState {
+    since: DateTime<Utc>,
+    operations: VecDeque<OperationSample>,
+    ai_requests: VecDeque<AiRequestSample>,
+    command_errors: VecDeque<&'static str>,
+    cache_lookups: VecDeque<bool>,
+}
+
+impl Default for MetricsState {
+    fn default() -> Self {
+        Self {
+            since: Utc::now(),
+            operations: VecDeque::new(),
+            ai_requests: VecDeque::new(),
+            command_errors: VecDeque::new(),
+            cache_lookups: VecDeque::new(),
+        }
+    }
+}
+
+/// Throughput of the recent operations of one kind
+#[derive(Debug, Serialize)]
+pub struct OperationThroughput {
+    pub kind: OperationKind,
+    pub count: u64,
+    /// Operations that failed outright or in part
+    pub failed: u64,
+    pub cancelled: u64,
+    pub items_per_second: Option<f64>,
+    pub bytes_per_second: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CacheMetrics {
+    pub lookups: u64,
+    pub hits: u64,
+    /// `None` until the cache has been consulted
+    pub hit_rate: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AiLatency {
+    pub requests: u64,
+    pub failed: u64,
+    pub p50_ms: u64,
+    pub p90_ms: u64,
+    pub p99_ms: u64,
+    pub max_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommandErrors {
+    pub command: String,
+    pub count: u64,
+}
+
+/// Everything `Metrics` currently holds, summarized
+#[derive(Debug, Serialize)]
+pub struct MetricsSnapshot {
+    /// When collection started: app start or the last reset
+    pub since: DateTime<Utc>,
+    pub operations: Vec<OperationThroughput>,
+    pub analysis_cache: CacheMetrics,
+    /// `None` until an AI request has been made
+    pub ai_latency: Option<AiLatency>,
+    /// Most frequent first
+    pub command_errors: Vec<CommandErrors>,
+}
+
+/// Rolling performance figures for the diagnostics panel.
+///
+/// Each kind of sample lives in its own bounded ring buffer, so memory stays
+/// fixed however long the app runs and the figures describe recent
+/// behavior. Nothing here is persisted or sent anywhere.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    state: Mutex<MetricsState>,
+}
+
+fn push_bounded<T>(buffer: &mut VecDeque<T>, limit: usize, value: T) {
+    if buffer.len() == limit {
+        buffer.pop_front();
+    }
+    buffer.push_back(value);
+}
+
+impl Metrics {
+    pub fn record_operation(&self, result: &OperationResult) {
+        let elapsed = result.finished_at.signed_duration_since(result.started_at);
+        let sample = OperationSample {
+            kind: result.kind,
+            outcome: result.outcome,
+            items: result.succeeded + result.failed,
+            bytes: result.bytes,
+            seconds: elapsed.num_milliseconds().max(0) as f64 / 1000.0,
+        };
+        push_bounded(&mut self.lock().operations, MAX_OPERATIONS, sample);
+    }
+
+    pub fn record_ai_request(&self, latency: Duration, failed: bool) {
+        let sample = AiRequestSample {
+            latency_ms: latency.as_millis() as u64,
+            failed,
+        };
+        push_bounded(&mut self.lock().ai_requests, MAX_AI_REQUESTS, sample);
+    }
+
+    pub fn record_command_error(&self, command: &'static str) {
+        push_bounded(&mut self.lock().command_errors, MAX_COMMAND_ERRORS, command);
+    }
+
+    /// Count `hits` analysis cache hits and `misses` misses
+    pub fn record_cache_lookups(&self, hits: usize, misses: usize) {
+        // Lookups that would be pushed out again at once are not added
+        let skip = (hits + misses).saturating_sub(MAX_CACHE_LOOKUPS);
+        let lookups = std::iter::repeat_n(true, hits).chain(std::iter::repeat_n(false, misses));
+        let mut state = self.lock();
+        for hit in lookups.skip(skip) {
+            push_bounded(&mut state.cache_lookups, MAX_CACHE_LOOKUPS, hit);
+        }
+    }
+
+    /// Drop every sample and start collecting afresh
+    pub fn reset(&self) {
+        *self.lock() = MetricsState::default();
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let state = self.lock();
+
+        let mut operations: Vec<OperationThroughput> = Vec::new();
+        for kind in [
+            OperationKind::Scan,
+            OperationKind::Analyze,
+            OperationKind::Delete,
+            OperationKind::Archive,
+        ] {
+            let samples: Vec<&OperationSample> =
+                state.operations.iter().filter(|s| s.kind == kind).collect();
+            if samples.is_empty() {
+                continue;
+            }
+            let seconds: f64 = samples.iter().map(|s| s.seconds).sum();
+            let rate = |total: u64| (seconds > 0.0).then(|| total as f64 / seconds);
+            operations.push(OperationThroughput {
+                kind,
+                count: samples.len() as u64,
+                failed: samples
+                    .iter()
+                    .filter(|s| {
+                        matches!(
+                            s.outcome,
+                            OperationOutcome::Failed | OperationOutcome::PartialFailure
+                        )
+                    })
+                    .count() as u64,
+                cancelled: samples
+                    .iter()
+                    .filter(|s| s.outcome == OperationOutcome::Cancelled)
+                    .count() as u64,
+                items_per_second: rate(samples.iter().map(|s| s.items).sum()),
+                bytes_per_second: rate(samples.iter().map(|s| s.bytes).sum()),
+            });
+        }
+
+        let lookups = state.cache_lookups.len() as u64;
+        let hits = state.cache_lookups.iter().filter(|&&hit| hit).count() as u64;
+        let analysis_cache = CacheMetrics {
+            lookups,
+            hits,
+            hit_rate: (lookups > 0).then(|| hits as f64 / lookups as f64),
+        };
+
+        let mut latencies: Vec<u64> = state.ai_requests.iter().map(|r| r.latency_ms).collect();
+        latencies.sort_unstable();
+        let percentile = |p: usize| latencies[(latencies.len() - 1) * p / 100];
+        let ai_latency = (!latencies.is_empty()).then(|| AiLatency {
+            requests: latencies.len() as u64,
+            failed: state.ai_requests.iter().filter(|r| r.failed).count() as u64,
+            p50_ms: percentile(50),
+            p90_ms: percentile(90),
+            p99_ms: percentile(99),
+            max_ms: latencies[latencies.len() - 1],
+        });
+
+        let mut by_command: HashMap<&str, u64> = HashMap::new();
+        for command in &state.command_errors {
+            *by_command.entry(command).or_default() += 1;
+        }
+        let mut command_errors: Vec<CommandErrors> = by_command
+            .into_iter()
+            .map(|(command, count)| CommandErrors {
+                command: command.to_string(),
+                count,
+            })
+            .collect();
+        command_errors.sort_by(|a, b| b.count.cmp(&a.count).then(a.command.cmp(&b.command)));
+
+        MetricsSnapshot {
+            since: state.since,
+            operations,
+            analysis_cache,
+            ai_latency,
+            command_errors,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MetricsState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
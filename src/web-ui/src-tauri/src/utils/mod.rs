@@ -3,9 +3,54 @@ pub mod config;
 pub mod platform;
 pub mod security;
 pub mod logging;
+pub mod walker;
+pub mod deletion;
+pub mod file_locks;
+pub mod credentials;
+pub mod rate_limiter;
+pub mod ai_client;
+pub mod paths;
+pub mod history;
+pub mod hashing;
+pub mod snapshots;
+pub mod operations;
+pub mod audit;
+pub mod classifier;
+pub mod analysis_cache;
+pub mod notification_throttle;
+pub mod dev_caches;
+pub mod dir_size_cache;
+pub mod gitignore;
+pub mod delete_tokens;
+pub mod validation_cache;
+pub mod tree_deletion;
+pub mod elevation;
+pub mod batch_tuner;
+pub mod backups;
+pub mod overrides;
+pub mod app_leftovers;
+pub mod eta;
+pub mod dir_duplicates;
+pub mod messages;
+pub mod free_space;
+pub mod volume_locks;
+pub mod browser_caches;
+pub mod throughput;
+pub mod trash_bin;
+pub mod archive;
+pub mod app_bundles;
+pub mod hash_index;
+pub mod crash_dumps;
+pub mod metrics;
+pub mod thumbnail_caches;
+pub mod scheduler;
+pub mod keep_policy;
+pub mod device_backups;
 
 // Re-export commonly used utilities
 pub use config::AppConfig;
 pub use platform::{PlatformDetection, get_platform_info};
 pub use security::{SecurityValidator, validate_path};
-pub use logging::{init_logging, setup_tracing};
\ No newline at end of file
+pub use logging::{init_logging, setup_tracing};
+pub use walker::{directory_size, DirWalker, DirectorySize, SkipReason, SkippedPath, WalkEntry, WalkOptions};
+pub use deletion::{safe_delete, DeletionReport};
\ No newline at end of file
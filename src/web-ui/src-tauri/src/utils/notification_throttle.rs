@@ -0,0 +1,204 @@
+use chrono::NaiveTime;
+use std::time::{Duration, Instant};
+
+use crate::utils::config::{NotificationSettings, QuietHours, QuietHoursBehavior};
+
+/// What the sending path should do with a notification
+#[derive(Debug, PartialEq)]
+pub enum ThrottleDecision<T> {
+    /// Deliver now, after `held`: notifications queued during quiet hours
+    /// that have not been delivered yet, oldest first
+    Send { notification: T, held: Vec<T> },
+    Throttled,
+    /// Held until quiet hours end. `deliver_in` is set for the first
+    /// notification held, telling the caller when to call `release_held`.
+    Queued { deliver_in: Option<Duration> },
+    Dropped,
+}
+
+/// Rate and quiet-hours state shared by every notification sent
+#[derive(Debug)]
+pub struct NotificationThrottle<T> {
+    last_sent: Option<Instant>,
+    held: Vec<T>,
+}
+
+impl<T> Default for NotificationThrottle<T> {
+    fn default() -> Self {
+        Self {
+            last_sent: None,
+            held: Vec::new(),
+        }
+    }
+}
+
+impl<T> NotificationThrottle<T> {
+    /// Decide whether `notification` may be delivered now, holding on to it
+    /// if quiet hours queue it
+    pub fn check(
+        &mut self,
+        settings: &NotificationSettings,
+        notification: T,
+        now: Instant,
+        local_time: NaiveTime,
+    ) -> ThrottleDecision<T> {
+        if let Some(quiet_hours) = &settings.quiet_hours {
+            if in_quiet_hours(quiet_hours, local_time) {
+                return match settings.quiet_hours_behavior {
+                    QuietHoursBehavior::Drop => ThrottleDecision::Dropped,
+                    QuietHoursBehavior::Queue => {
+                        let first = self.held.is_empty();
+                        self.held.push(notification);
+                        ThrottleDecision::Queued {
+                            deliver_in: first
+                                .then(|| until_quiet_hours_end(quiet_hours, local_time))
+                                .flatten(),
+                        }
+                    }
+                };
+            }
+        }
+
+        let min_interval = Duration::from_secs(settings.min_interval_seconds);
+        if self
+            .last_sent
+            .is_some_and(|last| now.duration_since(last) < min_interval)
+        {
+            return ThrottleDecision::Throttled;
+        }
+
+        self.last_sent = Some(now);
+        ThrottleDecision::Send {
+            notification,
+            held: std::mem::take(&mut self.held),
+        }
+    }
+
+    /// Hand back the held notifications once quiet hours are over, or how
+    /// much longer to wait if they are not (the window may have changed)
+    pub fn release_held(
+        &mut self,
+        settings: &NotificationSettings,
+        now: Instant,
+        local_time: NaiveTime,
+    ) -> Result<Vec<T>, Duration> {
+        if let Some(quiet_hours) = &settings.quiet_hours {
+            if in_quiet_hours(quiet_hours, local_time) && !self.held.is_empty() {
+                if let Some(wait) = until_quiet_hours_end(quiet_hours, local_time) {
+                    return Err(wait);
+                }
+            }
+        }
+
+        if !self.held.is_empty() {
+            self.last_sent = Some(now);
+        }
+        Ok(std::mem::take(&mut self.held))
+    }
+}
+
+fn parse_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value, "%H:%M").ok()
+}
+
+/// Whether `time` falls inside the window; malformed windows never match
+fn in_quiet_hours(quiet_hours: &QuietHours, time: NaiveTime) -> bool {
+    let (Some(start), Some(end)) = (parse_time(&quiet_hours.start), parse_time(&quiet_hours.end))
+    else {
+        return false;
+    };
+
+    if start <= end {
+        time >= start && time < end
+    } else {
+        // Window spans midnight, e.g. 22:00-07:00
+        time >= start || time < end
+    }
+}
+
+/// Time from `time` until the window next ends
+fn until_quiet_hours_end(quiet_hours: &QuietHours, time: NaiveTime) -> Option<Duration> {
+    let end = parse_time(&quiet_hours.end)?;
+    let seconds = (end - time).num_seconds().rem_euclid(24 * 60 * 60);
+    Some(Duration::from_secs(seconds as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> NaiveTime {
+        parse_time(time).unwrap()
+    }
+
+    fn quiet_overnight() -> NotificationSettings {
+        NotificationSettings {
+            quiet_hours: Some(QuietHours {
+                start: "22:00".to_string(),
+                end: "07:00".to_string(),
+            }),
+            quiet_hours_behavior: QuietHoursBehavior::Queue,
+            ..NotificationSettings::default()
+        }
+    }
+
+    #[test]
+    fn queued_notifications_keep_their_content_until_quiet_hours_end() {
+        let settings = quiet_overnight();
+        let mut throttle = NotificationThrottle::default();
+        let now = Instant::now();
+
+        assert_eq!(
+            throttle.check(&settings, "scan finished", now, at("23:30")),
+            ThrottleDecision::Queued {
+                deliver_in: Some(Duration::from_secs(7 * 3600 + 30 * 60))
+            }
+        );
+        assert_eq!(
+            throttle.check(&settings, "cleanup finished", now, at("02:00")),
+            ThrottleDecision::Queued { deliver_in: None }
+        );
+        assert_eq!(
+            throttle.release_held(&settings, now, at("06:00")),
+            Err(Duration::from_secs(3600))
+        );
+        assert_eq!(
+            throttle.release_held(&settings, now, at("07:00")),
+            Ok(vec!["scan finished", "cleanup finished"])
+        );
+        assert_eq!(throttle.release_held(&settings, now, at("07:01")), Ok(vec![]));
+    }
+
+    #[test]
+    fn the_next_send_after_quiet_hours_carries_anything_still_held() {
+        let settings = quiet_overnight();
+        let mut throttle = NotificationThrottle::default();
+        let now = Instant::now();
+
+        throttle.check(&settings, "held", now, at("23:00"));
+        assert_eq!(
+            throttle.check(&settings, "new", now, at("08:00")),
+            ThrottleDecision::Send {
+                notification: "new",
+                held: vec!["held"]
+            }
+        );
+    }
+
+    #[test]
+    fn notifications_are_not_rate_limited_by_default() {
+        let settings = NotificationSettings::default();
+        let mut throttle = NotificationThrottle::default();
+        let now = Instant::now();
+
+        for notification in ["first", "second"] {
+            assert_eq!(
+                throttle.check(&settings, notification, now, at("12:00")),
+                ThrottleDecision::Send {
+                    notification,
+                    held: vec![]
+                }
+            );
+        }
+    }
+}
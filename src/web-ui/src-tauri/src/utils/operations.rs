@@ -0,0 +1,446 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+use crate::utils::eta::EtaEstimator;
+use crate::utils::file_locks::FILE_IN_USE_REASON;
+
+/// Per-item errors kept in an `OperationResult`; `failed` still counts all
+const MAX_RESULT_ERRORS: usize = 100;
+
+/// Kind of long-running work tracked in the registry
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    Scan,
+    Analyze,
+    Delete,
+    Archive,
+}
+
+/// Whether an operation is still working or winding down after a cancel
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationState {
+    Running,
+    Cancelling,
+}
+
+/// Point-in-time view of a registered operation for the UI
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationInfo {
+    pub id: String,
+    pub kind: OperationKind,
+    pub target: String,
+    /// Percent complete, when the operation knows its total amount of work
+    pub progress: Option<f32>,
+    /// Estimated seconds remaining; withheld until throughput has settled
+    pub eta_seconds: Option<u64>,
+    pub state: OperationState,
+    pub started_at: DateTime<Utc>,
+}
+
+/// How an operation ended
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationOutcome {
+    Completed,
+    /// Stopped by the user or shutdown; counts cover the work done so far
+    Cancelled,
+    /// Some items failed and others succeeded
+    PartialFailure,
+    /// Nothing succeeded, or the operation stopped with an error
+    Failed,
+}
+
+/// Stable reason an item failed, for the UI and automation to act on
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    NotFound,
+    PermissionDenied,
+    /// Held open by another process
+    InUse,
+    /// Refused by a safety check such as a protected pattern
+    Protected,
+    /// The AI provider could not be used; a heuristic verdict was kept
+    AiUnavailable,
+    Other,
+}
+
+impl ErrorCode {
+    /// Best match for an error message from the filesystem or a safety check
+    pub fn from_message(message: &str) -> Self {
+        let lower = message.to_lowercase();
+        if message == FILE_IN_USE_REASON
+            || lower.contains("in use")
+            || lower.contains("being used by another process")
+        {
+            Self::InUse
+        } else if lower.contains("no such file")
+            || lower.contains("not found")
+            || lower.contains("cannot find")
+            || lower.contains("does not exist")
+        {
+            Self::NotFound
+        } else if lower.contains("permission denied")
+            || lower.contains("access is denied")
+            || lower.contains("operation not permitted")
+            || lower.contains("elevat")
+        {
+            Self::PermissionDenied
+        } else if lower.contains("protected")
+            || lower.contains("refus")
+            || lower.contains("outside")
+            || lower.contains("not allowed")
+        {
+            Self::Protected
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// One item an operation could not process
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemError {
+    pub path: String,
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl ItemError {
+    /// Error for `path` with its code inferred from `message`
+    pub fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        let message = message.into();
+        Self {
+            path: path.into(),
+            code: ErrorCode::from_message(&message),
+            message,
+        }
+    }
+
+    /// Parse a "path: reason" line as reported by tree deletions
+    pub fn from_line(line: &str) -> Self {
+        match line.split_once(": ") {
+            Some((path, message)) => Self::new(path, message),
+            None => Self::new("", line),
+        }
+    }
+}
+
+/// Counts an operation reports when it finishes
+#[derive(Debug, Clone, Default)]
+pub struct OperationTally {
+    pub succeeded: u64,
+    pub failed: u64,
+    /// Bytes scanned or analyzed, found reclaimable, or freed by a delete
+    pub bytes: u64,
+    pub errors: Vec<ItemError>,
+}
+
+impl OperationTally {
+    pub fn completed(succeeded: u64, bytes: u64) -> Self {
+        Self {
+            succeeded,
+            bytes,
+            ..Self::default()
+        }
+    }
+}
+
+/// Payload of the operation-complete event, shared by scans, analyses and
+/// deletes
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationResult {
+    pub id: String,
+    pub kind: OperationKind,
+    pub target: String,
+    pub outcome: OperationOutcome,
+    pub succeeded: u64,
+    pub failed: u64,
+    pub bytes: u64,
+    /// The first failed items; `failed` counts all of them
+    pub errors: Vec<ItemError>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+type CompletionListener = Box<dyn Fn(OperationResult) + Send + Sync>;
+
+#[derive(Debug, Clone)]
+struct OperationEntry {
+    kind: OperationKind,
+    target: String,
+    /// Canonical form of `target` when it is a path
+    path: Option<PathBuf>,
+    token: CancellationToken,
+    progress: Option<f32>,
+    eta: EtaEstimator,
+    eta_seconds: Option<u64>,
+    started_at: DateTime<Utc>,
+}
+
+/// Registry of in-flight operations and their cancellation tokens
+#[derive(Default)]
+pub struct OperationRegistry {
+    operations: Mutex<HashMap<String, OperationEntry>>,
+    on_complete: OnceLock<CompletionListener>,
+}
+
+impl fmt::Debug for OperationRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OperationRegistry")
+            .field("operations", &self.operations)
+            .finish_non_exhaustive()
+    }
+}
+
+impl OperationRegistry {
+    /// Register a new operation. It is removed again when the guard drops,
+    /// so finished, failed and panicked operations never linger.
+    pub fn start(self: &Arc<Self>, kind: OperationKind, target: &str) -> OperationGuard {
+        let id = uuid::Uuid::new_v4().to_string();
+        let token = CancellationToken::new();
+
+        self.lock().insert(
+            id.clone(),
+            OperationEntry {
+                kind,
+                target: target.to_string(),
+                path: canonical_target(Path::new(target)),
+                token: token.clone(),
+                progress: None,
+                eta: EtaEstimator::default(),
+                eta_seconds: None,
+                started_at: Utc::now(),
+            },
+        );
+
+        OperationGuard {
+            registry: Arc::clone(self),
+            id,
+            token,
+            tally: None,
+        }
+    }
+
+    /// Call `listener` with the result of every operation as it ends. Only
+    /// the first listener registered is kept.
+    pub fn on_complete(&self, listener: impl Fn(OperationResult) + Send + Sync + 'static) {
+        if self.on_complete.set(Box::new(listener)).is_err() {
+            debug!("Operation completion listener already registered");
+        }
+    }
+
+    /// Signal cancellation to every running operation.
+    ///
+    /// Returns how many operations were newly signalled, so calling this
+    /// again (or with nothing running) returns 0.
+    pub fn cancel_all(&self) -> usize {
+        let operations = self.lock();
+        let mut signalled = 0;
+        for entry in operations.values().filter(|e| !e.token.is_cancelled()) {
+            debug!("Cancelling {:?} operation on {}", entry.kind, entry.target);
+            entry.token.cancel();
+            signalled += 1;
+        }
+        signalled
+    }
+
+    /// All registered operations, oldest first. Entries are added and removed
+    /// under the same lock, so finished operations never appear here.
+    pub fn list(&self) -> Vec<OperationInfo> {
+        self.collect(|_| true)
+    }
+
+    /// Operations whose target is `path`, one of its ancestors or one of its
+    /// descendants, oldest first. Both sides are compared in canonical form
+    /// and by whole components, so `/data/../home` matches `/home` and
+    /// `/home/a` does not match `/home/ab`. Targets that are not paths (such
+    /// as "developer caches") never overlap.
+    pub fn overlapping(&self, path: &Path) -> Vec<OperationInfo> {
+        let Some(path) = canonical_target(path) else {
+            return Vec::new();
+        };
+        self.collect(|entry| {
+            entry
+                .path
+                .as_ref()
+                .is_some_and(|target| path.starts_with(target) || target.starts_with(&path))
+        })
+    }
+
+    /// Number of operations currently registered
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn collect(&self, filter: impl Fn(&OperationEntry) -> bool) -> Vec<OperationInfo> {
+        let mut operations: Vec<OperationInfo> = self
+            .lock()
+            .iter()
+            .filter(|(_, entry)| filter(entry))
+            .map(|(id, entry)| OperationInfo {
+                id: id.clone(),
+                kind: entry.kind,
+                target: entry.target.clone(),
+                progress: entry.progress,
+                eta_seconds: entry.eta_seconds,
+                state: if entry.token.is_cancelled() {
+                    OperationState::Cancelling
+                } else {
+                    OperationState::Running
+                },
+                started_at: entry.started_at,
+            })
+            .collect();
+        operations.sort_by_key(|operation| operation.started_at);
+        operations
+    }
+
+    fn set_progress(&self, id: &str, done: u64, total: u64) -> Option<u64> {
+        let mut operations = self.lock();
+        let entry = operations.get_mut(id)?;
+        entry.progress = Some((done as f32 / total as f32 * 100.0).clamp(0.0, 100.0));
+        entry.eta_seconds = entry.eta.record(done, total);
+        entry.eta_seconds
+    }
+
+    /// Deregister `id` and report how it ended. Without a tally the
+    /// operation returned an error, unless it was cancelled.
+    fn finish(&self, id: &str, tally: Option<OperationTally>) {
+        let Some(entry) = self.lock().remove(id) else {
+            return;
+        };
+        let Some(listener) = self.on_complete.get() else {
+            return;
+        };
+
+        let outcome = match &tally {
+            _ if entry.token.is_cancelled() => OperationOutcome::Cancelled,
+            None => OperationOutcome::Failed,
+            Some(tally) if tally.failed == 0 => OperationOutcome::Completed,
+            Some(tally) if tally.succeeded == 0 => OperationOutcome::Failed,
+            Some(_) => OperationOutcome::PartialFailure,
+        };
+        let mut tally = tally.unwrap_or_default();
+        tally.errors.truncate(MAX_RESULT_ERRORS);
+        listener(OperationResult {
+            id: id.to_string(),
+            kind: entry.kind,
+            target: entry.target,
+            outcome,
+            succeeded: tally.succeeded,
+            failed: tally.failed,
+            bytes: tally.bytes,
+            errors: tally.errors,
+            started_at: entry.started_at,
+            finished_at: Utc::now(),
+        });
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, OperationEntry>> {
+        // A poisoned registry is still structurally valid; keep using it
+        self.operations.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Canonical form of an absolute `target`, resolving symlinks and `..`.
+/// A path that does not exist is resolved through its nearest existing
+/// ancestor, so a target that is created or removed meanwhile still matches.
+fn canonical_target(target: &Path) -> Option<PathBuf> {
+    if !target.is_absolute() {
+        return None;
+    }
+    let resolved = target.ancestors().find_map(|ancestor| {
+        let base = fs::canonicalize(ancestor).ok()?;
+        let rest = target.strip_prefix(ancestor).ok()?;
+        Some(base.join(rest))
+    });
+    Some(resolved.unwrap_or_else(|| target.to_path_buf()))
+}
+
+/// Handle held by a running operation; deregisters it on drop and reports
+/// its result, which counts as failed unless `complete` was called
+#[derive(Debug)]
+pub struct OperationGuard {
+    registry: Arc<OperationRegistry>,
+    id: String,
+    token: CancellationToken,
+    tally: Option<OperationTally>,
+}
+
+impl OperationGuard {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Token that is cancelled when the user or shutdown stops this operation
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Report progress as `done` out of `total` units of work, returning
+    /// the estimated seconds remaining once enough progress has been seen
+    pub fn report_progress(&self, done: u64, total: u64) -> Option<u64> {
+        if total == 0 {
+            return None;
+        }
+        self.registry.set_progress(&self.id, done, total)
+    }
+
+    /// Record what the operation achieved; it is reported when the guard
+    /// drops
+    pub fn complete(&mut self, tally: OperationTally) {
+        self.tally = Some(tally);
+    }
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        self.registry.finish(&self.id, self.tally.take());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_compares_canonical_paths_by_component() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        fs::create_dir_all(root.join("home/a")).unwrap();
+        fs::create_dir_all(root.join("home/ab")).unwrap();
+        fs::create_dir_all(root.join("other")).unwrap();
+        let registry = Arc::new(OperationRegistry::default());
+        let _scan = registry.start(OperationKind::Scan, &root.join("home/a").to_string_lossy());
+
+        let dotted = root.join("other/../home/a/file.txt");
+        assert_eq!(registry.overlapping(&dotted).len(), 1);
+        assert_eq!(registry.overlapping(&root.join("home")).len(), 1);
+        assert!(registry.overlapping(&root.join("home/ab")).is_empty());
+
+        #[cfg(unix)]
+        {
+            let link = root.join("link");
+            std::os::unix::fs::symlink(root.join("home"), &link).unwrap();
+            assert_eq!(registry.overlapping(&link.join("a")).len(), 1);
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use chrono::{DateTime, Utc};
+use globset::{Glob, GlobMatcher};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::utils::classifier::{Classification, FileCategory};
+use crate::utils::paths;
+
+const OVERRIDES_FILE_NAME: &str = "classification_overrides.json";
+
+/// A user rule forcing the classification of matching files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverrideRule {
+    pub id: String,
+    /// Glob matched against the full path or the file name; a leading `~/`
+    /// stands for the home directory
+    pub pattern: String,
+    pub category: FileCategory,
+    /// Confidence to report instead of the model's; 1.0 when not given
+    pub confidence: Option<f32>,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Which rule changed a classification and what it replaced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedOverride {
+    pub rule_id: String,
+    pub pattern: String,
+    pub original_category: FileCategory,
+    pub original_confidence: f32,
+}
+
+/// Persistent user override rules, applied after classification.
+///
+/// Rules are checked in the order they were added and the first match wins.
+/// Overrides are applied on the way out and never written to the analysis
+/// cache, so removing a rule takes effect immediately.
+#[derive(Debug, Clone, Default)]
+pub struct OverrideRules {
+    rules: Vec<OverrideRule>,
+    matchers: Vec<Option<GlobMatcher>>,
+}
+
+impl OverrideRules {
+    /// Location of the rules file under the config directory
+    pub fn file_path() -> PathBuf {
+        paths::config_dir().join(OVERRIDES_FILE_NAME)
+    }
+
+    /// Load rules from disk, starting empty if missing or unreadable
+    pub fn load() -> Self {
+        let path = Self::file_path();
+        let Ok(contents) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        let rules = serde_json::from_str(&contents).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable override rules {}: {}", path.display(), e);
+            Vec::new()
+        });
+        Self::from_rules(rules)
+    }
+
+    fn from_rules(rules: Vec<OverrideRule>) -> Self {
+        let matchers = rules
+            .iter()
+            .map(|rule| match compile(&rule.pattern) {
+                Ok(matcher) => Some(matcher),
+                Err(e) => {
+                    warn!("Ignoring override rule '{}': {}", rule.pattern, e);
+                    None
+                }
+            })
+            .collect();
+        Self { rules, matchers }
+    }
+
+    /// Persist rules to disk
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = Self::file_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(&self.rules)?)?;
+        Ok(())
+    }
+
+    pub fn rules(&self) -> &[OverrideRule] {
+        &self.rules
+    }
+
+    /// Add a rule, rejecting patterns that are not valid globs
+    pub fn add(
+        &mut self,
+        pattern: String,
+        category: FileCategory,
+        confidence: Option<f32>,
+        note: Option<String>,
+    ) -> Result<OverrideRule, String> {
+        if confidence.is_some_and(|c| !(0.0..=1.0).contains(&c)) {
+            return Err("Override confidence must be between 0 and 1".to_string());
+        }
+        let matcher = compile(&pattern).map_err(|e| format!("Invalid pattern: {}", e))?;
+
+        let rule = OverrideRule {
+            id: uuid::Uuid::new_v4().simple().to_string(),
+            pattern,
+            category,
+            confidence,
+            note,
+            created_at: Utc::now(),
+        };
+        self.rules.push(rule.clone());
+        self.matchers.push(Some(matcher));
+        Ok(rule)
+    }
+
+    /// Remove a rule by id, returning whether it existed
+    pub fn remove(&mut self, id: &str) -> bool {
+        let Some(index) = self.rules.iter().position(|rule| rule.id == id) else {
+            return false;
+        };
+        self.rules.remove(index);
+        self.matchers.remove(index);
+        true
+    }
+
+    /// Apply the first rule matching `path`, recording it on the result
+    pub fn apply(&self, path: &Path, mut classification: Classification) -> Classification {
+        let file_name = path.file_name();
+        let Some(rule) = self
+            .rules
+            .iter()
+            .zip(&self.matchers)
+            .find(|(_, matcher)| {
+                matcher.as_ref().is_some_and(|matcher| {
+                    matcher.is_match(path) || file_name.is_some_and(|name| matcher.is_match(name))
+                })
+            })
+            .map(|(rule, _)| rule)
+        else {
+            return classification;
+        };
+
+        classification.overridden_by = Some(AppliedOverride {
+            rule_id: rule.id.clone(),
+            pattern: rule.pattern.clone(),
+            original_category: classification.category,
+            original_confidence: classification.confidence,
+        });
+        classification.category = rule.category;
+        classification.confidence = rule.confidence.unwrap_or(1.0);
+        classification.reason = match &rule.note {
+            Some(note) => format!("Your rule '{}': {}", rule.pattern, note),
+            None => format!("Your rule '{}'", rule.pattern),
+        };
+        classification
+    }
+}
+
+fn compile(pattern: &str) -> Result<GlobMatcher, globset::Error> {
+    let expanded = match (pattern.strip_prefix("~/"), home::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest).to_string_lossy().to_string(),
+        _ => pattern.to_string(),
+    };
+    Ok(Glob::new(&expanded)?.compile_matcher())
+}
@@ -0,0 +1,110 @@
+use directories::ProjectDirs;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::utils::config::{AppConfig, ConfigFormat};
+
+const CONFIG_FILE_STEM: &str = "config";
+
+/// Resolved locations where the application stores its own data
+#[derive(Debug, Clone, Serialize)]
+pub struct AppPaths {
+    pub config_file: PathBuf,
+    pub config_directory: PathBuf,
+    pub cache_directory: PathBuf,
+    pub temp_directory: PathBuf,
+    pub log_directory: PathBuf,
+    pub backup_directory: PathBuf,
+}
+
+impl AppPaths {
+    /// Resolve all app paths as absolute paths, honoring config overrides
+    pub fn resolve(config: &AppConfig) -> Self {
+        Self {
+            config_file: absolute(&config_file()),
+            config_directory: absolute(&config_dir()),
+            cache_directory: absolute(&config.cache_directory),
+            temp_directory: absolute(&config.temp_directory),
+            log_directory: absolute(&log_dir()),
+            backup_directory: absolute(&backup_dir()),
+        }
+    }
+}
+
+/// Platform-specific project directories for the application
+pub fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("com", "aidiskcleaner", "ai-disk-cleaner")
+}
+
+/// Directory holding the config file and other persistent app data.
+///
+/// Falls back to a folder in the OS temp dir if no home directory exists.
+pub fn config_dir() -> PathBuf {
+    project_dirs()
+        .map(|dirs| dirs.config_dir().to_path_buf())
+        .unwrap_or_else(|| fallback_dir("config"))
+}
+
+/// Path of the persisted configuration file: the most recently written of
+/// `config.toml` and `config.json`, or `config.toml` if neither exists
+pub fn config_file() -> PathBuf {
+    [ConfigFormat::Toml, ConfigFormat::Json]
+        .into_iter()
+        .map(config_file_for)
+        .filter_map(|path| Some((fs::metadata(&path).and_then(|m| m.modified()).ok()?, path)))
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+        .unwrap_or_else(|| config_file_for(ConfigFormat::default()))
+}
+
+/// Path the configuration is saved to in `format`
+pub fn config_file_for(format: ConfigFormat) -> PathBuf {
+    config_dir().join(format!("{}.{}", CONFIG_FILE_STEM, format.extension()))
+}
+
+/// File holding the random salt mixed into redacted identifiers
+pub fn redaction_salt_file() -> PathBuf {
+    config_dir().join("redaction-salt")
+}
+
+/// Default cache directory (the OS cache location, not the temp folder)
+pub fn default_cache_dir() -> PathBuf {
+    project_dirs()
+        .map(|dirs| dirs.cache_dir().to_path_buf())
+        .unwrap_or_else(|| fallback_dir("cache"))
+}
+
+/// Default scratch directory for short-lived working files
+pub fn default_temp_dir() -> PathBuf {
+    default_cache_dir().join("temp")
+}
+
+/// Folder inside `temp_directory` that belongs to the app alone. The
+/// configured directory may be shared, e.g. `/tmp`, so only this folder is
+/// ever cleared.
+pub fn app_temp_dir(temp_directory: &Path) -> PathBuf {
+    temp_directory.join("ai-disk-cleaner")
+}
+
+/// Directory for application log files
+pub fn log_dir() -> PathBuf {
+    project_dirs()
+        .map(|dirs| dirs.data_local_dir().join("logs"))
+        .unwrap_or_else(|| fallback_dir("logs"))
+}
+
+/// Directory where files are backed up before deletion
+pub fn backup_dir() -> PathBuf {
+    project_dirs()
+        .map(|dirs| dirs.data_local_dir().join("backups"))
+        .unwrap_or_else(|| fallback_dir("backups"))
+}
+
+fn fallback_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("ai-disk-cleaner-{}", name))
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
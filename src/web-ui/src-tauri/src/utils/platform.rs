@@ -1,5 +1,9 @@
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlatformInfo {
@@ -82,6 +86,14 @@ pub fn get_executable_extension() -> &'static str {
     }
 }
 
+/// Whether an executable called `name` is found on `PATH`
+pub fn is_program_available(name: &str) -> bool {
+    let file_name = format!("{}{}", name, get_executable_extension());
+    env::var_os("PATH").is_some_and(|path| {
+        env::split_paths(&path).any(|dir| dir.join(&file_name).is_file())
+    })
+}
+
 /// Get platform-specific path separator
 pub fn get_path_separator() -> &'static str {
     match env::consts::OS {
@@ -96,4 +108,331 @@ pub fn get_line_ending() -> &'static str {
         "windows" => "\r\n",
         _ => "\n",
     }
-}
\ No newline at end of file
+}
+
+/// Resolve symlinks in `path`, returning `None` when it cannot be resolved.
+///
+/// On Windows the `\\?\` prefix added by canonicalize is stripped again for
+/// local drives so the result can be compared against ordinary paths.
+pub fn resolve_symlinks(path: &Path) -> Option<PathBuf> {
+    let resolved = path.canonicalize().ok()?;
+
+    if cfg!(windows) {
+        let text = resolved.to_string_lossy();
+        if let Some(local) = text.strip_prefix(r"\\?\") {
+            if !local.starts_with(r"UNC\") {
+                return Some(PathBuf::from(local));
+            }
+        }
+    }
+
+    Some(resolved)
+}
+
+/// Warning attached to paths that live on removable or external media
+pub const REMOVABLE_MEDIA_WARNING: &str =
+    "Path is on a removable or external drive - make sure you intend to clean it";
+
+/// Whether `path` lives on a volume the OS reports as removable (USB sticks,
+/// SD cards, most external drives). Unknown volumes count as fixed.
+pub fn is_removable_drive(path: &Path) -> bool {
+    with_containing_disk(path, |disk| disk.is_removable()).unwrap_or(false)
+}
+
+/// Warning attached to paths on SMB, NFS and other network filesystems
+pub const NETWORK_DRIVE_WARNING: &str =
+    "Path is on a network drive - operations are slow and may affect other machines";
+
+/// Space available to the current user on the volume holding `path`
+pub fn available_space(path: &Path) -> Option<u64> {
+    with_containing_disk(path, |disk| {
+        disk.refresh();
+        disk.available_space()
+    })
+}
+
+/// Total and available space of the volume holding `path`
+pub fn volume_space(path: &Path) -> Option<(u64, u64)> {
+    with_containing_disk(path, |disk| {
+        disk.refresh();
+        (disk.total_space(), disk.available_space())
+    })
+}
+
+/// File slots (inodes) on the volume holding a path
+#[derive(Debug, Clone, Copy)]
+pub struct InodeUsage {
+    pub total: u64,
+    pub free: u64,
+}
+
+/// Inode counts of the volume holding `path` from `statvfs`. `None` where
+/// the filesystem allocates inodes on demand (Btrfs, ZFS and APFS report no
+/// fixed total) and on Windows, which has no inodes.
+#[cfg(unix)]
+pub fn inode_usage(path: &Path) -> Option<InodeUsage> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    // SAFETY: statvfs is plain data, valid when zeroed
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `c_path` is NUL-terminated and `stat` is a valid out-pointer
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    // fsfilcnt_t is 32 bits on macOS and 64 on Linux
+    let total = stat.f_files as u64;
+    (total > 0).then_some(InodeUsage {
+        total,
+        free: stat.f_favail as u64,
+    })
+}
+
+#[cfg(not(unix))]
+pub fn inode_usage(_path: &Path) -> Option<InodeUsage> {
+    None
+}
+
+/// Mount point of the volume holding `path`
+pub fn mount_point(path: &Path) -> Option<PathBuf> {
+    with_containing_disk(path, |disk| disk.mount_point().to_path_buf())
+}
+
+/// Filesystem type of the volume holding `path` (e.g. `ext4`, `nfs4`, `NTFS`)
+pub fn filesystem_type(path: &Path) -> Option<String> {
+    with_containing_disk(path, |disk| disk.file_system().to_string_lossy().to_string())
+}
+
+/// Whether `path` lives on a network filesystem. UNC paths always count on
+/// Windows; elsewhere the mount's filesystem type decides.
+pub fn is_network_drive(path: &Path) -> bool {
+    is_unc_path(path)
+        || filesystem_type(path).is_some_and(|fs_type| is_network_filesystem_type(&fs_type))
+}
+
+/// Windows UNC share paths, with or without the extended-length prefix
+fn is_unc_path(path: &Path) -> bool {
+    if !cfg!(windows) {
+        return false;
+    }
+    let text = path.to_string_lossy();
+    text.starts_with(r"\\?\UNC\") || (text.starts_with(r"\\") && !text.starts_with(r"\\?\"))
+}
+
+/// Aliases accepted in `excluded_filesystem_types` next to concrete types
+pub const NETWORK_FILESYSTEM_ALIAS: &str = "network";
+pub const REMOVABLE_FILESYSTEM_ALIAS: &str = "removable";
+pub const READ_ONLY_FILESYSTEM_ALIAS: &str = "readonly";
+
+/// Canonical spelling of a configured filesystem type: lowercase, with the
+/// read-only alias accepted in its common spellings
+pub fn normalize_filesystem_type(name: &str) -> String {
+    let name = name.trim().to_ascii_lowercase();
+    match name.as_str() {
+        "read-only" | "read_only" | "ro" => READ_ONLY_FILESYSTEM_ALIAS.to_string(),
+        _ => name,
+    }
+}
+
+/// Every name the volume holding `path` can be excluded by: its lowercased
+/// filesystem type (`ext4`, `ntfs`, `cifs`, ...) plus the `network`,
+/// `removable` and `readonly` aliases that apply to it
+pub fn volume_type_names(path: &Path) -> Vec<String> {
+    let mut names = with_containing_disk(path, |disk| {
+        let fs_type = disk.file_system().to_string_lossy().to_ascii_lowercase();
+        let mut names = Vec::new();
+        if is_network_filesystem_type(&fs_type) {
+            names.push(NETWORK_FILESYSTEM_ALIAS.to_string());
+        }
+        if disk.is_removable() {
+            names.push(REMOVABLE_FILESYSTEM_ALIAS.to_string());
+        }
+        if disk.is_read_only() {
+            names.push(READ_ONLY_FILESYSTEM_ALIAS.to_string());
+        }
+        names.push(fs_type);
+        names
+    })
+    .unwrap_or_default();
+
+    if is_unc_path(path) && !names.iter().any(|name| name == NETWORK_FILESYSTEM_ALIAS) {
+        names.push(NETWORK_FILESYSTEM_ALIAS.to_string());
+    }
+    names
+}
+
+/// Filesystem types used for network shares and remote mounts
+pub fn is_network_filesystem_type(fs_type: &str) -> bool {
+    const NETWORK_TYPES: &[&str] = &[
+        "nfs", "nfs4", "cifs", "smb", "smb2", "smb3", "smbfs", "afpfs", "webdav", "davfs",
+        "ncpfs", "afs", "9p", "sshfs", "fuse.sshfs", "fuse.rclone",
+    ];
+
+    let fs_type = fs_type.to_ascii_lowercase();
+    NETWORK_TYPES.contains(&fs_type.as_str())
+}
+
+/// Whether file names on the volume holding `path` ignore case. Windows and
+/// macOS volumes do by default; elsewhere only FAT, exFAT and NTFS mounts.
+pub fn is_case_insensitive_volume(path: &Path) -> bool {
+    const CASE_INSENSITIVE_TYPES: &[&str] =
+        &["vfat", "msdos", "fat", "fat32", "exfat", "ntfs", "ntfs3"];
+
+    if cfg!(any(target_os = "windows", target_os = "macos")) {
+        return true;
+    }
+    filesystem_type(path).is_some_and(|fs_type| {
+        CASE_INSENSITIVE_TYPES.contains(&fs_type.to_ascii_lowercase().as_str())
+    })
+}
+
+/// Copy-on-write filesystems write changes to new blocks, so overwriting a
+/// file in place leaves its old contents on disk
+pub fn is_copy_on_write_filesystem(fs_type: &str) -> bool {
+    const COW_TYPES: &[&str] = &["btrfs", "apfs", "zfs", "bcachefs", "refs"];
+
+    let fs_type = fs_type.to_ascii_lowercase();
+    COW_TYPES.contains(&fs_type.as_str())
+}
+
+/// How long the list of mounted disks is reused before it is read again.
+/// Classifying or validating a scan looks up the disk of every path, and
+/// listing disks reads every mount on the system.
+const DISK_LIST_TTL: Duration = Duration::from_secs(5);
+
+static DISK_LIST: Mutex<Option<(Instant, sysinfo::Disks)>> = Mutex::new(None);
+
+/// Run `f` on the disk whose mount point most specifically contains `path`.
+/// The disk list is shared and only re-read once `DISK_LIST_TTL` passes, so
+/// callers needing current free space refresh the disk they are given.
+fn with_containing_disk<T>(path: &Path, f: impl FnOnce(&mut sysinfo::Disk) -> T) -> Option<T> {
+    // canonicalize adds a `\\?\` prefix on Windows that mount points lack
+    let resolved = if cfg!(windows) {
+        std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+    } else {
+        path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+    };
+
+    let mut cached = DISK_LIST.lock().unwrap_or_else(|e| e.into_inner());
+    if cached.as_ref().is_none_or(|(listed, _)| listed.elapsed() >= DISK_LIST_TTL) {
+        *cached = Some((Instant::now(), sysinfo::Disks::new_with_refreshed_list()));
+    }
+    let (_, disks) = cached.as_mut()?;
+    disks
+        .list_mut()
+        .iter_mut()
+        .filter(|disk| resolved.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(f)
+}
+
+/// Windows path length above which the `\\?\` extended-length prefix is
+/// required (MAX_PATH minus room for an 8.3 file name, the directory limit)
+pub const WINDOWS_LONG_PATH_THRESHOLD: usize = 248;
+
+/// Prepare a path for a filesystem call.
+///
+/// On Windows, absolute paths at or beyond the MAX_PATH limit get the
+/// extended-length prefix so `std::fs` calls succeed in deeply nested trees.
+/// Short paths and all Unix paths are returned unchanged.
+pub fn prepare_path_for_io(path: &Path) -> Cow<'_, Path> {
+    if cfg!(windows) {
+        let raw = path.to_string_lossy();
+        if raw.len() >= WINDOWS_LONG_PATH_THRESHOLD && path.is_absolute() {
+            // The prefix disables `.`/`..` handling, so resolve those first
+            let resolved = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+            if let Some(extended) = to_extended_length(&resolved.to_string_lossy()) {
+                return Cow::Owned(extended.into());
+            }
+        }
+    }
+
+    Cow::Borrowed(path)
+}
+
+/// Convert an absolute Windows path to its extended-length form
+fn to_extended_length(raw: &str) -> Option<String> {
+    if raw.starts_with(r"\\?\") || raw.starts_with(r"\\.\") {
+        return None;
+    }
+
+    // Extended-length paths are passed through verbatim, so separators
+    // must already be backslashes
+    let normalized = raw.replace('/', "\\");
+    match normalized.strip_prefix(r"\\") {
+        Some(unc) => Some(format!(r"\\?\UNC\{}", unc)),
+        None => Some(format!(r"\\?\{}", normalized)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An absolute path of more than 260 characters under `base`
+    fn long_path(base: &str, separator: char) -> String {
+        let segment = "n".repeat(50);
+        let mut path = base.to_string();
+        for _ in 0..6 {
+            path.push(separator);
+            path.push_str(&segment);
+        }
+        path.push(separator);
+        path.push_str("file.txt");
+        assert!(path.len() > 260);
+        path
+    }
+
+    #[test]
+    fn long_windows_paths_get_the_extended_length_prefix() {
+        let drive = long_path(r"C:\Users\me", '\\');
+        assert_eq!(to_extended_length(&drive), Some(format!(r"\\?\{}", drive)));
+
+        let unc = long_path(r"\\server\share", '\\');
+        let expected = format!(r"\\?\UNC\{}", &unc[2..]);
+        assert_eq!(to_extended_length(&unc), Some(expected));
+
+        let mixed = long_path("C:/Users/me", '/');
+        let expected = format!(r"\\?\{}", mixed.replace('/', "\\"));
+        assert_eq!(to_extended_length(&mixed), Some(expected));
+        assert_eq!(to_extended_length(&format!(r"\\?\{}", drive)), None);
+    }
+
+    #[test]
+    fn prepare_path_for_io_only_changes_long_windows_paths() {
+        let short = Path::new(if cfg!(windows) { r"C:\temp\file.txt" } else { "/tmp/file.txt" });
+        assert_eq!(prepare_path_for_io(short), short);
+
+        #[cfg(windows)]
+        {
+            let long = long_path(r"C:\Users\me", '\\');
+            let prepared = prepare_path_for_io(Path::new(&long));
+            assert!(prepared.to_string_lossy().starts_with(r"\\?\C:\"));
+        }
+        #[cfg(unix)]
+        {
+            let long = long_path("/home/me", '/');
+            assert_eq!(prepare_path_for_io(Path::new(&long)), Path::new(&long));
+        }
+    }
+
+    #[test]
+    fn disk_lookups_share_one_listing() {
+        let dir = std::env::temp_dir();
+        let listed_at = || DISK_LIST.lock().unwrap().as_ref().map(|(listed, _)| *listed);
+
+        is_removable_drive(&dir);
+        let first = listed_at();
+        for _ in 0..50 {
+            is_removable_drive(&dir);
+            mount_point(&dir);
+            filesystem_type(&dir);
+        }
+        let second = listed_at();
+        assert!(first.is_some());
+        // Another test may have re-listed once the TTL lapsed, never per call
+        if first.is_some_and(|listed| listed.elapsed() < DISK_LIST_TTL) {
+            assert_eq!(first, second);
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Sliding-window limiter for AI provider requests
+#[derive(Debug)]
+pub struct RateLimiter {
+    requests_per_minute: u32,
+    sent: Mutex<VecDeque<Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_minute: u32) -> Self {
+        Self {
+            requests_per_minute: requests_per_minute.max(1),
+            sent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Wait until a request slot is free in the current window, then claim it
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut sent = self.sent.lock().await;
+                let now = Instant::now();
+                while sent.front().is_some_and(|t| now.duration_since(*t) >= WINDOW) {
+                    sent.pop_front();
+                }
+
+                if sent.len() < self.requests_per_minute as usize {
+                    sent.push_back(now);
+                    return;
+                }
+
+                // Oldest request leaves the window first
+                WINDOW - now.duration_since(sent[0])
+            };
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
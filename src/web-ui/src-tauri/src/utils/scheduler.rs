@@ -0,0 +1,463 @@
+use anyhow::{bail, Context};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::utils::paths;
+
+/// Argument that makes the executable run a cleanup plan without the UI
+pub const HEADLESS_ARG: &str = "--headless";
+/// Followed by the path of the plan a headless run executes
+pub const PLAN_ARG: &str = "--plan";
+
+/// Registered cleanups, kept next to the config file
+const MANIFEST_FILE_NAME: &str = "scheduled_cleanups.json";
+/// Prefix of every task, timer and cron marker this app creates
+const TASK_PREFIX: &str = "ai-disk-cleaner-cleanup";
+/// Local hour at which scheduled cleanups run
+const RUN_HOUR: u32 = 3;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleInterval {
+    Daily,
+    /// Sundays
+    Weekly,
+    /// The first of each month
+    Monthly,
+}
+
+/// OS facility that runs a scheduled cleanup
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SchedulerBackend {
+    TaskScheduler,
+    Launchd,
+    SystemdTimer,
+    Cron,
+}
+
+/// A cleanup plan registered to run unattended
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledCleanup {
+    pub id: String,
+    pub plan_path: PathBuf,
+    pub interval: ScheduleInterval,
+    pub backend: SchedulerBackend,
+    pub registered_at: DateTime<Utc>,
+}
+
+impl ScheduledCleanup {
+    /// Name of the task, timer or cron marker for this cleanup
+    pub fn task_name(&self) -> String {
+        format!("{}-{}", TASK_PREFIX, self.id)
+    }
+}
+
+/// Stable id for the plan at `plan_path`, so registering the same plan
+/// again replaces its task instead of adding a second one
+pub fn task_id(plan_path: &Path) -> String {
+    let hash = blake3::hash(plan_path.to_string_lossy().as_bytes()).to_hex();
+    hash[..12].to_string()
+}
+
+pub fn load_manifest() -> Vec<ScheduledCleanup> {
+    fs::read_to_string(paths::config_dir().join(MANIFEST_FILE_NAME))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_manifest(tasks: &[ScheduledCleanup]) -> anyhow::Result<()> {
+    let dir = paths::config_dir();
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(MANIFEST_FILE_NAME), serde_json::to_string_pretty(tasks)?)?;
+    Ok(())
+}
+
+/// Backend `install` uses on this machine: systemd user timers where a user
+/// manager is running, cron on other Unix systems
+pub fn preferred_backend() -> SchedulerBackend {
+    if cfg!(target_os = "windows") {
+        SchedulerBackend::TaskScheduler
+    } else if cfg!(target_os = "macos") {
+        SchedulerBackend::Launchd
+    } else if run(Command::new("systemctl").args(["--user", "show-environment"])).is_ok() {
+        SchedulerBackend::SystemdTimer
+    } else {
+        SchedulerBackend::Cron
+    }
+}
+
+/// Register `task` with its backend so that it runs `executable` in
+/// headless mode; an existing registration of the same task is replaced
+pub fn install(task: &ScheduledCleanup, executable: &Path) -> anyhow::Result<()> {
+    let name = task.task_name();
+    match task.backend {
+        SchedulerBackend::TaskScheduler => {
+            let (schedule, day): (&str, &[&str]) = match task.interval {
+                ScheduleInterval::Daily => ("DAILY", &[]),
+                ScheduleInterval::Weekly => ("WEEKLY", &["/D", "SUN"]),
+                ScheduleInterval::Monthly => ("MONTHLY", &["/D", "1"]),
+            };
+            let action = format!(
+                "\"{}\" {} {} \"{}\"",
+                executable.display(),
+                HEADLESS_ARG,
+                PLAN_ARG,
+                task.plan_path.display()
+            );
+            let start = format!("{:02}:00", RUN_HOUR);
+            run(Command::new("schtasks")
+                .args(["/Create", "/F", "/TN", &name, "/SC", schedule])
+                .args(day)
+                .args(["/ST", &start, "/TR", &action]))
+        }
+        SchedulerBackend::Launchd => {
+            let plist = launch_agent_path(&name)?;
+            if let Some(parent) = plist.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            // Unload a previous version first; failing because none is loaded is fine
+            let _ = run(Command::new("launchctl").arg("unload").arg(&plist));
+            fs::write(&plist, launch_agent_plist(task, executable))?;
+            run(Command::new("launchctl").args(["load", "-w"]).arg(&plist))
+        }
+        SchedulerBackend::SystemdTimer => {
+            let dir = systemd_user_dir()?;
+            fs::create_dir_all(&dir)?;
+            fs::write(
+                dir.join(format!("{}.service", name)),
+                systemd_service(task, executable),
+            )?;
+            fs::write(dir.join(format!("{}.timer", name)), systemd_timer(task))?;
+            run(Command::new("systemctl").args(["--user", "daemon-reload"]))?;
+            run(Command::new("systemctl")
+                .args(["--user", "enable", "--now"])
+                .arg(format!("{}.timer", name)))
+        }
+        SchedulerBackend::Cron => {
+            let mut lines = crontab_without(&name)?;
+            lines.push(cron_line(task, executable));
+            write_crontab(&lines)
+        }
+    }
+}
+
+/// Remove `task` from its backend; a task that is already gone is not an error
+pub fn uninstall(task: &ScheduledCleanup) -> anyhow::Result<()> {
+    let name = task.task_name();
+    match task.backend {
+        SchedulerBackend::TaskScheduler => {
+            if is_installed(task) {
+                run(Command::new("schtasks").args(["/Delete", "/F", "/TN", &name]))?;
+            }
+            Ok(())
+        }
+        SchedulerBackend::Launchd => {
+            let plist = launch_agent_path(&name)?;
+            if plist.exists() {
+                let _ = run(Command::new("launchctl").args(["unload", "-w"]).arg(&plist));
+                fs::remove_file(&plist)?;
+            }
+            Ok(())
+        }
+        SchedulerBackend::SystemdTimer => {
+            let dir = systemd_user_dir()?;
+            let timer = format!("{}.timer", name);
+            if dir.join(&timer).exists() {
+                let _ = run(Command::new("systemctl").args(["--user", "disable", "--now", &timer]));
+            }
+            for unit in [timer, format!("{}.service", name)] {
+                match fs::remove_file(dir.join(unit)) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                    _ => {}
+                }
+            }
+            run(Command::new("systemctl").args(["--user", "daemon-reload"]))
+        }
+        SchedulerBackend::Cron => write_crontab(&crontab_without(&name)?),
+    }
+}
+
+/// Whether the backend still knows `task`; users can remove tasks with the
+/// OS tools behind the app's back
+pub fn is_installed(task: &ScheduledCleanup) -> bool {
+    let name = task.task_name();
+    match task.backend {
+        SchedulerBackend::TaskScheduler => {
+            run(Command::new("schtasks").args(["/Query", "/TN", &name])).is_ok()
+        }
+        SchedulerBackend::Launchd => launch_agent_path(&name).is_ok_and(|plist| plist.exists()),
+        SchedulerBackend::SystemdTimer => {
+            systemd_user_dir().is_ok_and(|dir| dir.join(format!("{}.timer", name)).exists())
+        }
+        SchedulerBackend::Cron => read_crontab()
+            .is_ok_and(|lines| lines.iter().any(|line| line.ends_with(&format!("# {}", name)))),
+    }
+}
+
+/// Run `command`, turning a non-zero exit into an error with its stderr
+fn run(command: &mut Command) -> anyhow::Result<()> {
+    let program = command.get_program().to_string_lossy().to_string();
+    let output = command
+        .stdin(Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        bail!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+fn home() -> anyhow::Result<PathBuf> {
+    home::home_dir().context("Unable to determine home directory")
+}
+
+fn launch_agent_path(name: &str) -> anyhow::Result<PathBuf> {
+    Ok(home()?
+        .join("Library")
+        .join("LaunchAgents")
+        .join(format!("com.aidiskcleaner.{}.plist", name)))
+}
+
+fn launch_agent_plist(task: &ScheduledCleanup, executable: &Path) -> String {
+    let escape = |value: &str| {
+        value
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    };
+    let calendar = match task.interval {
+        ScheduleInterval::Daily => String::new(),
+        ScheduleInterval::Weekly => "<key>Weekday</key><integer>0</integer>".to_string(),
+        ScheduleInterval::Monthly => "<key>Day</key><integer>1</integer>".to_string(),
+    };
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>com.aidiskcleaner.{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+        <string>{}</string>
+        <string>{}</string>
+        <string>{}</string>
+    </array>
+    <key>StartCalendarInterval</key>
+    <dict>
+        <key>Hour</key><integer>{}</integer>
+        <key>Minute</key><integer>0</integer>
+        {}
+    </dict>
+</dict>
+</plist>
+"#,
+        task.task_name(),
+        escape(&executable.to_string_lossy()),
+        HEADLESS_ARG,
+        PLAN_ARG,
+        escape(&task.plan_path.to_string_lossy()),
+        RUN_HOUR,
+        calendar
+    )
+}
+
+fn systemd_user_dir() -> anyhow::Result<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute());
+    Ok(match config {
+        Some(config) => config,
+        None => home()?.join(".config"),
+    }
+    .join("systemd")
+    .join("user"))
+}
+
+/// `value` as a quoted systemd `ExecStart` word; `%` starts a specifier
+fn systemd_quote(value: &Path) -> String {
+    let value = value
+        .to_string_lossy()
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%");
+    format!("\"{}\"", value)
+}
+
+fn systemd_service(task: &ScheduledCleanup, executable: &Path) -> String {
+    format!(
+        "[Unit]\nDescription=AI Disk Cleaner scheduled cleanup {}\n\n\
+         [Service]\nType=oneshot\nExecStart={} {} {} {}\n",
+        task.id,
+        systemd_quote(executable),
+        HEADLESS_ARG,
+        PLAN_ARG,
+        systemd_quote(&task.plan_path)
+    )
+}
+
+fn systemd_timer(task: &ScheduledCleanup) -> String {
+    let calendar = match task.interval {
+        ScheduleInterval::Daily => format!("*-*-* {:02}:00:00", RUN_HOUR),
+        ScheduleInterval::Weekly => format!("Sun *-*-* {:02}:00:00", RUN_HOUR),
+        ScheduleInterval::Monthly => format!("*-*-01 {:02}:00:00", RUN_HOUR),
+    };
+    // Persistent catches up on runs missed while the machine was off
+    format!(
+        "[Unit]\nDescription=AI Disk Cleaner scheduled cleanup {}\n\n\
+         [Timer]\nOnCalendar={}\nPersistent=true\n\n\
+         [Install]\nWantedBy=timers.target\n",
+        task.id, calendar
+    )
+}
+
+/// `value` single-quoted for the shell cron runs; a bare `%` would end the
+/// command in cron
+fn cron_quote(value: &Path) -> String {
+    let value = value
+        .to_string_lossy()
+        .replace('\'', "'\\''")
+        .replace('%', "\\%");
+    format!("'{}'", value)
+}
+
+/// The crontab entry running `task`, marked with its task name
+fn cron_line(task: &ScheduledCleanup, executable: &Path) -> String {
+    let (day_of_month, day_of_week) = match task.interval {
+        ScheduleInterval::Daily => ("*", "*"),
+        ScheduleInterval::Weekly => ("*", "0"),
+        ScheduleInterval::Monthly => ("1", "*"),
+    };
+    format!(
+        "0 {} {} * {} {} {} {} {} # {}",
+        RUN_HOUR,
+        day_of_month,
+        day_of_week,
+        cron_quote(executable),
+        HEADLESS_ARG,
+        PLAN_ARG,
+        cron_quote(&task.plan_path),
+        task.task_name()
+    )
+}
+
+fn read_crontab() -> anyhow::Result<Vec<String>> {
+    let output = Command::new("crontab")
+        .arg("-l")
+        .stdin(Stdio::null())
+        .output()
+        .context("Failed to run crontab")?;
+    crontab_lines(output.status.success(), &output.stdout, &output.stderr)
+}
+
+/// Lines of `crontab -l` output. Having no crontab yet counts as an empty
+/// one; any other failure is an error, so a crontab that could not be read
+/// is never rewritten as empty.
+fn crontab_lines(success: bool, stdout: &[u8], stderr: &[u8]) -> anyhow::Result<Vec<String>> {
+    if !success {
+        let stderr = String::from_utf8_lossy(stderr);
+        if stderr.to_lowercase().contains("no crontab for") {
+            return Ok(Vec::new());
+        }
+        bail!("crontab -l failed: {}", stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(stdout)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// The user's crontab without the entry marked with `name`
+fn crontab_without(name: &str) -> anyhow::Result<Vec<String>> {
+    Ok(without_entry(read_crontab()?, name))
+}
+
+fn without_entry(mut lines: Vec<String>, name: &str) -> Vec<String> {
+    let marker = format!("# {}", name);
+    lines.retain(|line| !line.ends_with(&marker));
+    lines
+}
+
+fn write_crontab(lines: &[String]) -> anyhow::Result<()> {
+    let mut child = Command::new("crontab")
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run crontab")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        for line in lines {
+            writeln!(stdin, "{}", line)?;
+        }
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!(
+            "crontab failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(plan_path: &str, interval: ScheduleInterval) -> ScheduledCleanup {
+        let plan_path = PathBuf::from(plan_path);
+        ScheduledCleanup {
+            id: task_id(&plan_path),
+            plan_path,
+            interval,
+            backend: SchedulerBackend::Cron,
+            registered_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn cron_lines_quote_paths_and_replace_only_their_own_entry() {
+        let weekly = task("/home/me/100% plans/it's.json", ScheduleInterval::Weekly);
+        let daily = task("/home/me/daily.json", ScheduleInterval::Daily);
+        let line = cron_line(&weekly, Path::new("/opt/cleaner"));
+        assert_eq!(
+            line,
+            format!(
+                "0 3 * * 0 '/opt/cleaner' --headless --plan \
+                 '/home/me/100\\% plans/it'\\''s.json' # {}",
+                weekly.task_name()
+            )
+        );
+
+        let lines = vec![
+            "MAILTO=me".to_string(),
+            line,
+            cron_line(&daily, Path::new("/opt/cleaner")),
+        ];
+        let kept = without_entry(lines, &weekly.task_name());
+        assert_eq!(kept.len(), 2);
+        assert_eq!(kept[0], "MAILTO=me");
+        assert!(kept[1].ends_with(&daily.task_name()));
+    }
+
+    #[test]
+    fn only_a_missing_crontab_reads_as_empty() {
+        assert!(crontab_lines(false, b"", b"no crontab for me\n").unwrap().is_empty());
+        assert!(crontab_lines(false, b"", b"crontab: Permission denied\n").is_err());
+        assert_eq!(
+            crontab_lines(true, b"MAILTO=me\n0 3 * * * true\n", b"").unwrap(),
+            vec!["MAILTO=me", "0 3 * * * true"]
+        );
+    }
+}
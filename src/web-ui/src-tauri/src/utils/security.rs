@@ -1,5 +1,13 @@
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use anyhow::Result;
+use globset::Glob;
+use serde::Serialize;
+
+use crate::utils::config::SecurityConfig;
+use crate::utils::platform::{
+    is_network_drive, is_removable_drive, resolve_symlinks, NETWORK_DRIVE_WARNING,
+    REMOVABLE_MEDIA_WARNING,
+};
 
 pub struct SecurityValidator;
 
@@ -73,6 +81,15 @@ impl SecurityValidator {
             warnings.push("Very long path - may cause system limitations".to_string());
         }
 
+        // External media warning; users may still proceed deliberately
+        if is_removable_drive(path) {
+            warnings.push(REMOVABLE_MEDIA_WARNING.to_string());
+        }
+
+        if is_network_drive(path) {
+            warnings.push(NETWORK_DRIVE_WARNING.to_string());
+        }
+
         let (is_safe, risk_level) = Self::calculate_risk_level(&warnings, &blocked_reasons);
 
         Ok(PathValidation {
@@ -83,7 +100,81 @@ impl SecurityValidator {
         })
     }
 
+    /// Validate that a single file, symlink or empty directory may be deleted.
+    ///
+    /// Returns the reason the deletion is blocked, if any. Symlinks are checked
+    /// without following them, so dangling links can still be validated.
+    pub fn validate_deletion_target(
+        path: &Path,
+        config: &SecurityConfig,
+    ) -> std::result::Result<(), String> {
+        if std::fs::symlink_metadata(path).is_err() {
+            return Err("Path does not exist".to_string());
+        }
+
+        if Self::contains_path_traversal(path) {
+            return Err("Path contains traversal patterns".to_string());
+        }
+
+        if !config.allow_system_directories && Self::is_system_directory(path) {
+            return Err("System directory access is blocked".to_string());
+        }
+
+        if let Some(pattern) = Self::matching_protected_pattern(path, &config.protected_patterns) {
+            return Err(format!("Path matches protected pattern '{}'", pattern));
+        }
+
+        Ok(())
+    }
+
+    /// Ensure a deletion target lies strictly below the user-confirmed root.
+    ///
+    /// The target's parent is canonicalized (the target itself may be a
+    /// dangling symlink) so `..` segments and symlinked parents cannot be
+    /// used to escape the root.
+    pub fn validate_within_root(path: &Path, root: &Path) -> std::result::Result<(), String> {
+        let outside = || format!("Path is outside the confirmed scan root {}", root.display());
+
+        let file_name = path.file_name().ok_or_else(outside)?;
+        let parent = path
+            .parent()
+            .and_then(|parent| parent.canonicalize().ok())
+            .ok_or_else(outside)?;
+
+        let target = parent.join(file_name);
+        if target.starts_with(root) && target != root {
+            Ok(())
+        } else {
+            Err(outside())
+        }
+    }
+
+    /// Return the first protected pattern matching the path's file name or full path
+    pub fn matching_protected_pattern(path: &Path, patterns: &[String]) -> Option<String> {
+        let file_name = path.file_name().map(|n| n.to_string_lossy().to_string());
+
+        patterns
+            .iter()
+            .find(|pattern| {
+                let Ok(glob) = Glob::new(pattern) else {
+                    return false;
+                };
+                let matcher = glob.compile_matcher();
+                matcher.is_match(path)
+                    || file_name.as_deref().is_some_and(|name| matcher.is_match(name))
+            })
+            .cloned()
+    }
+
+    /// Whether the path, or the target it resolves to through symlinks, is a
+    /// system directory, so e.g. a link pointing into `/etc` is still blocked
     fn is_system_directory(path: &Path) -> bool {
+        Self::matches_system_directory(path)
+            || resolve_symlinks(path)
+                .is_some_and(|resolved| Self::matches_system_directory(&resolved))
+    }
+
+    fn matches_system_directory(path: &Path) -> bool {
         let path_str = path.to_string_lossy();
 
         // Windows system directories
@@ -166,7 +257,7 @@ impl SecurityValidator {
         path_str.chars().any(|c| {
             !c.is_ascii() ||
             c == '<' || c == '>' || c == ':' || c == '"' ||
-            c == '|' || c == '?' || c == '*''
+            c == '|' || c == '?' || c == '*'
         })
     }
 
@@ -205,6 +296,105 @@ pub fn validate_path(path: &str) -> Result<PathValidation> {
     SecurityValidator::validate_path(path)
 }
 
+/// Validate a user-supplied root directory before walking it
+pub fn validate_scan_root(path: &str) -> crate::AppResult<PathBuf> {
+    let validation = SecurityValidator::validate_path(path)
+        .map_err(|e| crate::AppError::SecurityError(e.to_string()))?;
+
+    if !validation.is_safe {
+        return Err(crate::AppError::SecurityError(
+            validation.blocked_reasons.join("; "),
+        ));
+    }
+
+    Ok(PathBuf::from(path))
+}
+
+/// Why `path` would sweep in far more than a cleanup usually means to as a
+/// scan root, if it would: a filesystem root, the home folder itself or one
+/// of the personal folders directly inside it
+pub fn broad_root_reason(path: &Path) -> Option<String> {
+    if path.parent().is_none() {
+        return Some("is the root of a filesystem".to_string());
+    }
+    if home::home_dir().is_some_and(|home| path == home) {
+        return Some("is the home folder".to_string());
+    }
+    if SecurityValidator::is_user_sensitive_directory(path) {
+        return Some("holds personal files".to_string());
+    }
+    None
+}
+
+/// Validate a user-chosen destination file before writing to it.
+///
+/// The parent directory must pass the same checks as a scan root, and the
+/// destination itself must not be an existing directory or symlink.
+pub fn validate_output_path(path: &str) -> crate::AppResult<PathBuf> {
+    let destination = PathBuf::from(path);
+    if SecurityValidator::contains_path_traversal(&destination) {
+        return Err(crate::AppError::SecurityError(
+            "Path contains traversal patterns".to_string(),
+        ));
+    }
+
+    let parent = destination
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .ok_or_else(|| crate::AppError::SecurityError("Destination has no parent directory".to_string()))?;
+    validate_scan_root(&parent.to_string_lossy())?;
+
+    if let Ok(metadata) = std::fs::symlink_metadata(&destination) {
+        if !metadata.is_file() {
+            return Err(crate::AppError::SecurityError(
+                "Destination exists and is not a regular file".to_string(),
+            ));
+        }
+    }
+
+    Ok(destination)
+}
+
+/// Join `base` with a relative path read from somewhere untrusted, such as
+/// a backup manifest, and make sure the result stays inside `base`.
+///
+/// Absolute paths, drive prefixes and `..` segments that climb above
+/// `base` are rejected outright. The deepest part of the result that exists
+/// is then canonicalized and compared with the canonical `base`, so a
+/// symlink inside `base` cannot point the result elsewhere either.
+pub fn safe_join(base: &Path, relative: impl AsRef<Path>) -> std::result::Result<PathBuf, String> {
+    let relative = relative.as_ref();
+    let escapes = || format!("{} escapes {}", relative.display(), base.display());
+
+    let mut parts: Vec<&std::ffi::OsStr> = Vec::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => parts.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                parts.pop().ok_or_else(escapes)?;
+            }
+            Component::RootDir | Component::Prefix(_) => return Err(escapes()),
+        }
+    }
+
+    let base = base
+        .canonicalize()
+        .map_err(|e| format!("Cannot resolve {}: {}", base.display(), e))?;
+    let joined: PathBuf = parts.iter().fold(base.clone(), |path, part| path.join(part));
+
+    // Only the part that exists can be resolved; the rest was checked above
+    let existing = joined
+        .ancestors()
+        .find_map(|ancestor| ancestor.canonicalize().ok())
+        .ok_or_else(escapes)?;
+    if existing.starts_with(&base) {
+        Ok(joined)
+    } else {
+        Err(escapes())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct PathValidation {
     pub is_safe: bool,
@@ -213,11 +403,95 @@ pub struct PathValidation {
     pub blocked_reasons: Vec<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, PartialEq, PartialOrd)]
 pub enum RiskLevel {
     Safe,
     Low,
     Medium,
     High,
     Critical,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn deleting_outside_the_scan_root_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().canonicalize().unwrap();
+        let root = base.join("root");
+        fs::create_dir_all(root.join("nested")).unwrap();
+        fs::create_dir_all(base.join("root-sibling")).unwrap();
+        fs::write(root.join("nested/inside.txt"), b"x").unwrap();
+        fs::write(base.join("outside.txt"), b"x").unwrap();
+        fs::write(base.join("root-sibling/file.txt"), b"x").unwrap();
+
+        let inside = root.join("nested/inside.txt");
+        assert!(SecurityValidator::validate_within_root(&inside, &root).is_ok());
+        for outside in [
+            base.join("outside.txt"),
+            root.join("nested/../../outside.txt"),
+            base.join("root-sibling/file.txt"),
+            root.clone(),
+        ] {
+            assert!(
+                SecurityValidator::validate_within_root(&outside, &root).is_err(),
+                "{} was accepted",
+                outside.display()
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_into_a_system_directory_is_blocked() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = SecurityConfig::default();
+        let to_dir = dir.path().join("etc-link");
+        let to_file = dir.path().join("passwd-link");
+        std::os::unix::fs::symlink("/etc", &to_dir).unwrap();
+        std::os::unix::fs::symlink("/etc/passwd", &to_file).unwrap();
+
+        for link in [&to_dir, &to_file] {
+            assert_eq!(
+                SecurityValidator::validate_deletion_target(link, &config),
+                Err("System directory access is blocked".to_string()),
+                "{} was accepted",
+                link.display()
+            );
+        }
+        assert!(!SecurityValidator::validate_path_buf(&to_dir).unwrap().is_safe);
+    }
+
+    #[test]
+    fn safe_join_rejects_parent_and_absolute_escapes() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("base");
+        fs::create_dir_all(base.join("sub")).unwrap();
+
+        assert_eq!(
+            safe_join(&base, "sub/../file.txt").unwrap(),
+            base.canonicalize().unwrap().join("file.txt")
+        );
+        for escape in ["..", "../outside.txt", "sub/../../outside.txt", "./../base/../.."] {
+            assert!(safe_join(&base, escape).is_err(), "{} was accepted", escape);
+        }
+        let absolute = dir.path().join("outside.txt");
+        assert!(safe_join(&base, &absolute).is_err());
+        #[cfg(windows)]
+        assert!(safe_join(&base, "C:\\Windows\\win.ini").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn safe_join_rejects_symlinks_out_of_the_base() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("base");
+        fs::create_dir_all(&base).unwrap();
+        std::os::unix::fs::symlink(dir.path(), base.join("up")).unwrap();
+
+        assert!(safe_join(&base, "up/outside.txt").is_err());
+    }
+}
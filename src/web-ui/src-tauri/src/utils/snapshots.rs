@@ -0,0 +1,248 @@
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::utils::classifier::{Classification, FileFacts};
+
+const SNAPSHOT_DIR_NAME: &str = "snapshots";
+/// Sidecar extension for session summaries, kept apart from `.json` snapshots
+const SUMMARY_EXTENSION: &str = "meta";
+/// Sidecar extension for the classifications of an analyzed session
+const RESULTS_EXTENSION: &str = "results";
+
+/// Compact per-file record kept in a scan snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    #[serde(rename = "p")]
+    pub path: String,
+    #[serde(rename = "s")]
+    pub size: u64,
+    /// Modification time as Unix seconds
+    #[serde(rename = "m", default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<i64>,
+}
+
+impl SnapshotEntry {
+    /// Whether `other` describes the same file with the same size and
+    /// modification time, i.e. the file has not changed between scans
+    pub fn unchanged_from(&self, other: &SnapshotEntry) -> bool {
+        self.path == other.path && self.size == other.size && self.modified == other.modified
+    }
+
+    /// Classifier facts for this entry as they were at scan time
+    pub fn facts(&self) -> FileFacts {
+        let modified = self
+            .modified
+            .and_then(|secs| DateTime::<Utc>::from_timestamp(secs, 0));
+        FileFacts::new(Path::new(&self.path), self.size, modified)
+    }
+}
+
+/// Persisted file listing of a completed scan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanSnapshot {
+    pub id: String,
+    pub root: String,
+    pub created_at: DateTime<Utc>,
+    pub entries: Vec<SnapshotEntry>,
+}
+
+impl ScanSnapshot {
+    /// Create a snapshot with a chronologically sortable id
+    pub fn new(root: &str, entries: Vec<SnapshotEntry>) -> Self {
+        let created_at = Utc::now();
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        Self {
+            id: format!("{}-{}", created_at.format("%Y%m%d%H%M%S"), &suffix[..8]),
+            root: root.to_string(),
+            created_at,
+            entries,
+        }
+    }
+
+    /// Metadata describing this snapshot without its file listing
+    pub fn summary(&self) -> SessionSummary {
+        SessionSummary {
+            id: self.id.clone(),
+            root: self.root.clone(),
+            created_at: self.created_at,
+            total_files: self.entries.len() as u64,
+            total_size: self.entries.iter().map(|entry| entry.size).sum(),
+        }
+    }
+}
+
+/// Lightweight description of a persisted scan session, enough for the UI
+/// to offer resuming it without loading the full listing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub id: String,
+    pub root: String,
+    pub created_at: DateTime<Utc>,
+    pub total_files: u64,
+    pub total_size: u64,
+}
+
+/// Classifications from analyzing a session, before user override rules,
+/// kept so a later scan of the same root can reuse them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionResults {
+    /// Language the reasons were written in
+    pub language: String,
+    /// Classification by file path
+    pub classifications: HashMap<String, Classification>,
+}
+
+/// On-disk store of scan snapshots under the cache directory
+pub struct SnapshotStore {
+    dir: PathBuf,
+}
+
+impl SnapshotStore {
+    pub fn new(cache_directory: &Path) -> Self {
+        Self {
+            dir: cache_directory.join(SNAPSHOT_DIR_NAME),
+        }
+    }
+
+    /// Persist a snapshot and prune the oldest beyond `retention`
+    pub fn save(&self, snapshot: &ScanSnapshot, retention: usize) -> anyhow::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.snapshot_path(&snapshot.id)?;
+        std::fs::write(&path, serde_json::to_vec(snapshot)?)?;
+        std::fs::write(
+            path.with_extension(SUMMARY_EXTENSION),
+            serde_json::to_vec(&snapshot.summary())?,
+        )?;
+        self.prune(retention);
+        Ok(())
+    }
+
+    /// Load a snapshot by id
+    pub fn load(&self, id: &str) -> anyhow::Result<ScanSnapshot> {
+        let path = self.snapshot_path(id)?;
+        let contents = std::fs::read(&path)
+            .with_context(|| format!("Scan snapshot '{}' not found", id))?;
+        Ok(serde_json::from_slice(&contents)?)
+    }
+
+    /// Store the analysis results of a session next to its snapshot
+    pub fn save_results(&self, id: &str, results: &SessionResults) -> anyhow::Result<()> {
+        let path = self.snapshot_path(id)?.with_extension(RESULTS_EXTENSION);
+        std::fs::write(path, serde_json::to_vec(results)?)?;
+        Ok(())
+    }
+
+    /// Analysis results of a session, `None` if it was never analyzed
+    pub fn load_results(&self, id: &str) -> anyhow::Result<Option<SessionResults>> {
+        let path = self.snapshot_path(id)?.with_extension(RESULTS_EXTENSION);
+        match std::fs::read(&path) {
+            Ok(contents) => Ok(Some(serde_json::from_slice(&contents)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Snapshot ids, oldest first
+    pub fn list_ids(&self) -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+
+        let mut ids: Vec<String> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                name.strip_suffix(".json").map(str::to_string)
+            })
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// Summaries of all stored sessions, newest first.
+    ///
+    /// Snapshots saved before summaries were written are loaded in full once
+    /// and get their summary backfilled.
+    pub fn list_summaries(&self) -> Vec<SessionSummary> {
+        let mut summaries: Vec<SessionSummary> = self
+            .list_ids()
+            .iter()
+            .filter_map(|id| match self.load_summary(id) {
+                Ok(summary) => Some(summary),
+                Err(e) => {
+                    warn!("Skipping unreadable scan session {}: {}", id, e);
+                    None
+                }
+            })
+            .collect();
+        summaries.reverse();
+        summaries
+    }
+
+    fn load_summary(&self, id: &str) -> anyhow::Result<SessionSummary> {
+        let summary_path = self.snapshot_path(id)?.with_extension(SUMMARY_EXTENSION);
+        if let Ok(contents) = std::fs::read(&summary_path) {
+            return Ok(serde_json::from_slice(&contents)?);
+        }
+
+        let summary = self.load(id)?.summary();
+        if let Err(e) = std::fs::write(&summary_path, serde_json::to_vec(&summary)?) {
+            warn!("Failed to backfill summary for scan session {}: {}", id, e);
+        }
+        Ok(summary)
+    }
+
+    fn prune(&self, retention: usize) {
+        let ids = self.list_ids();
+        let excess = ids.len().saturating_sub(retention.max(1));
+
+        for id in &ids[..excess] {
+            if let Err(e) = self.remove(id) {
+                warn!("Failed to prune scan snapshot {}: {}", id, e);
+            }
+        }
+    }
+
+    /// Delete a session's snapshot, summary and results, returning the
+    /// bytes they took
+    pub fn remove(&self, id: &str) -> anyhow::Result<u64> {
+        let path = self.snapshot_path(id)?;
+        let size = self.size_on_disk(id);
+        std::fs::remove_file(&path)?;
+        let _ = std::fs::remove_file(path.with_extension(SUMMARY_EXTENSION));
+        let _ = std::fs::remove_file(path.with_extension(RESULTS_EXTENSION));
+        Ok(size)
+    }
+
+    /// Bytes taken by a session's snapshot, summary and results
+    pub fn size_on_disk(&self, id: &str) -> u64 {
+        let Ok(path) = self.snapshot_path(id) else {
+            return 0;
+        };
+        [
+            path.clone(),
+            path.with_extension(SUMMARY_EXTENSION),
+            path.with_extension(RESULTS_EXTENSION),
+        ]
+        .iter()
+        .filter_map(|path| std::fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .sum()
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Map an id to its file, rejecting ids that could escape the store
+    fn snapshot_path(&self, id: &str) -> anyhow::Result<PathBuf> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            anyhow::bail!("Invalid scan snapshot id '{}'", id);
+        }
+        Ok(self.dir.join(format!("{}.json", id)))
+    }
+}
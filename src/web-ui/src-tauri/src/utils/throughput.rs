@@ -0,0 +1,131 @@
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::utils::volume_locks::VolumeKey;
+
+/// Recent operations kept per volume
+const MAX_SAMPLES: usize = 20;
+/// Smaller deletes are dominated by fixed overhead and say little about rates
+const MIN_SAMPLE_FILES: u64 = 10;
+/// Rough rates used before any delete has been measured on a volume
+const DEFAULT_FILES_PER_SECOND: f64 = 1000.0;
+const DEFAULT_BACKUP_FILES_PER_SECOND: f64 = 250.0;
+const DEFAULT_BACKUP_BYTES_PER_SECOND: f64 = 50.0 * 1024.0 * 1024.0;
+/// Spread applied around the default estimate, which is only a guess
+const DEFAULT_SPREAD: f64 = 3.0;
+
+/// One measured delete
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    files: u64,
+    bytes: u64,
+    seconds: f64,
+    backup: bool,
+}
+
+/// Likely duration of a delete, as a range
+#[derive(Debug, Clone, Serialize)]
+pub struct DurationEstimate {
+    pub file_count: u64,
+    pub total_bytes: u64,
+    pub backup: bool,
+    pub min_seconds: u64,
+    pub max_seconds: u64,
+    /// Recent deletes on the volume the range is based on; 0 when no
+    /// history exists and rough default rates were used
+    pub samples: usize,
+}
+
+/// Throughput of recent deletes per volume, kept for the session only
+#[derive(Debug, Default)]
+pub struct ThroughputHistory {
+    samples: Mutex<HashMap<VolumeKey, VecDeque<Sample>>>,
+}
+
+impl ThroughputHistory {
+    /// Remember that deleting `files` files of `bytes` below `path` took
+    /// `elapsed`
+    pub fn record(&self, path: &Path, files: u64, bytes: u64, elapsed: Duration, backup: bool) {
+        if files < MIN_SAMPLE_FILES || elapsed.is_zero() {
+            return;
+        }
+        let Some(volume) = VolumeKey::of(path) else {
+            return;
+        };
+        let mut samples = self.lock();
+        let history = samples.entry(volume).or_default();
+        if history.len() == MAX_SAMPLES {
+            history.pop_front();
+        }
+        history.push_back(Sample {
+            files,
+            bytes,
+            seconds: elapsed.as_secs_f64(),
+            backup,
+        });
+    }
+
+    /// Estimate deleting `files` files of `bytes` below `path`. Each recent
+    /// delete on the volume with the same backup setting is scaled to this
+    /// batch, and the range spans the slowest and fastest of them.
+    pub fn estimate(&self, path: &Path, files: u64, bytes: u64, backup: bool) -> DurationEstimate {
+        let scaled: Vec<f64> = VolumeKey::of(path)
+            .and_then(|volume| {
+                self.lock().get(&volume).map(|history| {
+                    history
+                        .iter()
+                        .filter(|sample| sample.backup == backup)
+                        .map(|sample| scale(sample, files, bytes))
+                        .collect()
+                })
+            })
+            .unwrap_or_default();
+
+        let (min, max) = if scaled.is_empty() {
+            let typical = default_seconds(files, bytes, backup);
+            (typical / DEFAULT_SPREAD, typical * DEFAULT_SPREAD)
+        } else {
+            let min = scaled.iter().copied().fold(f64::INFINITY, f64::min);
+            let max = scaled.iter().copied().fold(0.0, f64::max);
+            (min, max)
+        };
+
+        DurationEstimate {
+            file_count: files,
+            total_bytes: bytes,
+            backup,
+            min_seconds: min.floor() as u64,
+            max_seconds: max.ceil() as u64,
+            samples: scaled.len(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<VolumeKey, VecDeque<Sample>>> {
+        self.samples.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Seconds `sample` would have taken for this batch. Deletes scale with the
+/// number of files; backups also copy data, so whichever of files or bytes
+/// grew more sets the pace.
+fn scale(sample: &Sample, files: u64, bytes: u64) -> f64 {
+    let by_files = files as f64 / sample.files as f64;
+    let factor = if sample.backup && sample.bytes > 0 {
+        by_files.max(bytes as f64 / sample.bytes as f64)
+    } else {
+        by_files
+    };
+    sample.seconds * factor
+}
+
+fn default_seconds(files: u64, bytes: u64, backup: bool) -> f64 {
+    if backup {
+        files as f64 / DEFAULT_BACKUP_FILES_PER_SECOND
+            + bytes as f64 / DEFAULT_BACKUP_BYTES_PER_SECOND
+    } else {
+        files as f64 / DEFAULT_FILES_PER_SECOND
+    }
+}
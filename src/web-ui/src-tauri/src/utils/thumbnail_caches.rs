@@ -0,0 +1,101 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A folder where the OS file manager keeps generated thumbnails
+#[derive(Debug, Clone)]
+pub struct ThumbnailCacheLocation {
+    pub name: &'static str,
+    pub path: PathBuf,
+    /// Lowercased prefix of the files that make up the cache, for folders
+    /// shared with other data; `None` when everything inside is thumbnails
+    pub file_prefix: Option<&'static str>,
+}
+
+impl ThumbnailCacheLocation {
+    /// Whether `path`, inside this location, is part of the thumbnail cache
+    pub fn holds(&self, path: &Path) -> bool {
+        if !path.starts_with(&self.path) || path == self.path {
+            return false;
+        }
+        match self.file_prefix {
+            None => true,
+            Some(prefix) => {
+                path.parent() == Some(self.path.as_path())
+                    && path.file_name().is_some_and(|name| {
+                        name.to_string_lossy().to_lowercase().starts_with(prefix)
+                    })
+            }
+        }
+    }
+}
+
+/// Thumbnail cache folders for the current platform relative to `home`.
+/// Locations that do not exist are included; callers skip them.
+pub fn locations(home: &Path) -> Vec<ThumbnailCacheLocation> {
+    if cfg!(target_os = "windows") {
+        vec![ThumbnailCacheLocation {
+            name: "Explorer thumbnail cache",
+            path: home
+                .join("AppData")
+                .join("Local")
+                .join("Microsoft")
+                .join("Windows")
+                .join("Explorer"),
+            file_prefix: Some("thumbcache_"),
+        }]
+    } else if cfg!(target_os = "macos") {
+        let mut locations = vec![ThumbnailCacheLocation {
+            name: "QuickLook thumbnails",
+            path: home
+                .join("Library")
+                .join("Caches")
+                .join("com.apple.QuickLook.thumbnailcache"),
+            file_prefix: None,
+        }];
+        if let Some(cache_dir) = darwin_user_cache_dir() {
+            locations.push(ThumbnailCacheLocation {
+                name: "QuickLook thumbnails",
+                path: cache_dir.join("com.apple.QuickLook.thumbnailcache"),
+                file_prefix: None,
+            });
+        }
+        locations
+    } else {
+        let cache = std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .unwrap_or_else(|| home.join(".cache"));
+        vec![
+            ThumbnailCacheLocation {
+                name: "Freedesktop thumbnails",
+                path: cache.join("thumbnails"),
+                file_prefix: None,
+            },
+            ThumbnailCacheLocation {
+                name: "Legacy thumbnails",
+                path: home.join(".thumbnails"),
+                file_prefix: None,
+            },
+        ]
+    }
+}
+
+/// Per-user cache folder under `/var/folders` where QuickLook keeps its
+/// thumbnails on current macOS versions
+fn darwin_user_cache_dir() -> Option<PathBuf> {
+    let output = Command::new("getconf")
+        .arg("DARWIN_USER_CACHE_DIR")
+        .output()
+        .ok()?;
+    let dir = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !dir.is_empty()).then(|| PathBuf::from(dir))
+}
+
+/// Whether a file named `name` is a per-folder thumbnail database written by
+/// Windows Explorer, such as `Thumbs.db` or `ehthumbs_vista.db`
+pub fn is_thumbs_db(name: &str) -> bool {
+    matches!(
+        name.to_ascii_lowercase().as_str(),
+        "thumbs.db" | "ehthumbs.db" | "ehthumbs_vista.db"
+    )
+}
@@ -0,0 +1,162 @@
+use chrono::{DateTime, Utc};
+use std::path::{Path, PathBuf};
+use tokio_util::sync::CancellationToken;
+
+use crate::utils::walker::{allocated_size, directory_size, WalkOptions};
+
+/// An item in the user's trash or recycle bin
+#[derive(Debug, Clone)]
+pub struct TrashEntry {
+    pub name: String,
+    /// Where the item was before it was trashed, if the platform records it
+    pub original_path: Option<PathBuf>,
+    /// When the item was trashed; `None` where the platform keeps no
+    /// deletion date
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Disk space the item takes, summed over its files for folders
+    pub size: u64,
+    handle: Handle,
+}
+
+#[derive(Debug, Clone)]
+enum Handle {
+    #[cfg(not(target_os = "macos"))]
+    Item(trash::TrashItem),
+    #[cfg(target_os = "macos")]
+    Path(PathBuf),
+}
+
+impl TrashEntry {
+    /// Path shown for the entry: its original location where known
+    pub fn display_path(&self) -> String {
+        match &self.original_path {
+            Some(path) => path.to_string_lossy().to_string(),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Where the trash keeps the entry's data, falling back to its original
+    /// location, which is on the same volume for per-volume trash folders
+    pub fn location(&self) -> Option<PathBuf> {
+        match &self.handle {
+            #[cfg(not(target_os = "macos"))]
+            Handle::Item(item) => stored_path(item).or_else(|| self.original_path.clone()),
+            #[cfg(target_os = "macos")]
+            Handle::Path(path) => Some(path.clone()),
+        }
+    }
+}
+
+/// Disk space taken by a trashed file or folder at `path`
+fn stored_size(path: &Path, cancel: &CancellationToken) -> Option<u64> {
+    let metadata = std::fs::symlink_metadata(path).ok()?;
+    if metadata.is_dir() {
+        Some(directory_size(path, WalkOptions::default(), cancel).allocated)
+    } else {
+        Some(allocated_size(path, &metadata))
+    }
+}
+
+/// Every item currently in the trash, stopping early if `cancel` fires
+#[cfg(not(target_os = "macos"))]
+pub fn list(cancel: &CancellationToken) -> Result<Vec<TrashEntry>, String> {
+    let items = trash::os_limited::list().map_err(|e| format!("Failed to list the trash: {}", e))?;
+
+    let mut entries = Vec::with_capacity(items.len());
+    for item in items {
+        if cancel.is_cancelled() {
+            break;
+        }
+        let size = stored_path(&item)
+            .and_then(|path| stored_size(&path, cancel))
+            .or_else(|| {
+                trash::os_limited::metadata(&item)
+                    .ok()
+                    .and_then(|metadata| metadata.size.size())
+            })
+            .unwrap_or(0);
+        // Without a parseable deletion date the crate reports a negative time
+        let deleted_at = (item.time_deleted >= 0)
+            .then(|| DateTime::from_timestamp(item.time_deleted, 0))
+            .flatten();
+        entries.push(TrashEntry {
+            name: item.name.to_string_lossy().to_string(),
+            original_path: Some(item.original_path()),
+            deleted_at,
+            size,
+            handle: Handle::Item(item),
+        });
+    }
+    Ok(entries)
+}
+
+/// Where the trash keeps the item's data. On freedesktop systems the id is
+/// the `info/<name>.trashinfo` file and the data sits in `files/<name>`; on
+/// Windows the id is the item's path inside `$Recycle.Bin`.
+#[cfg(not(target_os = "macos"))]
+fn stored_path(item: &trash::TrashItem) -> Option<PathBuf> {
+    let id = Path::new(&item.id);
+    if cfg!(windows) {
+        return (id.is_absolute() && id.exists()).then(|| id.to_path_buf());
+    }
+    let info_dir = id.parent()?;
+    let name = id.file_name()?.to_string_lossy();
+    let name = name.strip_suffix(".trashinfo")?;
+    Some(info_dir.parent()?.join("files").join(name))
+}
+
+/// Every item currently in the trash, stopping early if `cancel` fires.
+/// macOS keeps no deletion date or original location that can be read
+/// without Finder, so those fields are always empty here.
+#[cfg(target_os = "macos")]
+pub fn list(cancel: &CancellationToken) -> Result<Vec<TrashEntry>, String> {
+    let home = home::home_dir().ok_or_else(|| "Unable to determine home directory".to_string())?;
+    let trash_dir = home.join(".Trash");
+    let dir_entries = std::fs::read_dir(&trash_dir).map_err(|e| {
+        format!(
+            "Failed to list {} (the app may need Full Disk Access): {}",
+            trash_dir.display(),
+            e
+        )
+    })?;
+
+    let mut entries = Vec::new();
+    for entry in dir_entries.flatten() {
+        if cancel.is_cancelled() {
+            break;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name == ".DS_Store" {
+            continue;
+        }
+        let path = entry.path();
+        entries.push(TrashEntry {
+            name,
+            original_path: None,
+            deleted_at: None,
+            size: stored_size(&path, cancel).unwrap_or(0),
+            handle: Handle::Path(path),
+        });
+    }
+    Ok(entries)
+}
+
+/// Permanently remove `entry` from the trash
+pub fn purge(entry: &TrashEntry) -> Result<(), String> {
+    match &entry.handle {
+        #[cfg(not(target_os = "macos"))]
+        Handle::Item(item) => {
+            trash::os_limited::purge_all([item]).map_err(|e| e.to_string())
+        }
+        #[cfg(target_os = "macos")]
+        Handle::Path(path) => {
+            let metadata = std::fs::symlink_metadata(path).map_err(|e| e.to_string())?;
+            if metadata.is_dir() {
+                std::fs::remove_dir_all(path)
+            } else {
+                std::fs::remove_file(path)
+            }
+            .map_err(|e| e.to_string())
+        }
+    }
+}
@@ -0,0 +1,430 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::Serialize;
+use std::fs::{self, ReadDir};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::utils::deletion::remove_link;
+use crate::utils::free_space::FreeSpaceChange;
+use crate::utils::operations::{ItemError, OperationTally};
+use crate::utils::platform::prepare_path_for_io;
+use crate::utils::walker::{allocated_size, device_id};
+
+/// Minimum time between two progress reports
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+/// Failures listed individually in the report; the rest are only counted
+const MAX_REPORTED_ERRORS: usize = 100;
+
+/// Where removed entries go
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TreeDeleteMode {
+    Permanent,
+    Trash,
+}
+
+/// Running totals reported while a tree is being removed
+#[derive(Debug, Clone, Serialize)]
+pub struct TreeDeleteProgress {
+    pub root: String,
+    pub entries_removed: u64,
+    pub bytes_freed: u64,
+    /// Entry being removed when the progress was reported
+    pub current: String,
+}
+
+/// Outcome of removing a directory tree
+#[derive(Debug, Clone, Serialize)]
+pub struct TreeDeletion {
+    pub root: String,
+    pub to_trash: bool,
+    /// Whether the root directory itself is gone
+    pub root_removed: bool,
+    /// Stopped by cancellation; everything counted so far is already gone
+    pub cancelled: bool,
+    pub files_removed: u64,
+    pub directories_removed: u64,
+    /// Bytes of removed files; entries moved to the trash free nothing yet
+    pub bytes_freed: u64,
+    pub failed_count: u64,
+    /// The first failures, as "path: reason"
+    pub errors: Vec<String>,
+    /// Free space on the volume measured around the removal
+    pub free_space: Option<FreeSpaceChange>,
+}
+
+impl TreeDeletion {
+    fn new(root: &Path, mode: TreeDeleteMode) -> Self {
+        Self {
+            root: root.to_string_lossy().to_string(),
+            to_trash: mode == TreeDeleteMode::Trash,
+            root_removed: false,
+            cancelled: false,
+            files_removed: 0,
+            directories_removed: 0,
+            bytes_freed: 0,
+            failed_count: 0,
+            errors: Vec::new(),
+            free_space: None,
+        }
+    }
+
+    fn fail(&mut self, path: &Path, reason: impl std::fmt::Display) {
+        self.failed_count += 1;
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(format!("{}: {}", path.display(), reason));
+        }
+    }
+
+    /// Counts and the reported errors for the operation-complete event
+    pub fn tally(&self) -> OperationTally {
+        OperationTally {
+            succeeded: self.entries_removed(),
+            failed: self.failed_count,
+            bytes: self.bytes_freed,
+            errors: self.errors.iter().map(|line| ItemError::from_line(line)).collect(),
+        }
+    }
+
+    fn entries_removed(&self) -> u64 {
+        self.files_removed + self.directories_removed
+    }
+}
+
+/// A directory being emptied, with the rest of its listing still unread
+struct Frame {
+    path: PathBuf,
+    entries: ReadDir,
+    /// Something below it was kept, so the directory itself must stay
+    kept: bool,
+}
+
+/// Remove `root` and everything below it.
+///
+/// Permanent deletion walks the tree iteratively, holding one open listing
+/// per level, so memory grows with depth rather than entry count. Each step
+/// removes exactly one file, link or emptied directory, so cancelling
+/// between steps leaves a consistent tree with only whole entries missing.
+/// Symlinks are removed, never followed, other filesystems mounted below
+/// `root` are left alone, and entries matching `protected_patterns` are kept
+/// along with the directories containing them.
+///
+/// Trash mode moves the top-level entries to the trash one at a time and
+/// then removes the emptied root. Each entry's subtree is first walked with
+/// the same protected-pattern and filesystem checks, and an entry holding
+/// anything permanent deletion would keep is not trashed at all.
+pub fn delete_tree(
+    root: &Path,
+    mode: TreeDeleteMode,
+    protected_patterns: &[String],
+    cancel: &CancellationToken,
+    on_progress: &mut dyn FnMut(&TreeDeleteProgress),
+) -> TreeDeletion {
+    let mut report = TreeDeletion::new(root, mode);
+    let io_root = prepare_path_for_io(root);
+    let protected = protected_set(protected_patterns);
+    let mut last_progress = Instant::now();
+    let mut progress = |report: &TreeDeletion, current: &Path| {
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            last_progress = Instant::now();
+            on_progress(&TreeDeleteProgress {
+                root: report.root.clone(),
+                entries_removed: report.entries_removed(),
+                bytes_freed: report.bytes_freed,
+                current: current.to_string_lossy().to_string(),
+            });
+        }
+    };
+
+    let root_device = match fs::symlink_metadata(&io_root) {
+        Ok(metadata) if metadata.is_dir() => device_id(&metadata),
+        Ok(_) => {
+            report.fail(root, "not a directory");
+            return report;
+        }
+        Err(e) => {
+            report.fail(root, e);
+            return report;
+        }
+    };
+
+    let entries = match fs::read_dir(&io_root) {
+        Ok(entries) => entries,
+        Err(e) => {
+            report.fail(root, e);
+            return report;
+        }
+    };
+
+    if mode == TreeDeleteMode::Trash {
+        let mut kept = false;
+        for entry in entries {
+            if cancel.is_cancelled() {
+                report.cancelled = true;
+                break;
+            }
+            let path = match entry {
+                Ok(entry) => entry.path(),
+                Err(e) => {
+                    report.fail(root, e);
+                    kept = true;
+                    continue;
+                }
+            };
+            if is_protected(&protected, &path) {
+                report.fail(&path, "matches a protected pattern");
+                kept = true;
+                continue;
+            }
+
+            let refusal = trash_refusal(&path, &protected, root_device, cancel);
+            if cancel.is_cancelled() {
+                report.cancelled = true;
+                break;
+            }
+            if let Err((nested, reason)) = refusal {
+                report.fail(
+                    &nested,
+                    format!("{}; {} was not moved to the trash", reason, path.display()),
+                );
+                kept = true;
+                continue;
+            }
+
+            let is_dir = fs::symlink_metadata(&path).is_ok_and(|m| m.is_dir());
+            match trash::delete(&path) {
+                Ok(()) if is_dir => report.directories_removed += 1,
+                Ok(()) => report.files_removed += 1,
+                Err(e) => {
+                    report.fail(&path, e);
+                    kept = true;
+                }
+            }
+            progress(&report, &path);
+        }
+
+        if !kept && !report.cancelled {
+            remove_root(&io_root, root, &mut report);
+        }
+        return report;
+    }
+
+    let mut stack = vec![Frame {
+        path: io_root.to_path_buf(),
+        entries,
+        kept: false,
+    }];
+
+    while let Some(frame) = stack.last_mut() {
+        if cancel.is_cancelled() {
+            report.cancelled = true;
+            break;
+        }
+
+        let entry = match frame.entries.next() {
+            Some(Ok(entry)) => entry,
+            Some(Err(e)) => {
+                frame.kept = true;
+                let path = frame.path.clone();
+                report.fail(&path, e);
+                continue;
+            }
+            None => {
+                let Some(done) = stack.pop() else { break };
+                if done.kept {
+                    if let Some(parent) = stack.last_mut() {
+                        parent.kept = true;
+                    }
+                } else if stack.is_empty() {
+                    remove_root(&io_root, root, &mut report);
+                } else {
+                    match fs::remove_dir(&done.path) {
+                        Ok(()) => report.directories_removed += 1,
+                        Err(e) => {
+                            if let Some(parent) = stack.last_mut() {
+                                parent.kept = true;
+                            }
+                            report.fail(&done.path, e);
+                        }
+                    }
+                    progress(&report, &done.path);
+                }
+                continue;
+            }
+        };
+
+        let path = entry.path();
+        let kept = if is_protected(&protected, &path) {
+            report.fail(&path, "matches a protected pattern");
+            true
+        } else {
+            match fs::symlink_metadata(&path) {
+                Err(e) => {
+                    report.fail(&path, e);
+                    true
+                }
+                Ok(metadata) if metadata.is_dir() => {
+                    if device_id(&metadata) != root_device {
+                        report.fail(&path, "on a different filesystem");
+                        true
+                    } else {
+                        match fs::read_dir(&path) {
+                            Ok(entries) => {
+                                stack.push(Frame {
+                                    path,
+                                    entries,
+                                    kept: false,
+                                });
+                                false
+                            }
+                            Err(e) => {
+                                report.fail(&path, e);
+                                true
+                            }
+                        }
+                    }
+                }
+                Ok(metadata) => {
+                    let removed = if metadata.is_symlink() {
+                        remove_link(&path)
+                    } else {
+                        fs::remove_file(&path)
+                    };
+                    match removed {
+                        Ok(()) => {
+                            report.files_removed += 1;
+                            if metadata.is_file() {
+                                report.bytes_freed += allocated_size(&path, &metadata);
+                            }
+                            progress(&report, &path);
+                            false
+                        }
+                        Err(e) => {
+                            report.fail(&path, e);
+                            true
+                        }
+                    }
+                }
+            }
+        };
+
+        if kept {
+            if let Some(frame) = stack.last_mut() {
+                frame.kept = true;
+            }
+        }
+    }
+
+    info!(
+        "Tree delete of {} finished: {} files, {} directories, {} failed{}",
+        report.root,
+        report.files_removed,
+        report.directories_removed,
+        report.failed_count,
+        if report.cancelled { " (cancelled)" } else { "" }
+    );
+
+    report
+}
+
+/// Check everything below a top-level entry before it is trashed as a
+/// whole, returning the first nested path permanent deletion would keep and
+/// why: a protected entry, a directory on another filesystem, or anything
+/// that cannot be read. Walks with one open listing per level and never
+/// follows symlinks. Stops early, passing, when `cancel` fires.
+fn trash_refusal(
+    path: &Path,
+    protected: &GlobSet,
+    root_device: Option<u64>,
+    cancel: &CancellationToken,
+) -> Result<(), (PathBuf, String)> {
+    let check_dir = |path: &Path| -> Result<Option<ReadDir>, (PathBuf, String)> {
+        let metadata = fs::symlink_metadata(path).map_err(|e| (path.to_path_buf(), e.to_string()))?;
+        if !metadata.is_dir() {
+            return Ok(None);
+        }
+        if device_id(&metadata) != root_device {
+            return Err((path.to_path_buf(), "on a different filesystem".to_string()));
+        }
+        fs::read_dir(path)
+            .map(Some)
+            .map_err(|e| (path.to_path_buf(), e.to_string()))
+    };
+
+    let mut stack: Vec<ReadDir> = check_dir(path)?.into_iter().collect();
+    while let Some(entries) = stack.last_mut() {
+        if cancel.is_cancelled() {
+            return Ok(());
+        }
+        match entries.next() {
+            None => {
+                stack.pop();
+            }
+            Some(Err(e)) => return Err((path.to_path_buf(), e.to_string())),
+            Some(Ok(entry)) => {
+                let child = entry.path();
+                if is_protected(protected, &child) {
+                    return Err((child, "matches a protected pattern".to_string()));
+                }
+                stack.extend(check_dir(&child)?);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn remove_root(io_root: &Path, root: &Path, report: &mut TreeDeletion) {
+    match fs::remove_dir(io_root) {
+        Ok(()) => {
+            report.directories_removed += 1;
+            report.root_removed = true;
+        }
+        Err(e) => report.fail(root, e),
+    }
+}
+
+fn protected_set(patterns: &[String]) -> GlobSet {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        match Glob::new(pattern) {
+            Ok(glob) => {
+                builder.add(glob);
+            }
+            Err(e) => warn!("Ignoring invalid protected pattern '{}': {}", pattern, e),
+        }
+    }
+    builder.build().unwrap_or_else(|_| GlobSet::empty())
+}
+
+/// Same matching as `SecurityValidator::matching_protected_pattern`: the
+/// full path or just the file name
+fn is_protected(protected: &GlobSet, path: &Path) -> bool {
+    protected.is_match(path) || path.file_name().is_some_and(|name| protected.is_match(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trash_mode_refuses_entries_with_protected_descendants() {
+        let root = tempfile::tempdir().unwrap();
+        let nested = root.path().join("build").join("out");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(nested.join("libkeep.so"), b"library").unwrap();
+
+        let report = delete_tree(
+            root.path(),
+            TreeDeleteMode::Trash,
+            &["*.so".to_string()],
+            &CancellationToken::new(),
+            &mut |_| {},
+        );
+
+        assert_eq!(report.failed_count, 1);
+        assert!(report.errors[0].contains("protected pattern"));
+        assert!(!report.root_removed);
+        assert!(nested.join("libkeep.so").exists());
+    }
+}
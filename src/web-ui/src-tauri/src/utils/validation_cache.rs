@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Cheap stat of a path, compared on lookup so a cached result is dropped as
+/// soon as the path appears, disappears or changes
+#[derive(Debug, Clone, PartialEq)]
+struct PathState {
+    exists: bool,
+    is_dir: bool,
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl PathState {
+    fn capture(path: &str) -> Self {
+        match fs::metadata(Path::new(path)) {
+            Ok(metadata) => Self {
+                exists: true,
+                is_dir: metadata.is_dir(),
+                len: metadata.len(),
+                modified: metadata.modified().ok(),
+            },
+            Err(_) => Self {
+                exists: false,
+                is_dir: false,
+                len: 0,
+                modified: None,
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CachedValidation<T> {
+    value: T,
+    state: PathState,
+    stored_at: Instant,
+    last_used: u64,
+}
+
+#[derive(Debug)]
+struct CacheState<T> {
+    entries: HashMap<String, CachedValidation<T>>,
+    clock: u64,
+}
+
+/// Small least-recently-used cache of per-path validation results with a
+/// short time-to-live, for UIs that re-validate paths while navigating
+#[derive(Debug)]
+pub struct ValidationCache<T> {
+    ttl: Duration,
+    state: Mutex<CacheState<T>>,
+}
+
+impl<T: Clone> ValidationCache<T> {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            state: Mutex::new(CacheState {
+                entries: HashMap::new(),
+                clock: 0,
+            }),
+        }
+    }
+
+    /// Cached result for `path`, if it is fresh and the path is unchanged
+    pub fn get(&self, path: &str) -> Option<T> {
+        let current = PathState::capture(path);
+        let mut state = self.lock();
+        state.clock += 1;
+        let clock = state.clock;
+
+        let entry = state.entries.get_mut(path)?;
+        if entry.stored_at.elapsed() >= self.ttl || entry.state != current {
+            state.entries.remove(path);
+            return None;
+        }
+        entry.last_used = clock;
+        Some(entry.value.clone())
+    }
+
+    /// Store a result, evicting the least recently used entry beyond
+    /// `capacity`. A capacity of 0 disables caching.
+    pub fn insert(&self, path: &str, value: T, capacity: usize) {
+        if capacity == 0 {
+            return;
+        }
+
+        let path_state = PathState::capture(path);
+        let mut state = self.lock();
+        state.clock += 1;
+        let clock = state.clock;
+
+        while state.entries.len() >= capacity && !state.entries.contains_key(path) {
+            let Some(oldest) = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            state.entries.remove(&oldest);
+        }
+
+        state.entries.insert(
+            path.to_string(),
+            CachedValidation {
+                value,
+                state: path_state,
+                stored_at: Instant::now(),
+                last_used: clock,
+            },
+        );
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState<T>> {
+        // A poisoned cache is still structurally valid; keep using it
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use tracing::debug;
+
+use crate::utils::config::VolumeDeletePolicy;
+use crate::utils::platform::mount_point;
+use crate::utils::walker::device_id;
+
+/// Identifies a physical volume: the device id where the platform reports
+/// one, otherwise the mount point
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum VolumeKey {
+    Device(u64),
+    Mount(String),
+}
+
+impl VolumeKey {
+    /// Volume holding `path`, or its nearest existing ancestor
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = path.ancestors().find_map(|dir| fs::metadata(dir).ok())?;
+        match device_id(&metadata) {
+            Some(device) => Some(Self::Device(device)),
+            None => mount_point(path).map(|mount| Self::Mount(mount.to_string_lossy().to_string())),
+        }
+    }
+}
+
+/// One delete per volume at a time.
+///
+/// Deletes on the same disk compete for I/O and make the free-space change
+/// of each impossible to attribute. Read-only work such as scans and
+/// analysis never takes these locks.
+#[derive(Debug, Default)]
+pub struct VolumeLocks {
+    /// Target of the delete holding each volume
+    held: Mutex<HashMap<VolumeKey, String>>,
+    released: Notify,
+}
+
+impl VolumeLocks {
+    /// Take the lock for the volume holding `path` according to `policy`.
+    ///
+    /// `Reject` fails if the volume is busy, `Queue` waits for it and
+    /// `Concurrent` never blocks. Volumes that cannot be identified are not
+    /// locked.
+    pub async fn acquire(
+        self: &Arc<Self>,
+        path: &Path,
+        policy: VolumeDeletePolicy,
+    ) -> Result<VolumeLockGuard, String> {
+        let key = match policy {
+            VolumeDeletePolicy::Concurrent => None,
+            _ => VolumeKey::of(path),
+        };
+        let Some(key) = key else {
+            return Ok(VolumeLockGuard {
+                locks: Arc::clone(self),
+                key: None,
+            });
+        };
+        let target = path.to_string_lossy().to_string();
+
+        loop {
+            // Registered before checking so a release in between is not missed
+            let released = self.released.notified();
+            {
+                let mut held = self.lock();
+                match held.get(&key) {
+                    None => {
+                        held.insert(key.clone(), target);
+                        return Ok(VolumeLockGuard {
+                            locks: Arc::clone(self),
+                            key: Some(key),
+                        });
+                    }
+                    Some(running) if policy == VolumeDeletePolicy::Reject => {
+                        return Err(format!(
+                            "A delete operation is already running on this volume ({}); \
+                             wait for it to finish",
+                            running
+                        ));
+                    }
+                    Some(running) => {
+                        debug!("Delete of {} queued behind {}", target, running);
+                    }
+                }
+            }
+            released.await;
+        }
+    }
+
+    /// Take the locks for every volume holding one of `paths`. Volumes are
+    /// locked in a fixed order, so two deletes spanning the same volumes
+    /// cannot each wait on the other under `Queue`.
+    pub async fn acquire_all(
+        self: &Arc<Self>,
+        paths: &[PathBuf],
+        policy: VolumeDeletePolicy,
+    ) -> Result<Vec<VolumeLockGuard>, String> {
+        let mut volumes: Vec<(VolumeKey, &PathBuf)> = paths
+            .iter()
+            .filter_map(|path| Some((VolumeKey::of(path)?, path)))
+            .collect();
+        volumes.sort_by(|a, b| a.0.cmp(&b.0));
+        volumes.dedup_by(|a, b| a.0 == b.0);
+
+        let mut guards = Vec::with_capacity(volumes.len());
+        for (_, path) in volumes {
+            guards.push(self.acquire(path, policy).await?);
+        }
+        Ok(guards)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<VolumeKey, String>> {
+        self.held.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Releases its volume when dropped
+#[derive(Debug)]
+pub struct VolumeLockGuard {
+    locks: Arc<VolumeLocks>,
+    key: Option<VolumeKey>,
+}
+
+impl Drop for VolumeLockGuard {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.locks.lock().remove(&key);
+            self.locks.released.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn acquire_all_locks_a_shared_volume_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = vec![dir.path().join("a"), dir.path().join("b")];
+        let locks = Arc::new(VolumeLocks::default());
+
+        let guards = locks
+            .acquire_all(&paths, VolumeDeletePolicy::Reject)
+            .await
+            .unwrap();
+        assert_eq!(guards.len(), 1);
+        assert!(locks.acquire(&paths[0], VolumeDeletePolicy::Reject).await.is_err());
+
+        drop(guards);
+        assert!(locks.acquire(&paths[0], VolumeDeletePolicy::Reject).await.is_ok());
+    }
+}
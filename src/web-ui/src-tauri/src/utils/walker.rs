@@ -0,0 +1,400 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, Metadata, ReadDir};
+use std::path::{Path, PathBuf};
+use tokio_util::sync::CancellationToken;
+
+use crate::utils::platform::{is_network_drive, normalize_filesystem_type, volume_type_names};
+
+/// A single filesystem entry produced by `DirWalker`
+#[derive(Debug)]
+pub struct WalkEntry {
+    pub path: PathBuf,
+    pub metadata: Metadata,
+    pub depth: usize,
+}
+
+impl WalkEntry {
+    pub fn is_symlink(&self) -> bool {
+        self.metadata.file_type().is_symlink()
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.metadata.is_dir()
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.metadata.is_file()
+    }
+}
+
+/// Options controlling a recursive directory walk
+#[derive(Debug, Clone, Default)]
+pub struct WalkOptions {
+    pub max_depth: Option<usize>,
+    /// Do not descend into directories on a different device than the root
+    pub stay_on_filesystem: bool,
+    /// Do not descend into network mounts (SMB, NFS, ...) below the root
+    pub skip_network_drives: bool,
+    /// Leave out hidden files and directories (dotfiles, or the hidden
+    /// attribute on Windows); hidden directories are not descended into
+    pub skip_hidden: bool,
+    /// Do not enter volumes whose filesystem type, or one of its aliases
+    /// (`network`, `removable`, `readonly`), is listed here
+    pub excluded_filesystem_types: Vec<String>,
+    /// Canonical paths whose subtrees are never entered; a walk path is
+    /// matched by the canonical form of the root it was reached from
+    pub excluded_paths: Vec<PathBuf>,
+}
+
+/// Why a directory subtree was excluded from a walk
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum SkipReason {
+    DifferentFilesystem,
+    Gitignored,
+    NetworkFilesystem,
+    ExcludedFilesystemType,
+    GlobalExclusion,
+}
+
+/// A directory subtree the walker deliberately did not enter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedPath {
+    pub path: String,
+    pub reason: SkipReason,
+}
+
+/// Iterative, depth-first directory walker.
+///
+/// Symlinks are reported but never followed, and only one directory handle
+/// is held open at a time so memory stays bounded on very large trees.
+pub struct DirWalker {
+    options: WalkOptions,
+    root: PathBuf,
+    /// Canonical form of `root`, resolved only when paths are excluded
+    canonical_root: Option<PathBuf>,
+    current: Option<(ReadDir, usize)>,
+    pending: Vec<(PathBuf, usize)>,
+    errors: u64,
+    hidden: u64,
+    root_device: Option<u64>,
+    skipped: Vec<SkippedPath>,
+    /// Whether each device seen below the root is a network mount
+    network_devices: HashMap<u64, bool>,
+    /// Whether each device seen below the root has an excluded filesystem type
+    excluded_devices: HashMap<u64, bool>,
+}
+
+impl DirWalker {
+    /// Create a walker rooted at `root` (the root itself is not yielded)
+    pub fn new(root: &Path, mut options: WalkOptions) -> Self {
+        let root_device = fs::metadata(root).ok().and_then(|m| device_id(&m));
+        for name in &mut options.excluded_filesystem_types {
+            *name = normalize_filesystem_type(name);
+        }
+
+        let canonical_root = if options.excluded_paths.is_empty() {
+            None
+        } else {
+            fs::canonicalize(root).ok()
+        };
+
+        let mut walker = Self {
+            options,
+            root: root.to_path_buf(),
+            canonical_root,
+            current: None,
+            pending: vec![(root.to_path_buf(), 1)],
+            errors: 0,
+            hidden: 0,
+            root_device,
+            skipped: Vec::new(),
+            network_devices: HashMap::new(),
+            excluded_devices: HashMap::new(),
+        };
+        if walker.is_excluded_volume(root) {
+            walker.skip_subtree(root, SkipReason::ExcludedFilesystemType);
+        } else if walker.is_excluded_path(root) {
+            walker.skip_subtree(root, SkipReason::GlobalExclusion);
+        }
+        walker
+    }
+
+    /// Number of entries or directories that could not be read
+    pub fn error_count(&self) -> u64 {
+        self.errors
+    }
+
+    /// Number of hidden entries left out because of `skip_hidden`
+    pub fn hidden_count(&self) -> u64 {
+        self.hidden
+    }
+
+    /// Directory subtrees excluded from the walk and why
+    pub fn skipped(&self) -> &[SkippedPath] {
+        &self.skipped
+    }
+
+    /// Consume the walker, returning the skipped subtrees
+    pub fn into_skipped(self) -> Vec<SkippedPath> {
+        self.skipped
+    }
+
+    /// Do not descend into a directory that was just yielded, recording why
+    pub fn skip_subtree(&mut self, dir: &Path, reason: SkipReason) {
+        let before = self.pending.len();
+        self.pending.retain(|(pending, _)| pending != dir);
+        if self.pending.len() != before {
+            self.skipped.push(SkippedPath {
+                path: dir.to_string_lossy().to_string(),
+                reason,
+            });
+        }
+    }
+
+    fn skip_reason(&mut self, path: &Path, metadata: &Metadata) -> Option<SkipReason> {
+        let (Some(root), Some(device)) = (self.root_device, device_id(metadata)) else {
+            return None;
+        };
+        if root == device {
+            return None;
+        }
+
+        if self.options.stay_on_filesystem {
+            return Some(SkipReason::DifferentFilesystem);
+        }
+        if !self.options.excluded_filesystem_types.is_empty() {
+            let excluded = match self.excluded_devices.get(&device) {
+                Some(&excluded) => excluded,
+                None => {
+                    let excluded = self.is_excluded_volume(path);
+                    self.excluded_devices.insert(device, excluded);
+                    excluded
+                }
+            };
+            if excluded {
+                return Some(SkipReason::ExcludedFilesystemType);
+            }
+        }
+        if !self.options.skip_network_drives {
+            return None;
+        }
+
+        // Only mount points change the device, so each device is looked up once
+        let is_network = *self
+            .network_devices
+            .entry(device)
+            .or_insert_with(|| is_network_drive(path));
+        is_network.then_some(SkipReason::NetworkFilesystem)
+    }
+
+    fn is_excluded_volume(&self, path: &Path) -> bool {
+        let excluded = &self.options.excluded_filesystem_types;
+        !excluded.is_empty()
+            && volume_type_names(path)
+                .iter()
+                .any(|name| excluded.contains(name))
+    }
+
+    /// Whether `path` lies in one of `excluded_paths`. Symlinks are never
+    /// followed below the root, so its canonical form is the canonical root
+    /// joined with the rest of the path.
+    fn is_excluded_path(&self, path: &Path) -> bool {
+        let Some(canonical_root) = &self.canonical_root else {
+            return false;
+        };
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        let canonical = canonical_root.join(relative);
+        self.options
+            .excluded_paths
+            .iter()
+            .any(|excluded| canonical.starts_with(excluded))
+    }
+
+    fn within_depth(&self, depth: usize) -> bool {
+        self.options.max_depth.is_none_or(|max| depth <= max)
+    }
+}
+
+impl Iterator for DirWalker {
+    type Item = WalkEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((entries, depth)) = self.current.as_mut() {
+                let depth = *depth;
+                match entries.next() {
+                    Some(Ok(entry)) => {
+                        let path = entry.path();
+                        let metadata = match fs::symlink_metadata(&path) {
+                            Ok(metadata) => metadata,
+                            Err(_) => {
+                                self.errors += 1;
+                                continue;
+                            }
+                        };
+
+                        if self.options.skip_hidden && is_hidden(&path, &metadata) {
+                            self.hidden += 1;
+                            continue;
+                        }
+
+                        if self.is_excluded_path(&path) {
+                            if metadata.is_dir() {
+                                self.skipped.push(SkippedPath {
+                                    path: path.to_string_lossy().to_string(),
+                                    reason: SkipReason::GlobalExclusion,
+                                });
+                            }
+                            continue;
+                        }
+
+                        if metadata.is_dir() {
+                            if let Some(reason) = self.skip_reason(&path, &metadata) {
+                                self.skipped.push(SkippedPath {
+                                    path: path.to_string_lossy().to_string(),
+                                    reason,
+                                });
+                                continue;
+                            }
+
+                            if self.within_depth(depth + 1) {
+                                self.pending.push((path.clone(), depth + 1));
+                            }
+                        }
+
+                        return Some(WalkEntry {
+                            path,
+                            metadata,
+                            depth,
+                        });
+                    }
+                    Some(Err(_)) => {
+                        self.errors += 1;
+                        continue;
+                    }
+                    None => self.current = None,
+                }
+            }
+
+            let (dir, depth) = self.pending.pop()?;
+            match fs::read_dir(&dir) {
+                Ok(entries) => self.current = Some((entries, depth)),
+                Err(_) => self.errors += 1,
+            }
+        }
+    }
+}
+
+/// Total size of the regular files below a directory
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct DirectorySize {
+    /// Sum of the files' apparent lengths
+    pub bytes: u64,
+    /// Space the files actually occupy on disk; what deleting them frees
+    pub allocated: u64,
+    pub files: u64,
+}
+
+/// Sum the sizes of all regular files under `root`, stopping early if
+/// `cancel` fires; callers should check the token before trusting the total
+pub fn directory_size(root: &Path, options: WalkOptions, cancel: &CancellationToken) -> DirectorySize {
+    let mut size = DirectorySize::default();
+    for entry in DirWalker::new(root, options) {
+        if cancel.is_cancelled() {
+            break;
+        }
+        if entry.is_file() {
+            size.bytes += entry.metadata.len();
+            size.allocated += allocated_size(&entry.path, &entry.metadata);
+            size.files += 1;
+        }
+    }
+    size
+}
+
+/// Dotfiles count as hidden everywhere; Windows also honours the hidden attribute
+pub fn is_hidden(path: &Path, metadata: &Metadata) -> bool {
+    let dotfile = path
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        if metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0 {
+            return true;
+        }
+    }
+    #[cfg(not(windows))]
+    let _ = metadata;
+
+    dotfile
+}
+
+/// Device id of the filesystem holding an entry (`st_dev` on Unix)
+#[cfg(unix)]
+pub fn device_id(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.dev())
+}
+
+/// Identity of a file with more than one hard link, so callers summing sizes
+/// can count its data once. Singly linked files return `None`.
+#[cfg(unix)]
+pub fn hardlink_id(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.nlink() > 1).then(|| (metadata.dev(), metadata.ino()))
+}
+
+/// File ids need an open handle on Windows, so hard links are counted per
+/// path there
+#[cfg(not(unix))]
+pub fn hardlink_id(_metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Windows mounted folders are reparse points, which the walker never
+/// follows, so no device comparison is needed there
+#[cfg(not(unix))]
+pub fn device_id(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+/// Bytes a file actually occupies on disk (`st_blocks * 512` on Unix).
+/// Sparse files, and compressed ones on NTFS or Btrfs, use less than their
+/// apparent length; small files usually use a little more because whole
+/// blocks are allocated.
+#[cfg(unix)]
+pub fn allocated_size(_path: &Path, metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.blocks() * 512
+}
+
+/// Bytes a file actually occupies on disk, from `GetCompressedFileSizeW`,
+/// which accounts for both sparse ranges and NTFS compression. Falls back to
+/// the apparent length if the call fails.
+#[cfg(windows)]
+pub fn allocated_size(path: &Path, metadata: &Metadata) -> u64 {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::{GetCompressedFileSizeW, INVALID_FILE_SIZE};
+
+    let io_path = crate::utils::platform::prepare_path_for_io(path);
+    let wide: Vec<u16> = io_path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut high = 0u32;
+    // SAFETY: `wide` is NUL-terminated and `high` outlives the call
+    let low = unsafe { GetCompressedFileSizeW(wide.as_ptr(), &mut high) };
+    // INVALID_FILE_SIZE is also a valid low word, so the error code decides
+    if low == INVALID_FILE_SIZE && std::io::Error::last_os_error().raw_os_error() != Some(0) {
+        return metadata.len();
+    }
+    (u64::from(high) << 32) | u64::from(low)
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn allocated_size(_path: &Path, metadata: &Metadata) -> u64 {
+    metadata.len()
+}
@@ -44,6 +44,8 @@ export interface SystemInfo {
   os_version: string
   arch: string
   hostname: string
+  username: string
+  identifiers_redacted: boolean
   total_memory?: number
   available_memory?: number
   disk_space?: {
@@ -103,20 +105,30 @@ export interface SafetyValidation {
   blocked_reasons: string[]
 }
 
-export async function validatePathSafety(path: string): Promise<SafetyValidation> {
+export async function validatePathSafety(path: string, force = false): Promise<SafetyValidation> {
   try {
-    return await invoke<SafetyValidation>('validate_path_safety', { path })
+    return await invoke<SafetyValidation>('validate_path_safety', { path, force })
   } catch (error) {
     console.error('Failed to validate path safety:', error)
     throw error
   }
 }
 
+export async function validatePaths(paths: string[], force = false): Promise<SafetyValidation[]> {
+  try {
+    return await invoke<SafetyValidation[]>('validate_paths', { paths, force })
+  } catch (error) {
+    console.error('Failed to validate paths:', error)
+    throw error
+  }
+}
+
 // Notification commands
 export interface NotificationConfig {
   title: string
   body: string
   icon?: string
+  kind?: 'info' | 'success' | 'warning'
   sound?: string
   duration?: number
 }
@@ -124,6 +136,7 @@ export interface NotificationConfig {
 export interface NotificationResult {
   success: boolean
   message: string
+  status: 'sent' | 'failed' | 'disabled' | 'throttled' | 'queued' | 'dropped'
 }
 
 export async function showNotification(config: NotificationConfig): Promise<NotificationResult> {